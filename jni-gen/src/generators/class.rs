@@ -59,10 +59,13 @@ impl<'a> ClassGenerator<'a> {
             "use jni::objects::JObject;",
             "use jni::objects::JValue;",
             "use jni::objects::JClass;",
+            "use jni::objects::GlobalRef;",
+            "use jni::objects::JMethodID;",
             "use jni::errors::Result as JNIResult;",
             "use jni::sys::*;",
             "use jni::signature::*;",
             "use std::str::FromStr;",
+            "use once_cell::sync::OnceCell;",
         ]
         .join("\n")
             + "\n"
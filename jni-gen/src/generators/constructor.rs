@@ -168,20 +168,29 @@ fn generate(
 
     code.push(") -> JNIResult<JObject<'a>> {".to_string());
 
-    code.push(format!("    let class = self.env.find_class(\"{}\")?;", class.path()));
-
+    // Looking up the class and the constructor takes several JNI calls, so
+    // they are looked up once. The global reference keeps the class loaded,
+    // which keeps the method id valid.
+    code.push("    static CONSTRUCTOR: OnceCell<(GlobalRef, usize)> = OnceCell::new();".to_string());
+    code.push("    let (class, method_id) = CONSTRUCTOR.get_or_try_init(|| -> JNIResult<_> {".to_string());
+    code.push(format!("        let class = self.env.find_class(\"{}\")?;", class.path()));
     code.push(format!(
-        "    let method_signature = \"{}\";",
+        "        let method_signature = \"{}\";",
         constructor_signature
     ));
-
-    code.push("    let method_id = self.env.get_method_id(".to_string());
-    code.push("        class,".to_string());
-    code.push("        \"<init>\",".to_string());
-    code.push("        method_signature".to_string());
-    code.push("    )?;".to_string());
-
-    code.push("    let result = self.env.new_object_unchecked(".to_string());
+    code.push("        let method_id = self.env.get_method_id(".to_string());
+    code.push("            class,".to_string());
+    code.push("            \"<init>\",".to_string());
+    code.push("            method_signature".to_string());
+    code.push("        )?;".to_string());
+    code.push("        let global_class = self.env.new_global_ref(class)?;".to_string());
+    code.push("        self.env.delete_local_ref(class.into())?;".to_string());
+    code.push("        Ok((global_class, method_id.into_inner() as usize))".to_string());
+    code.push("    })?;".to_string());
+    code.push("    let class = JClass::from(class.as_obj());".to_string());
+    code.push("    let method_id = JMethodID::from(*method_id as jmethodID);".to_string());
+
+    code.push("    self.env.new_object_unchecked(".to_string());
     code.push("        class,".to_string());
     code.push("        method_id,".to_string());
     code.push("        &[".to_string());
@@ -194,10 +203,7 @@ fn generate(
     }
 
     code.push("        ]".to_string());
-    code.push("    );".to_string());
-
-    code.push("    self.env.delete_local_ref(class.into()).unwrap();".to_string());
-    code.push("    result".to_string());
+    code.push("    )".to_string());
     code.push("}".to_string());
 
     code.join("\n") + "\n"
@@ -27,14 +27,19 @@ pub fn generate_scala_object_getter(env: &JNIEnv, class_name: &ClassName) -> Res
             class_name.path()
         ),
         "pub fn singleton(&self) -> JNIResult<JObject<'a>> {".to_string(),
-        format!( "    let class = self.env.find_class(\"{}\")?;", class_name.path()),
-        "    let result = self.env.get_static_field(".to_string(),
-        "        class,".to_string(),
-        "        \"MODULE$\",".to_string(),
-        format!("        \"L{};\",", class_name.path()),
-        "    ).and_then(|x| x.l());".to_string(),
-        "    self.env.delete_local_ref(class.into()).unwrap();".to_string(),
-        "    result".to_string(),
+        // The instance never changes, so it is looked up once.
+        "    static SINGLETON: OnceCell<GlobalRef> = OnceCell::new();".to_string(),
+        "    let singleton = SINGLETON.get_or_try_init(|| -> JNIResult<_> {".to_string(),
+        format!("        let class = self.env.find_class(\"{}\")?;", class_name.path()),
+        "        let result = self.env.get_static_field(".to_string(),
+        "            class,".to_string(),
+        "            \"MODULE$\",".to_string(),
+        format!("            \"L{};\",", class_name.path()),
+        "        ).and_then(|x| x.l());".to_string(),
+        "        self.env.delete_local_ref(class.into())?;".to_string(),
+        "        self.env.new_global_ref(result?)".to_string(),
+        "    })?;".to_string(),
+        "    self.env.new_local_ref(singleton.as_obj())".to_string(),
         "}".to_string(),
     ]
     .join("\n")
@@ -1,4 +1,5 @@
 extern crate jni;
+extern crate once_cell;
 
 #[path = "../gen/mod.rs"]
 pub mod wrappers;
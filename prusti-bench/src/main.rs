@@ -0,0 +1,278 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Encodes (without verifying) a corpus of programs and crates, records the
+//! size of the generated Viper programs, the peak memory and the time of each
+//! phase, and compares them with a stored baseline.
+//!
+//! Usage, from the root of the repository:
+//!
+//! ```text
+//! prusti-bench [--corpus <csv>] [--output <json>] [--baseline <json>] [--tolerance <percent>]
+//! ```
+
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+struct CorpusRecord {
+    /// A Rust file verified with `prusti-rustc`, or the directory of a crate
+    /// verified with `cargo-prusti`.
+    path: String,
+}
+
+/// A line of the statistics written by Prusti with `PRUSTI_ENCODING_STATISTICS`.
+#[derive(Debug, Deserialize)]
+struct CrateStatistics {
+    program: ProgramStatistics,
+    peak_memory_mb: Option<usize>,
+    phases: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProgramStatistics {
+    statements: usize,
+    expression_nodes: usize,
+}
+
+/// The measurements of an entry of the corpus, summed over its crates.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct Measurement {
+    statements: usize,
+    expression_nodes: usize,
+    peak_memory_mb: usize,
+    /// The wall time of each phase, in seconds.
+    phases: BTreeMap<String, f64>,
+}
+
+impl Measurement {
+    fn add(&mut self, statistics: CrateStatistics) {
+        self.statements += statistics.program.statements;
+        self.expression_nodes += statistics.program.expression_nodes;
+        self.peak_memory_mb = self.peak_memory_mb.max(statistics.peak_memory_mb.unwrap_or(0));
+        for (phase, time) in statistics.phases {
+            *self.phases.entry(phase).or_default() += time;
+        }
+    }
+
+    /// The metrics that are compared with the baseline.
+    fn metrics(&self) -> Vec<(String, f64)> {
+        let mut metrics = vec![
+            ("statements".to_string(), self.statements as f64),
+            ("expression nodes".to_string(), self.expression_nodes as f64),
+            ("peak memory (MB)".to_string(), self.peak_memory_mb as f64),
+        ];
+        for (phase, &time) in &self.phases {
+            metrics.push((format!("{} time (s)", phase), time));
+        }
+        metrics
+    }
+}
+
+/// The times of the phases that are shorter than this, in seconds, are too
+/// noisy to be compared.
+const MIN_COMPARED_TIME: f64 = 0.5;
+
+struct Options {
+    corpus: PathBuf,
+    output: PathBuf,
+    baseline: Option<PathBuf>,
+    /// The increase of a metric, in percent, that is reported as a regression.
+    tolerance: f64,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        corpus: PathBuf::from("prusti-bench/corpus.csv"),
+        output: PathBuf::from("benchmark-output/encoding-statistics.json"),
+        baseline: None,
+        tolerance: 10.0,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value of {}", arg));
+        match arg.as_str() {
+            "--corpus" => options.corpus = value()?.into(),
+            "--output" => options.output = value()?.into(),
+            "--baseline" => options.baseline = Some(value()?.into()),
+            "--tolerance" => {
+                options.tolerance = value()?
+                    .parse()
+                    .map_err(|_| "the tolerance must be a number".to_string())?;
+            }
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn prusti_home() -> &'static Path {
+    if cfg!(debug_assertions) {
+        Path::new("target/debug")
+    } else {
+        Path::new("target/release")
+    }
+}
+
+/// Encode an entry of the corpus and collect the statistics of its crates.
+/// The `output_dir` has to be absolute, because cargo runs Prusti in the
+/// directory of each crate.
+fn measure(path: &Path, output_dir: &Path) -> Result<Measurement, Box<dyn Error>> {
+    let statistics_path = output_dir.join("encoding-statistics.jsonl");
+    if statistics_path.exists() {
+        fs::remove_file(&statistics_path)?;
+    }
+    let mut command = if path.is_dir() {
+        let mut command = Command::new(prusti_home().join("cargo-prusti"));
+        command
+            .arg("--manifest-path")
+            .arg(path.join("Cargo.toml"))
+            // A fresh target directory, so that cargo does not skip the
+            // crates that were checked before.
+            .env("CARGO_TARGET_DIR", output_dir.join("target"));
+        command
+    } else {
+        let mut command = Command::new(prusti_home().join("prusti-rustc"));
+        command
+            .arg("--edition=2018")
+            .arg(path)
+            .arg("--out-dir")
+            .arg(output_dir);
+        command
+    };
+    let status = command
+        .env("PRUSTI_ENCODE_ONLY", "true")
+        .env("PRUSTI_ENCODING_STATISTICS", &statistics_path)
+        .status()?;
+    if !status.success() {
+        warn!("Prusti failed on {}: {}", path.display(), status);
+    }
+    let mut measurement = Measurement::default();
+    for line in fs::read_to_string(&statistics_path)?.lines() {
+        measurement.add(serde_json::from_str(line)?);
+    }
+    Ok(measurement)
+}
+
+/// Describe the metrics of `current` that exceed the ones of `baseline` by
+/// more than `tolerance` percent.
+fn find_regressions(
+    baseline: &BTreeMap<String, Measurement>,
+    current: &BTreeMap<String, Measurement>,
+    tolerance: f64,
+) -> Vec<String> {
+    let mut regressions = vec![];
+    for (path, measurement) in current {
+        let baseline_metrics: BTreeMap<_, _> = match baseline.get(path) {
+            Some(baseline_measurement) => baseline_measurement.metrics().into_iter().collect(),
+            None => continue,
+        };
+        for (metric, value) in measurement.metrics() {
+            let before = match baseline_metrics.get(&metric) {
+                Some(&before) => before,
+                None => continue,
+            };
+            if metric.ends_with("time (s)") && value < MIN_COMPARED_TIME {
+                continue;
+            }
+            if before > 0.0 && value > before * (1.0 + tolerance / 100.0) {
+                regressions.push(format!(
+                    "{}: {} increased from {:.2} to {:.2} ({:+.1}%)",
+                    path,
+                    metric,
+                    before,
+                    value,
+                    (value / before - 1.0) * 100.0,
+                ));
+            }
+        }
+    }
+    regressions
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::Builder::new()
+        .filter_module("prusti_bench", log::LevelFilter::Info)
+        .parse_env("PRUSTI_BENCH_LOG")
+        .init();
+
+    let options = parse_options().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(2);
+    });
+    let output_dir = env::current_dir()?
+        .join(options.output.parent().unwrap_or_else(|| Path::new(".")));
+    fs::create_dir_all(&output_dir)?;
+
+    let corpus: Vec<CorpusRecord> = csv::Reader::from_path(&options.corpus)?
+        .deserialize()
+        .collect::<Result<_, _>>()?;
+    let mut results = BTreeMap::new();
+    for (index, record) in corpus.iter().enumerate() {
+        info!("Encoding {}/{}: {}", index + 1, corpus.len(), record.path);
+        let measurement = measure(Path::new(&record.path), &output_dir)?;
+        info!("{:?}", measurement);
+        results.insert(record.path.clone(), measurement);
+    }
+    fs::write(&options.output, serde_json::to_string_pretty(&results)?)?;
+    info!("Wrote the statistics to {}", options.output.display());
+
+    if let Some(baseline_path) = options.baseline {
+        let baseline = serde_json::from_str(&fs::read_to_string(&baseline_path)?)?;
+        let regressions = find_regressions(&baseline, &results, options.tolerance);
+        if !regressions.is_empty() {
+            for regression in &regressions {
+                eprintln!("{}", regression);
+            }
+            eprintln!(
+                "{} metrics regressed by more than {}% with respect to {}",
+                regressions.len(),
+                options.tolerance,
+                baseline_path.display(),
+            );
+            process::exit(1);
+        }
+        info!("No regressions with respect to {}", baseline_path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(statements: usize, encoding_time: f64) -> Measurement {
+        Measurement {
+            statements,
+            expression_nodes: 100,
+            peak_memory_mb: 200,
+            phases: vec![("encoding".to_string(), encoding_time)].into_iter().collect(),
+        }
+    }
+
+    #[test]
+    fn regressions_exceed_the_tolerance() {
+        let baseline = vec![("a.rs".to_string(), measurement(100, 1.0))].into_iter().collect();
+        let within = vec![("a.rs".to_string(), measurement(105, 1.05))].into_iter().collect();
+        assert!(find_regressions(&baseline, &within, 10.0).is_empty());
+        let beyond = vec![("a.rs".to_string(), measurement(120, 2.0))].into_iter().collect();
+        assert_eq!(find_regressions(&baseline, &beyond, 10.0).len(), 2);
+    }
+
+    #[test]
+    fn short_phases_are_not_compared() {
+        let baseline = vec![("a.rs".to_string(), measurement(100, 0.1))].into_iter().collect();
+        let current = vec![("a.rs".to_string(), measurement(100, 0.3))].into_iter().collect();
+        assert!(find_regressions(&baseline, &current, 10.0).is_empty());
+    }
+}
@@ -9,6 +9,8 @@ pub mod commandline;
 use config_crate::{Config, Environment, File};
 use self::commandline::CommandLine;
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 use serde::Deserialize;
 
@@ -18,11 +20,13 @@ pub struct Optimizations {
     pub inline_constant_functions: bool,
     pub delete_unused_predicates: bool,
     pub optimize_folding: bool,
+    pub fold_constants: bool,
     pub remove_empty_if: bool,
     pub purify_vars: bool,
     pub fix_quantifiers: bool,
     pub remove_unused_vars: bool,
     pub remove_trivial_assertions: bool,
+    pub remove_dead_code: bool,
     pub clean_cfg: bool,
 }
 
@@ -32,11 +36,13 @@ impl Optimizations {
             inline_constant_functions: false,
             delete_unused_predicates: false,
             optimize_folding: false,
+            fold_constants: false,
             remove_empty_if: false,
             purify_vars: false,
             fix_quantifiers: false,
             remove_unused_vars: false,
             remove_trivial_assertions: false,
+            remove_dead_code: false,
             clean_cfg: false,
         }
     }
@@ -46,11 +52,15 @@ impl Optimizations {
             inline_constant_functions: true,
             delete_unused_predicates: true,
             optimize_folding: true,
+            // Changes the encoding of every method, so it must be enabled explicitly.
+            fold_constants: false,
             remove_empty_if: true,
             purify_vars: true,
             fix_quantifiers: true,
             remove_unused_vars: true,
             remove_trivial_assertions: true,
+            // Removes statements from every method, so it must be enabled explicitly.
+            remove_dead_code: false,
             clean_cfg: true,
         }
     }
@@ -67,6 +77,8 @@ lazy_static! {
         settings.set_default("check_foldunfold_state", false).unwrap();
         settings.set_default("check_overflows", false).unwrap();
         settings.set_default("check_panics", true).unwrap();
+        settings.set_default("infer_loop_invariants", false).unwrap();
+        settings.set_default("panic_freedom", false).unwrap();
         settings.set_default("encode_unsigned_num_constraint", false).unwrap();
         settings.set_default("simplify_encoding", true).unwrap();
         settings.set_default("log_dir", "./log/").unwrap();
@@ -75,6 +87,7 @@ lazy_static! {
         settings.set_default("max_log_file_name_length", 60).unwrap();
         settings.set_default("dump_path_ctxt_in_debug_info", false).unwrap();
         settings.set_default("dump_reborrowing_dag_in_debug_info", false).unwrap();
+        settings.set_default("dump_reborrowing_dags", false).unwrap();
         settings.set_default("dump_borrowck_info", false).unwrap();
         settings.set_default("dump_viper_program", false).unwrap();
         settings.set_default("foldunfold_state_filter", "").unwrap();
@@ -88,12 +101,47 @@ lazy_static! {
         settings.set_default("allow_unreachable_unsupported_code", false).unwrap();
         settings.set_default("no_verify", false).unwrap();
         settings.set_default("full_compilation", false).unwrap();
+        settings.set_default("runtime_checks", false).unwrap();
         settings.set_default("json_communication", false).unwrap();
         settings.set_default("json_communication", false).unwrap();
         settings.set_default("optimizations","all").unwrap();
         settings.set_default("intern_names", true).unwrap();
+        settings.set_default("clean_dead_borrow_tokens", false).unwrap();
         settings.set_default("enable_purification_optimization", false).unwrap();
-
+        settings.set_default("use_adt_snapshots", false).unwrap();
+        settings.set_default("inline_pure_functions", false).unwrap();
+        settings.set_default("pure_function_inlining_max_size", 10).unwrap();
+        settings.set_default("pure_function_inlining_max_depth", 2).unwrap();
+
+        settings.set_default("report_trusted_functions", false).unwrap();
+        settings.set_default("report_trusted_unsafe_functions", false).unwrap();
+        settings.set_default("check_vacuous_specs", false).unwrap();
+        settings.set_default("lint_specs", false).unwrap();
+        settings.set_default("progress_format", "none").unwrap();
+        settings.set_default("whole_program", false).unwrap();
+        settings.set_default("check_preamble", false).unwrap();
+        settings.set_default("incremental_cache", "").unwrap();
+        settings.set_default("cache_viper_results", false).unwrap();
+        settings.set_default("profile_quantifiers", false).unwrap();
+        settings.set_default("encoding_memory_limit", 0).unwrap();
+        settings.set_default("chrome_trace", "").unwrap();
+        settings.set_default("batch_report", "").unwrap();
+        settings.set_default("check_drop_obligations", false).unwrap();
+        settings.set_default("print_contract", "").unwrap();
+        settings.set_default("havoc_foreign_calls", false).unwrap();
+        settings.set_default("dump_vir_passes", "").unwrap();
+        settings.set_default("group_errors_by_cause", false).unwrap();
+        settings.set_default("check_pure_function_totality", false).unwrap();
+        settings.set_default("max_type_depth", 50).unwrap();
+        settings.set_default("max_type_size", 500).unwrap();
+        settings.set_default("seed_sweep_runs", 0).unwrap();
+        settings.set_default::<Vec<String>>("portfolio_backends", vec![]).unwrap();
+        settings.set_default("encode_only", false).unwrap();
+        settings.set_default("encoding_statistics", "").unwrap();
+        settings.set_default("export_obligations", "").unwrap();
+        settings.set_default("check_const_evaluation", false).unwrap();
+        settings.set_default("trusted_runtime_checks", false).unwrap();
+        settings.set_default("profile_quantifiers_top", 10).unwrap();
         settings.set_default("print_desugared_specs", false).unwrap();
         settings.set_default("print_typeckd_specs", false).unwrap();
         settings.set_default("print_collected_verification_items", false).unwrap();
@@ -107,10 +155,12 @@ lazy_static! {
         settings.set_default::<Vec<String>>("delete_basic_blocks", vec![]).unwrap();
 
 
-        // 2. Override with the optional TOML file "Prusti.toml" (if there is any)
-        settings.merge(
-            File::with_name("Prusti.toml").required(false)
-        ).unwrap();
+        // 2. Override with the optional TOML files "Prusti.toml" (if there are any)
+        for path in prusti_toml_paths() {
+            settings.merge(
+                File::from(path).required(false)
+            ).unwrap();
+        }
 
         // 3. Override with an optional TOML file specified by the `PRUSTI_CONFIG` env variable
         settings.merge(
@@ -131,6 +181,37 @@ lazy_static! {
     });
 }
 
+/// The `Prusti.toml` files to merge, from the lowest to the highest priority:
+/// the one in the current directory, the one in the root of the Cargo
+/// workspace and the one in the root of the crate being verified.
+fn prusti_toml_paths() -> Vec<PathBuf> {
+    prusti_toml_paths_for(env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from))
+}
+
+fn prusti_toml_paths_for(crate_root: Option<PathBuf>) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(".")];
+    if let Some(crate_root) = crate_root {
+        if let Some(workspace_root) = find_workspace_root(&crate_root) {
+            dirs.push(workspace_root);
+        }
+        dirs.push(crate_root);
+    }
+    dirs.into_iter().map(|dir| dir.join("Prusti.toml")).collect()
+}
+
+/// Find the closest ancestor of `crate_root` (or `crate_root` itself) whose
+/// `Cargo.toml` declares a workspace.
+fn find_workspace_root(crate_root: &Path) -> Option<PathBuf> {
+    crate_root
+        .ancestors()
+        .find(|dir| {
+            fs::read_to_string(dir.join("Cargo.toml")).map_or(false, |manifest| {
+                manifest.lines().any(|line| line.trim() == "[workspace]")
+            })
+        })
+        .map(Path::to_path_buf)
+}
+
 /// Return vector of arguments filtered out by prefix
 pub fn get_filtered_args() -> Vec<String> {
     CommandLine::with_prefix("-P")
@@ -177,7 +258,20 @@ pub fn viper_backend() -> String {
 
 /// Should we check absence of panics?
 pub fn check_panics() -> bool {
-    read_setting("check_panics")
+    read_setting("check_panics") || panic_freedom()
+}
+
+/// For loops without `body_invariant!`, use the comparison evaluated by the
+/// loop guard as loop invariant.
+pub fn infer_loop_invariants() -> bool {
+    read_setting("infer_loop_invariants")
+}
+
+/// Prove that the crate cannot panic: implies `check_panics` and
+/// `check_overflows`, and additionally checks that the receivers of
+/// `unwrap` and `expect` calls are `Some`/`Ok`.
+pub fn panic_freedom() -> bool {
+    read_setting("panic_freedom")
 }
 
 /// Should we simplify the encoding before passing it to Viper?
@@ -211,6 +305,12 @@ pub fn dump_reborrowing_dag_in_debug_info() -> bool {
     read_setting("dump_reborrowing_dag_in_debug_info")
 }
 
+/// Should we dump each reborrowing DAG that is expired by fold-unfold as a
+/// separate Graphviz file?
+pub fn dump_reborrowing_dags() -> bool {
+    read_setting("dump_reborrowing_dags")
+}
+
 /// Should we dump borrowck info?
 pub fn dump_borrowck_info() -> bool {
     read_setting("dump_borrowck_info")
@@ -226,6 +326,13 @@ pub fn foldunfold_state_filter() -> String {
     read_setting("foldunfold_state_filter")
 }
 
+/// The folder in which the VIR encoding of each item is written after each
+/// pass (CFG construction, fold-unfold, simplification and lowering). An empty
+/// string disables the dumps.
+pub fn dump_vir_passes() -> String {
+    read_setting("dump_vir_passes")
+}
+
 /// In which folder should we sore log/dumps?
 pub fn log_dir() -> String {
     read_setting("log_dir")
@@ -233,7 +340,7 @@ pub fn log_dir() -> String {
 
 /// Check binary operations for overflows
 pub fn check_overflows() -> bool {
-    read_setting("check_overflows")
+    read_setting("check_overflows") || panic_freedom()
 }
 
 /// Encode (and check) that unsigned integers are non-negative.
@@ -246,6 +353,14 @@ pub fn contracts_lib() -> String {
     read_setting("contracts_lib")
 }
 
+/// Memoize the verdicts of the backends for identical Viper programs within
+/// one JVM, e.g. in a long-running Prusti server. The key of a program is
+/// computed by pretty-printing it, which is only worth it if the same
+/// programs are verified repeatedly.
+pub fn cache_viper_results() -> bool {
+    read_setting("cache_viper_results")
+}
+
 /// Get extra JVM arguments
 pub fn extra_jvm_args() -> Vec<String> {
     read_setting("extra_jvm_args")
@@ -354,7 +469,9 @@ pub fn verify_only_basic_block_path() -> Vec<String> {
     read_setting("verify_only_basic_block_path")
 }
 
-/// Which optimizations should be enabled
+/// Which optimizations should be enabled. `all` enables all of them except
+/// `fold_constants` and `remove_dead_code`, which have to be listed
+/// explicitly.
 pub fn optimizations() -> Optimizations {
     let optimizations_string = read_setting::<String>("optimizations");
 
@@ -367,11 +484,13 @@ pub fn optimizations() -> Optimizations {
             "inline_constant_functions" => opt.inline_constant_functions = true,
             "delete_unused_predicates" => opt.delete_unused_predicates = true,
             "optimize_folding" => opt.optimize_folding = true,
+            "fold_constants" => opt.fold_constants = true,
             "remove_empty_if" => opt.remove_empty_if = true,
             "purify_vars" => opt.purify_vars = true,
             "fix_quantifiers" => opt.fix_quantifiers = true,
             "remove_unused_vars" => opt.remove_unused_vars = true,
             "remove_trivial_assertions" => opt.remove_trivial_assertions = true,
+            "remove_dead_code" => opt.remove_dead_code = true,
             "clean_cfg" => opt.clean_cfg = true,
             _ => warn!("Ignoring Unkown optimization '{}'", trimmed)
         }
@@ -388,6 +507,30 @@ pub fn enable_purification_optimization() -> bool {
     read_setting("enable_purification_optimization")
 }
 
+/// Encode all supported structs, enums and tuples as snapshot domains with
+/// constructor and selector axioms, even if they do not implement structural
+/// equality. This makes equality and pure function arguments of such types
+/// independent of the heap.
+pub fn use_adt_snapshots() -> bool {
+    read_setting("use_adt_snapshots")
+}
+
+/// Inline the bodies of small pure functions at their call sites.
+pub fn inline_pure_functions() -> bool {
+    read_setting("inline_pure_functions")
+}
+
+/// The maximal number of expression nodes in the body of a pure function that
+/// is inlined by ``INLINE_PURE_FUNCTIONS``.
+pub fn pure_function_inlining_max_size() -> usize {
+    read_setting("pure_function_inlining_max_size")
+}
+
+/// How many levels of nested calls are inlined by ``INLINE_PURE_FUNCTIONS``.
+pub fn pure_function_inlining_max_depth() -> usize {
+    read_setting("pure_function_inlining_max_depth")
+}
+
 /// Replace the given basic blocks with ``assume false``.
 pub fn delete_basic_blocks() -> Vec<String> {
     read_setting("delete_basic_blocks")
@@ -404,6 +547,210 @@ pub fn allow_unreachable_unsupported_code() -> bool {
     read_setting("allow_unreachable_unsupported_code")
 }
 
+/// At the end of the run, list the trusted functions, the verified functions
+/// that call them and the number of postconditions that were assumed.
+pub fn report_trusted_functions() -> bool {
+    read_setting("report_trusted_functions")
+}
+
+/// Before the verification, list the functions marked with
+/// `#[trusted_unsafe]`, whose unsafe code is not verified.
+pub fn report_trusted_unsafe_functions() -> bool {
+    read_setting("report_trusted_unsafe_functions")
+}
+
+/// Warn about functions whose precondition is unsatisfiable and about
+/// postconditions that do not mention the result or the arguments.
+pub fn check_vacuous_specs() -> bool {
+    read_setting("check_vacuous_specs")
+}
+
+/// Before the verification, warn about contracts that are likely wrong, e.g.
+/// postconditions that repeat a precondition or that only mention arguments
+/// moved into the function.
+pub fn lint_specs() -> bool {
+    read_setting("lint_specs")
+}
+
+/// How to print the progress of the verification: ``none``, ``text`` (one
+/// line per event) or ``json`` (one JSON object per line).
+pub fn progress_format() -> String {
+    read_setting("progress_format")
+}
+
+/// Verify only the functions that are reachable from `main`, callees first.
+/// Functions without a contract are verified for absence of panics (and
+/// overflows, if enabled) like any other function.
+pub fn whole_program() -> bool {
+    read_setting("whole_program")
+}
+
+/// Check only the crate-level preamble: the type predicates, the pure
+/// functions and the contracts of the procedures (encoded as functions), but
+/// not the bodies of the procedures, which are not encoded at all. This is a
+/// fast way to check that the specifications are well-formed.
+pub fn check_preamble() -> bool {
+    read_setting("check_preamble")
+}
+
+/// The file in which the hashes of the successfully verified items are
+/// stored. When set, items whose MIR, contract and dependencies did not
+/// change since their last successful verification are not verified again.
+/// An empty string disables the cache.
+pub fn incremental_cache() -> String {
+    read_setting("incremental_cache")
+}
+
+/// Let Z3 trace the instantiations of the quantifiers and report the
+/// quantifiers that were instantiated the most, to help finding matching
+/// loops. Only supported by Silicon. The trace is written to
+/// `quantifier_trace_file()`.
+pub fn profile_quantifiers() -> bool {
+    read_setting("profile_quantifiers")
+}
+
+/// The number of quantifiers reported by `profile_quantifiers`.
+pub fn profile_quantifiers_top() -> usize {
+    read_setting("profile_quantifiers_top")
+}
+
+/// The file to which Z3 writes its trace when `profile_quantifiers` is
+/// enabled.
+pub fn quantifier_trace_file() -> PathBuf {
+    PathBuf::from(log_dir()).join("z3").join("quantifier_trace.log")
+}
+
+/// The maximum amount of memory, in megabytes, that the encoding of a
+/// single procedure may use. The encoding of a procedure that exceeds it is
+/// aborted with an error, and the other procedures are still verified.
+/// `0` disables the limit. The memory is measured only on Linux.
+pub fn encoding_memory_limit() -> usize {
+    read_setting("encoding_memory_limit")
+}
+
+/// Write a profile of the run in the Chrome trace format to this file. The
+/// profile can be opened in `chrome://tracing` or in Perfetto, and contains
+/// the phases of the compilation, the encoding of each item and the sections
+/// timed by the stopwatches. An empty string disables the profile.
+pub fn chrome_trace() -> String {
+    read_setting("chrome_trace")
+}
+
+/// Append a one-line summary of the verification of each crate to this file.
+/// `cargo prusti` uses it to print a combined report of all the crates of a
+/// workspace. An empty string disables the summary.
+pub fn batch_report() -> String {
+    read_setting("batch_report")
+}
+
+/// When enabled, dropping a value whose type implements `Drop` checks the
+/// precondition of its `drop` method, so that the protocol of a resource
+/// (e.g. that a guard is released before going out of scope) can be proven.
+pub fn check_drop_obligations() -> bool {
+    read_setting("check_drop_obligations")
+}
+
+/// Print the contract that the encoder uses for the local item with this path
+/// (e.g. `Stack::push` or `<Stack as Container>::push`), including the
+/// specifications inherited from traits and the history invariants. An empty
+/// string disables the printing. Like the other messages for the user, the
+/// contract is not printed when `quiet` is enabled.
+pub fn print_contract() -> String {
+    read_setting("print_contract")
+}
+
+/// When enabled, calls to foreign (e.g. `extern "C"`) functions without an
+/// `extern_spec` are modelled conservatively: the result and everything
+/// reachable from `&mut` and `*mut` arguments are havocked.
+pub fn havoc_foreign_calls() -> bool {
+    read_setting("havoc_foreign_calls")
+}
+
+/// When enabled, the verification errors of a function are grouped by their
+/// root cause: each failing assertion is assumed in turn and the program is
+/// verified again, and the errors that disappear are reported as
+/// consequences of the assumed one. Each re-verification is a backend run.
+pub fn group_errors_by_cause() -> bool {
+    read_setting("group_errors_by_cause")
+}
+
+/// When enabled, the recursive calls of pure functions have to terminate: the
+/// integer arguments of a call of a pure function to itself have to decrease
+/// lexicographically while staying non-negative, and pure functions cannot
+/// call each other recursively. Panics and overflows in pure functions are
+/// checked independently of this flag.
+pub fn check_pure_function_totality() -> bool {
+    read_setting("check_pure_function_totality")
+}
+
+/// The maximum nesting depth of a type whose predicate is encoded (e.g.
+/// `Option<Box<(u32, S)>>` has depth 4 if the fields of `S` are primitive).
+/// Recursive types count one unfolding of their recursion. 0 disables the
+/// limit.
+pub fn max_type_depth() -> usize {
+    read_setting("max_type_depth")
+}
+
+/// The maximum number of distinct types that are reachable from the fields of
+/// a type whose predicate is encoded. 0 disables the limit.
+pub fn max_type_size() -> usize {
+    read_setting("max_type_size")
+}
+
+/// The number of additional runs of the verifier, each with a different
+/// random seed of Z3, that are used to detect brittle proofs: the functions
+/// whose verdict depends on the seed and programs whose verification time
+/// varies wildly are reported. Only supported by Silicon. 0 disables the
+/// sweep.
+pub fn seed_sweep_runs() -> u64 {
+    read_setting("seed_sweep_runs")
+}
+
+/// Additional backend configurations that verify the program together with
+/// the default one, e.g. `["Carbon", "Silicon --numberOfParallelVerifiers 1"]`:
+/// the name of the backend, optionally followed by extra arguments for it. An
+/// item is verified as soon as one configuration verifies it. With a server,
+/// the configurations run in parallel. Without a server, they run one after
+/// the other and those that use a backend other than `viper_backend()` are
+/// ignored. Empty disables the portfolio.
+pub fn portfolio_backends() -> Vec<String> {
+    read_setting("portfolio_backends")
+}
+
+/// Encode the program to Viper without running the backend. The items are
+/// not reported as verified.
+pub fn encode_only() -> bool {
+    read_setting("encode_only")
+}
+
+/// The path of a file to which the size of the encoded Viper program, the
+/// peak memory and the time of each phase of the verification of each crate
+/// are appended as JSON lines, e.g. for `prusti-bench`. Empty disables the
+/// statistics.
+pub fn encoding_statistics() -> String {
+    read_setting("encoding_statistics")
+}
+
+/// The path of a file to which the proof obligations of the encoded
+/// program are appended, so that other tools can use them without parsing
+/// Viper. Empty disables the export. Each verified crate appends a line with
+/// a JSON object with the `crate` name and a list of `obligations`, each with:
+/// - `kind`: `precondition`, `postcondition`, `loop_invariant`, `overflow`,
+///   `assertion`, `panic`, `refinement` or `other`;
+/// - `item`: the Viper method or function that checks the obligation;
+/// - `spans`: the Rust source locations, each with a `location` string and
+///   1-based `start_line`, `start_column`, `end_line` and `end_column`;
+/// - `formula`: the formula that has to hold, in Viper syntax.
+pub fn export_obligations() -> String {
+    read_setting("export_obligations")
+}
+
+/// Check that the encoding of the `const fn` calls that initialize the
+/// constants of the crate agrees with the values computed by the compiler.
+pub fn check_const_evaluation() -> bool {
+    read_setting("check_const_evaluation")
+}
+
 /// Skip the verification
 pub fn no_verify() -> bool {
     read_setting("no_verify")
@@ -414,7 +761,81 @@ pub fn full_compilation() -> bool {
     read_setting("full_compilation")
 }
 
+/// When `full_compilation` is enabled, compile the preconditions and
+/// postconditions that can be evaluated at runtime to `debug_assert!`-like
+/// checks in the generated binary. Postconditions of functions that contain
+/// `return` or `?` are not checked.
+pub fn runtime_checks() -> bool {
+    read_setting("runtime_checks")
+}
+
+/// Like `runtime_checks`, but only for the trusted functions, whose
+/// contracts are assumed by the verification. The body of a trusted function
+/// is wrapped, so that its postconditions are also checked when it returns
+/// early.
+pub fn trusted_runtime_checks() -> bool {
+    read_setting("trusted_runtime_checks")
+}
+
 /// Intern Viper identifiers to shorten them when possible.
 pub fn intern_names() -> bool {
     read_setting("intern_names")
 }
+
+/// When borrows expire, inhale the dead borrow tokens of adjacent magic wand
+/// applications with a single statement instead of one per application.
+pub fn clean_dead_borrow_tokens() -> bool {
+    read_setting("clean_dead_borrow_tokens")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A workspace with a member crate, each with a `Prusti.toml`.
+    fn create_workspace() -> (PathBuf, PathBuf) {
+        let workspace_root = env::temp_dir().join(format!("prusti-config-{}", uuid::Uuid::new_v4()));
+        let crate_root = workspace_root.join("member");
+        fs::create_dir_all(&crate_root).unwrap();
+        fs::write(workspace_root.join("Cargo.toml"), "[workspace]\nmembers = [\"member\"]\n").unwrap();
+        fs::write(
+            workspace_root.join("Prusti.toml"),
+            "check_overflows = false\ncheck_panics = false\n",
+        ).unwrap();
+        fs::write(crate_root.join("Cargo.toml"), "[package]\nname = \"member\"\n").unwrap();
+        fs::write(crate_root.join("Prusti.toml"), "check_overflows = true\n").unwrap();
+        (workspace_root, crate_root)
+    }
+
+    #[test]
+    fn workspace_root_is_found_from_a_member() {
+        let (workspace_root, crate_root) = create_workspace();
+        assert_eq!(find_workspace_root(&crate_root), Some(workspace_root.clone()));
+        assert_eq!(find_workspace_root(&workspace_root), Some(workspace_root.clone()));
+        fs::remove_dir_all(workspace_root).unwrap();
+    }
+
+    #[test]
+    fn crate_prusti_toml_overrides_the_workspace_one() {
+        let (workspace_root, crate_root) = create_workspace();
+        let paths = prusti_toml_paths_for(Some(crate_root.clone()));
+        assert_eq!(paths, vec![
+            PathBuf::from("./Prusti.toml"),
+            workspace_root.join("Prusti.toml"),
+            crate_root.join("Prusti.toml"),
+        ]);
+
+        let mut settings = Config::default();
+        for path in paths {
+            settings.merge(File::from(path).required(false)).unwrap();
+        }
+        assert!(settings.get::<bool>("check_overflows").unwrap());
+        assert!(!settings.get::<bool>("check_panics").unwrap());
+        fs::remove_dir_all(workspace_root).unwrap();
+    }
+
+    #[test]
+    fn only_the_current_directory_without_a_crate() {
+        assert_eq!(prusti_toml_paths_for(None), vec![PathBuf::from("./Prusti.toml")]);
+    }
+}
@@ -20,6 +20,7 @@ extern crate lazy_static;
 extern crate regex;
 #[macro_use]
 extern crate serde;
+extern crate serde_json;
 extern crate uuid;
 extern crate viper;
 
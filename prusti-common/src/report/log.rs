@@ -71,3 +71,46 @@ pub fn report_with_writer<S: ToString, F: FnOnce(&mut Box<dyn Write>)>(
     func(&mut writer);
     writer.flush().map_err(|e| panic!("{}", e)).ok().unwrap();
 }
+
+/// Write the VIR encoding of `item` after the pass `pass` to the folder given
+/// by `dump_vir_passes`, if any. The file names only depend on the item and on
+/// the pass, so that the dumps of two runs can be compared with `diff`.
+pub fn report_vir_pass<S: ToString>(item: &str, pass_index: usize, pass: &str, data: S) {
+    let dump_dir = config::dump_vir_passes();
+    if dump_dir.is_empty() {
+        return;
+    }
+    let path = vir_pass_path(PathBuf::from(dump_dir), item, pass_index, pass);
+    fs::create_dir_all(path.parent().unwrap())
+        .and_then(|_| fs::write(&path, data.to_string()))
+        .map_err(|e| panic!("{}", e))
+        .ok();
+}
+
+/// The file `<dump_dir>/<item>/<pass_index>_<pass>.vpr`, in which the
+/// characters of the item name that are not valid in file names are
+/// replaced by `_`.
+fn vir_pass_path(dump_dir: PathBuf, item: &str, pass_index: usize, pass: &str) -> PathBuf {
+    let item_dir: String = item
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '$' { c } else { '_' })
+        .collect();
+    dump_dir.join(item_dir).join(format!("{}_{}.vpr", pass_index, pass))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vir_pass_paths_are_stable_file_names() {
+        assert_eq!(
+            vir_pass_path(PathBuf::from("dumps"), "m_foo$bar::<T>", 2, "foldunfold"),
+            PathBuf::from("dumps/m_foo$bar___T_/2_foldunfold.vpr"),
+        );
+        assert_eq!(
+            vir_pass_path(PathBuf::from("dumps"), "../secret", 1, "cfg"),
+            PathBuf::from("dumps/___secret/1_cfg.vpr"),
+        );
+    }
+}
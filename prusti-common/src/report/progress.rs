@@ -0,0 +1,72 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! This module defines progress events, which report the status of each
+//! verified item while a crate is being verified.
+//!
+//! By default the events are printed to stderr in the format selected by
+//! `config::progress_format()`. Tools that embed Prusti can instead receive
+//! them with a callback installed by `set_listener`.
+
+use crate::config;
+use std::sync::Mutex;
+
+/// The status of a verified item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    /// The encoding of the item started.
+    Started,
+    /// The item has been encoded and will be verified.
+    Encoded,
+    /// The item verified.
+    Verified,
+    /// The encoding or the verification of the item failed.
+    Failed,
+    /// The item is not verified, because it is trusted or did not change
+    /// since its last successful verification.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub status: ItemStatus,
+    /// The absolute path of the item.
+    pub item: String,
+    /// The source location of the item.
+    pub location: String,
+    /// How many items reached this status so far, including this one.
+    pub done: usize,
+    /// How many items will reach this status at most.
+    pub total: usize,
+}
+
+type Listener = Box<dyn Fn(&ProgressEvent) + Send>;
+
+lazy_static! {
+    static ref LISTENER: Mutex<Option<Listener>> = Mutex::new(None);
+}
+
+/// Send the progress events to `listener` instead of printing them.
+pub fn set_listener<F: Fn(&ProgressEvent) + Send + 'static>(listener: F) {
+    *LISTENER.lock().unwrap() = Some(Box::new(listener));
+}
+
+/// Report a progress event.
+pub fn report(event: ProgressEvent) {
+    if let Some(listener) = LISTENER.lock().unwrap().as_ref() {
+        listener(&event);
+        return;
+    }
+    match config::progress_format().as_str() {
+        "json" => eprintln!("{}", serde_json::to_string(&event).unwrap()),
+        "text" => eprintln!(
+            "[{}/{}] {:?}: {} ({})",
+            event.done, event.total, event.status, event.item, event.location
+        ),
+        _ => {}
+    }
+}
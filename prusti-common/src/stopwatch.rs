@@ -9,6 +9,7 @@ use std::{
     marker::PhantomData,
     time::{Duration, Instant},
 };
+use tracing::Span;
 
 pub trait LogLevel {
     fn log_start(prefix: &String, name: &String);
@@ -19,6 +20,10 @@ pub struct Stopwatch<Level: LogLevel> {
     start_time: Instant,
     prefix: String,
     section_name: String,
+    /// The tracing span of the current section. It lasts from the start to
+    /// the end of the section, but it is only entered while logging, because
+    /// the sections of different stopwatches may overlap.
+    span: Span,
     is_finished: bool,
     level: PhantomData<Level>,
 }
@@ -32,12 +37,14 @@ impl Stopwatch<log_level::Info> {
 
 impl<Level: LogLevel> Stopwatch<Level> {
     fn _start(prefix: String, section_name: String) -> Self {
-        Level::log_start(&prefix, &section_name);
+        let span = section_span(&prefix, &section_name);
+        span.in_scope(|| Level::log_start(&prefix, &section_name));
 
         Self {
             start_time: Instant::now(),
             prefix,
             section_name,
+            span,
             is_finished: false,
             level: PhantomData,
         }
@@ -46,13 +53,16 @@ impl<Level: LogLevel> Stopwatch<Level> {
     /// Finishes up the current section, logging the time taken, and starts timing the next one.
     pub fn start_next<S: ToString>(&mut self, section_name: S) {
         let now = Instant::now();
-        Level::log_finish(
-            &self.prefix,
-            &self.section_name,
-            now.duration_since(self.start_time),
-        );
+        self.span.in_scope(|| {
+            Level::log_finish(
+                &self.prefix,
+                &self.section_name,
+                now.duration_since(self.start_time),
+            )
+        });
         self.section_name = section_name.to_string();
-        Level::log_start(&self.prefix, &self.section_name);
+        self.span = section_span(&self.prefix, &self.section_name);
+        self.span.in_scope(|| Level::log_start(&self.prefix, &self.section_name));
         self.start_time = now;
     }
 
@@ -64,11 +74,28 @@ impl<Level: LogLevel> Stopwatch<Level> {
     }
 
     fn _finish(&mut self) {
-        Level::log_finish(&self.prefix, &self.section_name, self.start_time.elapsed());
+        self.span.in_scope(|| {
+            Level::log_finish(&self.prefix, &self.section_name, self.start_time.elapsed())
+        });
+        // Close the span of the section.
+        self.span = Span::none();
         self.is_finished = true;
     }
 }
 
+/// Create a tracing span for a section, so that the sections timed by
+/// stopwatches show up in the profiles of `config::chrome_trace()`.
+fn section_span(prefix: &str, section_name: &str) -> Span {
+    let domain = section_domain(prefix);
+    tracing::info_span!("section", domain, section = section_name)
+}
+
+/// The domain of a stopwatch from its log prefix, e.g. `prusti-viper` from
+/// `[prusti-viper] `.
+fn section_domain(prefix: &str) -> &str {
+    prefix.trim().trim_start_matches('[').trim_end_matches(']')
+}
+
 impl<Level: LogLevel> Drop for Stopwatch<Level> {
     fn drop(&mut self) {
         if !self.is_finished {
@@ -115,3 +142,109 @@ pub mod log_level {
     log_level!(Debug, debug, start_debug);
     log_level!(Trace, trace, start_trace);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{field, span, Event, Metadata, Subscriber};
+
+    #[test]
+    fn section_domains_are_taken_from_the_prefix() {
+        assert_eq!(section_domain("[prusti-viper] "), "prusti-viper");
+        assert_eq!(section_domain("[viper] "), "viper");
+        assert_eq!(section_domain(""), "");
+    }
+
+    /// Records the creation, entering, exiting and closing of the spans of
+    /// stopwatch sections.
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        sections: Arc<Mutex<Vec<String>>>,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl SpanRecorder {
+        fn push(&self, event: &str, id: &span::Id) {
+            let section = &self.sections.lock().unwrap()[id.into_u64() as usize - 1];
+            self.events.lock().unwrap().push(format!("{} {}", event, section));
+        }
+    }
+
+    struct SectionVisitor(String);
+
+    impl field::Visit for SectionVisitor {
+        fn record_debug(&mut self, field: &field::Field, value: &dyn fmt::Debug) {
+            if field.name() == "section" {
+                self.0 = format!("{:?}", value).trim_matches('"').to_string();
+            }
+        }
+    }
+
+    impl Subscriber for SpanRecorder {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &span::Attributes) -> span::Id {
+            let mut visitor = SectionVisitor(String::new());
+            attributes.record(&mut visitor);
+            let mut sections = self.sections.lock().unwrap();
+            sections.push(visitor.0);
+            let id = span::Id::from_u64(sections.len() as u64);
+            drop(sections);
+            self.push("new", &id);
+            id
+        }
+
+        fn record(&self, _span: &span::Id, _values: &span::Record) {}
+
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+        fn event(&self, _event: &Event) {}
+
+        fn enter(&self, span: &span::Id) {
+            self.push("enter", span);
+        }
+
+        fn exit(&self, span: &span::Id) {
+            self.push("exit", span);
+        }
+
+        fn try_close(&self, span: span::Id) -> bool {
+            self.push("close", &span);
+            true
+        }
+    }
+
+    #[test]
+    fn stopwatches_can_be_sent_to_other_threads() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Stopwatch<log_level::Info>>();
+    }
+
+    #[test]
+    fn overlapping_sections_are_recorded_as_spans() {
+        let recorder = SpanRecorder::default();
+        let events = recorder.events.clone();
+        tracing::subscriber::with_default(recorder, || {
+            let mut first = Stopwatch::start("prusti", "first");
+            let second = Stopwatch::start("viper", "second");
+            first.start_next("third");
+            second.finish();
+            first.finish();
+        });
+        let expected = vec![
+            "new first", "enter first", "exit first",
+            "new second", "enter second", "exit second",
+            "enter first", "exit first", "new third", "close first",
+            "enter third", "exit third",
+            "enter second", "exit second", "close second",
+            "enter third", "exit third", "close third",
+        ];
+        assert_eq!(*events.lock().unwrap(), expected);
+    }
+}
@@ -0,0 +1,216 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Transitive hashes of the nodes of a dependency graph, e.g. of the
+//! contracts of procedures that depend on the contracts they call.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+/// A node of a dependency graph.
+pub struct DepNode<N> {
+    /// A name that identifies the node across compilations.
+    pub name: String,
+    /// The hash of the content of the node itself.
+    pub hash: u64,
+    /// The nodes that the node depends on.
+    pub dependencies: Vec<N>,
+}
+
+/// Computes the hash of a node from its own hash and the hashes of all the
+/// nodes it transitively depends on. The nodes of a cycle, e.g. mutually
+/// recursive functions, are hashed together as a strongly connected
+/// component, so that their hashes do not depend on the node from which
+/// the cycle is entered.
+pub struct DepGraphHasher<N> {
+    nodes: HashMap<N, Rc<DepNode<N>>>,
+    hashes: HashMap<N, u64>,
+}
+
+impl<N: Copy + Eq + Hash> Default for DepGraphHasher<N> {
+    fn default() -> Self {
+        DepGraphHasher {
+            nodes: HashMap::new(),
+            hashes: HashMap::new(),
+        }
+    }
+}
+
+/// The state of Tarjan's algorithm.
+struct SccSearch<N> {
+    index: HashMap<N, usize>,
+    low_link: HashMap<N, usize>,
+    stack: Vec<N>,
+    on_stack: HashSet<N>,
+}
+
+impl<N: Copy + Eq + Hash> DepGraphHasher<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The transitive hash of `root`. `new_node` builds the nodes of the
+    /// graph; it is called at most once per node.
+    pub fn hash(&mut self, root: N, new_node: &mut dyn FnMut(N) -> DepNode<N>) -> u64 {
+        if let Some(&hash) = self.hashes.get(&root) {
+            return hash;
+        }
+        let mut search = SccSearch {
+            index: HashMap::new(),
+            low_link: HashMap::new(),
+            stack: vec![],
+            on_stack: HashSet::new(),
+        };
+        self.visit(root, new_node, &mut search);
+        self.hashes[&root]
+    }
+
+    fn node(&mut self, id: N, new_node: &mut dyn FnMut(N) -> DepNode<N>) -> Rc<DepNode<N>> {
+        self.nodes
+            .entry(id)
+            .or_insert_with(|| Rc::new(new_node(id)))
+            .clone()
+    }
+
+    fn visit(
+        &mut self,
+        id: N,
+        new_node: &mut dyn FnMut(N) -> DepNode<N>,
+        search: &mut SccSearch<N>,
+    ) {
+        let index = search.index.len();
+        search.index.insert(id, index);
+        search.low_link.insert(id, index);
+        search.stack.push(id);
+        search.on_stack.insert(id);
+
+        let node = self.node(id, new_node);
+        for &dependency in &node.dependencies {
+            if self.hashes.contains_key(&dependency) {
+                continue;
+            }
+            let low_link = if !search.index.contains_key(&dependency) {
+                self.visit(dependency, new_node, search);
+                search.low_link[&dependency]
+            } else if search.on_stack.contains(&dependency) {
+                search.index[&dependency]
+            } else {
+                continue;
+            };
+            if low_link < search.low_link[&id] {
+                search.low_link.insert(id, low_link);
+            }
+        }
+
+        if search.low_link[&id] == index {
+            let mut component = vec![];
+            loop {
+                let member = search.stack.pop().unwrap();
+                search.on_stack.remove(&member);
+                component.push(member);
+                if member == id {
+                    break;
+                }
+            }
+            self.hash_component(component);
+        }
+    }
+
+    /// Hash a strongly connected component whose dependencies outside of
+    /// the component are already hashed.
+    fn hash_component(&mut self, component: Vec<N>) {
+        let mut members: Vec<_> = component.iter().map(|id| self.nodes[id].clone()).collect();
+        members.sort_by(|a, b| a.name.cmp(&b.name));
+        let member_ids: HashSet<_> = component.iter().copied().collect();
+        let mut hasher = DefaultHasher::new();
+        for member in &members {
+            member.name.hash(&mut hasher);
+            member.hash.hash(&mut hasher);
+            for dependency in &member.dependencies {
+                if member_ids.contains(dependency) {
+                    self.nodes[dependency].name.hash(&mut hasher);
+                } else {
+                    self.hashes[dependency].hash(&mut hasher);
+                }
+            }
+        }
+        let component_hash = hasher.finish();
+        for id in component {
+            let mut hasher = DefaultHasher::new();
+            component_hash.hash(&mut hasher);
+            self.nodes[&id].name.hash(&mut hasher);
+            self.hashes.insert(id, hasher.finish());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hash every node of the graph, starting from the nodes in `order`.
+    fn hash_all(graph: &[(u64, Vec<usize>)], order: &[usize]) -> Vec<u64> {
+        let mut hasher = DepGraphHasher::new();
+        let mut new_node = |id: usize| DepNode {
+            name: format!("node{}", id),
+            hash: graph[id].0,
+            dependencies: graph[id].1.clone(),
+        };
+        for &id in order {
+            hasher.hash(id, &mut new_node);
+        }
+        (0..graph.len()).map(|id| hasher.hash(id, &mut new_node)).collect()
+    }
+
+    /// 0 -> 1 -> 2 -> 3 -> 1, and 4 is independent.
+    fn graph() -> Vec<(u64, Vec<usize>)> {
+        vec![
+            (10, vec![1]),
+            (11, vec![2]),
+            (12, vec![3]),
+            (13, vec![1]),
+            (14, vec![]),
+        ]
+    }
+
+    #[test]
+    fn cycle_hashes_do_not_depend_on_the_entry_node() {
+        let from_first = hash_all(&graph(), &[0]);
+        let from_cycle = hash_all(&graph(), &[2, 3, 1]);
+        assert_eq!(from_first, from_cycle);
+    }
+
+    #[test]
+    fn cycle_members_have_distinct_hashes() {
+        let hashes = hash_all(&graph(), &[0]);
+        assert_ne!(hashes[1], hashes[2]);
+        assert_ne!(hashes[2], hashes[3]);
+    }
+
+    #[test]
+    fn changes_in_a_cycle_propagate() {
+        let before = hash_all(&graph(), &[0]);
+        let mut changed = graph();
+        changed[3].0 = 99;
+        let after = hash_all(&changed, &[0]);
+        for id in 0..4 {
+            assert_ne!(before[id], after[id], "node {}", id);
+        }
+        assert_eq!(before[4], after[4]);
+    }
+
+    #[test]
+    fn changes_do_not_propagate_to_dependencies() {
+        let before = hash_all(&graph(), &[0]);
+        let mut changed = graph();
+        changed[0].0 = 99;
+        let after = hash_all(&changed, &[0]);
+        assert_ne!(before[0], after[0]);
+        assert_eq!(&before[1..], &after[1..]);
+    }
+}
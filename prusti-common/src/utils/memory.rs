@@ -0,0 +1,56 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fs;
+
+/// The resident memory of the current process in megabytes, or `None` if
+/// it cannot be measured on this platform.
+pub fn resident_memory_mb() -> Option<usize> {
+    read_process_status("VmRSS:")
+}
+
+/// The peak resident memory of the current process in megabytes, or `None`
+/// if it cannot be measured on this platform.
+pub fn peak_memory_mb() -> Option<usize> {
+    read_process_status("VmHWM:")
+}
+
+fn read_process_status(key: &str) -> Option<usize> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    parse_process_status(&status, key)
+}
+
+/// The memory in megabytes of the entry `key` of the content of
+/// `/proc/self/status`.
+fn parse_process_status(status: &str, key: &str) -> Option<usize> {
+    // The line has the form `VmRSS:    123456 kB`.
+    let line = status.lines().find(|line| line.starts_with(key))?;
+    let kilobytes: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes / 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = "Name:\tprusti-driver\n\
+        VmPeak:\t  409600 kB\n\
+        VmHWM:\t  307200 kB\n\
+        VmRSS:\t  204800 kB\n\
+        Threads:\t4\n";
+
+    #[test]
+    fn memory_is_parsed_from_the_status() {
+        assert_eq!(parse_process_status(STATUS, "VmRSS:"), Some(200));
+        assert_eq!(parse_process_status(STATUS, "VmHWM:"), Some(300));
+    }
+
+    #[test]
+    fn memory_is_missing_from_the_status() {
+        assert_eq!(parse_process_status("Name:\tprusti-driver\n", "VmRSS:"), None);
+        assert_eq!(parse_process_status("VmRSS:\tunknown kB\n", "VmRSS:"), None);
+    }
+}
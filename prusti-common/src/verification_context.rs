@@ -7,6 +7,7 @@
 use crate::config;
 use std::{fs::create_dir_all, path::PathBuf};
 use crate::verification_service::ViperBackendConfig;
+use crate::vir::ViperPreamble;
 use viper::{self, AstFactory, VerificationBackend, Viper};
 
 /// A verifier builder is an object that lives entire program's
@@ -16,6 +17,9 @@ use viper::{self, AstFactory, VerificationBackend, Viper};
 /// The main motivation for having a builder is to be able to cache the JVM
 /// initialization.
 pub struct VerifierBuilder {
+    /// The builtins shared by all programs verified with this JVM. They are
+    /// declared before `viper` to release their global references first.
+    preamble: ViperPreamble,
     viper: Viper,
 }
 
@@ -26,13 +30,21 @@ impl VerifierBuilder {
 
     pub fn new_with_backend(backend: VerificationBackend) -> Self {
         Self {
-            viper: Viper::new_with_args(config::extra_jvm_args(), backend),
+            preamble: ViperPreamble::new(),
+            viper: {
+                let viper = Viper::new_with_args(config::extra_jvm_args(), backend);
+                if config::cache_viper_results() {
+                    viper.with_verification_cache()
+                } else {
+                    viper
+                }
+            },
         }
     }
 
     pub fn new_verification_context(&self) -> VerificationContext {
         let verification_ctx = self.viper.new_verification_context();
-        VerificationContext::new(verification_ctx)
+        VerificationContext::new(verification_ctx, &self.preamble)
     }
 }
 
@@ -48,11 +60,20 @@ impl Default for VerifierBuilder {
 /// thread from the JVM when the verification context goes out of scope.
 pub struct VerificationContext<'v> {
     verification_ctx: viper::VerificationContext<'v>,
+    preamble: &'v ViperPreamble,
 }
 
 impl<'v> VerificationContext<'v> {
-    fn new(verification_ctx: viper::VerificationContext<'v>) -> Self {
-        VerificationContext { verification_ctx }
+    fn new(
+        verification_ctx: viper::VerificationContext<'v>,
+        preamble: &'v ViperPreamble,
+    ) -> Self {
+        VerificationContext { verification_ctx, preamble }
+    }
+
+    /// The builtins shared by all verification contexts of the builder.
+    pub fn preamble(&self) -> &'v ViperPreamble {
+        self.preamble
     }
 
     pub fn new_viper_verifier(
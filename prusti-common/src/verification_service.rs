@@ -25,7 +25,14 @@ pub struct ViperBackendConfig {
 
 impl Default for ViperBackendConfig {
     fn default() -> Self {
-        let backend = VerificationBackend::from_str(&config::viper_backend());
+        Self::new(VerificationBackend::from_str(&config::viper_backend()))
+    }
+}
+
+impl ViperBackendConfig {
+    /// The configuration of the given backend, with the arguments set by the
+    /// Prusti configuration.
+    pub fn new(backend: VerificationBackend) -> Self {
         let mut verifier_args = config::extra_verifier_args();
         match backend {
             VerificationBackend::Silicon => {
@@ -45,9 +52,66 @@ impl Default for ViperBackendConfig {
                 ]);
             }
         }
-        Self {
+        let mut backend_config = Self {
             backend,
             verifier_args,
+        };
+        if backend == VerificationBackend::Silicon && config::profile_quantifiers() {
+            // The parallel Z3 instances would write to the same trace.
+            backend_config.verifier_args.extend(vec![
+                "--numberOfParallelVerifiers".to_string(),
+                "1".to_string(),
+            ]);
+            backend_config.add_z3_args(&format!(
+                "trace=true proof=true trace_file_name={}",
+                config::quantifier_trace_file().display(),
+            ));
         }
+        backend_config
+    }
+
+    /// Pass `args` to Z3 in addition to the arguments that are already
+    /// passed to it. Silicon accepts only one `--z3Args` option, so the
+    /// arguments are appended to its value if it is already set.
+    pub fn add_z3_args(&mut self, args: &str) {
+        let value_index = self.verifier_args
+            .iter()
+            .position(|arg| arg == "--z3Args")
+            .map(|index| index + 1);
+        match value_index.and_then(|index| self.verifier_args.get_mut(index)) {
+            Some(value) => {
+                *value = format!("\"{} {}\"", value.trim_matches('"'), args);
+            }
+            None => {
+                self.verifier_args.extend(vec![
+                    "--z3Args".to_string(),
+                    format!("\"{}\"", args),
+                ]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn z3_args_are_passed_in_a_single_option() {
+        let mut backend_config = ViperBackendConfig {
+            backend: VerificationBackend::Silicon,
+            verifier_args: vec!["--logLevel".to_string(), "ERROR".to_string()],
+        };
+        backend_config.add_z3_args("trace=true");
+        backend_config.add_z3_args("smt.random_seed=1 sat.random_seed=1");
+        assert_eq!(
+            backend_config.verifier_args,
+            vec![
+                "--logLevel".to_string(),
+                "ERROR".to_string(),
+                "--z3Args".to_string(),
+                "\"trace=true smt.random_seed=1 sat.random_seed=1\"".to_string(),
+            ],
+        );
     }
 }
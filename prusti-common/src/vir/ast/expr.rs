@@ -621,6 +621,26 @@ impl Expr {
         }
     }
 
+    /// Is the expression well-defined in every state? Removing such an
+    /// expression does not remove a check of the backend, such as the
+    /// permission to read a field or a non-zero divisor.
+    pub fn is_well_defined_everywhere(&self) -> bool {
+        match self {
+            Expr::Local(..) | Expr::Const(..) => true,
+            Expr::UnaryOp(_, ref arg, _) => arg.is_well_defined_everywhere(),
+            Expr::BinOp(BinOpKind::Div, ..) | Expr::BinOp(BinOpKind::Mod, ..) => false,
+            Expr::BinOp(_, ref left, ref right, _) => {
+                left.is_well_defined_everywhere() && right.is_well_defined_everywhere()
+            }
+            Expr::Cond(ref guard, ref then_expr, ref else_expr, _) => {
+                guard.is_well_defined_everywhere()
+                    && then_expr.is_well_defined_everywhere()
+                    && else_expr.is_well_defined_everywhere()
+            }
+            _ => false,
+        }
+    }
+
     pub fn is_variant(&self) -> bool {
         matches!(self, Expr::Variant(..))
     }
@@ -250,6 +250,12 @@ impl fmt::Display for EnumVariantIndex {
 
 impl EnumPredicate {
     /// Construct an expression that represents the body of this predicate.
+    ///
+    /// The permissions of a variant, including the one of its `enum_<variant>`
+    /// field, are guarded by the discriminant of the variant. Unfolding the
+    /// predicate thus only yields the permissions of the variant selected by
+    /// the discriminant, which is also what the fold-unfold pass tracks (see
+    /// `get_body_footprint`).
     pub fn body(&self) -> Expr {
         let discriminant_loc = Expr::from(self.this.clone()).field(self.discriminant_field.clone());
         let discriminant_perm = Expr::acc_permission(discriminant_loc, PermAmount::Write);
@@ -262,9 +268,9 @@ impl EnumPredicate {
             let location: Expr = Expr::from(self.this.clone()).field(field).into();
             let field_perm = Expr::acc_permission(location.clone(), PermAmount::Write);
             let pred_perm = variant.construct_access(location, PermAmount::Write);
-            parts.push(Expr::and(
-                field_perm,
-                Expr::implies(guard.clone(), pred_perm),
+            parts.push(Expr::implies(
+                guard.clone(),
+                Expr::and(field_perm, pred_perm),
             ));
         }
         parts.into_iter().conjoin()
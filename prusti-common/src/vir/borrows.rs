@@ -7,6 +7,7 @@
 use super::ast::{Expr, ExprIterator, Stmt};
 use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::io::Write;
 
 /// The method-unique borrow identifier.
 #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Copy, Hash, Serialize, Deserialize)]
@@ -35,7 +36,7 @@ pub fn borrow_id(borrow: Borrow) -> usize {
 }
 
 /// Node of the reborrowing DAG.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Node {
     /// The basic block at which the borrow occured was executed only
     /// iff the `guard` is true.
@@ -91,11 +92,8 @@ impl fmt::Debug for Node {
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DAG {
     /// Mapping from borrows to their node indices.
-    #[serde(skip)]
     borrow_indices: HashMap<Borrow, usize>,
-    #[serde(skip)]
     nodes: Vec<Node>,
-    #[serde(skip)]
     borrowed_places: Vec<Expr>,
 }
 
@@ -161,6 +159,74 @@ impl DAG {
     }
 }
 
+impl DAG {
+    /// Render the DAG in the Graphviz format. Each node lists the borrowed
+    /// place, the guard under which the borrow is alive, and the statements
+    /// that are executed when the borrow expires. Solid edges point from a
+    /// borrow to the borrows that reborrow from it; dashed edges connect
+    /// conflicting borrows.
+    pub fn to_graphviz(&self, graph: &mut dyn Write, title: &str) {
+        writeln!(graph, "digraph ReborrowingDAG {{").unwrap();
+        writeln!(graph, "graph [fontname=monospace];").unwrap();
+        writeln!(graph, "node [fontname=monospace];").unwrap();
+        writeln!(graph, "edge [fontname=monospace];").unwrap();
+        writeln!(graph, "labelloc=\"t\";").unwrap();
+        writeln!(graph, "label=\"{}\";", escape_html(title)).unwrap();
+        for node in &self.nodes {
+            writeln!(graph, "\"{:?}\" [shape=none,label=<", node.borrow).unwrap();
+            writeln!(graph, "<table>").unwrap();
+            writeln!(
+                graph,
+                "<tr><td colspan=\"2\"><b>{:?}</b></td></tr>",
+                node.borrow
+            )
+            .unwrap();
+            writeln!(
+                graph,
+                "<tr><td>guard</td><td>{}</td></tr>",
+                escape_html(&self.guard(node.borrow))
+            )
+            .unwrap();
+            if let Some(ref place) = node.place {
+                writeln!(graph, "<tr><td>place</td><td>{}</td></tr>", escape_html(place)).unwrap();
+            }
+            for place in &node.borrowed_places {
+                writeln!(graph, "<tr><td>borrowed</td><td>{}</td></tr>", escape_html(place))
+                    .unwrap();
+            }
+            for (i, stmt) in node.stmts.iter().enumerate() {
+                writeln!(graph, "<tr><td>{}</td><td>{}</td></tr>", i, escape_html(stmt)).unwrap();
+            }
+            writeln!(graph, "</table>").unwrap();
+            writeln!(graph, ">];").unwrap();
+        }
+        for node in &self.nodes {
+            for reborrowing in &node.reborrowing_nodes {
+                writeln!(graph, "\"{:?}\" -> \"{:?}\";", node.borrow, reborrowing).unwrap();
+            }
+            for conflicting in &node.conflicting_borrows {
+                if node.borrow < *conflicting {
+                    writeln!(
+                        graph,
+                        "\"{:?}\" -> \"{:?}\" [style=dashed,dir=none];",
+                        node.borrow, conflicting
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        writeln!(graph, "}}").unwrap();
+    }
+}
+
+fn escape_html<S: ToString>(s: S) -> String {
+    s.to_string()
+        .replace("&", "&amp;")
+        .replace(">", "&gt;")
+        .replace("<", "&lt;")
+        .replace("\n", "<br/>")
+}
+
 /// A struct for constructing the reborrowing DAG.
 pub struct DAGBuilder {
     dag: DAG,
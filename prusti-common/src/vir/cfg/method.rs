@@ -17,8 +17,6 @@ pub(super) const RETURN_LABEL: &str = "end_of_method";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CfgMethod {
-    // TODO: extract logic using (most) skipped fields to CfgMethodBuilder
-    #[serde(skip)]
     pub(super) uuid: Uuid,
     pub(super) method_name: String,
     pub(in super::super) formal_arg_count: usize,
@@ -27,13 +25,10 @@ pub struct CfgMethod {
     // that depends on snapshots needs to modify this field.
     pub local_vars: Vec<LocalVar>,
     pub(super) labels: HashSet<String>,
-    #[serde(skip)]
     pub(super) reserved_labels: HashSet<String>,
     pub basic_blocks: Vec<CfgBlock>, // FIXME: Hack, should be pub(super).
     pub(super) basic_blocks_labels: Vec<String>,
-    #[serde(skip)]
     fresh_var_index: i32,
-    #[serde(skip)]
     fresh_label_index: i32,
 }
 
@@ -53,7 +48,6 @@ pub enum Successor {
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub struct CfgBlockIndex {
-    #[serde(skip)]
     pub(super) method_uuid: Uuid,
     pub(in super::super) block_index: usize,
 }
@@ -315,6 +309,52 @@ impl CfgMethod {
         result
     }
 
+    /// Remove the basic blocks that are not reachable from the start block.
+    pub fn remove_unreachable_blocks(&mut self) {
+        let mut reachable = vec![false; self.basic_blocks.len()];
+        let mut to_visit = vec![0];
+        reachable[0] = true;
+        while let Some(index) = to_visit.pop() {
+            for target in self.basic_blocks[index].successor.get_following() {
+                if !reachable[target.block_index] {
+                    reachable[target.block_index] = true;
+                    to_visit.push(target.block_index);
+                }
+            }
+        }
+        if reachable.iter().all(|&is_reachable| is_reachable) {
+            return;
+        }
+        let mut new_indices = HashMap::new();
+        let mut new_basic_blocks = vec![];
+        let mut new_labels = vec![];
+        let basic_blocks = std::mem::replace(&mut self.basic_blocks, vec![]);
+        let labels = std::mem::replace(&mut self.basic_blocks_labels, vec![]);
+        for (index, (block, label)) in basic_blocks.into_iter().zip(labels).enumerate() {
+            if reachable[index] {
+                new_indices.insert(index, new_basic_blocks.len());
+                new_basic_blocks.push(block);
+                new_labels.push(label);
+            }
+        }
+        for block in &mut new_basic_blocks {
+            match &mut block.successor {
+                Successor::Undefined | Successor::Return => {}
+                Successor::Goto(target) => {
+                    target.block_index = new_indices[&target.block_index];
+                }
+                Successor::GotoSwitch(conditional_targets, default_target) => {
+                    default_target.block_index = new_indices[&default_target.block_index];
+                    for (_, target) in conditional_targets {
+                        target.block_index = new_indices[&target.block_index];
+                    }
+                }
+            }
+        }
+        self.basic_blocks = new_basic_blocks;
+        self.basic_blocks_labels = new_labels;
+    }
+
     #[allow(dead_code)]
     pub fn get_indices(&self) -> Vec<CfgBlockIndex> {
         (0..self.basic_blocks.len())
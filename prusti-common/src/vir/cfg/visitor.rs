@@ -91,11 +91,13 @@ pub trait CfgReplacer<
         pctxts: Vec<&PathCtxt>
     ) -> Result<(Vec<Action>, PathCtxt), Self::Error>;
 
-    /// Convert actions to statements.
+    /// Convert actions to statements. `edge` is the pair of blocks of the
+    /// original CFG between which the actions are performed, if any.
     fn perform_prejoin_action(
         &mut self,
         pctxt: &mut PathCtxt,
         block_index: CfgBlockIndex,
+        edge: Option<(CfgBlockIndex, CfgBlockIndex)>,
         actions: Action) -> Result<Vec<Stmt>, Self::Error>;
 
     /// The main method: visit and replace the reachable blocks of a CFG.
@@ -200,7 +202,10 @@ pub trait CfgReplacer<
                                 new_label
                             ))],
                         );
-                        let stmts_to_add = self.perform_prejoin_action(&mut pctxt, new_block_index, action)?;
+                        let edge = (cfg.block_index(src_index), cfg.block_index(curr_index));
+                        let stmts_to_add = self.perform_prejoin_action(
+                            &mut pctxt, new_block_index, Some(edge), action
+                        )?;
                         new_cfg.add_stmts(new_block_index, stmts_to_add);
                         new_cfg.set_successor(new_block_index, Successor::Goto(curr_block_index));
                         new_cfg.set_successor(
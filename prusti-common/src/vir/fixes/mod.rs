@@ -7,5 +7,7 @@
 //! Fix the potentially broken encoding.
 
 pub use self::ghost_vars::fix_ghost_vars;
+pub use self::package_footprints::fix_package_footprints;
 
 mod ghost_vars;
+mod package_footprints;
@@ -0,0 +1,163 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Make the footprint of statements inside package statements explicit.
+
+use super::super::ast;
+use super::super::cfg;
+use std::mem;
+
+/// When packaging a magic wand, Silicon needs help in showing that it has
+/// access to the paths used by the statements of the package. Therefore,
+/// before each statement inside a package statement (including nested
+/// package statements and conditionals) we assert read permissions to all
+/// paths read by the statement.
+pub fn fix_package_footprints(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    for block in &mut method.basic_blocks {
+        let stmts = mem::replace(&mut block.stmts, vec![]);
+        block.stmts = stmts.into_iter().map(fix_stmt).collect();
+    }
+    method
+}
+
+/// Fix the package statements nested in `stmt`.
+fn fix_stmt(stmt: ast::Stmt) -> ast::Stmt {
+    match stmt {
+        ast::Stmt::PackageMagicWand(wand, body, label, vars, pos) => {
+            ast::Stmt::PackageMagicWand(wand, add_footprint_asserts(body), label, vars, pos)
+        }
+        ast::Stmt::If(guard, then_stmts, else_stmts) => ast::Stmt::If(
+            guard,
+            then_stmts.into_iter().map(fix_stmt).collect(),
+            else_stmts.into_iter().map(fix_stmt).collect(),
+        ),
+        stmt => stmt,
+    }
+}
+
+/// Add footprint assertions before the statements of a package body.
+fn add_footprint_asserts(stmts: Vec<ast::Stmt>) -> Vec<ast::Stmt> {
+    let mut result = Vec::with_capacity(stmts.len());
+    for stmt in stmts {
+        for access in stmt_footprint(&stmt) {
+            result.push(ast::Stmt::Assert(access, ast::Position::default()));
+        }
+        result.push(match stmt {
+            ast::Stmt::PackageMagicWand(wand, body, label, vars, pos) => {
+                ast::Stmt::PackageMagicWand(wand, add_footprint_asserts(body), label, vars, pos)
+            }
+            ast::Stmt::If(guard, then_stmts, else_stmts) => ast::Stmt::If(
+                guard,
+                add_footprint_asserts(then_stmts),
+                add_footprint_asserts(else_stmts),
+            ),
+            stmt => stmt,
+        });
+    }
+    result
+}
+
+/// The accessibility predicates of the paths read by the statement.
+fn stmt_footprint(stmt: &ast::Stmt) -> Vec<ast::Expr> {
+    let read_exprs: Vec<&ast::Expr> = match stmt {
+        ast::Stmt::Assign(ref lhs, ref rhs, _) => {
+            let mut exprs = vec![rhs];
+            if let Some(parent) = lhs.get_parent_ref() {
+                exprs.push(parent);
+            }
+            exprs
+        }
+        ast::Stmt::Exhale(ref expr, _) | ast::Stmt::Assert(ref expr, _) => vec![expr],
+        ast::Stmt::Fold(_, ref args, ..) |
+        ast::Stmt::Unfold(_, ref args, ..) |
+        ast::Stmt::MethodCall(_, ref args, _) => args.iter().collect(),
+        ast::Stmt::If(ref guard, ..) => vec![guard],
+        _ => vec![],
+    };
+    let mut footprint: Vec<ast::Expr> = vec![];
+    for expr in read_exprs {
+        for access in expr.compute_footprint(ast::PermAmount::Read) {
+            if !footprint.contains(&access) {
+                footprint.push(access);
+            }
+        }
+    }
+    footprint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{Expr, Field, LocalVar, PermAmount, Position, Stmt, Type};
+
+    fn x_f() -> Expr {
+        Expr::local(LocalVar::new("x", Type::TypedRef("T".to_string())))
+            .field(Field::new("f", Type::Int))
+    }
+
+    fn y() -> Expr {
+        Expr::local(LocalVar::new("y", Type::Int))
+    }
+
+    fn package(body: Vec<Stmt>) -> Stmt {
+        Stmt::PackageMagicWand(
+            Expr::magic_wand(true.into(), true.into(), None),
+            body,
+            "l".to_string(),
+            vec![],
+            Position::default(),
+        )
+    }
+
+    fn fix(stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let mut method = cfg::CfgMethod::new("m".to_string(), 0, vec![], vec![], vec![]);
+        let start = method.add_block("start", stmts);
+        method.set_successor(start, cfg::Successor::Return);
+        fix_package_footprints(method).basic_blocks.remove(0).stmts
+    }
+
+    fn assert_read(expr: Expr) -> Stmt {
+        Stmt::Assert(Expr::acc_permission(expr, PermAmount::Read), Position::default())
+    }
+
+    #[test]
+    fn asserts_the_footprint_of_statements_in_packages() {
+        let assign = Stmt::Assign(y(), x_f(), ast::AssignKind::Copy);
+        assert_eq!(
+            fix(vec![package(vec![assign.clone()])]),
+            vec![package(vec![assert_read(x_f()), assign])],
+        );
+    }
+
+    #[test]
+    fn handles_nested_packages_and_conditionals() {
+        let assign = Stmt::Assign(y(), x_f(), ast::AssignKind::Copy);
+        let conditional = Stmt::If(true.into(), vec![package(vec![assign.clone()])], vec![]);
+        assert_eq!(
+            fix(vec![conditional]),
+            vec![Stmt::If(
+                true.into(),
+                vec![package(vec![assert_read(x_f()), assign.clone()])],
+                vec![],
+            )],
+        );
+        let nested = package(vec![Stmt::If(true.into(), vec![assign.clone()], vec![])]);
+        assert_eq!(
+            fix(vec![nested]),
+            vec![package(vec![Stmt::If(
+                true.into(),
+                vec![assert_read(x_f()), assign],
+                vec![],
+            )])],
+        );
+    }
+
+    #[test]
+    fn keeps_statements_outside_of_packages() {
+        let assign = Stmt::Assign(y(), x_f(), ast::AssignKind::Copy);
+        assert_eq!(fix(vec![assign.clone()]), vec![assign]);
+    }
+}
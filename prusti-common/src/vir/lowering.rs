@@ -0,0 +1,346 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Lowering from the high-level VIR to the backend-level VIR.
+//!
+//! The encoders produce *high-level* VIR, which contains constructs that
+//! only make sense for the Rust-specific passes (mostly fold-unfold):
+//! enum variants, downcasts, frames, permission transfers and reborrowing
+//! DAGs. The *backend-level* VIR is the subset of VIR that corresponds
+//! one-to-one to Viper:
+//!
+//! * `Expr::Variant` is replaced by the equivalent `Expr::Field`;
+//! * `Expr::Downcast` is replaced by the expression in which the downcast
+//!   is visible;
+//! * `Stmt::Obtain`, `Stmt::BeginFrame`, `Stmt::EndFrame`,
+//!   `Stmt::TransferPerm`, `Stmt::ExpireBorrows` and `Stmt::Downcast` are
+//!   replaced by comments;
+//! * `Stmt::ApplyMagicWand` of the wand of a borrow is preceded by the
+//!   inhale of the dead borrow token that the left-hand side of the wand
+//!   requires. With `config::clean_dead_borrow_tokens()`, the tokens of
+//!   adjacent applications are inhaled by a single statement.
+//!
+//! Passes that are specific to a backend (optimizations, conversion to
+//! Viper, textual output) should work on the backend-level VIR, which can
+//! be checked with `LoweredProgram::check`.
+
+use crate::config;
+use crate::vir::{
+    ast::*,
+    borrows::{borrow_id, Borrow, DAG as ReborrowingDAG},
+    cfg::CfgMethod,
+    Program,
+};
+use std::mem;
+
+/// A program that contains only backend-level VIR.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoweredProgram {
+    program: Program,
+}
+
+impl LoweredProgram {
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn into_program(self) -> Program {
+        self.program
+    }
+
+    /// Returns the high-level constructs that are still present in the
+    /// program. An empty result means that the program is backend-level.
+    pub fn check(program: &Program) -> Vec<String> {
+        let mut checker = HighLevelChecker { found: vec![] };
+        for method in &program.methods {
+            method.walk_statements(|stmt| StmtWalker::walk(&mut checker, stmt));
+        }
+        for function in &program.functions {
+            for expr in function.pres.iter().chain(&function.posts).chain(&function.body) {
+                ExprWalker::walk(&mut checker, expr);
+            }
+        }
+        checker.found
+    }
+}
+
+impl Program {
+    /// Lower the program to the backend-level VIR.
+    pub fn lower(self) -> LoweredProgram {
+        let Program {
+            domains,
+            fields,
+            builtin_methods,
+            methods,
+            functions,
+            viper_predicates,
+        } = self;
+        let program = Program {
+            domains,
+            fields,
+            builtin_methods,
+            methods: methods.into_iter().map(lower_method).collect(),
+            functions: functions.into_iter().map(lower_function).collect(),
+            viper_predicates,
+        };
+        debug_assert!(
+            LoweredProgram::check(&program).is_empty(),
+            "{:?}",
+            LoweredProgram::check(&program)
+        );
+        LoweredProgram { program }
+    }
+}
+
+fn lower_method(mut method: CfgMethod) -> CfgMethod {
+    let mut lowerer = Lowerer {};
+    let mut sentinel_stmt = Stmt::Comment(String::from("moved out stmt"));
+    for block in &mut method.basic_blocks {
+        for stmt in &mut block.stmts {
+            mem::swap(&mut sentinel_stmt, stmt);
+            sentinel_stmt = StmtFolder::fold(&mut lowerer, sentinel_stmt);
+            mem::swap(&mut sentinel_stmt, stmt);
+        }
+        let stmts = mem::replace(&mut block.stmts, vec![]);
+        block.stmts = inhale_dead_borrow_tokens(stmts, config::clean_dead_borrow_tokens());
+    }
+    method
+}
+
+/// The dead borrow token of `borrow`, which is required by the left-hand
+/// side of its magic wand.
+fn dead_borrow_token(borrow: Borrow, pos: Position) -> Expr {
+    let borrow: Expr = borrow_id(borrow).into();
+    Expr::PredicateAccessPredicate("DeadBorrowToken$".to_string(), box borrow, PermAmount::Write, pos)
+}
+
+/// Inhale the dead borrow token of each application of the wand of a
+/// borrow right before it. If `merge` is set, the tokens of adjacent
+/// applications, which happen when several borrows expire at once, are
+/// inhaled by a single statement before the applications. Each application
+/// consumes the token that is inhaled for it, so repeated applications of
+/// the same wand inhale the token repeatedly.
+fn inhale_dead_borrow_tokens(stmts: Vec<Stmt>, merge: bool) -> Vec<Stmt> {
+    let mut result = Vec::with_capacity(stmts.len());
+    let mut tokens = vec![];
+    let mut applications = vec![];
+    for stmt in stmts {
+        let stmt = match stmt {
+            Stmt::ApplyMagicWand(wand, pos) => {
+                if let Expr::MagicWand(_, _, Some(borrow), _) = &wand {
+                    tokens.push(dead_borrow_token(*borrow, pos));
+                } else {
+                    unreachable!("{}", wand)
+                }
+                applications.push(Stmt::ApplyMagicWand(wand, pos));
+                if !merge {
+                    flush_wand_applications(&mut result, &mut tokens, &mut applications);
+                }
+                continue;
+            }
+            Stmt::If(guard, then_stmts, else_stmts) => Stmt::If(
+                guard,
+                inhale_dead_borrow_tokens(then_stmts, merge),
+                inhale_dead_borrow_tokens(else_stmts, merge),
+            ),
+            Stmt::PackageMagicWand(wand, body, label, vars, pos) => Stmt::PackageMagicWand(
+                wand,
+                inhale_dead_borrow_tokens(body, merge),
+                label,
+                vars,
+                pos,
+            ),
+            stmt => stmt,
+        };
+        flush_wand_applications(&mut result, &mut tokens, &mut applications);
+        result.push(stmt);
+    }
+    flush_wand_applications(&mut result, &mut tokens, &mut applications);
+    result
+}
+
+fn flush_wand_applications(
+    result: &mut Vec<Stmt>,
+    tokens: &mut Vec<Expr>,
+    applications: &mut Vec<Stmt>,
+) {
+    if let Some(tokens) = mem::replace(tokens, vec![]).into_iter().reduce(Expr::and) {
+        result.push(Stmt::Inhale(tokens));
+    }
+    result.extend(applications.drain(..));
+}
+
+fn lower_function(mut function: Function) -> Function {
+    let mut lowerer = Lowerer {};
+    function.pres = function
+        .pres
+        .into_iter()
+        .map(|expr| ExprFolder::fold(&mut lowerer, expr))
+        .collect();
+    function.posts = function
+        .posts
+        .into_iter()
+        .map(|expr| ExprFolder::fold(&mut lowerer, expr))
+        .collect();
+    function.body = function
+        .body
+        .map(|expr| ExprFolder::fold(&mut lowerer, expr));
+    function
+}
+
+struct Lowerer {}
+
+impl ExprFolder for Lowerer {
+    fn fold_variant(&mut self, base: Box<Expr>, variant: Field, pos: Position) -> Expr {
+        Expr::Field(self.fold_boxed(base), variant, pos)
+    }
+
+    fn fold_downcast(&mut self, base: Box<Expr>, _enum_place: Box<Expr>, _field: Field) -> Expr {
+        self.fold(*base)
+    }
+}
+
+impl StmtFolder for Lowerer {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        ExprFolder::fold(self, expr)
+    }
+
+    fn fold_obtain(&mut self, expr: Expr, pos: Position) -> Stmt {
+        Stmt::Comment(Stmt::Obtain(expr, pos).to_string())
+    }
+
+    fn fold_begin_frame(&mut self) -> Stmt {
+        Stmt::Comment(Stmt::BeginFrame.to_string())
+    }
+
+    fn fold_end_frame(&mut self) -> Stmt {
+        Stmt::Comment(Stmt::EndFrame.to_string())
+    }
+
+    fn fold_transfer_perm(&mut self, expiring: Expr, restored: Expr, unchecked: bool) -> Stmt {
+        Stmt::Comment(Stmt::TransferPerm(expiring, restored, unchecked).to_string())
+    }
+
+    fn fold_expire_borrows(&mut self, dag: ReborrowingDAG) -> Stmt {
+        Stmt::Comment(Stmt::ExpireBorrows(dag).to_string())
+    }
+
+    fn fold_downcast(&mut self, expr: Expr, field: Field) -> Stmt {
+        Stmt::Comment(Stmt::Downcast(expr, field).to_string())
+    }
+}
+
+/// Collects the high-level constructs of a program.
+struct HighLevelChecker {
+    found: Vec<String>,
+}
+
+impl ExprWalker for HighLevelChecker {
+    fn walk_variant(&mut self, base: &Expr, variant: &Field, _pos: &Position) {
+        self.found.push(format!("variant {}", variant.name));
+        ExprWalker::walk(self, base);
+    }
+
+    fn walk_downcast(&mut self, base: &Expr, enum_place: &Expr, _field: &Field) {
+        self.found.push(format!("downcast of {}", enum_place));
+        ExprWalker::walk(self, base);
+    }
+}
+
+impl StmtWalker for HighLevelChecker {
+    fn walk_expr(&mut self, expr: &Expr) {
+        ExprWalker::walk(self, expr);
+    }
+
+    fn walk_obtain(&mut self, expr: &Expr, _pos: &Position) {
+        self.found.push(format!("obtain {}", expr));
+    }
+
+    fn walk_begin_frame(&mut self) {
+        self.found.push("begin frame".to_string());
+    }
+
+    fn walk_end_frame(&mut self) {
+        self.found.push("end frame".to_string());
+    }
+
+    fn walk_transfer_perm(&mut self, from: &Expr, to: &Expr, _unchecked: &bool) {
+        self.found.push(format!("transfer perm {} --> {}", from, to));
+    }
+
+    fn walk_expire_borrows(&mut self, dag: &ReborrowingDAG) {
+        self.found.push(format!("expire borrows {:?}", dag));
+    }
+
+    fn walk_downcast(&mut self, expr: &Expr, field: &Field) {
+        self.found.push(format!("downcast {} to {}", expr, field.name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn application(borrow: usize) -> Stmt {
+        let wand = Expr::MagicWand(
+            box true.into(),
+            box true.into(),
+            Some(Borrow::from(borrow)),
+            Position::default(),
+        );
+        Stmt::ApplyMagicWand(wand, Position::default())
+    }
+
+    fn token(borrow: usize) -> Expr {
+        dead_borrow_token(Borrow::from(borrow), Position::default())
+    }
+
+    #[test]
+    fn inhales_a_token_before_each_application() {
+        let stmts = vec![application(1), application(2)];
+        assert_eq!(
+            inhale_dead_borrow_tokens(stmts, false),
+            vec![
+                Stmt::Inhale(token(1)),
+                application(1),
+                Stmt::Inhale(token(2)),
+                application(2),
+            ],
+        );
+    }
+
+    #[test]
+    fn merges_the_tokens_of_adjacent_applications() {
+        let stmts = vec![
+            application(1),
+            application(2),
+            application(1),
+            Stmt::Comment("expired".to_string()),
+            application(3),
+        ];
+        assert_eq!(
+            inhale_dead_borrow_tokens(stmts, true),
+            vec![
+                Stmt::Inhale(Expr::and(Expr::and(token(1), token(2)), token(1))),
+                application(1),
+                application(2),
+                application(1),
+                Stmt::Comment("expired".to_string()),
+                Stmt::Inhale(token(3)),
+                application(3),
+            ],
+        );
+    }
+
+    #[test]
+    fn inhales_the_tokens_in_nested_statements() {
+        let stmts = vec![Stmt::If(true.into(), vec![application(1)], vec![])];
+        assert_eq!(
+            inhale_dead_borrow_tokens(stmts, true),
+            vec![Stmt::If(true.into(), vec![Stmt::Inhale(token(1)), application(1)], vec![])],
+        );
+    }
+}
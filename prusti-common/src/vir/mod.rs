@@ -8,12 +8,14 @@ pub use self::ast::*;
 pub use self::cfg::*;
 pub use self::conversions::*;
 pub use self::to_viper::*;
+pub use self::lowering::*;
 pub use self::program::*;
 
 mod ast;
 pub mod borrows;
 mod cfg;
 mod conversions;
+mod lowering;
 pub mod fixes;
 pub mod optimizations;
 mod to_viper;
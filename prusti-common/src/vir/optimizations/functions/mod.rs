@@ -8,6 +8,8 @@
 
 mod inliner;
 mod simplifier;
+mod small_function_inliner;
 
 pub use self::inliner::inline_constant_functions;
 pub use self::simplifier::Simplifier;
+pub use self::small_function_inliner::inline_small_functions;
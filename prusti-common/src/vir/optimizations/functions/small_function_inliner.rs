@@ -0,0 +1,378 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Inliner of small pure functions.
+
+use super::super::super::ast;
+use super::super::super::cfg;
+use super::super::super::utils::{walk_functions, walk_methods};
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+/// Inline the bodies of small pure functions at their call sites. For
+/// example, the getter
+///
+/// ```viper
+/// function get_x(self: Ref): Int
+///     requires acc(Point(self), read$())
+/// {
+///     unfolding acc(Point(self), read$()) in self.x.val_int
+/// }
+/// ```
+///
+/// is replaced by its (substituted) body at each call site, so that the
+/// verifier does not need to reason about an additional function.
+///
+/// Only functions whose body has at most `max_size` nodes, whose
+/// preconditions contain only permissions (their well-definedness is then
+/// still checked by the `unfolding` expressions of the inlined body), and
+/// that have no postconditions are inlined. Calls inside inlined bodies are
+/// inlined up to `max_depth` levels, which also bounds the inlining of
+/// recursive functions. Calls inside quantifiers are not inlined, because
+/// the triggers of a quantifier have to be function applications that match
+/// its body. Inlined functions that are no longer called are removed from
+/// the program.
+pub fn inline_small_functions(
+    methods: Vec<cfg::CfgMethod>,
+    functions: Vec<ast::Function>,
+    max_size: usize,
+    max_depth: usize,
+) -> (Vec<cfg::CfgMethod>, Vec<ast::Function>) {
+    trace!("[enter] inline_small_functions");
+    let inlinable_functions: HashMap<String, ast::Function> = functions
+        .iter()
+        .filter(|function| is_inlinable(function, max_size))
+        .map(|function| (function.name.clone(), function.clone()))
+        .collect();
+    debug!(
+        "Functions that will be inlined: {:?}",
+        inlinable_functions.keys().collect::<Vec<_>>()
+    );
+    if inlinable_functions.is_empty() {
+        return (methods, functions);
+    }
+    let mut inliner = SmallFunctionInliner {
+        inlinable_functions: &inlinable_functions,
+        max_depth,
+        depth: 0,
+    };
+    let methods: Vec<_> = methods
+        .into_iter()
+        .map(|mut method| {
+            let mut sentinel_stmt = ast::Stmt::Comment(String::from("moved out stmt"));
+            for block in &mut method.basic_blocks {
+                for stmt in &mut block.stmts {
+                    mem::swap(&mut sentinel_stmt, stmt);
+                    sentinel_stmt = ast::StmtFolder::fold(&mut inliner, sentinel_stmt);
+                    mem::swap(&mut sentinel_stmt, stmt);
+                }
+                if let cfg::Successor::GotoSwitch(ref mut guarded_targets, _) = block.successor {
+                    for (guard, _) in guarded_targets {
+                        let old_guard = mem::replace(guard, true.into());
+                        *guard = ast::ExprFolder::fold(&mut inliner, old_guard);
+                    }
+                }
+            }
+            method
+        })
+        .collect();
+    let functions: Vec<_> = functions
+        .into_iter()
+        .map(|mut function| {
+            function.body = function.body.map(|body| ast::ExprFolder::fold(&mut inliner, body));
+            function
+        })
+        .collect();
+    let functions = remove_uncalled_functions(&methods, functions, &inlinable_functions);
+    (methods, functions)
+}
+
+fn is_inlinable(function: &ast::Function, max_size: usize) -> bool {
+    if let Some(ref body) = function.body {
+        function.posts.is_empty() &&
+            function.pres.iter().all(|pre| pre.is_only_permissions()) &&
+            !has_bound_variables(body) &&
+            expr_size(body) <= max_size
+    } else {
+        false
+    }
+}
+
+/// Count the number of nodes of the expression.
+fn expr_size(expr: &ast::Expr) -> usize {
+    struct SizeCounter {
+        size: usize,
+    }
+    impl ast::ExprWalker for SizeCounter {
+        fn walk(&mut self, expr: &ast::Expr) {
+            self.size += 1;
+            ast::default_walk_expr(self, expr);
+        }
+    }
+    let mut counter = SizeCounter { size: 0 };
+    ast::ExprWalker::walk(&mut counter, expr);
+    counter.size
+}
+
+/// `Function::inline_body` does not handle variable capturing, therefore we
+/// do not inline bodies that introduce new variables.
+fn has_bound_variables(expr: &ast::Expr) -> bool {
+    struct BinderFinder {
+        found: bool,
+    }
+    impl ast::ExprWalker for BinderFinder {
+        fn walk(&mut self, expr: &ast::Expr) {
+            if matches!(expr, ast::Expr::ForAll(..) | ast::Expr::LetExpr(..)) {
+                self.found = true;
+            } else {
+                ast::default_walk_expr(self, expr);
+            }
+        }
+    }
+    let mut finder = BinderFinder { found: false };
+    ast::ExprWalker::walk(&mut finder, expr);
+    finder.found
+}
+
+/// Remove the inlined functions that are not called anymore, neither from
+/// methods nor from (transitively) called functions.
+fn remove_uncalled_functions(
+    methods: &[cfg::CfgMethod],
+    functions: Vec<ast::Function>,
+    inlined_functions: &HashMap<String, ast::Function>,
+) -> Vec<ast::Function> {
+    let mut collector = CalledFunctionCollector {
+        called_functions: HashSet::new(),
+    };
+    walk_methods(methods, &mut collector);
+    walk_functions(
+        &functions
+            .iter()
+            .filter(|function| !inlined_functions.contains_key(&function.name))
+            .cloned()
+            .collect::<Vec<_>>(),
+        &mut collector,
+    );
+    let mut to_visit: Vec<_> = collector.called_functions.iter().cloned().collect();
+    while let Some(name) = to_visit.pop() {
+        if let Some(function) = inlined_functions.get(&name) {
+            let mut callee_collector = CalledFunctionCollector {
+                called_functions: HashSet::new(),
+            };
+            walk_functions(std::slice::from_ref(function), &mut callee_collector);
+            for callee in callee_collector.called_functions {
+                if collector.called_functions.insert(callee.clone()) {
+                    to_visit.push(callee);
+                }
+            }
+        }
+    }
+    functions
+        .into_iter()
+        .filter(|function| {
+            !inlined_functions.contains_key(&function.name) ||
+                collector.called_functions.contains(&function.name)
+        })
+        .collect()
+}
+
+struct CalledFunctionCollector {
+    called_functions: HashSet<String>,
+}
+
+impl ast::ExprWalker for CalledFunctionCollector {
+    fn walk_func_app(
+        &mut self,
+        name: &str,
+        args: &Vec<ast::Expr>,
+        _formal_args: &Vec<ast::LocalVar>,
+        _return_type: &ast::Type,
+        _pos: &ast::Position,
+    ) {
+        self.called_functions.insert(name.to_string());
+        for arg in args {
+            ast::ExprWalker::walk(self, arg);
+        }
+    }
+
+    fn walk_forall(
+        &mut self,
+        _vars: &Vec<ast::LocalVar>,
+        triggers: &Vec<ast::Trigger>,
+        body: &ast::Expr,
+        _pos: &ast::Position,
+    ) {
+        for trigger in triggers {
+            for element in trigger.elements() {
+                ast::ExprWalker::walk(self, element);
+            }
+        }
+        ast::ExprWalker::walk(self, body);
+    }
+}
+
+impl ast::StmtWalker for CalledFunctionCollector {
+    fn walk_expr(&mut self, expr: &ast::Expr) {
+        ast::ExprWalker::walk(self, expr);
+    }
+}
+
+/// Replace calls to small functions with their bodies.
+struct SmallFunctionInliner<'a> {
+    inlinable_functions: &'a HashMap<String, ast::Function>,
+    max_depth: usize,
+    depth: usize,
+}
+
+impl<'a> ast::StmtFolder for SmallFunctionInliner<'a> {
+    fn fold_expr(&mut self, expr: ast::Expr) -> ast::Expr {
+        ast::ExprFolder::fold(self, expr)
+    }
+}
+
+impl<'a> ast::ExprFolder for SmallFunctionInliner<'a> {
+    fn fold_func_app(
+        &mut self,
+        name: String,
+        args: Vec<ast::Expr>,
+        formal_args: Vec<ast::LocalVar>,
+        return_type: ast::Type,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        let args: Vec<_> = args.into_iter().map(|e| self.fold(e)).collect();
+        if self.depth < self.max_depth {
+            if let Some(function) = self.inlinable_functions.get(&name) {
+                let body = function.inline_body(args).set_default_pos(pos);
+                self.depth += 1;
+                let inlined_body = self.fold(body);
+                self.depth -= 1;
+                return inlined_body;
+            }
+        }
+        ast::Expr::FuncApp(name, args, formal_args, return_type, pos)
+    }
+
+    fn fold_forall(
+        &mut self,
+        vars: Vec<ast::LocalVar>,
+        triggers: Vec<ast::Trigger>,
+        body: Box<ast::Expr>,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        ast::Expr::ForAll(vars, triggers, body, pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{Expr, LocalVar, Position, Stmt, Type};
+
+    fn function(name: &str, body: Expr, posts: Vec<Expr>) -> ast::Function {
+        ast::Function {
+            name: name.to_string(),
+            formal_args: vec![LocalVar::new("x", Type::Int)],
+            return_type: Type::Int,
+            pres: vec![],
+            posts,
+            body: Some(body),
+        }
+    }
+
+    fn call(name: &str, arg: Expr) -> Expr {
+        Expr::func_app(
+            name.to_string(),
+            vec![arg],
+            vec![LocalVar::new("x", Type::Int)],
+            Type::Int,
+            Position::default(),
+        )
+    }
+
+    fn x() -> Expr {
+        Expr::local(LocalVar::new("x", Type::Int))
+    }
+
+    /// Inline the functions in a method that asserts `expr == 0`, and return
+    /// the asserted expression and the names of the remaining functions.
+    fn inline_in_assertion(
+        expr: Expr,
+        functions: Vec<ast::Function>,
+        max_depth: usize,
+    ) -> (Expr, Vec<String>) {
+        let y = LocalVar::new("y", Type::Int);
+        let mut method = cfg::CfgMethod::new("m".to_string(), 0, vec![], vec![y], vec![]);
+        let start = method.add_block(
+            "start",
+            vec![Stmt::Assert(Expr::eq_cmp(expr, 0.into()), Position::default())],
+        );
+        method.set_successor(start, cfg::Successor::Return);
+        let (methods, functions) = inline_small_functions(vec![method], functions, 10, max_depth);
+        let assertion = match &methods[0].basic_blocks[0].stmts[0] {
+            Stmt::Assert(Expr::BinOp(_, box lhs, _, _), _) => lhs.clone(),
+            stmt => unreachable!("{}", stmt),
+        };
+        (assertion, functions.into_iter().map(|function| function.name).collect())
+    }
+
+    #[test]
+    fn inlines_small_functions() {
+        let y = Expr::local(LocalVar::new("y", Type::Int));
+        let double = function("double", Expr::add(x(), x()), vec![]);
+        let (assertion, functions) = inline_in_assertion(call("double", y.clone()), vec![double], 2);
+        assert_eq!(assertion, Expr::add(y.clone(), y));
+        assert!(functions.is_empty());
+    }
+
+    #[test]
+    fn keeps_functions_with_postconditions_or_large_bodies() {
+        let y = Expr::local(LocalVar::new("y", Type::Int));
+        let with_post = function("with_post", x(), vec![Expr::ge_cmp(x(), 0.into())]);
+        let large_body = (0..10).fold(x(), |body, i| Expr::add(body, i.into()));
+        let large = function("large", large_body, vec![]);
+        let expr = Expr::add(call("with_post", y.clone()), call("large", y));
+        let (assertion, functions) = inline_in_assertion(expr.clone(), vec![with_post, large], 2);
+        assert_eq!(assertion, expr);
+        assert_eq!(functions, vec!["with_post".to_string(), "large".to_string()]);
+    }
+
+    #[test]
+    fn bounds_the_inlining_of_recursive_functions() {
+        let y = Expr::local(LocalVar::new("y", Type::Int));
+        let recursive = function("recursive", Expr::add(call("recursive", x()), 1.into()), vec![]);
+        let (assertion, functions) = inline_in_assertion(call("recursive", y.clone()), vec![recursive], 2);
+        assert_eq!(
+            assertion,
+            Expr::add(Expr::add(call("recursive", y), 1.into()), 1.into()),
+        );
+        assert_eq!(functions, vec!["recursive".to_string()]);
+    }
+
+    #[test]
+    fn keeps_calls_in_triggered_quantifiers() {
+        let i = LocalVar::new("i", Type::Int);
+        let double = function("double", Expr::add(x(), x()), vec![]);
+        let call_double = call("double", Expr::local(i.clone()));
+        let quantifier = Expr::forall(
+            vec![i],
+            vec![ast::Trigger::new(vec![call_double.clone()])],
+            Expr::ge_cmp(call_double, 0.into()),
+        );
+        let mut method = cfg::CfgMethod::new("m".to_string(), 0, vec![], vec![], vec![]);
+        let start = method.add_block(
+            "start",
+            vec![Stmt::Assert(quantifier.clone(), Position::default())],
+        );
+        method.set_successor(start, cfg::Successor::Return);
+        let (methods, functions) = inline_small_functions(vec![method], vec![double], 10, 2);
+        match &methods[0].basic_blocks[0].stmts[0] {
+            Stmt::Assert(assertion, _) => assert_eq!(assertion, &quantifier),
+            stmt => unreachable!("{}", stmt),
+        }
+        let functions: Vec<_> = functions.into_iter().map(|function| function.name).collect();
+        assert_eq!(functions, vec!["double".to_string()]);
+    }
+}
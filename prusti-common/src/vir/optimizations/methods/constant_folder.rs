@@ -0,0 +1,291 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Optimization that folds constant expressions and prunes branches whose
+//! guard is known statically.
+
+use crate::vir::{ast, cfg};
+use std::mem;
+
+/// Fold constant expressions and prune statically decided branches:
+/// * `1 + 2` becomes `3`, `!(true)` becomes `false`, and so on;
+/// * `if (true) { A } else { B }` becomes `A`;
+/// * `if (false) { A } else { B }` becomes `B`;
+/// * guarded successors whose guard folds to `false` are removed and the
+///   first guard that folds to `true` becomes an unconditional `goto`.
+///
+/// Assertions that become trivially `true` are removed later by
+/// `remove_trivial_assertions`.
+pub fn fold_constants(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    let mut folder = ConstantFolder {};
+    for block in &mut method.basic_blocks {
+        let stmts = mem::replace(&mut block.stmts, vec![]);
+        block.stmts = fold_stmts(&mut folder, stmts);
+        let successor = mem::replace(&mut block.successor, cfg::Successor::Undefined);
+        block.successor = fold_successor(&mut folder, successor);
+    }
+    method
+}
+
+fn fold_stmts(folder: &mut ConstantFolder, stmts: Vec<ast::Stmt>) -> Vec<ast::Stmt> {
+    let mut result = Vec::with_capacity(stmts.len());
+    for stmt in stmts {
+        match ast::StmtFolder::fold(folder, stmt) {
+            ast::Stmt::If(ast::Expr::Const(ast::Const::Bool(guard), _), then_stmts, else_stmts) => {
+                result.extend(if guard { then_stmts } else { else_stmts });
+            }
+            folded_stmt => result.push(folded_stmt),
+        }
+    }
+    result
+}
+
+fn fold_successor(folder: &mut ConstantFolder, successor: cfg::Successor) -> cfg::Successor {
+    match successor {
+        cfg::Successor::GotoSwitch(guarded_targets, default_target) => {
+            let mut new_guarded_targets = vec![];
+            for (guard, target) in guarded_targets {
+                match ast::ExprFolder::fold(folder, guard) {
+                    ast::Expr::Const(ast::Const::Bool(false), _) => {}
+                    ast::Expr::Const(ast::Const::Bool(true), _) => {
+                        if new_guarded_targets.is_empty() {
+                            return cfg::Successor::Goto(target);
+                        }
+                        return cfg::Successor::GotoSwitch(new_guarded_targets, target);
+                    }
+                    folded_guard => new_guarded_targets.push((folded_guard, target)),
+                }
+            }
+            if new_guarded_targets.is_empty() {
+                cfg::Successor::Goto(default_target)
+            } else {
+                cfg::Successor::GotoSwitch(new_guarded_targets, default_target)
+            }
+        }
+        successor => successor,
+    }
+}
+
+struct ConstantFolder {}
+
+impl ConstantFolder {
+    fn fold_int_op(
+        &self,
+        kind: ast::BinOpKind,
+        left: i64,
+        right: i64,
+    ) -> Option<ast::Const> {
+        use ast::BinOpKind::*;
+        let value = match kind {
+            EqCmp => return Some(ast::Const::Bool(left == right)),
+            NeCmp => return Some(ast::Const::Bool(left != right)),
+            GtCmp => return Some(ast::Const::Bool(left > right)),
+            GeCmp => return Some(ast::Const::Bool(left >= right)),
+            LtCmp => return Some(ast::Const::Bool(left < right)),
+            LeCmp => return Some(ast::Const::Bool(left <= right)),
+            Add => left.checked_add(right),
+            Sub => left.checked_sub(right),
+            Mul => left.checked_mul(right),
+            // Viper and Rust disagree on the rounding of negative operands,
+            // so only fold the cases on which they agree.
+            Div if left >= 0 && right > 0 => left.checked_div(right),
+            Mod if left >= 0 && right > 0 => left.checked_rem(right),
+            _ => None,
+        };
+        value.map(ast::Const::Int)
+    }
+
+    fn fold_bool_op(
+        &self,
+        kind: ast::BinOpKind,
+        left: bool,
+        right: bool,
+    ) -> Option<bool> {
+        match kind {
+            ast::BinOpKind::EqCmp => Some(left == right),
+            ast::BinOpKind::NeCmp => Some(left != right),
+            ast::BinOpKind::And => Some(left && right),
+            ast::BinOpKind::Or => Some(left || right),
+            ast::BinOpKind::Implies => Some(!left || right),
+            _ => None,
+        }
+    }
+}
+
+impl ast::StmtFolder for ConstantFolder {
+    fn fold_expr(&mut self, expr: ast::Expr) -> ast::Expr {
+        ast::ExprFolder::fold(self, expr)
+    }
+
+    fn fold_if(&mut self, guard: ast::Expr, then_stmts: Vec<ast::Stmt>, else_stmts: Vec<ast::Stmt>) -> ast::Stmt {
+        ast::Stmt::If(
+            ast::ExprFolder::fold(self, guard),
+            fold_stmts(self, then_stmts),
+            fold_stmts(self, else_stmts),
+        )
+    }
+
+    fn fold_package_magic_wand(
+        &mut self,
+        wand: ast::Expr,
+        body: Vec<ast::Stmt>,
+        label: String,
+        vars: Vec<ast::LocalVar>,
+        pos: ast::Position,
+    ) -> ast::Stmt {
+        ast::Stmt::PackageMagicWand(
+            ast::ExprFolder::fold(self, wand),
+            fold_stmts(self, body),
+            label,
+            vars,
+            pos,
+        )
+    }
+}
+
+impl ast::ExprFolder for ConstantFolder {
+    fn fold_unary_op(
+        &mut self,
+        kind: ast::UnaryOpKind,
+        expr: Box<ast::Expr>,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        match (kind, self.fold(*expr)) {
+            (ast::UnaryOpKind::Not, ast::Expr::Const(ast::Const::Bool(value), _)) => {
+                ast::Expr::Const(ast::Const::Bool(!value), pos)
+            }
+            (ast::UnaryOpKind::Minus, ast::Expr::Const(ast::Const::Int(value), _))
+                if value != i64::MIN =>
+            {
+                ast::Expr::Const(ast::Const::Int(-value), pos)
+            }
+            (kind, folded_expr) => ast::Expr::UnaryOp(kind, box folded_expr, pos),
+        }
+    }
+
+    fn fold_bin_op(
+        &mut self,
+        kind: ast::BinOpKind,
+        left: Box<ast::Expr>,
+        right: Box<ast::Expr>,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        let folded_left = self.fold(*left);
+        let folded_right = self.fold(*right);
+        let folded_const = match (&folded_left, &folded_right) {
+            (
+                ast::Expr::Const(ast::Const::Int(left_value), _),
+                ast::Expr::Const(ast::Const::Int(right_value), _),
+            ) => self.fold_int_op(kind, *left_value, *right_value),
+            (
+                ast::Expr::Const(ast::Const::Bool(left_value), _),
+                ast::Expr::Const(ast::Const::Bool(right_value), _),
+            ) => self
+                .fold_bool_op(kind, *left_value, *right_value)
+                .map(ast::Const::Bool),
+            _ => None,
+        };
+        if let Some(value) = folded_const {
+            return ast::Expr::Const(value, pos);
+        }
+        match (kind, folded_left, folded_right) {
+            (ast::BinOpKind::And, ast::Expr::Const(ast::Const::Bool(true), _), other) |
+            (ast::BinOpKind::And, other, ast::Expr::Const(ast::Const::Bool(true), _)) |
+            (ast::BinOpKind::Or, ast::Expr::Const(ast::Const::Bool(false), _), other) |
+            (ast::BinOpKind::Or, other, ast::Expr::Const(ast::Const::Bool(false), _)) |
+            (ast::BinOpKind::Implies, ast::Expr::Const(ast::Const::Bool(true), _), other) => other,
+            (ast::BinOpKind::And, ast::Expr::Const(ast::Const::Bool(false), _), _) => {
+                ast::Expr::Const(ast::Const::Bool(false), pos)
+            }
+            (ast::BinOpKind::Or, ast::Expr::Const(ast::Const::Bool(true), _), _) |
+            (ast::BinOpKind::Implies, ast::Expr::Const(ast::Const::Bool(false), _), _) => {
+                ast::Expr::Const(ast::Const::Bool(true), pos)
+            }
+            // The left operand is evaluated first, so it can be dropped only
+            // if it does not contain checks of the backend.
+            (ast::BinOpKind::And, ref left, ast::Expr::Const(ast::Const::Bool(false), _))
+                if left.is_well_defined_everywhere() =>
+            {
+                ast::Expr::Const(ast::Const::Bool(false), pos)
+            }
+            (ast::BinOpKind::Or, ref left, ast::Expr::Const(ast::Const::Bool(true), _)) |
+            (ast::BinOpKind::Implies, ref left, ast::Expr::Const(ast::Const::Bool(true), _))
+                if left.is_well_defined_everywhere() =>
+            {
+                ast::Expr::Const(ast::Const::Bool(true), pos)
+            }
+            (kind, folded_left, folded_right) => {
+                ast::Expr::BinOp(kind, box folded_left, box folded_right, pos)
+            }
+        }
+    }
+
+    fn fold_cond(
+        &mut self,
+        guard: Box<ast::Expr>,
+        then_expr: Box<ast::Expr>,
+        else_expr: Box<ast::Expr>,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        match self.fold(*guard) {
+            ast::Expr::Const(ast::Const::Bool(true), _) => self.fold(*then_expr),
+            ast::Expr::Const(ast::Const::Bool(false), _) => self.fold(*else_expr),
+            folded_guard => ast::Expr::Cond(
+                box folded_guard,
+                self.fold_boxed(then_expr),
+                self.fold_boxed(else_expr),
+                pos,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{Expr, ExprFolder};
+
+    fn fold(expr: Expr) -> Expr {
+        ConstantFolder {}.fold(expr)
+    }
+
+    #[test]
+    fn folds_arithmetic() {
+        let expr = Expr::add(Expr::mul(2.into(), 3.into()), 4.into());
+        assert_eq!(fold(expr), 10.into());
+        let expr = Expr::lt_cmp(Expr::sub(1.into(), 2.into()), 0.into());
+        assert_eq!(fold(expr), true.into());
+    }
+
+    #[test]
+    fn keeps_unsafe_arithmetic() {
+        let expr = Expr::div(Expr::minus(7.into()), 2.into());
+        assert_eq!(fold(expr), Expr::div((-7).into(), 2.into()));
+        let expr = Expr::add(i64::MAX.into(), 1.into());
+        assert_eq!(fold(expr.clone()), expr);
+    }
+
+    #[test]
+    fn folds_boolean_connectives() {
+        let x = Expr::local(ast::LocalVar::new("x", ast::Type::Bool));
+        assert_eq!(fold(Expr::and(true.into(), x.clone())), x);
+        assert_eq!(fold(Expr::or(x.clone(), true.into())), true.into());
+        assert_eq!(fold(Expr::implies(false.into(), x.clone())), true.into());
+        assert_eq!(fold(Expr::ite(Expr::not(false.into()), x.clone(), false.into())), x);
+        assert_eq!(fold(Expr::and(x.clone(), false.into())), false.into());
+        assert_eq!(fold(Expr::implies(x.clone(), true.into())), true.into());
+    }
+
+    #[test]
+    fn keeps_checks_of_dropped_operands() {
+        let x = Expr::local(ast::LocalVar::new("x", ast::Type::Int));
+        let division = Expr::eq_cmp(Expr::div(1.into(), x), 0.into());
+        let expr = Expr::or(division.clone(), true.into());
+        assert_eq!(fold(expr.clone()), expr);
+        let expr = Expr::and(division, false.into());
+        assert_eq!(fold(expr.clone()), expr);
+    }
+}
@@ -0,0 +1,193 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Optimization that removes dead code from encoded methods.
+
+use crate::vir::{ast, cfg};
+use std::collections::HashSet;
+use std::mem;
+
+/// Remove code that does not influence the verification result:
+/// * basic blocks that are not reachable from the start block;
+/// * assignments to non-reference temporaries that are never read, if the
+///   assigned expression is well-defined in every state;
+/// * statements that are skipped when converting to Viper (`obtain`,
+///   `begin frame`, `expire borrows`, ...).
+///
+/// Assignments to references are kept, because the variable may still be
+/// mentioned in the accessibility predicates that transfer permissions.
+/// Assignments of expressions that might not be well-defined are kept
+/// together with their target, because the backend checks, e.g., that the
+/// fields they read are accessible and that their divisors are not zero.
+pub fn remove_dead_code(mut method: cfg::CfgMethod) -> cfg::CfgMethod {
+    method.remove_unreachable_blocks();
+    let mut collector = ReadVarCollector {
+        read_vars: HashSet::new(),
+    };
+    method.walk_statements(|stmt| {
+        ast::StmtWalker::walk(&mut collector, stmt);
+    });
+    method.walk_successors(|successor| {
+        if let cfg::Successor::GotoSwitch(conditional_targets, _) = successor {
+            for (expr, _) in conditional_targets {
+                ast::ExprWalker::walk(&mut collector, expr);
+            }
+        }
+    });
+    let mut checked_vars = HashSet::new();
+    for block in &method.basic_blocks {
+        collect_checked_assignments(&block.stmts, &mut checked_vars);
+    }
+    let dead_vars: HashSet<_> = method
+        .local_vars
+        .iter()
+        .filter(|var| {
+            !var.typ.is_ref()
+                && !collector.read_vars.contains(&var.name)
+                && !checked_vars.contains(&var.name)
+        })
+        .cloned()
+        .collect();
+    method.local_vars.retain(|var| !dead_vars.contains(var));
+    for block in &mut method.basic_blocks {
+        let stmts = mem::replace(&mut block.stmts, vec![]);
+        block.stmts = remove_dead_stmts(stmts, &dead_vars);
+    }
+    method
+}
+
+/// Collect the local variables to which an expression that is not
+/// well-defined in every state is assigned.
+fn collect_checked_assignments(stmts: &[ast::Stmt], checked_vars: &mut HashSet<String>) {
+    for stmt in stmts {
+        match stmt {
+            ast::Stmt::Assign(ast::Expr::Local(ref var, _), ref expr, _)
+                if !expr.is_well_defined_everywhere() =>
+            {
+                checked_vars.insert(var.name.clone());
+            }
+            ast::Stmt::If(_, ref then_stmts, ref else_stmts) => {
+                collect_checked_assignments(then_stmts, checked_vars);
+                collect_checked_assignments(else_stmts, checked_vars);
+            }
+            ast::Stmt::PackageMagicWand(_, ref body, ..) => {
+                collect_checked_assignments(body, checked_vars);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn remove_dead_stmts(stmts: Vec<ast::Stmt>, dead_vars: &HashSet<ast::LocalVar>) -> Vec<ast::Stmt> {
+    stmts
+        .into_iter()
+        .filter_map(|stmt| match stmt {
+            ast::Stmt::Assign(ast::Expr::Local(ref var, _), _, _) if dead_vars.contains(var) => {
+                None
+            }
+            ast::Stmt::Obtain(..) |
+            ast::Stmt::BeginFrame |
+            ast::Stmt::EndFrame |
+            ast::Stmt::TransferPerm(..) |
+            ast::Stmt::ExpireBorrows(..) |
+            ast::Stmt::Downcast(..) => None,
+            ast::Stmt::If(guard, then_stmts, else_stmts) => Some(ast::Stmt::If(
+                guard,
+                remove_dead_stmts(then_stmts, dead_vars),
+                remove_dead_stmts(else_stmts, dead_vars),
+            )),
+            ast::Stmt::PackageMagicWand(wand, body, label, vars, pos) => {
+                Some(ast::Stmt::PackageMagicWand(
+                    wand,
+                    remove_dead_stmts(body, dead_vars),
+                    label,
+                    vars,
+                    pos,
+                ))
+            }
+            stmt => Some(stmt),
+        })
+        .collect()
+}
+
+/// Collects all variables that are read. Assigning to a variable does not
+/// count as reading it.
+struct ReadVarCollector {
+    read_vars: HashSet<String>,
+}
+
+impl ast::ExprWalker for ReadVarCollector {
+    fn walk_local_var(&mut self, local_var: &ast::LocalVar) {
+        self.read_vars.insert(local_var.name.clone());
+    }
+}
+
+impl ast::StmtWalker for ReadVarCollector {
+    fn walk_expr(&mut self, expr: &ast::Expr) {
+        ast::ExprWalker::walk(self, expr);
+    }
+    fn walk_local_var(&mut self, local_var: &ast::LocalVar) {
+        self.read_vars.insert(local_var.name.clone());
+    }
+    fn walk_assign(&mut self, target: &ast::Expr, expr: &ast::Expr, _kind: &ast::AssignKind) {
+        if !target.is_local() {
+            self.walk_expr(target);
+        }
+        self.walk_expr(expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::{Expr, LocalVar, Position, Stmt, Type};
+
+    fn method_with_stmts(local_vars: Vec<LocalVar>, stmts: Vec<Stmt>) -> cfg::CfgMethod {
+        let mut method = cfg::CfgMethod::new("m".to_string(), 0, vec![], local_vars, vec![]);
+        let start = method.add_block("start", stmts);
+        method.set_successor(start, cfg::Successor::Return);
+        method
+    }
+
+    #[test]
+    fn removes_unread_assignments() {
+        let x = LocalVar::new("x", Type::Int);
+        let y = LocalVar::new("y", Type::Int);
+        let method = remove_dead_code(method_with_stmts(
+            vec![x.clone(), y.clone()],
+            vec![
+                Stmt::Assign(
+                    Expr::local(x.clone()),
+                    Expr::add(Expr::local(y.clone()), 1.into()),
+                    ast::AssignKind::Copy,
+                ),
+                Stmt::Assert(Expr::ge_cmp(Expr::local(y.clone()), 0.into()), Position::default()),
+            ],
+        ));
+        assert_eq!(method.local_vars, vec![y.clone()]);
+        assert_eq!(
+            method.basic_blocks[0].stmts,
+            vec![Stmt::Assert(Expr::ge_cmp(Expr::local(y), 0.into()), Position::default())],
+        );
+    }
+
+    #[test]
+    fn keeps_assignments_with_checks() {
+        let x = LocalVar::new("x", Type::Int);
+        let y = LocalVar::new("y", Type::Int);
+        let assign = Stmt::Assign(
+            Expr::local(x.clone()),
+            Expr::div(1.into(), Expr::local(y.clone())),
+            ast::AssignKind::Copy,
+        );
+        let method = remove_dead_code(method_with_stmts(
+            vec![x.clone(), y.clone()],
+            vec![Stmt::If(true.into(), vec![assign.clone()], vec![])],
+        ));
+        assert_eq!(method.local_vars, vec![x, y]);
+        assert_eq!(method.basic_blocks[0].stmts, vec![Stmt::If(true.into(), vec![assign], vec![])]);
+    }
+}
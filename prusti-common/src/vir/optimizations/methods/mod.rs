@@ -7,6 +7,8 @@
 //! A module that contains optimizations for methods.
 
 mod cfg_cleaner;
+mod constant_folder;
+mod dead_code_remover;
 mod empty_if_remover;
 mod assert_remover;
 mod var_remover;
@@ -18,6 +20,8 @@ use crate::vir::cfg::CfgMethod;
 use super::log_method;
 
 use self::cfg_cleaner::clean_cfg;
+use self::constant_folder::fold_constants;
+use self::dead_code_remover::remove_dead_code;
 use self::empty_if_remover::remove_empty_if;
 use self::assert_remover::remove_trivial_assertions;
 use self::var_remover::remove_unused_vars;
@@ -39,9 +43,11 @@ pub fn optimize_method_encoding(cfg: CfgMethod, source_file_name: &str, optimiza
     }
     let cfg = apply!(purify_vars, cfg);
     let cfg = apply!(fix_quantifiers, cfg);
+    let cfg = apply!(fold_constants, cfg);
     let cfg = apply!(remove_empty_if, cfg);
     let cfg = apply!(remove_unused_vars, cfg);
     let cfg = apply!(remove_trivial_assertions, cfg);
+    let cfg = apply!(remove_dead_code, cfg);
     let cfg = apply!(clean_cfg, cfg);
 
     cfg
@@ -8,6 +8,7 @@
 
 use crate::vir::{CfgMethod, Program};
 use crate::config::{self, optimizations, Optimizations};
+use std::collections::HashSet;
 
 pub mod folding;
 pub mod functions;
@@ -47,7 +48,17 @@ fn log_methods(
 }
 
 impl Program {
-    pub fn optimized(mut self, source_file_name: &str) -> Self {
+    pub fn optimized(self, source_file_name: &str) -> Self {
+        self.optimized_except(source_file_name, &HashSet::new())
+    }
+
+    /// Optimize the program, without applying the optimizations that work on
+    /// single methods to `unoptimized_methods`.
+    pub fn optimized_except(
+        mut self,
+        source_file_name: &str,
+        unoptimized_methods: &HashSet<String>,
+    ) -> Self {
         let optimizations = config::optimizations();
         info!("Enabled optimisations: {:?}", optimizations);
 
@@ -70,6 +81,28 @@ impl Program {
                 true
             );
         }
+        if config::inline_pure_functions() {
+            log_methods(
+                source_file_name,
+                &self.methods,
+                "inline_small_functions",
+                false
+            );
+            let (new_methods, new_functions) = functions::inline_small_functions(
+                self.methods,
+                self.functions,
+                config::pure_function_inlining_max_size(),
+                config::pure_function_inlining_max_depth(),
+            );
+            self.methods = new_methods;
+            self.functions = new_functions;
+            log_methods(
+                source_file_name,
+                &self.methods,
+                "inline_small_functions",
+                true
+            );
+        }
         if optimizations.optimize_folding {
             log_methods(
                 source_file_name,
@@ -80,7 +113,11 @@ impl Program {
             self.methods = self.methods
                 .into_iter()
                 .map(|cfg| {
-                    folding::FoldingOptimizer::optimize(cfg)
+                    if unoptimized_methods.contains(&cfg.name()) {
+                        cfg
+                    } else {
+                        folding::FoldingOptimizer::optimize(cfg)
+                    }
                 })
                 .collect();
             self.functions = self.functions
@@ -95,7 +132,11 @@ impl Program {
             );
         }
         self.methods = self.methods.into_iter().map(|method| {
-            methods::optimize_method_encoding(method, source_file_name, &optimizations)
+            if unoptimized_methods.contains(&method.name()) {
+                method
+            } else {
+                methods::optimize_method_encoding(method, source_file_name, &optimizations)
+            }
         }).collect();
         if optimizations.delete_unused_predicates {
             self.viper_predicates = predicates::delete_unused_predicates(
@@ -109,6 +150,7 @@ impl Program {
             self.methods=purification::purify_methods(self.methods, &self.viper_predicates);
         }
 
+        self.sort_items();
         self
     }
 }
@@ -9,3 +9,158 @@ pub struct Program {
     pub functions: Vec<Function>,
     pub viper_predicates: Vec<Predicate>,
 }
+
+impl Program {
+    /// Sort all items by their identifiers so that the emitted Viper program
+    /// does not depend on the iteration order of the encoder's hash maps.
+    pub fn sort_items(&mut self) {
+        for domain in &mut self.domains {
+            domain.functions.sort_by_key(|f| f.get_identifier());
+            domain.axioms.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        self.domains.sort_by_key(|d| d.get_identifier());
+        self.fields.sort_by_key(|f| f.get_identifier());
+        self.builtin_methods.sort_by_key(|m| m.get_identifier());
+        self.methods.sort_by_key(|m| m.get_identifier());
+        self.functions.sort_by_key(|f| f.get_identifier());
+        self.viper_predicates.sort_by_key(|p| p.get_identifier());
+    }
+
+    /// The size of the methods and functions of the program.
+    pub fn statistics(&self) -> ProgramStatistics {
+        let mut counter = ExprCounter::default();
+        let mut statements = 0;
+        for method in &self.methods {
+            for block in &method.basic_blocks {
+                statements += count_statements(&block.stmts);
+                for stmt in &block.stmts {
+                    StmtWalker::walk(&mut counter, stmt);
+                }
+            }
+        }
+        for function in &self.functions {
+            let exprs = function.pres.iter()
+                .chain(function.posts.iter())
+                .chain(function.body.iter());
+            for expr in exprs {
+                ExprWalker::walk(&mut counter, expr);
+            }
+        }
+        ProgramStatistics {
+            methods: self.methods.len(),
+            functions: self.functions.len(),
+            predicates: self.viper_predicates.len(),
+            statements,
+            expression_nodes: counter.expression_nodes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProgramStatistics {
+    pub methods: usize,
+    pub functions: usize,
+    pub predicates: usize,
+    /// The statements of the methods, including the nested ones.
+    pub statements: usize,
+    /// The nodes of the expressions of the statements of the methods and of
+    /// the functions.
+    pub expression_nodes: usize,
+}
+
+fn count_statements(stmts: &[Stmt]) -> usize {
+    stmts.iter()
+        .map(|stmt| 1 + match stmt {
+            Stmt::If(_, then_stmts, else_stmts) => {
+                count_statements(then_stmts) + count_statements(else_stmts)
+            }
+            Stmt::PackageMagicWand(_, body, _, _, _) => count_statements(body),
+            _ => 0,
+        })
+        .sum()
+}
+
+#[derive(Default)]
+struct ExprCounter {
+    expression_nodes: usize,
+}
+
+impl ExprWalker for ExprCounter {
+    fn walk(&mut self, expr: &Expr) {
+        self.expression_nodes += 1;
+        default_walk_expr(self, expr);
+    }
+}
+
+impl StmtWalker for ExprCounter {
+    fn walk_expr(&mut self, expr: &Expr) {
+        ExprWalker::walk(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vir::cfg::Successor;
+
+    fn program() -> Program {
+        let mut method = CfgMethod::new(
+            "m".to_string(),
+            0,
+            vec![],
+            vec![LocalVar::new("x", Type::Int)],
+            vec![],
+        );
+        method.add_fresh_local_var(Type::Bool);
+        let x = Expr::local(LocalVar::new("x", Type::Int));
+        let block = method.add_block(
+            "start",
+            vec![Stmt::Inhale(Expr::gt_cmp(x, 0.into()))],
+        );
+        method.set_successor(block, Successor::Return);
+        Program {
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![method],
+            functions: vec![],
+            viper_predicates: vec![],
+        }
+    }
+
+    #[test]
+    fn statistics_count_nested_nodes() {
+        let statistics = program().statistics();
+        assert_eq!(statistics.methods, 1);
+        assert_eq!(statistics.statements, 1);
+        // `x > 0` consists of the comparison and its two operands.
+        assert_eq!(statistics.expression_nodes, 3);
+    }
+
+    #[test]
+    fn sorted_programs_are_identical() {
+        let mut first = program();
+        first.fields = vec![Field::new("f$b", Type::Int), Field::new("f$a", Type::Bool)];
+        let mut second = program();
+        second.fields = first.fields.iter().rev().cloned().collect();
+        second.methods = first.methods.clone();
+        first.sort_items();
+        second.sort_items();
+        assert_eq!(
+            serde_json::to_string(&first).unwrap(),
+            serde_json::to_string(&second).unwrap(),
+        );
+    }
+
+    #[test]
+    fn program_round_trips() {
+        let json = serde_json::to_string(&program()).unwrap();
+        let mut deserialized: Program = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+        // The deserialized method can still be extended.
+        let method = &mut deserialized.methods[0];
+        assert_eq!(method.add_fresh_local_var(Type::Int).name, "__t1");
+        let block = method.add_block("next", vec![]);
+        method.set_successor(block, Successor::Return);
+    }
+}
@@ -5,6 +5,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::config;
+use std::{collections::HashMap, sync::Mutex};
 use viper::{self, AstFactory};
 use crate::vir::{ast::*, borrows::borrow_id, Program};
 
@@ -18,11 +19,112 @@ pub trait ToViperDecl<'v, T> {
 
 impl<'v> ToViper<'v, viper::Program<'v>> for Program {
     fn to_viper(&self, ast: &AstFactory<'v>) -> viper::Program<'v> {
-        let domains = self.domains.to_viper(ast);
+        self.to_viper_with_preamble(ast, &ViperPreamble::new())
+    }
+}
+
+/// The part of the Viper encoding that is shared by all programs: domains,
+/// builtin methods, and the `read$` function. Each of them is converted
+/// only once and then reused by all programs of the session. The converted
+/// nodes are kept as global references, which stay valid in every
+/// verification context and thread.
+///
+/// Domains and builtin methods are cached by name. A domain can gain
+/// functions while a crate is encoded, so a cached conversion is only
+/// reused for an equal domain, and it is replaced (and its reference
+/// freed) when the domain changes. All references are freed when the last
+/// verifier runner that uses the preamble shuts down.
+#[derive(Default)]
+pub struct ViperPreamble {
+    domains: Mutex<HashMap<String, (Domain, viper::GlobalRef)>>,
+    builtin_methods: Mutex<HashMap<String, (BodylessMethod, viper::GlobalRef)>>,
+    read_function: Mutex<Option<viper::GlobalRef>>,
+    /// The number of verifier runners that currently use the preamble.
+    runners: Mutex<usize>,
+}
+
+impl ViperPreamble {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a verifier runner that uses the preamble.
+    pub fn attach_runner(&self) {
+        *self.runners.lock().unwrap() += 1;
+    }
+
+    /// Unregister a verifier runner, freeing the global references once no
+    /// runner uses the preamble anymore.
+    pub fn detach_runner(&self) {
+        let mut runners = self.runners.lock().unwrap();
+        *runners -= 1;
+        if *runners == 0 {
+            self.domains.lock().unwrap().clear();
+            self.builtin_methods.lock().unwrap().clear();
+            self.read_function.lock().unwrap().take();
+        }
+    }
+
+    fn domain<'v>(&self, domain: &Domain, ast: &AstFactory<'v>) -> viper::Domain<'v> {
+        let mut domains = self.domains.lock().unwrap();
+        if let Some((cached, global)) = domains.get(&domain.name) {
+            if cached == domain {
+                return viper::Domain::from_global(global, ast);
+            }
+        }
+        let global = domain.to_viper(ast).to_global(ast);
+        let viper_domain = viper::Domain::from_global(&global, ast);
+        domains.insert(domain.name.clone(), (domain.clone(), global));
+        viper_domain
+    }
+
+    fn builtin_method<'v>(&self, method: &BodylessMethod, ast: &AstFactory<'v>) -> viper::Method<'v> {
+        let mut builtin_methods = self.builtin_methods.lock().unwrap();
+        if let Some((cached, global)) = builtin_methods.get(&method.name) {
+            if cached == method {
+                return viper::Method::from_global(global, ast);
+            }
+        }
+        let global = method.to_viper(ast).to_global(ast);
+        let viper_method = viper::Method::from_global(&global, ast);
+        builtin_methods.insert(method.name.clone(), (method.clone(), global));
+        viper_method
+    }
+
+    /// A function that represents the symbolic read permission amount.
+    fn read_function<'v>(&self, ast: &AstFactory<'v>) -> viper::Function<'v> {
+        let mut read_function = self.read_function.lock().unwrap();
+        let global = read_function.get_or_insert_with(|| {
+            ast.function(
+                "read$",
+                &[],
+                ast.perm_type(),
+                &[],
+                &[
+                    ast.lt_cmp(ast.no_perm(), ast.result_with_pos(ast.perm_type(), ast.no_position())),
+                    ast.lt_cmp(ast.result_with_pos(ast.perm_type(), ast.no_position()), ast.full_perm()),
+                ],
+                ast.no_position(),
+                None,
+            ).to_global(ast)
+        });
+        viper::Function::from_global(global, ast)
+    }
+}
+
+impl Program {
+    /// Convert the program to Viper, reusing the already converted parts of
+    /// the `preamble`.
+    pub fn to_viper_with_preamble<'v>(
+        &self,
+        ast: &AstFactory<'v>,
+        preamble: &ViperPreamble,
+    ) -> viper::Program<'v> {
+        let domains: Vec<_> = self.domains.iter().map(|d| preamble.domain(d, ast)).collect();
         let fields = self.fields.to_viper(ast);
 
         let mut viper_methods: Vec<_> = self.methods.iter().map(|m| m.to_viper(ast)).collect();
-        viper_methods.extend(self.builtin_methods.iter().map(|m| m.to_viper(ast)));
+        viper_methods.extend(self.builtin_methods.iter().map(|m| preamble.builtin_method(m, ast)));
         if config::verify_only_preamble() {
             viper_methods = Vec::new();
         }
@@ -39,19 +141,7 @@ impl<'v> ToViper<'v, viper::Program<'v>> for Program {
             viper_methods.len()
         );
 
-        // Add a function that represents the symbolic read permission amount.
-        viper_functions.push(ast.function(
-            "read$",
-            &[],
-            ast.perm_type(),
-            &[],
-            &[
-                ast.lt_cmp(ast.no_perm(), ast.result_with_pos(ast.perm_type(), ast.no_position())),
-                ast.lt_cmp(ast.result_with_pos(ast.perm_type(), ast.no_position()), ast.full_perm()),
-            ],
-            ast.no_position(),
-            None,
-        ));
+        viper_functions.push(preamble.read_function(ast));
 
         ast.program(
             &domains,
@@ -165,74 +255,9 @@ impl<'v> ToViper<'v, viper::Stmt<'v>> for Stmt {
                 ast.comment(&self.to_string())
             }
             Stmt::PackageMagicWand(ref wand, ref package_stmts, ref _label, ref vars, ref pos) => {
-                // FIXME: When packaging a magic wand, Silicon needs help in showing that it has
-                // access to the needed paths.
-                fn stmt_to_viper_in_packge<'v>(
-                    stmt: &Stmt,
-                    ast: &AstFactory<'v>,
-                ) -> viper::Stmt<'v> {
-                    let create_footprint_asserts = |expr: &Expr, perm| -> Vec<viper::Stmt> {
-                        expr.compute_footprint(perm)
-                            .into_iter()
-                            .map(|access| {
-                                let fake_position = Position::default();
-                                let assert =
-                                    Stmt::Assert(access, fake_position);
-                                assert.to_viper(ast)
-                            })
-                            .collect()
-                    };
-                    match stmt {
-                        Stmt::Assign(ref lhs, ref rhs, _) => {
-                            let mut stmts = create_footprint_asserts(rhs, PermAmount::Read);
-                            stmts.push(ast.abstract_assign(lhs.to_viper(ast), rhs.to_viper(ast)));
-                            ast.seqn(stmts.as_slice(), &[])
-                        }
-                        Stmt::Exhale(ref expr, ref pos) => {
-                            assert!(!pos.is_default());
-                            let mut stmts = create_footprint_asserts(expr, PermAmount::Read);
-                            stmts.push(ast.exhale(expr.to_viper(ast), pos.to_viper(ast)));
-                            ast.seqn(stmts.as_slice(), &[])
-                        }
-                        Stmt::Fold(ref pred_name, ref args, perm, ref _variant, ref pos) => {
-                            assert_eq!(args.len(), 1);
-                            let place = &args[0];
-                            assert!(place.is_place());
-                            let mut stmts = create_footprint_asserts(place, PermAmount::Read);
-                            stmts.push(ast.fold_with_pos(
-                                ast.predicate_access_predicate_with_pos(
-                                    ast.predicate_access_with_pos(
-                                        &args.to_viper(ast),
-                                        &pred_name,
-                                        pos.to_viper(ast),
-                                    ),
-                                    perm.to_viper(ast),
-                                    pos.to_viper(ast),
-                                ),
-                                pos.to_viper(ast),
-                            ));
-                            ast.seqn(stmts.as_slice(), &[])
-                        }
-                        Stmt::If(ref guard, ref then_stmts, ref else_stmts) => {
-                            let then_stmts: Vec<_> = then_stmts.iter()
-                                .map(|stmt| stmt_to_viper_in_packge(stmt, ast))
-                                .collect();
-                            let else_stmts: Vec<_> = else_stmts.iter()
-                                .map(|stmt| stmt_to_viper_in_packge(stmt, ast))
-                                .collect();
-                            ast.if_stmt(
-                                guard.to_viper(ast),
-                                ast.seqn(&then_stmts, &[]),
-                                ast.seqn(&else_stmts, &[]),
-                            )
-                        }
-                        _ => stmt.to_viper(ast),
-                    }
-                }
-                let stmts: Vec<_> = package_stmts
-                    .iter()
-                    .map(|stmt| stmt_to_viper_in_packge(stmt, ast))
-                    .collect();
+                // Silicon needs help in showing that it has access to the needed paths. The
+                // necessary footprint assertions are added by `fixes::fix_package_footprints`.
+                let stmts = package_stmts.to_viper(ast);
                 let var_decls: Vec<_> = vars
                     .into_iter()
                     .map(|var| var.to_viper_decl(ast).into())
@@ -244,22 +269,10 @@ impl<'v> ToViper<'v, viper::Stmt<'v>> for Stmt {
                 )
             }
             Stmt::ApplyMagicWand(ref wand, ref pos) => {
-                let inhale = if let Expr::MagicWand(_, _, Some(borrow), _) = wand {
-                    let borrow: usize = borrow_id(*borrow);
-                    let borrow: Expr = borrow.into();
-                    ast.inhale(
-                        ast.predicate_access_predicate(
-                            ast.predicate_access(&[borrow.to_viper(ast)], "DeadBorrowToken$"),
-                            ast.full_perm(),
-                        ),
-                        pos.to_viper(ast),
-                    )
-                } else {
-                    unreachable!()
-                };
+                // The dead borrow token that the wand requires is inhaled by
+                // a separate statement, which is added by the lowering.
                 let position = ast.identifier_position(pos.line(), pos.column(), &pos.id().to_string());
-                let apply = ast.apply(wand.to_viper(ast), position);
-                ast.seqn(&[inhale, apply], &[])
+                ast.apply(wand.to_viper(ast), position)
             }
             Stmt::ExpireBorrows(_) => {
                 // Skip
@@ -6,32 +6,47 @@ use quote::quote_spanned;
 
 #[proc_macro_attribute]
 pub fn requires(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
-    tokens
+    prusti_specs::erase_ghost_params(tokens.into()).into()
 }
 
 #[proc_macro_attribute]
 pub fn ensures(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
-    tokens
+    prusti_specs::erase_ghost_params(tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn refine_ensures(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    prusti_specs::erase_ghost_params(tokens.into()).into()
 }
 
 #[proc_macro_attribute]
 pub fn after_expiry(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
-    tokens
+    prusti_specs::erase_ghost_params(tokens.into()).into()
 }
 
 #[proc_macro_attribute]
 pub fn after_expiry_if(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
-    tokens
+    prusti_specs::erase_ghost_params(tokens.into()).into()
 }
 
 #[proc_macro_attribute]
 pub fn pure(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
-    tokens
+    prusti_specs::erase_ghost_params(tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn abstraction(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    prusti_specs::erase_ghost_params(tokens.into()).into()
 }
 
 #[proc_macro_attribute]
 pub fn trusted(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
-    tokens
+    prusti_specs::erase_ghost_params(tokens.into()).into()
+}
+
+#[proc_macro_attribute]
+pub fn trusted_unsafe(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    prusti_specs::erase_ghost_params(tokens.into()).into()
 }
 
 #[proc_macro]
@@ -45,6 +60,11 @@ pub fn closure(tokens: TokenStream) -> TokenStream {
     prusti_specs::closure(tokens.into(), true).into()
 }
 
+#[proc_macro]
+pub fn spec_library(tokens: TokenStream) -> TokenStream {
+    prusti_specs::spec_library(tokens.into()).into()
+}
+
 #[proc_macro_attribute]
 pub fn refine_trait_spec(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
     tokens
@@ -59,3 +79,8 @@ pub fn extern_spec(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
 pub fn predicate(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
     tokens
 }
+
+#[proc_macro_attribute]
+pub fn history_invariant(_attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    tokens
+}
@@ -13,6 +13,11 @@ pub fn ensures(attr: TokenStream, tokens: TokenStream) -> TokenStream {
     rewrite_prusti_attributes(SpecAttributeKind::Ensures, attr.into(), tokens.into()).into()
 }
 
+#[proc_macro_attribute]
+pub fn refine_ensures(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::RefineEnsures, attr.into(), tokens.into()).into()
+}
+
 #[proc_macro_attribute]
 pub fn after_expiry(attr: TokenStream, tokens: TokenStream) -> TokenStream {
     rewrite_prusti_attributes(SpecAttributeKind::AfterExpiry, attr.into(), tokens.into()).into()
@@ -28,11 +33,21 @@ pub fn pure(attr: TokenStream, tokens: TokenStream) -> TokenStream {
     rewrite_prusti_attributes(SpecAttributeKind::Pure, attr.into(), tokens.into()).into()
 }
 
+#[proc_macro_attribute]
+pub fn abstraction(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::Abstraction, attr.into(), tokens.into()).into()
+}
+
 #[proc_macro_attribute]
 pub fn trusted(attr: TokenStream, tokens: TokenStream) -> TokenStream {
     rewrite_prusti_attributes(SpecAttributeKind::Trusted, attr.into(), tokens.into()).into()
 }
 
+#[proc_macro_attribute]
+pub fn trusted_unsafe(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    rewrite_prusti_attributes(SpecAttributeKind::TrustedUnsafe, attr.into(), tokens.into()).into()
+}
+
 #[proc_macro]
 pub fn body_invariant(tokens: TokenStream) -> TokenStream {
     prusti_specs::body_invariant(tokens.into()).into()
@@ -43,6 +58,11 @@ pub fn closure(tokens: TokenStream) -> TokenStream {
     prusti_specs::closure(tokens.into(), false).into()
 }
 
+#[proc_macro]
+pub fn spec_library(tokens: TokenStream) -> TokenStream {
+    prusti_specs::spec_library(tokens.into()).into()
+}
+
 #[proc_macro_attribute]
 pub fn refine_trait_spec(attr: TokenStream, tokens: TokenStream) -> TokenStream {
     prusti_specs::refine_trait_spec(attr.into(), tokens.into()).into()
@@ -57,3 +77,8 @@ pub fn extern_spec(attr: TokenStream, tokens: TokenStream) -> TokenStream {
 pub fn predicate(attr: TokenStream, tokens: TokenStream) -> TokenStream {
     prusti_specs::predicate(attr.into(), tokens.into()).into()
 }
+
+#[proc_macro_attribute]
+pub fn history_invariant(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    prusti_specs::history_invariant(attr.into(), tokens.into()).into()
+}
@@ -8,6 +8,10 @@ mod private {
     /// A macro for writing a postcondition on a function.
     pub use prusti_contracts_impl::ensures;
 
+    /// A macro for writing a postcondition that holds only when the type
+    /// parameters satisfy extra bounds.
+    pub use prusti_contracts_impl::refine_ensures;
+
     /// A macro for writing a pledge on a function.
     pub use prusti_contracts_impl::after_expiry;
 
@@ -17,15 +21,26 @@ mod private {
     /// A macro for marking a function as pure.
     pub use prusti_contracts_impl::pure;
 
+    /// A macro for marking a pure function as an abstraction, whose
+    /// definition is only visible in the module that defines it.
+    pub use prusti_contracts_impl::abstraction;
+
     /// A macro for marking a function as trusted.
     pub use prusti_contracts_impl::trusted;
 
+    /// A macro for marking a function that contains unsafe code as trusted.
+    pub use prusti_contracts_impl::trusted_unsafe;
+
     /// A macro for writing a loop body invariant.
     pub use prusti_contracts_impl::body_invariant;
 
     /// A macro for defining a closure with a specification.
     pub use prusti_contracts_impl::closure;
 
+    /// A macro for defining a specification library, whose pure functions,
+    /// predicates and external specifications other crates can import.
+    pub use prusti_contracts_impl::spec_library;
+
     /// A macro for impl blocks that refine trait specifications.
     pub use prusti_contracts_impl::refine_trait_spec;
 
@@ -35,6 +50,10 @@ mod private {
     /// A macro for defining a predicate using prusti expression syntax instead
     /// of just Rust expressions.
     pub use prusti_contracts_impl::predicate;
+
+    /// A macro for specifying how the values of a type may evolve in the
+    /// methods that mutate it.
+    pub use prusti_contracts_impl::history_invariant;
 }
 
 #[cfg(feature = "prusti")]
@@ -45,6 +64,10 @@ mod private {
     /// A macro for writing a postcondition on a function.
     pub use prusti_contracts_internal::ensures;
 
+    /// A macro for writing a postcondition that holds only when the type
+    /// parameters satisfy extra bounds.
+    pub use prusti_contracts_internal::refine_ensures;
+
     /// A macro for writing a pledge on a function.
     pub use prusti_contracts_internal::after_expiry;
 
@@ -54,15 +77,26 @@ mod private {
     /// A macro for marking a function as pure.
     pub use prusti_contracts_internal::pure;
 
+    /// A macro for marking a pure function as an abstraction, whose
+    /// definition is only visible in the module that defines it.
+    pub use prusti_contracts_internal::abstraction;
+
     /// A macro for marking a function as trusted.
     pub use prusti_contracts_internal::trusted;
 
+    /// A macro for marking a function that contains unsafe code as trusted.
+    pub use prusti_contracts_internal::trusted_unsafe;
+
     /// A macro for writing a loop body invariant.
     pub use prusti_contracts_internal::body_invariant;
 
     /// A macro for defining a closure with a specification.
     pub use prusti_contracts_internal::closure;
 
+    /// A macro for defining a specification library, whose pure functions,
+    /// predicates and external specifications other crates can import.
+    pub use prusti_contracts_internal::spec_library;
+
     /// A macro for impl blocks that refine trait specifications.
     pub use prusti_contracts_internal::refine_trait_spec;
 
@@ -72,6 +106,10 @@ mod private {
     /// A macro for defining a predicate using prusti expression syntax instead
     /// of just Rust expressions.
     pub use prusti_contracts_internal::predicate;
+
+    /// A macro for specifying how the values of a type may evolve in the
+    /// methods that mutate it.
+    pub use prusti_contracts_internal::history_invariant;
 }
 
 
@@ -81,10 +119,127 @@ pub fn before_expiry<T>(arg: T) -> T {
     arg
 }
 
+/// This function is used to check a specification when the program runs,
+/// if Prusti is configured to emit runtime checks. Like `debug_assert!`, the
+/// check is skipped in release builds.
+#[doc(hidden)]
+pub fn runtime_check(condition: bool, message: &str) {
+    debug_assert!(condition, "{}", message);
+}
+
 /// This function is used to evaluate an expression in the “old”
 /// context, that is at the beginning of the method call.
 pub fn old<T>(arg: T) -> T {
     arg
 }
 
+/// This function is used in preconditions and postconditions to transfer
+/// `amount` credits of the kind `K` (a marker type) between a function and
+/// its callers: a precondition `credits::<K>(n)` takes `n` credits from the
+/// caller, a postcondition gives them back. It can only be used as a
+/// top-level conjunct of a contract.
+pub fn credits<K>(_amount: usize) -> bool {
+    true
+}
+
+/// This function is used to create credits of the kind `K` out of nothing,
+/// usually through `obtain_credits!`. Since it does not consume anything,
+/// it should be used where the resource being modelled is created.
+pub fn obtain_credits<K>(_amount: usize) {}
+
+/// This function is used to consume credits of the kind `K`, usually
+/// through `consume_credits!`. Prusti checks that enough credits are
+/// available.
+pub fn consume_credits<K>(_amount: usize) {}
+
+/// A macro for obtaining credits of a kind, e.g. `obtain_credits!(Tick, 3)`.
+#[macro_export]
+macro_rules! obtain_credits {
+    ($kind:ty, $amount:expr) => {
+        $crate::obtain_credits::<$kind>($amount)
+    };
+}
+
+/// A macro for consuming credits of a kind, e.g. `consume_credits!(Tick, 1)`.
+#[macro_export]
+macro_rules! consume_credits {
+    ($kind:ty, $amount:expr) => {
+        $crate::consume_credits::<$kind>($amount)
+    };
+}
+
+/// The type of a ghost parameter (a parameter marked with `#[ghost]`) when
+/// the program is compiled without Prusti. It has no runtime representation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ghost<T>(std::marker::PhantomData<T>);
+
+impl<T> Ghost<T> {
+    /// Typechecks the argument of `ghost!` without evaluating it.
+    #[doc(hidden)]
+    pub fn erased<F: FnOnce()>(_arg: F) -> Self {
+        Ghost(std::marker::PhantomData)
+    }
+}
+
+/// A macro for passing an argument to a ghost parameter, e.g.
+/// `v.push(x, ghost!(v.len()))`. The argument is only evaluated by Prusti.
+#[cfg(feature = "prusti")]
+#[macro_export]
+macro_rules! ghost {
+    ($arg:expr) => {
+        $arg
+    };
+}
+
+/// A macro for passing an argument to a ghost parameter, e.g.
+/// `v.push(x, ghost!(v.len()))`. The argument is only evaluated by Prusti.
+#[cfg(not(feature = "prusti"))]
+#[macro_export]
+macro_rules! ghost {
+    ($arg:expr) => {
+        $crate::Ghost::erased(|| { let _ = &($arg); })
+    };
+}
+
+/// Arithmetic lemmas that Prusti knows to hold. Calling one of these
+/// functions (usually through `prusti_use_lemma!`) makes the stated property
+/// available to the verifier for the given arguments, which helps with
+/// nonlinear arithmetic that the SMT solver cannot prove on its own. At
+/// runtime the calls do nothing.
+///
+/// Division and remainder lemmas only talk about non-negative dividends,
+/// because only there Rust's truncating division agrees with the Euclidean
+/// one of the verifier.
+pub mod lemmas {
+    /// `0 <= c && a <= b ==> a * c <= b * c`
+    pub fn mul_mono<T>(_a: T, _b: T, _c: T) {}
+
+    /// `0 < c && a < b ==> a * c < b * c`
+    pub fn mul_strict_mono<T>(_a: T, _b: T, _c: T) {}
+
+    /// `0 <= a && 0 <= b ==> 0 <= a * b`
+    pub fn mul_nonneg<T>(_a: T, _b: T) {}
+
+    /// `0 <= a * a`
+    pub fn square_nonneg<T>(_a: T) {}
+
+    /// `0 <= a && 0 < b ==> a == (a / b) * b + a % b && 0 <= a % b && a % b < b`
+    pub fn div_mod<T>(_a: T, _b: T) {}
+
+    /// `0 <= a && a <= b && 0 < c ==> a / c <= b / c`
+    pub fn div_mono<T>(_a: T, _b: T, _c: T) {}
+
+    /// `0 < b ==> (a * b) / b == a`
+    pub fn mul_div_cancel<T>(_a: T, _b: T) {}
+}
+
+/// A macro for instantiating one of the arithmetic `lemmas` in a proof,
+/// e.g. `prusti_use_lemma!(mul_mono(x, y, z))`.
+#[macro_export]
+macro_rules! prusti_use_lemma {
+    ($lemma:ident ( $($arg:expr),* $(,)? )) => {
+        $crate::lemmas::$lemma($($arg),*)
+    };
+}
+
 pub use private::*;
@@ -0,0 +1,24 @@
+// These feature flags are not needed when executing under Prusti
+// because it generates them for us.
+#![feature(register_tool)]
+#![register_tool(prusti)]
+
+use prusti_contracts::*;
+
+struct Stack {
+    items: Vec<u32>,
+}
+
+impl Stack {
+    #[requires(old_len == self.items.len())]
+    #[ensures(self.items.len() == old_len + 1)]
+    fn push(&mut self, x: u32, #[ghost] old_len: usize) {
+        self.items.push(x);
+    }
+}
+
+fn main() {
+    let mut stack = Stack { items: vec![] };
+    stack.push(1, ghost!(stack.items.len()));
+    assert_eq!(std::mem::size_of::<Ghost<usize>>(), 0);
+}
@@ -0,0 +1,35 @@
+// These feature flags are not needed when executing under Prusti
+// because it generates them for us.
+#![feature(register_tool)]
+#![register_tool(prusti)]
+
+use prusti_contracts::*;
+
+spec_library! {
+    #[pure]
+    #[ensures(result >= a && result >= b)]
+    pub fn max(a: i32, b: i32) -> i32 {
+        if a > b { a } else { b }
+    }
+}
+
+spec_library! {
+    macro import_min_specs;
+
+    #[pure]
+    #[ensures(result <= a && result <= b)]
+    pub fn min(a: i32, b: i32) -> i32 {
+        if a < b { a } else { b }
+    }
+}
+
+mod imported {
+    use prusti_contracts::*;
+    import_specs!();
+    import_min_specs!();
+}
+
+fn main() {
+    assert_eq!(max(1, 2), imported::max(1, 2));
+    assert_eq!(min(1, 2), imported::min(1, 2));
+}
@@ -62,7 +62,9 @@ impl<'a, 'tcx> ItemLikeVisitor<'tcx> for CollectPrustiSpecVisitor<'a, 'tcx> {
             return;
         }
 
-        // Skip body-less trait methods
+        // Skip body-less trait methods. Default method bodies are verified
+        // once here, against the contracts of the trait; impls that don't
+        // override them have nothing left to verify.
         if let hir::TraitItemKind::Fn(_, hir::TraitFn::Required(_)) = trait_item.kind {
             return;
         }
@@ -300,7 +300,9 @@ impl ProcedureLoops {
         // Here, we identify the CFG blocks that decide whether to exit from the loop or not.
         // They are those blocks in the loop that:
         // 1. have a SwitchInt terminator
-        // 2. have an out-edge that exists from the loop
+        // 2. have an out-edge that exists from the loop, possibly through a
+        //    block that only assigns the value of a `break value` (or of the
+        //    result of a `while let`) before jumping out of the loop.
         let mut loop_exit_blocks = HashMap::new();
         let mut nonconditional_loop_blocks = HashMap::new();
         for &loop_head in loop_heads.iter() {
@@ -327,8 +329,15 @@ impl ProcedureLoops {
                     mir::TerminatorKind::SwitchInt { .. } => true,
                     _ => false
                 };
+                let exits_loop = |bb: BasicBlockIndex| get_loop_depth(bb) < loop_head_depth;
+                let breaks_loop = |bb: BasicBlockIndex| {
+                    loop_body.contains(&bb) && match mir[bb].terminator().kind {
+                        mir::TerminatorKind::Goto { target } => exits_loop(target),
+                        _ => false,
+                    }
+                };
                 let has_exit_edge = real_edges.successors(curr_bb).iter().any(
-                    |&bb| get_loop_depth(bb) < loop_head_depth
+                    |&bb| exits_loop(bb) || breaks_loop(bb)
                 );
                 if is_switch_int && has_exit_edge {
                     exit_blocks.push(curr_bb);
@@ -444,6 +453,24 @@ impl ProcedureLoops {
         }
     }
 
+    /// Get the last block that is executed in every iteration of the loop
+    /// before ``bbi``. This is ``bbi`` itself if it is not in a conditional
+    /// branch of the loop.
+    pub fn get_nonconditional_dominator(
+        &self,
+        loop_head: BasicBlockIndex,
+        bbi: BasicBlockIndex,
+    ) -> BasicBlockIndex {
+        debug_assert!(self.is_block_in_loop(loop_head, bbi));
+        let nonconditional_blocks = &self.nonconditional_loop_blocks[&loop_head];
+        let mut curr_bb = bbi;
+        // Terminates because the loop head is nonconditional and dominates `bbi`.
+        while !nonconditional_blocks.contains(&curr_bb) {
+            curr_bb = self.dominators.immediate_dominator(curr_bb);
+        }
+        curr_bb
+    }
+
     /// Check if ``block`` is inside a given loop.
     pub fn is_block_in_loop(&self, loop_head: BasicBlockIndex, block: BasicBlockIndex) -> bool {
         self.dominators.is_dominated_by(block, loop_head)
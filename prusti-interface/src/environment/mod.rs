@@ -15,7 +15,8 @@ use rustc_middle::ty::{self, TyCtxt, ParamEnv, WithOptConstParam};
 use std::path::PathBuf;
 use std::cell::Ref;
 use rustc_span::{Span, MultiSpan, symbol::Symbol};
-use std::collections::HashSet;
+use rustc_errors::DiagnosticId;
+use std::collections::{HashMap, HashSet};
 use log::debug;
 
 pub mod borrowck;
@@ -115,10 +116,14 @@ impl<'tcx> Environment<'tcx> {
         sp: S,
         msg: &str,
         help: &Option<String>,
-        note: &Option<(String, S)>
+        note: &Option<(String, S)>,
+        code: &Option<String>,
     ) {
         let mut diagnostic = self.tcx.sess.struct_err(msg);
         diagnostic.set_span(sp);
+        if let Some(code) = code {
+            diagnostic.code(DiagnosticId::Error(code.clone()));
+        }
         if let Some(help_msg) = help {
             diagnostic.help(help_msg);
         }
@@ -134,10 +139,14 @@ impl<'tcx> Environment<'tcx> {
         sp: S,
         msg: &str,
         help: &Option<String>,
-        note: &Option<(String, S)>
+        note: &Option<(String, S)>,
+        code: &Option<String>,
     ) {
         let mut diagnostic = self.tcx.sess.struct_warn(msg);
         diagnostic.set_span(sp);
+        if let Some(code) = code {
+            diagnostic.code(DiagnosticId::Error(code.clone()));
+        }
         if let Some(help_msg) = help {
             diagnostic.help(help_msg);
         }
@@ -166,12 +175,169 @@ impl<'tcx> Environment<'tcx> {
         result
     }
 
+    /// Get the procedures among `procedures` that are reachable from the
+    /// `main` function of the crate, ordered so that callees come before
+    /// their callers. Closures are considered reachable together with the
+    /// function that defines them. Returns `None` if the crate has no `main`
+    /// function.
+    pub fn get_procedures_reachable_from_main(
+        &self,
+        procedures: &[ProcedureDefId],
+    ) -> Option<Vec<ProcedureDefId>> {
+        let tcx = self.tcx();
+        let main = *procedures.iter().find(|&&def_id| tcx.def_path_str(def_id) == "main")?;
+        let candidates: HashSet<_> = procedures.iter().cloned().collect();
+        let mut visited = HashSet::new();
+        let mut result = vec![];
+        // Depth-first search, emitting each procedure after its callees.
+        let mut stack = vec![(main, false)];
+        while let Some((def_id, callees_done)) = stack.pop() {
+            if callees_done {
+                result.push(def_id);
+                continue;
+            }
+            if !visited.insert(def_id) {
+                continue;
+            }
+            stack.push((def_id, true));
+            for callee in self.get_called_procedures(def_id) {
+                if candidates.contains(&callee) && !visited.contains(&callee) {
+                    stack.push((callee, false));
+                }
+            }
+            for &closure in procedures {
+                if tcx.is_closure(closure) && tcx.closure_base_def_id(closure) == def_id &&
+                    !visited.contains(&closure)
+                {
+                    stack.push((closure, false));
+                }
+            }
+        }
+        Some(result)
+    }
+
+    /// Get the cycles of mutually recursive procedures, i.e. the strongly
+    /// connected components of the call graph restricted to `procedures` that
+    /// contain a recursive call.
+    pub fn get_recursive_cycles(
+        &self,
+        procedures: &[ProcedureDefId],
+    ) -> Vec<Vec<ProcedureDefId>> {
+        let candidates: HashSet<_> = procedures.iter().cloned().collect();
+        let callees: HashMap<ProcedureDefId, Vec<ProcedureDefId>> = procedures
+            .iter()
+            .map(|&def_id| {
+                let mut called = self.get_called_procedures(def_id);
+                // Remove the duplicates, which need not be adjacent.
+                let mut seen = HashSet::new();
+                called.retain(|&callee| candidates.contains(&callee) && seen.insert(callee));
+                (def_id, called)
+            })
+            .collect();
+
+        // Tarjan's algorithm, with an explicit stack of (procedure, index of
+        // the next callee to visit).
+        let mut index: HashMap<ProcedureDefId, usize> = HashMap::new();
+        let mut low_link: HashMap<ProcedureDefId, usize> = HashMap::new();
+        let mut on_stack = HashSet::new();
+        let mut component_stack = vec![];
+        let mut cycles = vec![];
+        for &root in procedures {
+            if index.contains_key(&root) {
+                continue;
+            }
+            let mut work = vec![(root, 0)];
+            while let Some((def_id, next_callee)) = work.pop() {
+                if next_callee == 0 {
+                    index.insert(def_id, index.len());
+                    low_link.insert(def_id, index[&def_id]);
+                    component_stack.push(def_id);
+                    on_stack.insert(def_id);
+                }
+                if let Some(&callee) = callees[&def_id].get(next_callee) {
+                    work.push((def_id, next_callee + 1));
+                    if !index.contains_key(&callee) {
+                        work.push((callee, 0));
+                    } else if on_stack.contains(&callee) {
+                        let low = low_link[&def_id].min(index[&callee]);
+                        low_link.insert(def_id, low);
+                    }
+                    continue;
+                }
+                if low_link[&def_id] == index[&def_id] {
+                    let mut component = vec![];
+                    loop {
+                        let member = component_stack.pop().unwrap();
+                        on_stack.remove(&member);
+                        component.push(member);
+                        if member == def_id {
+                            break;
+                        }
+                    }
+                    let is_recursive = component.len() > 1 || callees[&def_id].contains(&def_id);
+                    if is_recursive {
+                        component.reverse();
+                        cycles.push(component);
+                    }
+                }
+                if let Some(&(caller, _)) = work.last() {
+                    let low = low_link[&caller].min(low_link[&def_id]);
+                    low_link.insert(caller, low);
+                }
+            }
+        }
+        cycles
+    }
+
+    /// Get the procedures called in the body of a local procedure, resolving
+    /// trait methods to their implementation when it is known.
+    fn get_called_procedures(&self, def_id: ProcedureDefId) -> Vec<ProcedureDefId> {
+        let tcx = self.tcx();
+        let local_def_id = match def_id.as_local() {
+            Some(local_def_id) => local_def_id,
+            None => return vec![],
+        };
+        let param_env = tcx.param_env(def_id);
+        let mir = self.local_mir(local_def_id);
+        mir.basic_blocks()
+            .iter()
+            .filter_map(|bb_data| match bb_data.terminator().kind {
+                mir::TerminatorKind::Call { ref func, .. } => {
+                    match func.ty(&*mir, tcx).kind() {
+                        ty::TyKind::FnDef(callee_def_id, substs) => {
+                            let resolved = ty::Instance::resolve(tcx, param_env, *callee_def_id, substs)
+                                .ok()
+                                .flatten()
+                                .map(|instance| instance.def_id());
+                            Some(resolved.unwrap_or(*callee_def_id))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Find whether the procedure has a particular `prusti::<name>` attribute.
     pub fn has_prusti_attribute(&self, def_id: ProcedureDefId, name: &str) -> bool {
         let tcx = self.tcx();
         crate::utils::has_prusti_attr(tcx.get_attrs(def_id), name)
     }
 
+    /// Read a configuration flag that is overridden for a single item with
+    /// `#[prusti::<name>(true)]` or `#[prusti::<name>(false)]`. An argument
+    /// that is not a boolean is returned as error.
+    pub fn get_item_config_flag(&self, def_id: DefId, name: &str) -> Result<Option<bool>, String> {
+        let attrs = self.tcx().get_attrs(def_id);
+        match crate::utils::read_prusti_attr_arg(name, attrs).as_deref() {
+            Some("true") => Ok(Some(true)),
+            Some("false") => Ok(Some(false)),
+            Some(value) => Err(value.to_string()),
+            None => Ok(None),
+        }
+    }
+
     /// Dump various information from the borrow checker.
     ///
     /// Mostly used for experiments and debugging.
@@ -216,6 +382,18 @@ impl<'tcx> Environment<'tcx> {
         ).0.borrow()
     }
 
+    /// Find whether a local procedure is an `unsafe fn` or contains an
+    /// `unsafe` block, according to the safety of the scopes of its MIR.
+    pub fn contains_unsafe_code(&self, def_id: LocalDefId) -> bool {
+        let mir = self.local_mir(def_id);
+        mir.source_scopes.iter().any(|scope| {
+            match scope.local_data.as_ref().assert_crate_local().safety {
+                mir::Safety::FnUnsafe | mir::Safety::ExplicitUnsafe(_) => true,
+                mir::Safety::Safe | mir::Safety::BuiltinUnsafe => false,
+            }
+        })
+    }
+
     /// Get the MIR body of an external procedure.
     pub fn external_mir<'a>(&self, def_id: DefId) -> &'a mir::Body<'tcx> {
         self.tcx().optimized_mir(def_id)
@@ -255,6 +433,81 @@ impl<'tcx> Environment<'tcx> {
         result
     }
 
+    /// Get the `Drop::drop` implementation that is called when a value of
+    /// type `ty` is dropped, if there is one.
+    pub fn get_drop_impl(&self, ty: ty::Ty<'tcx>) -> Option<ProcedureDefId> {
+        match ty.kind() {
+            ty::TyKind::Adt(adt_def, _) => self.tcx.adt_destructor(adt_def.did)
+                .map(|destructor| destructor.did),
+            _ => None,
+        }
+    }
+
+    /// Find a type that is reachable from `ty` and that implements `Send`
+    /// because of a hand-written `unsafe impl Send` of the local crate. Such an
+    /// implementation may allow values that alias across threads.
+    pub fn find_unchecked_send_type(&self, ty: ty::Ty<'tcx>) -> Option<ty::Ty<'tcx>> {
+        let send_trait = self.tcx.get_diagnostic_item(rustc_span::sym::send_trait)?;
+        let unchecked_send_adts: HashSet<DefId> = self.tcx.all_impls(send_trait)
+            .filter(|impl_def_id| impl_def_id.is_local())
+            .filter(|&impl_def_id| self.tcx.impl_polarity(impl_def_id) == ty::ImplPolarity::Positive)
+            .filter_map(|impl_def_id| self.tcx.impl_trait_ref(impl_def_id))
+            .filter_map(|trait_ref| match trait_ref.self_ty().kind() {
+                ty::TyKind::Adt(adt_def, _) => Some(adt_def.did),
+                _ => None,
+            })
+            .collect();
+        if unchecked_send_adts.is_empty() {
+            return None;
+        }
+        let mut visited = HashSet::new();
+        let mut to_visit = vec![ty];
+        while let Some(curr_ty) = to_visit.pop() {
+            if !visited.insert(curr_ty) {
+                continue;
+            }
+            for arg in curr_ty.walk() {
+                if let ty::subst::GenericArgKind::Type(inner_ty) = arg.unpack() {
+                    if let ty::TyKind::Adt(adt_def, substs) = inner_ty.kind() {
+                        if unchecked_send_adts.contains(&adt_def.did) {
+                            return Some(inner_ty);
+                        }
+                        // The fields of local types may contain other local types.
+                        if adt_def.did.is_local() {
+                            to_visit.extend(adt_def.all_fields().map(|f| f.ty(self.tcx, substs)));
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Is the implementation of the trait `trait_def_id` for the type `ty`
+    /// generated by `#[derive(..)]`?
+    pub fn has_derived_impl(&self, trait_def_id: DefId, ty: ty::Ty<'tcx>) -> bool {
+        let adt_did = match ty.kind() {
+            ty::TyKind::Adt(adt_def, _) => adt_def.did,
+            _ => return false,
+        };
+        self.tcx.all_impls(trait_def_id)
+            .filter(|&impl_def_id| {
+                self.tcx.impl_trait_ref(impl_def_id).map_or(false, |trait_ref| {
+                    matches!(trait_ref.self_ty().kind(), ty::TyKind::Adt(adt_def, _) if adt_def.did == adt_did)
+                })
+            })
+            .any(|impl_def_id| {
+                self.tcx.has_attr(impl_def_id, rustc_span::sym::automatically_derived)
+            })
+    }
+
+    /// Is `def_id` the `drop` method of an implementation of `Drop`?
+    pub fn is_drop_impl(&self, def_id: ProcedureDefId) -> bool {
+        self.tcx.impl_of_method(def_id)
+            .and_then(|impl_def_id| self.tcx.trait_id_of_impl(impl_def_id))
+            .map_or(false, |trait_def_id| Some(trait_def_id) == self.tcx.lang_items().drop_trait())
+    }
+
     pub fn type_is_copy(&self, ty: ty::Ty<'tcx>) -> bool {
         let copy_trait = self.tcx.lang_items().copy_trait();
         if let Some(copy_trait_def_id) = copy_trait {
@@ -550,6 +550,38 @@ fn remove_back_edges(
     all_facts
 }
 
+/// Remove the loans of the shallow borrows that are created when lowering the
+/// guards of a `match`. They only prevent the guards from modifying the
+/// matched place, have no effect at runtime, and are never encoded.
+fn remove_fake_borrows(
+    mut all_facts: facts::AllInputFacts,
+    interner: &facts::Interner,
+    mir: &mir::Body,
+) -> facts::AllInputFacts {
+    let fake_loans: HashSet<facts::Loan> = all_facts.borrow_region.iter()
+        .filter(|&&(_, _, point)| {
+            let location = interner.get_point(point).location;
+            match mir.statement_at(location) {
+                Some(mir::Statement {
+                    kind: mir::StatementKind::Assign(
+                        box (_, mir::Rvalue::Ref(_, mir::BorrowKind::Shallow, _))
+                    ),
+                    ..
+                }) => true,
+                _ => false,
+            }
+        })
+        .map(|&(_, loan, _)| loan)
+        .collect();
+    if !fake_loans.is_empty() {
+        debug!("remove fake borrows: {:?}", fake_loans);
+        all_facts.borrow_region.retain(|(_, loan, _)| !fake_loans.contains(loan));
+        all_facts.killed.retain(|(loan, _)| !fake_loans.contains(loan));
+        all_facts.invalidates.retain(|(_, loan)| !fake_loans.contains(loan));
+    }
+    all_facts
+}
+
 /// Returns the place that is borrowed by the assignment. We assume that
 /// all shared references are created only via assignments and ignore
 /// all other cases.
@@ -687,7 +719,7 @@ impl<'a, 'tcx: 'a> PoloniusInfo<'a, 'tcx> {
 
         let mut call_magic_wands = HashMap::new();
 
-        let mut all_facts = facts_loader.facts;
+        let mut all_facts = remove_fake_borrows(facts_loader.facts, &facts_loader.interner, mir);
 
         let real_edges = RealEdges::new(&mir);
         let loop_info = loops::ProcedureLoops::new(&mir, &real_edges);
@@ -25,6 +25,8 @@ pub struct PrustiError {
     span: MultiSpan,
     help: Option<String>,
     note: Option<(String, MultiSpan)>,
+    /// The stable code that classifies the error (e.g. `P0101`).
+    code: Option<String>,
 }
 
 impl PrustiError {
@@ -36,6 +38,7 @@ impl PrustiError {
             span,
             help: None,
             note: None,
+            code: None,
         }
     }
 
@@ -70,6 +73,18 @@ impl PrustiError {
         )
     }
 
+    /// Report a suspicious but valid usage of Prusti (e.g. a postcondition
+    /// that holds trivially)
+    pub fn warning<S: ToString>(message: S, span: MultiSpan) -> Self {
+        check_message(message.to_string());
+        let mut error = PrustiError::new(
+            format!("[Prusti: warning] {}", message.to_string()),
+            span
+        );
+        error.set_warning();
+        error
+    }
+
     /// Report an internal error of Prusti (e.g. failure of the fold-unfold)
     pub fn internal<S: ToString>(message: S, span: MultiSpan) -> Self {
         check_message(message.to_string());
@@ -93,6 +108,28 @@ impl PrustiError {
         self
     }
 
+    /// Add a help message, after the existing one if there is any.
+    pub fn add_help<S: ToString>(mut self, message: S) -> Self {
+        self.help = Some(match self.help.take() {
+            Some(help) => format!("{}\n{}", help, message.to_string()),
+            None => message.to_string(),
+        });
+        self
+    }
+
+    pub fn set_code<S: ToString>(mut self, code: S) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn span(&self) -> &MultiSpan {
+        &self.span
+    }
+
     pub fn set_note<S: ToString>(mut self, note: S, note_span: Span) -> Self {
         self.note = Some((note.to_string(), MultiSpan::from_span(note_span)));
         self
@@ -106,6 +143,7 @@ impl PrustiError {
                 &self.message,
                 &self.help,
                 &self.note,
+                &self.code,
             );
         } else {
             env.span_warn_with_help_and_note(
@@ -113,6 +151,7 @@ impl PrustiError {
                 &self.message,
                 &self.help,
                 &self.note,
+                &self.code,
             );
         }
     }
@@ -127,6 +166,18 @@ impl PrustiError {
         self
     }
 
+    /// Note that the error is in code generated by the given macro, unless
+    /// the error already has a note.
+    pub fn set_macro_expansion_note(mut self, macro_name: &str, expansion_span: Span) -> Self {
+        if self.note.is_none() {
+            self.note = Some((
+                format!("the error originates in the expansion of `{}`", macro_name),
+                MultiSpan::from_span(expansion_span),
+            ));
+        }
+        self
+    }
+
     /// Convert the original error span to a note, and add a new error span.
     ///
     /// Note: this is a noop if `opt_span` is None
@@ -0,0 +1,257 @@
+//! Lints for specifications that are valid, but that are likely not what the
+//! user meant, e.g. a postcondition that only restates a precondition.
+
+use log::debug;
+use rustc_hir::{
+    self as hir,
+    def_id::LocalDefId,
+    intravisit::{self, Visitor},
+};
+use rustc_middle::{hir::map::Map, ty::{self, TyCtxt}};
+use rustc_span::{MultiSpan, Span};
+
+use std::collections::HashMap;
+
+use crate::{environment::Environment, specs::typed, PrustiError};
+
+/// How the procedure can affect an argument of a spec item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ArgKind {
+    /// The `result` of a postcondition.
+    Result,
+    /// An argument that contains a mutable reference.
+    Mutable,
+    /// An argument that is moved into the procedure.
+    Moved,
+    /// Any other argument, e.g. a shared reference or a `Copy` value.
+    Immutable,
+}
+
+/// Warn about the contracts of the crate that are likely wrong.
+pub fn lint_specs<'tcx>(env: &Environment<'tcx>, def_spec: &typed::DefSpecificationMap<'tcx>) {
+    let mut procedures: Vec<_> = def_spec.specs.iter()
+        .filter_map(|(local_id, spec)| match spec {
+            typed::SpecificationSet::Procedure(spec) => Some((*local_id, spec)),
+            _ => None,
+        })
+        .collect();
+    // Report the warnings in the order of the source code.
+    procedures.sort_by_key(|(local_id, _)| env.get_item_span(local_id.to_def_id()));
+    for (local_id, spec) in procedures {
+        debug!("Lint the specification of {:?}", local_id);
+        lint_procedure_spec(env, spec);
+    }
+}
+
+fn lint_procedure_spec<'tcx>(env: &Environment<'tcx>, spec: &typed::ProcedureSpecification<'tcx>) {
+    let tcx = env.tcx();
+    let pres: Vec<_> = spec.pres.iter().map(|pre| ClauseInfo::new(tcx, pre)).collect();
+    for post in &spec.posts {
+        let info = ClauseInfo::new(tcx, post);
+        for &span in &info.redundant_olds {
+            PrustiError::warning(
+                "`old(..)` has no effect on values that the function cannot modify",
+                MultiSpan::from_span(span),
+            ).set_help(
+                "in a postcondition, the arguments that are not mutable references \
+                always refer to their value at the call"
+            ).emit(env);
+        }
+        if info.mentions(ArgKind::Result) || info.mentions(ArgKind::Mutable) {
+            continue;
+        }
+        let warning = if spec.pure {
+            PrustiError::warning(
+                "this postcondition of a pure function does not mention `result`",
+                MultiSpan::from_spans(info.spans.clone()),
+            ).set_help("it only constrains the arguments, so it might be meant as a precondition")
+        } else if pres.iter().any(|pre| pre.snippets == info.snippets) {
+            PrustiError::warning(
+                "this postcondition repeats a precondition, so it holds trivially",
+                MultiSpan::from_spans(info.spans.clone()),
+            ).set_help(
+                "in a postcondition, the arguments that are not mutable references \
+                always refer to their value at the call"
+            )
+        } else if info.mentions(ArgKind::Moved) {
+            PrustiError::warning(
+                "this postcondition only mentions arguments that are moved into the function",
+                MultiSpan::from_spans(info.spans.clone()),
+            ).set_help(
+                "the caller cannot observe these arguments after the call, \
+                so the postcondition should relate them to `result`"
+            )
+        } else {
+            continue;
+        };
+        warning.emit(env);
+    }
+}
+
+/// What a clause of a contract mentions.
+struct ClauseInfo {
+    /// The spans of the Rust expressions of the clause.
+    spans: Vec<Span>,
+    /// The source code of the Rust expressions, with normalized whitespace.
+    snippets: Vec<String>,
+    /// The kinds of the arguments mentioned by the clause.
+    mentioned: Vec<ArgKind>,
+    /// The `old(..)` expressions that only mention arguments that the
+    /// procedure cannot modify.
+    redundant_olds: Vec<Span>,
+}
+
+impl ClauseInfo {
+    fn new<'tcx>(tcx: TyCtxt<'tcx>, assertion: &typed::Assertion<'tcx>) -> Self {
+        let mut info = ClauseInfo {
+            spans: vec![],
+            snippets: vec![],
+            mentioned: vec![],
+            redundant_olds: vec![],
+        };
+        info.collect(tcx, assertion);
+        info
+    }
+
+    fn mentions(&self, kind: ArgKind) -> bool {
+        self.mentioned.contains(&kind)
+    }
+
+    fn collect<'tcx>(&mut self, tcx: TyCtxt<'tcx>, assertion: &typed::Assertion<'tcx>) {
+        match *assertion.kind {
+            typed::AssertionKind::Expr(ref expr) => self.collect_expr(tcx, expr),
+            typed::AssertionKind::And(ref assertions) => {
+                for assertion in assertions {
+                    self.collect(tcx, assertion);
+                }
+            }
+            typed::AssertionKind::Implies(ref lhs, ref rhs) => {
+                self.collect(tcx, lhs);
+                self.collect(tcx, rhs);
+            }
+            typed::AssertionKind::TypeCond(_, ref body) |
+            typed::AssertionKind::ForAll(_, _, ref body) => self.collect(tcx, body),
+            typed::AssertionKind::SpecEntailment { ref closure, .. } => {
+                self.collect_expr(tcx, closure)
+            }
+        }
+    }
+
+    fn collect_expr(&mut self, tcx: TyCtxt, expr: &typed::Expression) {
+        let hir = tcx.hir();
+        let body = hir.body(hir.body_owned_by(hir.local_def_id_to_hir_id(expr.expr)));
+        let snippet = tcx.sess.source_map()
+            .span_to_snippet(body.value.span)
+            .unwrap_or_default();
+        self.spans.push(tcx.def_span(expr.expr));
+        self.snippets.push(snippet.split_whitespace().collect::<Vec<_>>().join(" "));
+
+        let spec_item = tcx.closure_base_def_id(expr.expr.to_def_id()).expect_local();
+        let args = spec_item_args(tcx, spec_item);
+        let mut visitor = ArgsVisitor::new(tcx, tcx.typeck(expr.expr), &args);
+        visitor.visit_expr(&body.value);
+        self.mentioned.extend(visitor.mentioned);
+        self.redundant_olds.extend(visitor.redundant_olds);
+    }
+}
+
+/// The arguments of a spec item, which are the arguments of the procedure and,
+/// for postconditions, its `result`.
+fn spec_item_args(tcx: TyCtxt, spec_item: LocalDefId) -> HashMap<hir::HirId, ArgKind> {
+    let hir = tcx.hir();
+    let body = hir.body(hir.body_owned_by(hir.local_def_id_to_hir_id(spec_item)));
+    let typeck = tcx.typeck(spec_item);
+    let param_env = tcx.param_env(spec_item);
+    body.params.iter()
+        .map(|param| {
+            let ty = typeck.node_type(param.pat.hir_id);
+            let is_result = param.pat.simple_ident()
+                .map_or(false, |ident| ident.name.to_ident_string() == "result");
+            let kind = if is_result {
+                ArgKind::Result
+            } else if contains_mut_ref(ty) {
+                ArgKind::Mutable
+            } else if !ty.is_copy_modulo_regions(tcx.at(param.span), param_env) {
+                ArgKind::Moved
+            } else {
+                ArgKind::Immutable
+            };
+            (param.pat.hir_id, kind)
+        })
+        .collect()
+}
+
+fn contains_mut_ref(ty: ty::Ty) -> bool {
+    ty.walk().any(|arg| match arg.unpack() {
+        ty::subst::GenericArgKind::Type(ty) => {
+            matches!(ty.kind(), ty::TyKind::Ref(_, _, hir::Mutability::Mut))
+        }
+        _ => false,
+    })
+}
+
+/// Collect the arguments of the spec item mentioned by a Rust expression of
+/// a specification.
+struct ArgsVisitor<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    typeck: &'tcx ty::TypeckResults<'tcx>,
+    args: &'a HashMap<hir::HirId, ArgKind>,
+    mentioned: Vec<ArgKind>,
+    redundant_olds: Vec<Span>,
+}
+
+impl<'a, 'tcx> ArgsVisitor<'a, 'tcx> {
+    fn new(
+        tcx: TyCtxt<'tcx>,
+        typeck: &'tcx ty::TypeckResults<'tcx>,
+        args: &'a HashMap<hir::HirId, ArgKind>,
+    ) -> Self {
+        ArgsVisitor {
+            tcx,
+            typeck,
+            args,
+            mentioned: vec![],
+            redundant_olds: vec![],
+        }
+    }
+
+    fn is_old(&self, func: &hir::Expr) -> bool {
+        if let hir::ExprKind::Path(ref qpath) = func.kind {
+            if let hir::def::Res::Def(_, def_id) = self.typeck.qpath_res(qpath, func.hir_id) {
+                return self.tcx.def_path_str(def_id) == "prusti_contracts::old";
+            }
+        }
+        false
+    }
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for ArgsVisitor<'a, 'tcx> {
+    type Map = Map<'tcx>;
+
+    fn nested_visit_map(&mut self) -> intravisit::NestedVisitorMap<Self::Map> {
+        intravisit::NestedVisitorMap::None
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        match ex.kind {
+            hir::ExprKind::Path(hir::QPath::Resolved(None, path)) => {
+                if let hir::def::Res::Local(hir_id) = path.res {
+                    if let Some(&kind) = self.args.get(&hir_id) {
+                        self.mentioned.push(kind);
+                    }
+                }
+            }
+            hir::ExprKind::Call(func, [arg]) if self.is_old(func) => {
+                let mut arg_visitor = ArgsVisitor::new(self.tcx, self.typeck, self.args);
+                arg_visitor.visit_expr(arg);
+                let only_immutable = arg_visitor.mentioned.iter()
+                    .all(|&kind| kind == ArgKind::Moved || kind == ArgKind::Immutable);
+                if !arg_visitor.mentioned.is_empty() && only_immutable {
+                    self.redundant_olds.push(ex.span);
+                }
+            }
+            _ => {}
+        }
+        intravisit::walk_expr(self, ex);
+    }
+}
@@ -18,6 +18,7 @@ use log::debug;
 pub mod external;
 pub mod typed;
 pub mod checker;
+pub mod lints;
 
 use typed::StructuralToTyped;
 use typed::SpecIdRef;
@@ -59,12 +60,19 @@ pub struct SpecCollector<'tcx> {
 
     typed_expressions: HashMap<String, LocalDefId>,
 
+    /// The spec items that define the collected assertions.
+    spec_item_ids: HashMap<SpecificationId, LocalDefId>,
+
     /// Collected, deserialised assertions, keyed by their specification id.
     typed_specs: typed::SpecificationMap<'tcx>,
 
+    /// The user-provided messages of the collected assertions.
+    spec_messages: HashMap<SpecificationId, String>,
+
     /// Resolved specifications.
     procedure_specs: HashMap<LocalDefId, ProcedureSpecRef>,
     loop_specs: HashMap<LocalDefId, Vec<SpecificationId>>,
+    history_invariants: HashMap<LocalDefId, Vec<SpecificationId>>,
 }
 
 impl<'tcx> SpecCollector<'tcx> {
@@ -73,9 +81,12 @@ impl<'tcx> SpecCollector<'tcx> {
             tcx: tcx,
             spec_items: Vec::new(),
             typed_specs: HashMap::new(),
+            spec_messages: HashMap::new(),
             procedure_specs: HashMap::new(),
             loop_specs: HashMap::new(),
+            history_invariants: HashMap::new(),
             typed_expressions: HashMap::new(),
+            spec_item_ids: HashMap::new(),
             extern_resolver: ExternSpecResolver::new(tcx),
         }
     }
@@ -100,9 +111,12 @@ impl<'tcx> SpecCollector<'tcx> {
 
         let mut def_spec = typed::DefSpecificationMap::new();
         self.determine_procedure_specs(&mut def_spec);
+        self.check_refined_posts(&def_spec, env);
         self.determine_extern_specs(&mut def_spec, env);
         self.determine_loop_specs(&mut def_spec);
         self.determine_struct_specs(&mut def_spec);
+        self.determine_history_invariants(&mut def_spec);
+        def_spec.spec_messages = self.spec_messages;
         def_spec
     }
 
@@ -131,6 +145,7 @@ impl<'tcx> SpecCollector<'tcx> {
             let mut posts = Vec::new();
             let mut pledges = Vec::new();
             let mut predicate_body = None;
+            let mut refined_posts = Vec::new();
             for spec_id_ref in &refs.spec_id_refs {
                 match spec_id_ref {
                     SpecIdRef::Precondition(spec_id) => {
@@ -149,6 +164,12 @@ impl<'tcx> SpecCollector<'tcx> {
                     SpecIdRef::Predicate(spec_id) => {
                         predicate_body = Some(self.typed_specs.get(&spec_id).unwrap().clone());
                     }
+                    SpecIdRef::RefinedPostcondition(spec_id) => {
+                        refined_posts.push((
+                            self.spec_item_ids[spec_id],
+                            self.typed_specs.get(&spec_id).unwrap().clone(),
+                        ));
+                    }
                 }
             }
             def_spec.specs.insert(
@@ -158,6 +179,7 @@ impl<'tcx> SpecCollector<'tcx> {
                     posts,
                     pledges,
                     predicate_body,
+                    refined_posts,
                     pure: refs.pure,
                     trusted: refs.trusted,
                 })
@@ -165,6 +187,27 @@ impl<'tcx> SpecCollector<'tcx> {
         }
     }
 
+    /// The body of a function cannot be verified against a postcondition
+    /// that depends on bounds that the function does not have, so refined
+    /// postconditions are only allowed on trusted functions (including
+    /// external specifications), where they are assumed.
+    fn check_refined_posts(&self, def_spec: &typed::DefSpecificationMap<'tcx>, env: &Environment<'tcx>) {
+        for (local_id, spec) in def_spec.specs.iter() {
+            if let typed::SpecificationSet::Procedure(spec) = spec {
+                if !spec.refined_posts.is_empty() && !spec.trusted {
+                    PrustiError::incorrect(
+                        "`#[refine_ensures(..)]` is only supported on trusted functions \
+                        and external specifications",
+                        MultiSpan::from_span(env.get_item_span(local_id.to_def_id())),
+                    ).set_help(
+                        "the postcondition is assumed at the calls whose types satisfy the bounds, \
+                        so the function has to be marked with `#[trusted]`"
+                    ).emit(env);
+                }
+            }
+        }
+    }
+
     fn determine_loop_specs(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>) {
         for (local_id, spec_ids) in self.loop_specs.iter() {
             let specs = spec_ids.iter()
@@ -178,6 +221,15 @@ impl<'tcx> SpecCollector<'tcx> {
 
     // TODO: struct specs
     fn determine_struct_specs(&self, _def_spec: &mut typed::DefSpecificationMap<'tcx>) {}
+
+    fn determine_history_invariants(&self, def_spec: &mut typed::DefSpecificationMap<'tcx>) {
+        for (local_id, spec_ids) in self.history_invariants.iter() {
+            let invariants = spec_ids.iter()
+                .map(|spec_id| self.typed_specs.get(&spec_id).unwrap().clone())
+                .collect();
+            def_spec.history_invariants.insert(*local_id, invariants);
+        }
+    }
 }
 
 fn get_procedure_spec_ids(def_id: DefId, attrs: &[ast::Attribute]) -> Option<ProcedureSpecRef> {
@@ -199,6 +251,11 @@ fn get_procedure_spec_ids(def_id: DefId, attrs: &[ast::Attribute]) -> Option<Pro
             |raw_spec_id| SpecIdRef::Postcondition(parse_spec_id(raw_spec_id))
         )
     );
+    spec_id_refs.extend(
+        read_prusti_attrs("refined_post_spec_id_ref", attrs).into_iter().map(
+            |raw_spec_id| SpecIdRef::RefinedPostcondition(parse_spec_id(raw_spec_id))
+        )
+    );
     spec_id_refs.extend(
         read_prusti_attrs("pledge_spec_id_ref", attrs).into_iter().map(
             |value| {
@@ -258,6 +315,27 @@ impl<'tcx> intravisit::Visitor<'tcx> for SpecCollector<'tcx> {
         intravisit::NestedVisitorMap::All(map)
     }
 
+    fn visit_item(
+        &mut self,
+        item: &'tcx rustc_hir::Item,
+    ) {
+        intravisit::walk_item(self, item);
+
+        // Collect history invariants
+        if let ItemKind::Struct(..) | ItemKind::Enum(..) = item.kind {
+            let attrs = self.tcx.hir().attrs(item.hir_id());
+            let spec_ids: Vec<SpecificationId> = read_prusti_attrs("history_invariant_spec_id_ref", attrs)
+                .into_iter()
+                .map(|raw_spec_id| raw_spec_id.try_into().expect(
+                    &format!("cannot parse the spec_id attached to {:?}", item.def_id)
+                ))
+                .collect();
+            if !spec_ids.is_empty() {
+                self.history_invariants.insert(item.def_id, spec_ids);
+            }
+        }
+    }
+
     fn visit_trait_item(
         &mut self,
         ti: &'tcx rustc_hir::TraitItem,
@@ -309,6 +387,10 @@ impl<'tcx> intravisit::Visitor<'tcx> for SpecCollector<'tcx> {
             let spec_id: SpecificationId = raw_spec_id.try_into()
                 .expect("failed conversion to SpecificationId");
             let specification = deserialize_spec_from_attrs(attrs);
+            self.spec_item_ids.insert(spec_id, local_id);
+            if let Some(message) = read_prusti_attr("spec_message", attrs) {
+                self.spec_messages.insert(spec_id, message);
+            }
 
             // Detect the kind of specification
             // FIXME: (minor) there is some redundancy here: the type of the
@@ -41,6 +41,11 @@ pub type Pledge<'tcx> = common::Pledge<ExpressionId, LocalDefId, (mir::Local, ty
 pub struct DefSpecificationMap<'tcx> {
     pub specs: HashMap<LocalDefId, SpecificationSet<'tcx>>,
     pub extern_specs: HashMap<DefId, LocalDefId>,
+    /// History invariants, keyed by the type that they constrain.
+    pub history_invariants: HashMap<LocalDefId, Vec<Assertion<'tcx>>>,
+    /// The messages given by the user to some clauses, e.g.
+    /// `#[ensures(x > 0, "x must stay positive")]`.
+    pub spec_messages: HashMap<common::SpecificationId, String>,
 }
 
 impl<'tcx> DefSpecificationMap<'tcx> {
@@ -48,6 +53,8 @@ impl<'tcx> DefSpecificationMap<'tcx> {
         Self {
             specs: HashMap::new(),
             extern_specs: HashMap::new(),
+            history_invariants: HashMap::new(),
+            spec_messages: HashMap::new(),
         }
     }
     pub fn get(&self, def_id: &DefId) -> Option<&SpecificationSet<'tcx>> {
@@ -58,6 +65,17 @@ impl<'tcx> DefSpecificationMap<'tcx> {
         };
         self.specs.get(&id)
     }
+    /// The history invariants of the type `def_id`, if any.
+    pub fn get_history_invariants(&self, def_id: &DefId) -> &[Assertion<'tcx>] {
+        def_id.as_local()
+            .and_then(|local_id| self.history_invariants.get(&local_id))
+            .map(|invariants| invariants.as_slice())
+            .unwrap_or(&[])
+    }
+    /// The message given by the user to the clause `spec_id`, if any.
+    pub fn get_spec_message(&self, spec_id: common::SpecificationId) -> Option<&str> {
+        self.spec_messages.get(&spec_id).map(|message| message.as_str())
+    }
 }
 
 /// This trait is implemented for specification-related types that have one or
@@ -331,3 +331,41 @@ pub fn read_prusti_attrs(attr_name: &str, attrs: &[ast::Attribute]) -> Vec<Strin
 pub fn read_prusti_attr(attr_name: &str, attrs: &[ast::Attribute]) -> Option<String> {
     read_prusti_attrs(attr_name, attrs).pop()
 }
+
+/// Read the argument of a Prusti attribute that takes a single identifier or
+/// literal (e.g. `prusti::<attr_name>(...)`).
+pub fn read_prusti_attr_arg(attr_name: &str, attrs: &[ast::Attribute]) -> Option<String> {
+    read_prusti_attr_args(attr_name, attrs).pop()
+}
+
+/// Read the arguments of all the occurrences of a Prusti attribute that
+/// takes a single identifier or literal, in order.
+pub fn read_prusti_attr_args(attr_name: &str, attrs: &[ast::Attribute]) -> Vec<String> {
+    use rustc_ast::token::{Lit, TokenKind};
+    use rustc_ast::tokenstream::TokenTree;
+    attrs.iter().filter_map(|attr| match &attr.kind {
+        ast::AttrKind::Normal(ast::AttrItem {
+                                  path: ast::Path { span: _, segments, tokens: _ },
+                                  args: ast::MacArgs::Delimited(_, _, tokens),
+                                  tokens: _,
+                              }, _) if segments.len() == 2
+            && segments[0].ident.as_str() == "prusti"
+            && segments[1].ident.as_str() == attr_name => {
+            let mut trees = tokens.trees();
+            let value = match trees.next() {
+                Some(TokenTree::Token(token)) => match token.kind {
+                    TokenKind::Ident(symbol, _) => symbol.to_string(),
+                    TokenKind::Literal(Lit { symbol, .. }) => symbol.to_string(),
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            if trees.next().is_some() {
+                None
+            } else {
+                Some(value)
+            }
+        }
+        _ => None,
+    }).collect()
+}
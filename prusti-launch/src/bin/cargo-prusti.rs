@@ -4,8 +4,12 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use std::process::Command;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
 use prusti_launch::get_rust_toolchain_channel;
+#[cfg(target_family = "unix")]
+use nix::{sys::signal::{killpg, Signal}, unistd::Pid};
 
 fn main(){
     if let Err(code) = process(std::env::args().skip(1)) {
@@ -26,23 +30,185 @@ fn process<I>(args: I) -> Result<(), i32>
 
     // Remove the leading "prusti" argument when `cargo-prusti` is invocated
     // as `cargo prusti` (note the space)
-    let clean_args = args.skip_while(|x| x == "prusti");
+    let clean_args: Vec<String> = args.skip_while(|x| x == "prusti").collect();
 
     let cargo_path = std::env::var("CARGO_PATH").unwrap_or("cargo".to_string());
 
-    let exit_status = Command::new(cargo_path)
-        .arg("check")
-        .args(clean_args)
-        .env("RUST_TOOLCHAIN", get_rust_toolchain_channel())
-        .env("PRUSTI_QUIET", "true")
-        .env("PRUSTI_FULL_COMPILATION", "true")
-        .env("RUSTC_WRAPPER", prusti_rustc_path)
-        .status()
-        .expect("could not run cargo");
-
-    if exit_status.success() {
-        Ok(())
+    // Cargo runs `prusti-rustc` on the crates in dependency order, so the
+    // specifications exported by a crate are available to its dependents.
+    // Unless the user already provides a server, all the crates share one
+    // verification server, so that the JVM is started only once.
+    let (mut server, server_address) = if std::env::var("PRUSTI_SERVER_ADDRESS").is_ok() {
+        (None, None)
     } else {
-        Err(exit_status.code().unwrap_or(-1))
+        let (child, address) = spawn_server();
+        (Some(child), Some(address))
+    };
+
+    let report_path = std::env::temp_dir()
+        .join(format!("prusti-batch-report-{}.tsv", std::process::id()));
+    let _ = std::fs::remove_file(&report_path);
+
+    // When verifying several feature combinations, the items that are the
+    // same in all of them are verified only once, unless the user manages
+    // the verification cache.
+    let configurations = feature_matrix();
+    let shared_cache_path = if configurations.len() > 1
+        && std::env::var("PRUSTI_INCREMENTAL_CACHE").is_err()
+    {
+        let path = std::env::temp_dir()
+            .join(format!("prusti-feature-matrix-cache-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        Some(path)
+    } else {
+        None
+    };
+
+    let mut reports = vec![];
+    let mut exit_code = None;
+    for features in &configurations {
+        let mut cmd = Command::new(&cargo_path);
+        cmd.arg("check")
+            .args(&clean_args)
+            .env("RUST_TOOLCHAIN", get_rust_toolchain_channel())
+            .env("PRUSTI_QUIET", "true")
+            .env("PRUSTI_FULL_COMPILATION", "true")
+            .env("PRUSTI_BATCH_REPORT", &report_path)
+            .env("RUSTC_WRAPPER", &prusti_rustc_path);
+        if let Some(features) = features {
+            cmd.arg("--features").arg(features);
+        }
+        if let Some(address) = &server_address {
+            cmd.env("PRUSTI_SERVER_ADDRESS", address);
+        }
+        if let Some(cache_path) = &shared_cache_path {
+            cmd.env("PRUSTI_INCREMENTAL_CACHE", cache_path);
+        }
+
+        let exit_status = cmd.status().expect("could not run cargo");
+        if !exit_status.success() && exit_code.is_none() {
+            exit_code = Some(exit_status.code().unwrap_or(-1));
+        }
+        reports.push((features.clone(), read_report(&report_path)));
+        let _ = std::fs::remove_file(&report_path);
+    }
+
+    if let Some(server) = server.as_mut() {
+        stop_server(server);
+    }
+    if let Some(cache_path) = &shared_cache_path {
+        let _ = std::fs::remove_file(cache_path);
+    }
+    let print_configurations = reports.len() > 1;
+    for (features, entries) in &reports {
+        let configuration = if print_configurations {
+            Some(features.as_deref().unwrap_or("default features"))
+        } else {
+            None
+        };
+        print_report(entries, configuration);
+    }
+
+    match exit_code {
+        None => Ok(()),
+        Some(code) => Err(code),
+    }
+}
+
+/// The feature combinations to verify, from `PRUSTI_FEATURE_MATRIX`: a list
+/// of combinations separated by `;`, each a comma-separated list of features
+/// (e.g. `;std;std,serde`). An empty combination verifies the crate with its
+/// default features. Without the variable, the crate is verified once with
+/// the features given on the command line.
+fn feature_matrix() -> Vec<Option<String>> {
+    match std::env::var("PRUSTI_FEATURE_MATRIX") {
+        Ok(matrix) if !matrix.trim().is_empty() => matrix
+            .split(';')
+            .map(|features| features.trim())
+            .map(|features| {
+                if features.is_empty() {
+                    None
+                } else {
+                    Some(features.to_string())
+                }
+            })
+            .collect(),
+        _ => vec![None],
+    }
+}
+
+/// Start a `prusti-server` on a free port, returning the process and the
+/// address on which it listens.
+fn spawn_server() -> (Child, String) {
+    let mut prusti_server_path = std::env::current_exe()
+        .expect("current executable path invalid")
+        .with_file_name("prusti-server");
+    if cfg!(windows) {
+        prusti_server_path.set_extension("exe");
+    }
+
+    let mut child = Command::new(prusti_server_path)
+        .arg("--port")
+        .arg("0")
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("could not run prusti-server");
+
+    // The server prints "port: N" to stdout once it is ready.
+    let stdout = child.stdout.take().expect("could not read the output of prusti-server");
+    let mut lines = BufReader::new(stdout).lines();
+    let port = lines
+        .by_ref()
+        .filter_map(|line| line.ok())
+        .find_map(|line| line.strip_prefix("port: ").map(|port| port.trim().to_string()))
+        .expect("prusti-server did not report its port");
+    // Keep reading the rest of the output, otherwise the server would block
+    // or fail once it writes to the closed pipe.
+    std::thread::spawn(move || lines.for_each(drop));
+
+    (child, format!("localhost:{}", port))
+}
+
+/// Stop a server started by `spawn_server`.
+fn stop_server(server: &mut Child) {
+    // `prusti-server` moves itself to its own process group, which also
+    // contains the `prusti-server-driver` running the JVM.
+    #[cfg(target_family = "unix")]
+    let _ = killpg(Pid::from_raw(server.id() as i32), Signal::SIGTERM);
+    let _ = server.kill();
+    let _ = server.wait();
+}
+
+/// Read the one-line summaries of the crates verified by one `cargo check`.
+fn read_report(report_path: &Path) -> Vec<Vec<String>> {
+    let report = match std::fs::read_to_string(report_path) {
+        Ok(report) => report,
+        Err(_) => return vec![],
+    };
+    report
+        .lines()
+        .map(|line| line.split('\t').map(String::from).collect::<Vec<_>>())
+        .filter(|fields| fields.len() == 3)
+        .collect()
+}
+
+/// Print the combined verification report of all the crates, verified with
+/// the given feature combination if there are several.
+fn print_report(entries: &[Vec<String>], configuration: Option<&str>) {
+    if entries.is_empty() {
+        return;
+    }
+    let failed = entries.iter().filter(|fields| fields[2] != "verified").count();
+    match configuration {
+        Some(features) => println!("Prusti verification report ({}):", features),
+        None => println!("Prusti verification report:"),
+    }
+    for fields in entries {
+        println!("  {}: {} ({} items)", fields[0], fields[2], fields[1]);
     }
+    println!(
+        "{} crates verified, {} failed",
+        entries.len() - failed,
+        failed
+    );
 }
@@ -0,0 +1,141 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Compares the reports of two verification runs, e.g. before and after a
+//! refactoring, and prints the items that are newly failing, newly passing
+//! and newly skipped as Markdown, for code reviews and CI status comments.
+//!
+//! A report is the output of a run with `PRUSTI_PROGRESS_FORMAT=json`, e.g.
+//! `PRUSTI_PROGRESS_FORMAT=json cargo prusti 2> before.jsonl`. The lines that
+//! are not progress events, like the messages of the compiler, are ignored.
+//!
+//! Usage: `prusti-report-diff <before> <after>`. The exit code is 1 if some
+//! item is newly failing.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ItemStatus {
+    Started,
+    Encoded,
+    Verified,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProgressEvent {
+    status: ItemStatus,
+    item: String,
+    location: String,
+}
+
+/// The final status and the location of each item of a report.
+type Report = BTreeMap<String, (ItemStatus, String)>;
+
+fn parse_report(content: &str) -> Report {
+    let mut report = Report::new();
+    for line in content.lines() {
+        let event: ProgressEvent = match serde_json::from_str(line) {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+        if let ItemStatus::Verified | ItemStatus::Failed | ItemStatus::Skipped = event.status {
+            report.insert(event.item, (event.status, event.location));
+        }
+    }
+    report
+}
+
+/// The items of `after` that reached `status`, but did not have it in `before`.
+fn changed_to(before: &Report, after: &Report, status: ItemStatus) -> Vec<String> {
+    after
+        .iter()
+        .filter(|(item, (after_status, _))| {
+            *after_status == status
+                && before.get(*item).map(|(before_status, _)| *before_status) != Some(status)
+        })
+        .map(|(item, (_, location))| format!("- `{}` ({})", item, location))
+        .collect()
+}
+
+fn format_diff(before: &Report, after: &Report) -> (String, bool) {
+    let sections = [
+        ("Newly failing", changed_to(before, after, ItemStatus::Failed)),
+        ("Newly passing", changed_to(before, after, ItemStatus::Verified)),
+        ("Newly skipped", changed_to(before, after, ItemStatus::Skipped)),
+    ];
+    let mut output = String::new();
+    for (title, items) in &sections {
+        if !items.is_empty() {
+            output.push_str(&format!("### {} ({})\n\n{}\n\n", title, items.len(), items.join("\n")));
+        }
+    }
+    if output.is_empty() {
+        output.push_str("No changes in the verification results.\n");
+    }
+    (output, !sections[0].1.is_empty())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() != 2 {
+        eprintln!("Usage: prusti-report-diff <before> <after>");
+        std::process::exit(2);
+    }
+    let reports: Vec<Report> = args
+        .iter()
+        .map(|path| match fs::read_to_string(path) {
+            Ok(content) => parse_report(&content),
+            Err(err) => {
+                eprintln!("Failed to read {}: {}", path, err);
+                std::process::exit(2);
+            }
+        })
+        .collect();
+    let (output, has_new_failures) = format_diff(&reports[0], &reports[1]);
+    print!("{}", output);
+    if has_new_failures {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(status: &str, item: &str) -> String {
+        format!(
+            r#"{{"status":"{}","item":"{}","location":"src/lib.rs:1:1: 1:10","done":1,"total":1}}"#,
+            status, item,
+        )
+    }
+
+    #[test]
+    fn final_statuses_are_compared() {
+        let before = parse_report(&[
+            event("started", "a"),
+            event("verified", "a"),
+            event("failed", "b"),
+            event("verified", "c"),
+            "error: some message of the compiler".to_string(),
+        ].join("\n"));
+        let after = parse_report(&[
+            event("failed", "a"),
+            event("verified", "b"),
+            event("skipped", "c"),
+            event("failed", "d"),
+        ].join("\n"));
+        assert_eq!(changed_to(&before, &after, ItemStatus::Failed).len(), 2);
+        assert_eq!(changed_to(&before, &after, ItemStatus::Verified).len(), 1);
+        assert_eq!(changed_to(&before, &after, ItemStatus::Skipped).len(), 1);
+        assert!(format_diff(&before, &after).1);
+        assert!(!format_diff(&after, &after).1);
+    }
+}
@@ -57,7 +57,8 @@ fn process(mut args: Vec<String>) -> Result<(), i32> {
             panic!(
                 "Could not find the Viper home. \
                 Please set the VIPER_HOME environment variable, which should contain the path of \
-                the folder that contains all Viper JAR files."
+                the folder that contains all Viper JAR files, or run `prusti-setup` to install \
+                the Viper tools."
             );
         }
     };
@@ -69,7 +70,7 @@ fn process(mut args: Vec<String>) -> Result<(), i32> {
             panic!(
                 "Could not find the Z3 executable. \
                 Please set the Z3_EXE environment variable, which should contain the path of a \
-                Z3 executable."
+                Z3 executable, or run `prusti-setup` to install the Viper tools."
             );
         }
     };
@@ -0,0 +1,25 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Installs the pinned version of the Viper tools in the cache directory, so
+//! that `prusti-rustc`, `cargo-prusti` and `prusti-server` find them without
+//! `VIPER_HOME` and `Z3_EXE`. Prints the two variables, which is useful to
+//! build Prusti itself, e.g. `eval $(prusti-setup)`.
+
+use prusti_launch::setup::{install, Distribution};
+use std::process;
+
+fn main() {
+    let installation = match Distribution::pinned().and_then(|dist| install(&dist)) {
+        Ok(installation) => installation,
+        Err(e) => {
+            eprintln!("Failed to install the Viper tools: {}", e);
+            process::exit(1);
+        }
+    };
+    println!("export VIPER_HOME={:?}", installation.viper_home);
+    println!("export Z3_EXE={:?}", installation.z3_exe);
+}
@@ -15,6 +15,8 @@ use serde::Deserialize;
 #[cfg(target_family = "unix")]
 use nix::{sys::signal::{Signal, killpg}, unistd::getpgrp};
 
+pub mod setup;
+
 /// Append paths to the loader environment variable
 pub fn add_to_loader_path(paths: Vec<PathBuf>, cmd: &mut Command) {
     #[cfg(target_os = "windows")]
@@ -156,7 +158,7 @@ pub fn find_viper_home(base_dir: &PathBuf) -> Option<PathBuf> {
         }
     }
 
-    None
+    setup::find_installed().map(|installation| installation.viper_home)
 }
 
 /// Find Z3 executable
@@ -176,7 +178,7 @@ pub fn find_z3_exe(base_dir: &PathBuf) -> Option<PathBuf> {
         }
     }
 
-    None
+    setup::find_installed().map(|installation| installation.z3_exe)
 }
 
 #[cfg(target_family = "unix")]
@@ -0,0 +1,311 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Download and installation of the Viper tools (ViperServer, Silicon,
+//! Carbon and Z3).
+//!
+//! The tools are installed in a cache directory, which is
+//! `$PRUSTI_CACHE_DIR/viper_tools/<version>` if `PRUSTI_CACHE_DIR` is set,
+//! otherwise `$XDG_CACHE_HOME/prusti/...` or `$HOME/.cache/prusti/...`.
+//! The downloaded archive is checked against the SHA-256 checksum of the
+//! distribution before being unpacked, and a marker file is written only
+//! once the unpacking succeeded, so that an interrupted installation is
+//! restarted from scratch.
+//!
+//! Nothing is downloaded implicitly: the tools are installed by the
+//! `prusti-setup` command, and the other launchers only look up an existing
+//! installation with `find_installed`.
+//!
+//! This module lives in `prusti-launch` rather than in the `viper` crate,
+//! because building `viper` (through the build script of `viper-sys`)
+//! already requires `VIPER_HOME` to point to the Viper JARs. The launchers
+//! pass the installed paths to the driver and the server as `VIPER_HOME`
+//! and `Z3_EXE`, which is how the JNI layer finds them.
+
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+type Result<T> = std::result::Result<T, String>;
+
+/// The version of the Viper tools that is installed by default.
+pub const PINNED_VERSION: &str = "v-2021-03-08-0945";
+
+/// The SHA-256 checksums of the pinned distributions, one per platform.
+/// They must be updated together with `PINNED_VERSION`; a missing checksum
+/// makes the installation fail instead of unpacking an unchecked archive.
+/// TODO: the checksums of the release are not recorded yet, so for now
+/// `VIPER_TOOLS_SHA256` has to be set when running `prusti-setup`.
+const PINNED_CHECKSUMS: &[(&str, &str)] = &[];
+
+/// The name of the file that marks a complete installation.
+const INSTALLED_MARKER: &str = ".installed";
+
+/// A downloadable distribution of the Viper tools.
+#[derive(Debug, Clone)]
+pub struct Distribution {
+    pub version: String,
+    pub url: String,
+    /// The expected SHA-256 checksum of the archive, as a hex string.
+    pub sha256: String,
+}
+
+impl Distribution {
+    /// The pinned distribution for the current platform. The defaults can
+    /// be overridden with the `VIPER_TOOLS_URL` and `VIPER_TOOLS_SHA256`
+    /// environment variables, in which case `VIPER_TOOLS_VERSION` names
+    /// the cache directory.
+    pub fn pinned() -> Result<Self> {
+        let platform = platform_name();
+        let version = env::var("VIPER_TOOLS_VERSION").unwrap_or_else(|_| PINNED_VERSION.to_string());
+        let url = env::var("VIPER_TOOLS_URL").unwrap_or_else(|_| format!(
+            "https://github.com/viperproject/viper-ide/releases/download/{}/ViperTools{}.zip",
+            version, platform
+        ));
+        let sha256 = match env::var("VIPER_TOOLS_SHA256") {
+            Ok(sha256) => sha256,
+            Err(_) => PINNED_CHECKSUMS
+                .iter()
+                .find(|(name, _)| *name == platform)
+                .map(|(_, sha256)| sha256.to_string())
+                .ok_or_else(|| format!(
+                    "no checksum is known for the Viper tools {} on {}; \
+                    set VIPER_TOOLS_SHA256 to install them",
+                    version, platform
+                ))?,
+        };
+        Ok(Distribution { version, url, sha256 })
+    }
+}
+
+/// The paths of an installation of the Viper tools.
+#[derive(Debug, Clone)]
+pub struct Installation {
+    pub viper_home: PathBuf,
+    pub z3_exe: PathBuf,
+}
+
+impl Installation {
+    fn in_dir(dir: &Path) -> Self {
+        let mut z3_exe = dir.join("z3").join("bin").join("z3");
+        if cfg!(windows) {
+            z3_exe.set_extension("exe");
+        }
+        Installation {
+            viper_home: dir.join("backends"),
+            z3_exe,
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.viper_home.is_dir() && self.z3_exe.is_file()
+    }
+}
+
+fn platform_name() -> &'static str {
+    if cfg!(windows) {
+        "Win"
+    } else if cfg!(target_os = "macos") {
+        "Mac"
+    } else {
+        "Linux"
+    }
+}
+
+/// The root of the cache directory in which the tools are installed.
+pub fn cache_dir() -> Result<PathBuf> {
+    if let Ok(dir) = env::var("PRUSTI_CACHE_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Ok(dir) = env::var("XDG_CACHE_HOME") {
+        return Ok(PathBuf::from(dir).join("prusti"));
+    }
+    let home = env::var("HOME")
+        .or_else(|_| env::var("USERPROFILE"))
+        .map_err(|_| "cannot determine the cache directory; set PRUSTI_CACHE_DIR".to_string())?;
+    Ok(PathBuf::from(home).join(".cache").join("prusti"))
+}
+
+/// Fetches the archive of a distribution.
+pub trait Fetch {
+    fn fetch(&self, url: &str) -> Result<Box<dyn Read>>;
+}
+
+/// Fetches archives over HTTP(S).
+pub struct HttpFetch;
+
+impl Fetch for HttpFetch {
+    fn fetch(&self, url: &str) -> Result<Box<dyn Read>> {
+        let response = ureq::get(url)
+            .call()
+            .map_err(|e| format!("failed to download {}: {}", url, e))?;
+        Ok(Box::new(response.into_reader()))
+    }
+}
+
+/// Install the distribution in the cache directory, unless it is already
+/// installed, and return the paths of the installation.
+pub fn install(distribution: &Distribution) -> Result<Installation> {
+    install_in(&cache_dir()?, distribution, &HttpFetch)
+}
+
+/// Install the distribution in the given cache directory, fetching its
+/// archive with `fetch`, unless it is already installed.
+pub fn install_in(
+    cache_dir: &Path,
+    distribution: &Distribution,
+    fetch: &dyn Fetch,
+) -> Result<Installation> {
+    let install_dir = versioned_dir(cache_dir, &distribution.version);
+    let installation = Installation::in_dir(&install_dir);
+    if install_dir.join(INSTALLED_MARKER).is_file() && installation.is_complete() {
+        return Ok(installation);
+    }
+    if install_dir.exists() {
+        fs::remove_dir_all(&install_dir)
+            .map_err(|e| format!("failed to clean up {:?}: {}", install_dir, e))?;
+    }
+    fs::create_dir_all(&install_dir)
+        .map_err(|e| format!("failed to create {:?}: {}", install_dir, e))?;
+
+    let archive = install_dir.join("viper_tools.zip");
+    eprintln!("Downloading the Viper tools from {}", distribution.url);
+    download(fetch, &distribution.url, &archive)?;
+    verify_checksum(&archive, &distribution.sha256)?;
+    unpack(&archive, &install_dir)?;
+    fs::remove_file(&archive)
+        .map_err(|e| format!("failed to remove {:?}: {}", archive, e))?;
+
+    if !installation.is_complete() {
+        return Err(format!(
+            "the archive {} does not contain the Viper backends and Z3",
+            distribution.url
+        ));
+    }
+    fs::write(install_dir.join(INSTALLED_MARKER), &distribution.url)
+        .map_err(|e| format!("failed to write to {:?}: {}", install_dir, e))?;
+    Ok(installation)
+}
+
+/// The installation of the pinned version in the cache directory, if it
+/// has been installed by `prusti-setup`. This never downloads anything.
+pub fn find_installed() -> Option<Installation> {
+    let version = env::var("VIPER_TOOLS_VERSION").unwrap_or_else(|_| PINNED_VERSION.to_string());
+    let install_dir = install_dir(&version).ok()?;
+    let installation = Installation::in_dir(&install_dir);
+    if install_dir.join(INSTALLED_MARKER).is_file() && installation.is_complete() {
+        Some(installation)
+    } else {
+        None
+    }
+}
+
+fn install_dir(version: &str) -> Result<PathBuf> {
+    Ok(versioned_dir(&cache_dir()?, version))
+}
+
+fn versioned_dir(cache_dir: &Path, version: &str) -> PathBuf {
+    cache_dir.join("viper_tools").join(version)
+}
+
+fn download(fetch: &dyn Fetch, url: &str, target: &Path) -> Result<()> {
+    let mut reader = fetch.fetch(url)?;
+    let mut file = fs::File::create(target)
+        .map_err(|e| format!("failed to create {:?}: {}", target, e))?;
+    io::copy(&mut reader, &mut file)
+        .map_err(|e| format!("failed to download {}: {}", url, e))?;
+    Ok(())
+}
+
+fn verify_checksum(archive: &Path, expected: &str) -> Result<()> {
+    let mut file = fs::File::open(archive)
+        .map_err(|e| format!("failed to open {:?}: {}", archive, e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("failed to read {:?}: {}", archive, e))?;
+    let actual: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(format!(
+            "checksum mismatch for {:?}: expected {}, found {}",
+            archive, expected, actual
+        ));
+    }
+    Ok(())
+}
+
+fn unpack(archive: &Path, target: &Path) -> Result<()> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("tar");
+        command.arg("-xf").arg(archive).arg("-C").arg(target);
+        command
+    } else {
+        let mut command = Command::new("unzip");
+        command.arg("-q").arg(archive).arg("-d").arg(target);
+        command
+    };
+    let status = command
+        .status()
+        .map_err(|e| format!("failed to run the unpacking tool: {}", e))?;
+    if !status.success() {
+        return Err(format!("failed to unpack {:?} ({})", archive, status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serves the same bytes for every URL.
+    struct FixedFetch(&'static [u8]);
+
+    impl Fetch for FixedFetch {
+        fn fetch(&self, _url: &str) -> Result<Box<dyn Read>> {
+            Ok(Box::new(self.0))
+        }
+    }
+
+    fn temp_cache_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("prusti-setup-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn matching_checksums_are_accepted() {
+        let dir = temp_cache_dir("matching");
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive");
+        fs::write(&archive, b"abc").unwrap();
+        let sha256 = "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD";
+        assert!(verify_checksum(&archive, sha256).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn archives_with_a_wrong_checksum_are_not_installed() {
+        let dir = temp_cache_dir("mismatch");
+        let distribution = Distribution {
+            version: "test".to_string(),
+            url: "https://example.com/ViperTools.zip".to_string(),
+            sha256: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string(),
+        };
+        let error = install_in(&dir, &distribution, &FixedFetch(b"not the Viper tools"))
+            .unwrap_err();
+        assert!(error.starts_with("checksum mismatch"), "{}", error);
+        let install_dir = versioned_dir(&dir, "test");
+        assert!(!install_dir.join(INSTALLED_MARKER).exists());
+        assert!(!install_dir.join("backends").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
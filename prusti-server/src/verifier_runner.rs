@@ -9,7 +9,7 @@ use prusti_common::{
     report::log,
     verification_context::*,
     verification_service::ViperBackendConfig,
-    vir::{Program, ToViper},
+    vir::{Program, ViperPreamble},
     Stopwatch,
 };
 use viper::{self, VerificationResult};
@@ -18,6 +18,8 @@ pub struct VerifierRunner<'v> {
     verifier: viper::Verifier<'v, viper::state::Started>,
     ast_factory: viper::AstFactory<'v>,
     ast_utils: viper::AstUtils<'v>,
+    /// The builtins shared by all programs verified with the same JVM.
+    preamble: &'v ViperPreamble,
 }
 
 impl<'v> VerifierRunner<'v> {
@@ -48,16 +50,18 @@ impl<'v> VerifierRunner<'v> {
     }
 
     fn new(context: &'v VerificationContext, backend_config: &ViperBackendConfig) -> Self {
+        context.preamble().attach_runner();
         Self {
             verifier: context.new_viper_verifier(backend_config),
             ast_factory: context.new_ast_factory(),
             ast_utils: context.new_ast_utils(),
+            preamble: context.preamble(),
         }
     }
 
     pub fn verify(&self, program: Program, program_name: &str) -> VerificationResult {
         let mut stopwatch = Stopwatch::start("prusti-server", "construction of JVM objects");
-        let viper_program = program.to_viper(&self.ast_factory);
+        let viper_program = program.to_viper_with_preamble(&self.ast_factory, self.preamble);
         if config::dump_viper_program() {
             stopwatch.start_next("dumping viper program");
             self.dump(viper_program, program_name);
@@ -74,3 +78,9 @@ impl<'v> VerifierRunner<'v> {
         log::report(namespace, filename, self.ast_utils.pretty_print(program));
     }
 }
+
+impl<'v> Drop for VerifierRunner<'v> {
+    fn drop(&mut self) {
+        self.preamble.detach_runner();
+    }
+}
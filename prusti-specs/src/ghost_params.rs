@@ -0,0 +1,112 @@
+//! Ghost parameters are function parameters marked with `#[ghost]` that may
+//! only be used in specifications. When compiling with Prusti they are
+//! regular parameters; otherwise their type `T` is replaced by the zero-sized
+//! `prusti_contracts::Ghost<T>`, so that they are erased at runtime.
+//! Callers pass ghost arguments with `ghost!(...)`.
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+use crate::specifications::untyped::AnyFnItem;
+
+/// Remove the `#[ghost]` attribute, returning whether it was present.
+fn take_ghost_attr(attrs: &mut Vec<syn::Attribute>) -> bool {
+    let len = attrs.len();
+    attrs.retain(|attr| !attr.path.is_ident("ghost"));
+    attrs.len() != len
+}
+
+/// The ghost parameters of `sig`, with their `#[ghost]` attributes removed.
+fn take_ghost_params(sig: &mut syn::Signature) -> syn::Result<Vec<&mut syn::PatType>> {
+    let mut ghost_params = vec![];
+    for input in sig.inputs.iter_mut() {
+        match input {
+            syn::FnArg::Typed(pat_type) => {
+                if take_ghost_attr(&mut pat_type.attrs) {
+                    ghost_params.push(pat_type);
+                }
+            }
+            syn::FnArg::Receiver(receiver) => {
+                if take_ghost_attr(&mut receiver.attrs) {
+                    return Err(syn::Error::new(
+                        receiver.span(),
+                        "the receiver of a method cannot be a ghost parameter",
+                    ));
+                }
+            }
+        }
+    }
+    Ok(ghost_params)
+}
+
+/// Finds the uses of ghost parameters outside of macros (such as
+/// `body_invariant!` or `ghost!`), whose arguments are specifications.
+struct GhostUseFinder<'a> {
+    ghost_params: &'a [syn::Ident],
+    error: Option<syn::Error>,
+}
+
+impl<'a> VisitMut for GhostUseFinder<'a> {
+    fn visit_expr_path_mut(&mut self, expr: &mut syn::ExprPath) {
+        if let Some(ident) = expr.path.get_ident() {
+            if expr.qself.is_none() && self.ghost_params.contains(ident) && self.error.is_none() {
+                self.error = Some(syn::Error::new(
+                    ident.span(),
+                    format!("the ghost parameter `{}` can only be used in specifications", ident),
+                ));
+            }
+        }
+    }
+}
+
+/// Remove the `#[ghost]` attributes from the parameters of `item`, checking
+/// that the ghost parameters are not used by its body.
+pub(crate) fn strip_ghost_params(item: &mut AnyFnItem) -> syn::Result<()> {
+    let mut ghost_params = vec![];
+    for pat_type in take_ghost_params(item.sig_mut())? {
+        match &*pat_type.pat {
+            syn::Pat::Ident(pat_ident) => ghost_params.push(pat_ident.ident.clone()),
+            pat => {
+                return Err(syn::Error::new(
+                    pat.span(),
+                    "a ghost parameter has to be a single identifier",
+                ));
+            }
+        }
+    }
+    if ghost_params.is_empty() {
+        return Ok(());
+    }
+    if let Some(block) = item.block_mut() {
+        let mut finder = GhostUseFinder { ghost_params: &ghost_params, error: None };
+        finder.visit_block_mut(block);
+        if let Some(error) = finder.error {
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// Replace the type `T` of the ghost parameters of a function by
+/// `prusti_contracts::Ghost<T>`. Tokens that are not a function are returned
+/// unchanged.
+pub fn erase_ghost_params(tokens: TokenStream) -> TokenStream {
+    let mut item: AnyFnItem = match syn::parse2(tokens.clone()) {
+        Ok(item) => item,
+        Err(_) => return tokens,
+    };
+    match take_ghost_params(item.sig_mut()) {
+        Ok(ghost_params) => {
+            if ghost_params.is_empty() {
+                return tokens;
+            }
+            for pat_type in ghost_params {
+                let ty = &pat_type.ty;
+                pat_type.ty = Box::new(syn::parse_quote! { ::prusti_contracts::Ghost<#ty> });
+            }
+        }
+        Err(err) => return err.to_compile_error(),
+    }
+    item.into_token_stream()
+}
@@ -11,6 +11,9 @@ mod extern_spec_rewriter;
 mod rewriter;
 mod parse_closure_macro;
 mod spec_attribute_kind;
+mod runtime_checks;
+mod ghost_params;
+mod self_projection_qualifier;
 pub mod specifications;
 
 use proc_macro2::{Span, TokenStream, TokenTree};
@@ -21,6 +24,7 @@ use std::convert::{TryFrom, TryInto};
 use specifications::untyped;
 use parse_closure_macro::ClosureWithSpec;
 pub use spec_attribute_kind::SpecAttributeKind;
+pub use ghost_params::erase_ghost_params;
 
 macro_rules! handle_result {
     ($parse_result: expr) => {
@@ -62,6 +66,9 @@ pub fn rewrite_prusti_attributes(
     // Collect the remaining Prusti attributes, removing them from `item`.
     prusti_attributes.extend(extract_prusti_attributes(&mut item));
 
+    // Ghost parameters are regular parameters for Prusti.
+    handle_result!(ghost_params::strip_ghost_params(&mut item));
+
     // make sure to also update the check in the #[predicate] handling method
     if prusti_attributes
         .iter()
@@ -73,10 +80,38 @@ pub fn rewrite_prusti_attributes(
         ).to_compile_error();
     }
 
+    if prusti_attributes.iter().any(|(ak, _)| ak == &SpecAttributeKind::Abstraction)
+        && !prusti_attributes.iter().any(|(ak, _)| ak == &SpecAttributeKind::Pure)
+    {
+        return syn::Error::new(
+            item.span(),
+            "`#[abstraction]` can only be used on `#[pure]` functions",
+        ).to_compile_error();
+    }
+
+    let is_trusted = prusti_attributes.iter().any(|(ak, _)| {
+        ak == &SpecAttributeKind::Trusted || ak == &SpecAttributeKind::TrustedUnsafe
+    });
+    let runtime_checks = if runtime_checks::enabled()
+        || (is_trusted && runtime_checks::enabled_for_trusted())
+    {
+        Some(handle_result!(generate_runtime_checks(&prusti_attributes)))
+    } else {
+        None
+    };
+
     let (generated_spec_items, generated_attributes) = handle_result!(
         generate_spec_and_assertions(prusti_attributes, &item)
     );
 
+    if let Some((preconditions, postconditions)) = runtime_checks {
+        if is_trusted {
+            runtime_checks::wrap(&mut item, preconditions, postconditions);
+        } else {
+            runtime_checks::instrument(&mut item, preconditions, postconditions);
+        }
+    }
+
     quote_spanned! {item.span()=>
         #(#generated_spec_items)*
         #(#generated_attributes)*
@@ -98,10 +133,13 @@ fn generate_spec_and_assertions(
         let rewriting_result = match attr_kind {
             SpecAttributeKind::Requires => generate_for_requires(attr_tokens, item),
             SpecAttributeKind::Ensures => generate_for_ensures(attr_tokens, item),
+            SpecAttributeKind::RefineEnsures => generate_for_refine_ensures(attr_tokens, item),
             SpecAttributeKind::AfterExpiry => generate_for_after_expiry(attr_tokens, item),
             SpecAttributeKind::AfterExpiryIf => generate_for_after_expiry_if(attr_tokens, item),
             SpecAttributeKind::Pure => generate_for_pure(attr_tokens, item),
+            SpecAttributeKind::Abstraction => generate_for_abstraction(attr_tokens, item),
             SpecAttributeKind::Trusted => generate_for_trusted(attr_tokens, item),
+            SpecAttributeKind::TrustedUnsafe => generate_for_trusted_unsafe(attr_tokens, item),
             // Predicates are handled separately below; the entry in the SpecAttributeKind enum
             // only exists so we successfully parse it and emit an error in
             // `check_incompatible_attrs`; so we'll never reach here.
@@ -115,18 +153,56 @@ fn generate_spec_and_assertions(
     Ok((generated_items, generated_attributes))
 }
 
+/// Translate the preconditions and postconditions that can be evaluated at
+/// runtime to boolean Rust expressions.
+fn generate_runtime_checks(
+    prusti_attributes: &[(SpecAttributeKind, TokenStream)],
+) -> syn::Result<(Vec<TokenStream>, Vec<TokenStream>)> {
+    let mut rewriter = rewriter::AstRewriter::new();
+    let mut preconditions = vec![];
+    let mut postconditions = vec![];
+    let mut specs = vec![];
+    for (attr_kind, attr_tokens) in prusti_attributes {
+        match attr_kind {
+            SpecAttributeKind::Requires => specs.push((true, split_spec_message(attr_tokens.clone())?.0)),
+            SpecAttributeKind::Ensures => specs.push((false, split_spec_message(attr_tokens.clone())?.0)),
+            SpecAttributeKind::TrustedUnsafe => {
+                for (name, tokens) in split_named_arguments(attr_tokens.clone())? {
+                    specs.push((name == "requires", tokens));
+                }
+            }
+            _ => {}
+        }
+    }
+    for (is_precondition, tokens) in specs {
+        let checks = if is_precondition { &mut preconditions } else { &mut postconditions };
+        let spec_id = rewriter.generate_spec_id();
+        let assertion = rewriter.parse_assertion(spec_id, tokens)?;
+        checks.extend(runtime_checks::translate_assertion(&assertion));
+    }
+    Ok((preconditions, postconditions))
+}
+
 /// Generate spec items and attributes to typecheck the and later retrieve "requires" annotations.
 fn generate_for_requires(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
     let mut rewriter = rewriter::AstRewriter::new();
     let spec_id = rewriter.generate_spec_id();
     let spec_id_str = spec_id.to_string();
-    let assertion = rewriter.parse_assertion(spec_id, attr)?;
-    let spec_item = rewriter.generate_spec_item_fn(
+    let (assertion_tokens, message) = split_spec_message(attr)?;
+    let assertion = rewriter.parse_assertion(spec_id, assertion_tokens)?;
+    let mut spec_item = rewriter.generate_spec_item_fn(
         rewriter::SpecItemType::Precondition,
         spec_id,
         assertion,
         &item
     )?;
+    if let Some(message) = message {
+        if let syn::Item::Fn(ref mut spec_fn) = spec_item {
+            spec_fn.attrs.push(parse_quote_spanned! {item.span()=>
+                #[prusti::spec_message = #message]
+            });
+        }
+    }
     Ok((
         vec![spec_item],
         vec![parse_quote_spanned! {item.span()=>
@@ -140,13 +216,21 @@ fn generate_for_ensures(attr: TokenStream, item: &untyped::AnyFnItem) -> Generat
     let mut rewriter = rewriter::AstRewriter::new();
     let spec_id = rewriter.generate_spec_id();
     let spec_id_str = spec_id.to_string();
-    let assertion = rewriter.parse_assertion(spec_id, attr)?;
-    let spec_item = rewriter.generate_spec_item_fn(
+    let (assertion_tokens, message) = split_spec_message(attr)?;
+    let assertion = rewriter.parse_assertion(spec_id, assertion_tokens)?;
+    let mut spec_item = rewriter.generate_spec_item_fn(
         rewriter::SpecItemType::Postcondition,
         spec_id,
         assertion,
         &item
     )?;
+    if let Some(message) = message {
+        if let syn::Item::Fn(ref mut spec_fn) = spec_item {
+            spec_fn.attrs.push(parse_quote_spanned! {item.span()=>
+                #[prusti::spec_message = #message]
+            });
+        }
+    }
     Ok((
         vec![spec_item],
         vec![parse_quote_spanned! {item.span()=>
@@ -155,6 +239,84 @@ fn generate_for_ensures(attr: TokenStream, item: &untyped::AnyFnItem) -> Generat
     ))
 }
 
+/// Generate spec items and attributes to typecheck and later retrieve "refine_ensures"
+/// annotations, e.g. `#[refine_ensures(T: Ord => result.is_sorted())]`. The bounds are
+/// added to the spec item, so that the postcondition can use them.
+fn generate_for_refine_ensures(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    let (bounds_tokens, assertion_tokens) = split_ghost_constraint(attr)?;
+    let bounds = syn::parse::Parser::parse2(
+        syn::punctuated::Punctuated::<syn::WherePredicate, syn::Token![,]>::parse_terminated,
+        bounds_tokens,
+    )?;
+    let mut rewriter = rewriter::AstRewriter::new();
+    let spec_id = rewriter.generate_spec_id();
+    let spec_id_str = spec_id.to_string();
+    let assertion = rewriter.parse_assertion(spec_id, assertion_tokens)?;
+    let mut spec_item = rewriter.generate_spec_item_fn(
+        rewriter::SpecItemType::Postcondition,
+        spec_id,
+        assertion,
+        &item
+    )?;
+    if let syn::Item::Fn(ref mut spec_fn) = spec_item {
+        spec_fn.sig.generics.make_where_clause().predicates.extend(bounds);
+    }
+    Ok((
+        vec![spec_item],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::refined_post_spec_id_ref = #spec_id_str]
+        }],
+    ))
+}
+
+/// Split the tokens of a ghost constraint `<bounds> => <assertion>` at the
+/// first top-level `=>`.
+fn split_ghost_constraint(tokens: TokenStream) -> syn::Result<(TokenStream, TokenStream)> {
+    let span = tokens.span();
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let arrow = tokens.windows(2).position(|pair| match pair {
+        [TokenTree::Punct(first), TokenTree::Punct(second)] => {
+            first.as_char() == '='
+                && first.spacing() == proc_macro2::Spacing::Joint
+                && second.as_char() == '>'
+        }
+        _ => false,
+    });
+    match arrow {
+        Some(index) => Ok((
+            tokens[..index].iter().cloned().collect(),
+            tokens[index + 2..].iter().cloned().collect(),
+        )),
+        None => Err(syn::Error::new(
+            span,
+            "expected a ghost constraint of the form `T: Trait => assertion`",
+        )),
+    }
+}
+
+/// Split the message of a clause, e.g. `#[ensures(x > 0, "x must stay positive")]`,
+/// from its assertion. The message is a string literal after the last
+/// top-level comma; clauses without one are returned unchanged.
+fn split_spec_message(tokens: TokenStream) -> syn::Result<(TokenStream, Option<syn::LitStr>)> {
+    let mut tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    if let [.., TokenTree::Punct(comma), TokenTree::Literal(literal)] = tokens.as_slice() {
+        if comma.as_char() == ',' {
+            let literal = syn::Lit::new(literal.clone());
+            if let syn::Lit::Str(message) = literal {
+                if message.value().trim().is_empty() {
+                    return Err(syn::Error::new(
+                        message.span(),
+                        "the message of a specification clause cannot be empty",
+                    ));
+                }
+                tokens.truncate(tokens.len() - 2);
+                return Ok((tokens.into_iter().collect(), Some(message)));
+            }
+        }
+    }
+    Ok((tokens.into_iter().collect(), None))
+}
+
 /// Check if the given expression is identifier `result`.
 fn check_is_result(reference: &Option<untyped::Expression>) -> syn::Result<()> {
     if let Some(untyped::Expression { expr, ..}) = reference {
@@ -244,6 +406,24 @@ fn generate_for_pure(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedR
     ))
 }
 
+/// Generate spec items and attributes to typecheck and later retrieve "abstraction"
+/// annotations.
+fn generate_for_abstraction(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    if !attr.is_empty() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the `#[abstraction]` attribute does not take parameters"
+        ));
+    }
+
+    Ok((
+        vec![],
+        vec![parse_quote_spanned! {item.span()=>
+            #[prusti::abstraction]
+        }],
+    ))
+}
+
 /// Generate spec items and attributes to typecheck and later retrieve "trusted" annotations.
 fn generate_for_trusted(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
     if !attr.is_empty() {
@@ -261,6 +441,68 @@ fn generate_for_trusted(attr: TokenStream, item: &untyped::AnyFnItem) -> Generat
     ))
 }
 
+/// Generate spec items and attributes for "trusted_unsafe" annotations, which
+/// mark a function containing unsafe code as trusted. The optional
+/// `requires = ...` and `ensures = ...` arguments are handled as the
+/// corresponding attributes.
+fn generate_for_trusted_unsafe(attr: TokenStream, item: &untyped::AnyFnItem) -> GeneratedResult {
+    let mut generated_items = vec![];
+    let mut generated_attributes: Vec<syn::Attribute> = vec![
+        parse_quote_spanned! {item.span()=>
+            #[prusti::trusted]
+        },
+        parse_quote_spanned! {item.span()=>
+            #[prusti::trusted_unsafe]
+        },
+    ];
+    for (name, tokens) in split_named_arguments(attr)? {
+        let (new_items, new_attributes) = match name.to_string().as_str() {
+            "requires" => generate_for_requires(tokens, item)?,
+            "ensures" => generate_for_ensures(tokens, item)?,
+            _ => {
+                return Err(syn::Error::new(
+                    name.span(),
+                    "expected `requires = ...` or `ensures = ...`"
+                ));
+            }
+        };
+        generated_items.extend(new_items);
+        generated_attributes.extend(new_attributes);
+    }
+    Ok((generated_items, generated_attributes))
+}
+
+/// Split `name1 = tokens1, name2 = tokens2, ...` at the top-level commas.
+fn split_named_arguments(tokens: TokenStream) -> syn::Result<Vec<(syn::Ident, TokenStream)>> {
+    let mut arguments = vec![];
+    let mut iter = tokens.into_iter().peekable();
+    while iter.peek().is_some() {
+        let name = match iter.next() {
+            Some(TokenTree::Ident(ident)) => ident,
+            Some(token) => return Err(syn::Error::new(token.span(), "expected an argument name")),
+            None => unreachable!(),
+        };
+        match iter.next() {
+            Some(TokenTree::Punct(ref punct)) if punct.as_char() == '=' => {}
+            _ => return Err(syn::Error::new(name.span(), "expected `=` after the argument name")),
+        }
+        let mut value = TokenStream::new();
+        for token in &mut iter {
+            if let TokenTree::Punct(ref punct) = token {
+                if punct.as_char() == ',' {
+                    break;
+                }
+            }
+            value.extend(std::iter::once(token));
+        }
+        if value.is_empty() {
+            return Err(syn::Error::new(name.span(), "expected an expression after `=`"));
+        }
+        arguments.push((name, value));
+    }
+    Ok(arguments)
+}
+
 pub fn body_invariant(tokens: TokenStream) -> TokenStream {
     let mut rewriter = rewriter::AstRewriter::new();
     let spec_id = rewriter.generate_spec_id();
@@ -399,6 +641,15 @@ pub fn refine_trait_spec(_attr: TokenStream, tokens: TokenStream) -> TokenStream
         }
     }
     impl_block.items = new_items;
+    if let Some((_, trait_path, _)) = &impl_block.trait_ {
+        let mut qualifier = self_projection_qualifier::SelfProjectionQualifier::new(
+            &impl_block.self_ty,
+            trait_path,
+        );
+        for spec_item in generated_spec_items.iter_mut() {
+            syn::visit_mut::visit_impl_item_mut(&mut qualifier, spec_item);
+        }
+    }
     let spec_impl_block = syn::ItemImpl {
         attrs: Vec::new(),
         defaultness: impl_block.defaultness,
@@ -524,3 +775,111 @@ pub fn predicate(attr: TokenStream, tokens: TokenStream) -> TokenStream {
         }
     }
 }
+
+/// Generate the spec item of a `#[history_invariant(...)]` attribute on a
+/// struct or enum. The assertion is type-checked as the postcondition of a
+/// synthetic `fn(&mut self)` method, so that it can relate `old(self)` and
+/// `self`, and the type refers to it with a
+/// `#[prusti::history_invariant_spec_id_ref]` attribute.
+pub fn history_invariant(attr: TokenStream, tokens: TokenStream) -> TokenStream {
+    let mut item: syn::Item = handle_result!(syn::parse2(tokens));
+    let item_span = item.span();
+    let (attrs, ident, generics) = match &mut item {
+        syn::Item::Struct(item_struct) => {
+            (&mut item_struct.attrs, item_struct.ident.clone(), item_struct.generics.clone())
+        }
+        syn::Item::Enum(item_enum) => {
+            (&mut item_enum.attrs, item_enum.ident.clone(), item_enum.generics.clone())
+        }
+        _ => {
+            return syn::Error::new(
+                item_span,
+                "`#[history_invariant]` can only be used on structs and enums",
+            ).to_compile_error();
+        }
+    };
+
+    let mut rewriter = rewriter::AstRewriter::new();
+    let spec_id = rewriter.generate_spec_id();
+    let spec_id_str = spec_id.to_string();
+    let assertion = handle_result!(rewriter.parse_assertion(spec_id, attr));
+    let mutating_method = untyped::AnyFnItem::ImplMethod(parse_quote_spanned! {item_span=>
+        fn history_invariant(&mut self) {}
+    });
+    let spec_item = match handle_result!(rewriter.generate_spec_item_fn(
+        rewriter::SpecItemType::Postcondition,
+        spec_id,
+        assertion,
+        &mutating_method,
+    )) {
+        syn::Item::Fn(spec_item_fn) => spec_item_fn,
+        x => unreachable!("Unexpected variant: {:?}", x),
+    };
+    attrs.push(parse_quote_spanned! {item_span=>
+        #[prusti::history_invariant_spec_id_ref = #spec_id_str]
+    });
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote_spanned! {item_span=>
+        #item
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #spec_item
+        }
+    }
+}
+
+/// Like `closure`, this function is called from both prusti-contracts-impl
+/// and prusti-contracts-internal. It emits the items of a specification
+/// library and exports them as a macro, so that other crates can expand them
+/// locally, where Prusti collects specifications. The macro is called
+/// `import_specs!` unless the library starts with `macro <name>;`, which is
+/// needed to define several libraries in the same crate.
+pub fn spec_library(tokens: TokenStream) -> TokenStream {
+    let (macro_name, tokens) = handle_result!(split_spec_library_name(tokens));
+    let file: syn::File = handle_result!(syn::parse2(tokens.clone()));
+    if let Some(dollar) = find_dollar(tokens.clone()) {
+        return syn::Error::new(
+            dollar,
+            "a specification library cannot contain `$`",
+        ).to_compile_error();
+    }
+    let items = &file.items;
+    let callsite_span = Span::call_site();
+    quote_spanned! {callsite_span=>
+        #(#items)*
+
+        /// Expands the specifications of this library in the current module.
+        #[macro_export]
+        macro_rules! #macro_name {
+            () => {
+                #tokens
+            };
+        }
+    }
+}
+
+/// Split the optional `macro <name>;` header off a specification library.
+fn split_spec_library_name(tokens: TokenStream) -> syn::Result<(syn::Ident, TokenStream)> {
+    let parser = |input: syn::parse::ParseStream| {
+        let macro_name = if input.peek(syn::Token![macro]) {
+            input.parse::<syn::Token![macro]>()?;
+            let macro_name = input.parse()?;
+            input.parse::<syn::Token![;]>()?;
+            macro_name
+        } else {
+            syn::Ident::new("import_specs", Span::call_site())
+        };
+        Ok((macro_name, input.parse::<TokenStream>()?))
+    };
+    syn::parse::Parser::parse2(parser, tokens)
+}
+
+/// The span of a `$` in `tokens`, which would be interpreted by
+/// `macro_rules!`.
+fn find_dollar(tokens: TokenStream) -> Option<Span> {
+    tokens.into_iter().find_map(|token| match token {
+        TokenTree::Punct(punct) if punct.as_char() == '$' => Some(punct.span()),
+        TokenTree::Group(group) => find_dollar(group.stream()),
+        _ => None,
+    })
+}
@@ -0,0 +1,295 @@
+//! Translation of specifications to checks that are evaluated when the
+//! program runs. The checks are emitted only when the `PRUSTI_RUNTIME_CHECKS`
+//! environment variable is `true`, which the Prusti driver sets when both
+//! `runtime_checks` and `full_compilation` are enabled. The checks of the
+//! trusted functions alone are emitted when `PRUSTI_TRUSTED_RUNTIME_CHECKS`
+//! is `true` (for `trusted_runtime_checks`).
+
+use crate::specifications::untyped;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+
+/// Functions of `prusti_contracts` that cannot be evaluated at runtime.
+const GHOST_FUNCTIONS: &[&str] = &["old", "before_expiry"];
+
+pub fn enabled() -> bool {
+    std::env::var("PRUSTI_RUNTIME_CHECKS").map_or(false, |value| value == "true")
+}
+
+pub fn enabled_for_trusted() -> bool {
+    enabled() || std::env::var("PRUSTI_TRUSTED_RUNTIME_CHECKS").map_or(false, |value| value == "true")
+}
+
+/// Translate an assertion to a boolean Rust expression, or return `None`
+/// if the assertion cannot be evaluated at runtime (e.g. quantifiers or
+/// `old(..)` expressions).
+pub fn translate_assertion(assertion: &untyped::Assertion) -> Option<TokenStream> {
+    match &*assertion.kind {
+        untyped::AssertionKind::Expr(expression) => {
+            let expr = &expression.expr;
+            if mentions_ghost_function(expr.to_token_stream()) {
+                return None;
+            }
+            Some(quote_spanned! {expr.span()=> (#expr) })
+        }
+        untyped::AssertionKind::And(assertions) => {
+            let conjuncts = assertions
+                .iter()
+                .map(translate_assertion)
+                .collect::<Option<Vec<_>>>()?;
+            Some(quote_spanned! {proc_macro2::Span::call_site()=> (true #(&& #conjuncts)*) })
+        }
+        untyped::AssertionKind::Implies(lhs, rhs) => {
+            let lhs = translate_assertion(lhs)?;
+            let rhs = translate_assertion(rhs)?;
+            Some(quote_spanned! {proc_macro2::Span::call_site()=> (!#lhs || #rhs) })
+        }
+        untyped::AssertionKind::TypeCond(..) |
+        untyped::AssertionKind::ForAll(..) |
+        untyped::AssertionKind::SpecEntailment { .. } => None,
+    }
+}
+
+fn mentions_ghost_function(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => GHOST_FUNCTIONS.iter().any(|name| ident == name),
+        TokenTree::Group(group) => mentions_ghost_function(group.stream()),
+        _ => false,
+    })
+}
+
+/// Whether the block may leave the function without reaching its end, in
+/// which case the postconditions cannot be checked after the block.
+fn has_early_exit(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "return",
+        TokenTree::Punct(punct) => punct.as_char() == '?',
+        TokenTree::Group(group) => has_early_exit(group.stream()),
+        TokenTree::Literal(_) => false,
+    })
+}
+
+/// Integer, floating-point, boolean and character types, whose arguments
+/// can be copied before the body to check the postconditions.
+const COPY_TYPES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize",
+    "i8", "i16", "i32", "i64", "i128", "isize",
+    "f32", "f64", "bool", "char",
+];
+
+/// The arguments that the checks of the postconditions read after the body.
+struct PostStateArgs {
+    /// The arguments that are copied before the body, because the body may
+    /// modify them while the postconditions refer to their value at the call.
+    copied: Vec<syn::Ident>,
+    /// The names of the arguments that the body may move or modify and that
+    /// cannot be copied.
+    moved: Vec<String>,
+}
+
+impl PostStateArgs {
+    /// Arguments of a reference type are not copied: the postconditions
+    /// refer to the final value of the target of a mutable reference, and
+    /// to the unchanged target of a shared reference.
+    fn new(sig: &syn::Signature) -> Self {
+        let mut args = PostStateArgs { copied: vec![], moved: vec![] };
+        for input in &sig.inputs {
+            match input {
+                syn::FnArg::Receiver(receiver) => {
+                    if receiver.reference.is_none() {
+                        args.moved.push("self".to_string());
+                    }
+                }
+                syn::FnArg::Typed(pat_type) => match (&*pat_type.pat, &*pat_type.ty) {
+                    (_, syn::Type::Reference(_)) => {}
+                    (syn::Pat::Ident(pat_ident), ty)
+                        if pat_ident.by_ref.is_none()
+                            && pat_ident.subpat.is_none()
+                            && is_copy_type(ty) =>
+                    {
+                        args.copied.push(pat_ident.ident.clone());
+                    }
+                    (pat, _) => args.moved.extend(pattern_names(pat.to_token_stream())),
+                },
+            }
+        }
+        args
+    }
+
+    /// Keep the postconditions that do not mention a moved argument, and
+    /// restrict the copied arguments to the ones that they mention.
+    fn select(&mut self, postconditions: Vec<TokenStream>) -> Vec<TokenStream> {
+        let moved = &self.moved;
+        let postconditions: Vec<_> = postconditions
+            .into_iter()
+            .filter(|check| !moved.iter().any(|name| mentions(check.clone(), name)))
+            .collect();
+        self.copied.retain(|ident| {
+            postconditions.iter().any(|check| mentions(check.clone(), &ident.to_string()))
+        });
+        postconditions
+    }
+
+    /// The statements that copy the arguments before the body and that
+    /// shadow them with the copies before the checks of the postconditions.
+    fn copy_and_restore(&self, span: proc_macro2::Span) -> (TokenStream, TokenStream) {
+        if self.copied.is_empty() {
+            return (TokenStream::new(), TokenStream::new());
+        }
+        let copied = &self.copied;
+        (
+            quote_spanned! {span=> let prusti_args_at_call = (#(#copied,)*); },
+            quote_spanned! {span=> let (#(#copied,)*) = prusti_args_at_call; },
+        )
+    }
+}
+
+fn is_copy_type(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(type_path) if type_path.qself.is_none() => type_path.path
+            .get_ident()
+            .map_or(false, |ident| COPY_TYPES.iter().any(|name| ident == name)),
+        _ => false,
+    }
+}
+
+/// The identifiers of a pattern, which over-approximate the variables that
+/// it binds.
+fn pattern_names(tokens: TokenStream) -> Vec<String> {
+    let mut names = vec![];
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) if ident != "mut" && ident != "ref" && ident != "_" => {
+                names.push(ident.to_string());
+            }
+            TokenTree::Group(group) => names.extend(pattern_names(group.stream())),
+            _ => {}
+        }
+    }
+    names
+}
+
+fn mentions(tokens: TokenStream, name: &str) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => mentions(group.stream(), name),
+        _ => false,
+    })
+}
+
+/// Add the checks of the preconditions at the beginning of the body of
+/// `item` and the checks of the postconditions at its end. The
+/// postconditions are not checked if the body contains a `return` or a `?`.
+/// The postconditions refer to the arguments of integer, boolean and
+/// character types by their value at the call, and the ones that mention
+/// other arguments taken by value are not checked, since the body may have
+/// moved them.
+pub fn instrument(
+    item: &mut untyped::AnyFnItem,
+    preconditions: Vec<TokenStream>,
+    postconditions: Vec<TokenStream>,
+) {
+    let sig = item.sig().clone();
+    let block = match item {
+        untyped::AnyFnItem::Fn(item) => &mut *item.block,
+        untyped::AnyFnItem::ImplMethod(item) => &mut item.block,
+        untyped::AnyFnItem::TraitMethod(item) => match &mut item.default {
+            Some(block) => block,
+            None => return,
+        },
+    };
+    let span = block.span();
+    let mut args = PostStateArgs::new(&sig);
+    let postconditions = args.select(postconditions);
+    let check_posts = !postconditions.is_empty() && !has_early_exit(block.to_token_stream());
+    if preconditions.is_empty() && !check_posts {
+        return;
+    }
+    let body = if check_posts {
+        let post_checks = postconditions.into_iter().map(|check| runtime_check("postcondition", check));
+        let (copy_args, restore_args) = args.copy_and_restore(span);
+        quote_spanned! {span=>
+            #copy_args
+            let result = #block;
+            {
+                #restore_args
+                #(#post_checks)*
+            }
+            result
+        }
+    } else {
+        block.stmts.iter().map(|stmt| stmt.to_token_stream()).collect()
+    };
+    let pre_checks = preconditions.into_iter().map(|check| runtime_check("precondition", check));
+    *block = parse_quote_spanned! {span=>
+        {
+            #(#pre_checks)*
+            #body
+        }
+    };
+}
+
+/// Like `instrument`, but the body of `item` becomes a closure that is called
+/// between the checks of the preconditions and of the postconditions, so
+/// that the postconditions are also checked when the body returns early.
+/// This is used for trusted functions, whose contracts are not verified.
+/// The arguments are handled as in `instrument`. `const` and `async`
+/// functions, and the ones that return an `impl Trait`, cannot be wrapped
+/// and are instrumented instead.
+pub fn wrap(
+    item: &mut untyped::AnyFnItem,
+    preconditions: Vec<TokenStream>,
+    postconditions: Vec<TokenStream>,
+) {
+    let sig = item.sig();
+    let return_type = match &sig.output {
+        syn::ReturnType::Default => quote_spanned! {sig.span()=> () },
+        syn::ReturnType::Type(_, ty) => ty.to_token_stream(),
+    };
+    if sig.constness.is_some() || sig.asyncness.is_some() || mentions_impl(return_type.clone()) {
+        instrument(item, preconditions, postconditions);
+        return;
+    }
+    let mut args = PostStateArgs::new(sig);
+    let postconditions = args.select(postconditions);
+    let block = match item.block_mut() {
+        Some(block) => block,
+        None => return,
+    };
+    if preconditions.is_empty() && postconditions.is_empty() {
+        return;
+    }
+    let span = block.span();
+    let pre_checks = preconditions.into_iter().map(|check| runtime_check("precondition", check));
+    let post_checks = postconditions.into_iter().map(|check| runtime_check("postcondition", check));
+    let (copy_args, restore_args) = args.copy_and_restore(span);
+    *block = parse_quote_spanned! {span=>
+        {
+            #(#pre_checks)*
+            #copy_args
+            #[allow(unused_mut, clippy::redundant_closure_call)]
+            let result = (|| -> #return_type #block)();
+            {
+                #restore_args
+                #(#post_checks)*
+            }
+            result
+        }
+    };
+}
+
+fn mentions_impl(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "impl",
+        TokenTree::Group(group) => mentions_impl(group.stream()),
+        _ => false,
+    })
+}
+
+fn runtime_check(kind: &str, check: TokenStream) -> TokenStream {
+    let message = format!("{} might not hold: {}", kind, check);
+    quote_spanned! {check.span()=>
+        prusti_contracts::runtime_check(#check, #message);
+    }
+}
@@ -0,0 +1,34 @@
+use syn::spanned::Spanned;
+
+/// Qualify the associated type projections of `Self` (e.g. `Self::Output`)
+/// with the trait that defines them (`<Ty as Trait>::Output`). This is needed
+/// when the spec items of a trait implementation are moved to an inherent
+/// impl block, where `Self::Output` would be ambiguous.
+pub struct SelfProjectionQualifier<'a> {
+    self_ty: &'a syn::Type,
+    trait_path: &'a syn::Path,
+}
+
+impl<'a> SelfProjectionQualifier<'a> {
+    pub fn new(self_ty: &'a syn::Type, trait_path: &'a syn::Path) -> Self {
+        SelfProjectionQualifier {
+            self_ty,
+            trait_path,
+        }
+    }
+}
+
+impl<'a> syn::visit_mut::VisitMut for SelfProjectionQualifier<'a> {
+    fn visit_type_path_mut(&mut self, type_path: &mut syn::TypePath) {
+        syn::visit_mut::visit_type_path_mut(self, type_path);
+        let segments = &type_path.path.segments;
+        if type_path.qself.is_none() && segments.len() > 1 && segments[0].ident == "Self" {
+            let self_ty = self.self_ty;
+            let trait_path = self.trait_path;
+            let projection = segments.iter().skip(1);
+            *type_path = parse_quote_spanned! {type_path.span()=>
+                <#self_ty as #trait_path>::#(#projection)::*
+            };
+        }
+    }
+}
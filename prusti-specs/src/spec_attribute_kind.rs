@@ -5,10 +5,13 @@ use std::convert::TryFrom;
 pub enum SpecAttributeKind {
     Requires,
     Ensures,
+    RefineEnsures,
     AfterExpiry,
     AfterExpiryIf,
     Pure,
+    Abstraction,
     Trusted,
+    TrustedUnsafe,
     Predicate,
 }
 
@@ -19,10 +22,13 @@ impl TryFrom<String> for SpecAttributeKind {
         match name.as_str() {
             "requires" => Ok(SpecAttributeKind::Requires),
             "ensures" => Ok(SpecAttributeKind::Ensures),
+            "refine_ensures" => Ok(SpecAttributeKind::RefineEnsures),
             "after_expiry" => Ok(SpecAttributeKind::AfterExpiry),
             "after_expiry_if" => Ok(SpecAttributeKind::AfterExpiryIf),
             "pure" => Ok(SpecAttributeKind::Pure),
+            "abstraction" => Ok(SpecAttributeKind::Abstraction),
             "trusted" => Ok(SpecAttributeKind::Trusted),
+            "trusted_unsafe" => Ok(SpecAttributeKind::TrustedUnsafe),
             "predicate" => Ok(SpecAttributeKind::Predicate),
             _ => Err(name),
         }
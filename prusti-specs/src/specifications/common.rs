@@ -60,6 +60,7 @@ pub enum SpecIdRef {
         rhs: SpecificationId,
     },
     Predicate(SpecificationId),
+    RefinedPostcondition(SpecificationId),
 }
 
 impl Display for SpecificationId {
@@ -324,6 +325,10 @@ pub struct ProcedureSpecification<EID, ET, AT> {
     /// otherwise.
     pub predicate_body: Option<Assertion<EID, ET, AT>>,
 
+    /// Postconditions that hold only when the type parameters satisfy the
+    /// extra bounds of the given spec item, e.g. `T: Ord`.
+    pub refined_posts: Vec<(ET, Assertion<EID, ET, AT>)>,
+
     pub pure: bool,
     pub trusted: bool,
 }
@@ -340,6 +345,7 @@ impl<EID, ET, AT> ProcedureSpecification<EID, ET, AT> {
             posts,
             pledges,
             predicate_body,
+            refined_posts: Vec::new(),
             pure: false,
             trusted: false,
         }
@@ -382,11 +388,17 @@ impl<EID: Clone + Debug, ET: Clone + Debug, AT: Clone + Debug> ProcedureSpecific
         } else {
             other.predicate_body.clone()
         };
+        let refined_posts = if other.refined_posts.is_empty() {
+            self.refined_posts.clone()
+        } else {
+            other.refined_posts.clone()
+        };
         Self {
             pres,
             posts,
             pledges,
             predicate_body,
+            refined_posts,
             pure: other.pure,
             trusted: other.trusted,
         }
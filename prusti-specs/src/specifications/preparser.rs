@@ -12,6 +12,7 @@ use quote::quote;
 use super::common;
 use crate::specifications::common::{ForAllVars, SpecEntailmentVars, TriggerSet, Trigger};
 use syn::spanned::Spanned;
+use crate::parse_quote_spanned;
 
 pub type AssertionWithoutId = common::Assertion<(), syn::Expr, Arg>;
 pub type PledgeWithoutId = common::Pledge<(), syn::Expr, Arg>;
@@ -113,6 +114,17 @@ impl ParserStream {
         }
         false
     }
+    /// Check if the input starts with the keyword followed by a parenthesized
+    /// block, like a call of a function named as the keyword. Does not set
+    /// the span.
+    fn peek_keyword_call(&self, keyword: &str) -> bool {
+        match (self.tokens.get(0), self.tokens.get(1)) {
+            (Some(TokenTree::Ident(ident)), Some(TokenTree::Group(group))) => {
+                ident == keyword && group.delimiter() == Delimiter::Parenthesis
+            }
+            _ => false,
+        }
+    }
     /// Check if the input starts with the operator. Does not set the span.
     fn peek_operator(&self, operator: &str) -> bool {
         for (i, c) in operator.char_indices() {
@@ -275,6 +287,21 @@ impl Parse for ForAllArgs {
     }
 }
 
+/// The places that are not modified, in `unchanged(self.x, self.y)`
+#[derive(Debug)]
+struct UnchangedPlaces {
+    places: syn::punctuated::Punctuated<syn::Expr, Token![,]>
+}
+
+impl Parse for UnchangedPlaces {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let parsed: syn::punctuated::Punctuated<syn::Expr, Token![,]> = input.parse_terminated(syn::Expr::parse)?;
+        Ok(Self{
+            places: parsed
+        })
+    }
+}
+
 #[derive(Debug)]
 struct SpecEntArgs {
     args: syn::punctuated::Punctuated<Arg, Token![,]>
@@ -517,6 +544,39 @@ impl Parser {
             return Err(self.error_expected_parenthesis());
         }
     }
+    /// Desugar `unchanged(a, b)` into the conjuncts `a == old(a)` and
+    /// `b == old(b)`.
+    fn resolve_unchanged(&mut self) -> syn::Result<()> {
+        if self.expected_operator {
+            return Err(self.error_expected_operator());
+        }
+
+        let group = match self.input.check_and_consume_parenthesized_block() {
+            Some(group) => group,
+            None => return Err(self.error_expected_parenthesis()),
+        };
+        let unchanged: UnchangedPlaces = syn::parse2(group.stream())?;
+        if unchanged.places.is_empty() {
+            return Err(self.error_no_unchanged_places());
+        }
+        for place in unchanged.places {
+            let expr: syn::Expr = parse_quote_spanned! {place.span()=>
+                (#place) == old(#place)
+            };
+            self.conjuncts.push(AssertionWithoutId {
+                kind: box common::AssertionKind::Expr(ExpressionWithoutId {
+                    spec_id: common::SpecificationId::dummy(),
+                    id: (),
+                    expr,
+                })
+            });
+        }
+
+        self.previous_expression_resolved = true;
+        self.expected_only_operator = true;
+        self.expected_operator = true;
+        Ok(())
+    }
     fn resolve_spec_ent(&mut self) -> syn::Result<()> {
         // handles the case when there is no lhs of the |= operator
         if !self.expected_operator {
@@ -684,6 +744,12 @@ impl Parser {
                     return Err(err);
                 }
             }
+            else if self.expr.is_empty() && self.input.peek_keyword_call("unchanged") {
+                self.input.check_and_consume_keyword("unchanged");
+                if let Err(err) = self.resolve_unchanged() {
+                    return Err(err);
+                }
+            }
             else if self.input.check_and_consume_operator("|=") {
                 if let Err(err) = self.resolve_spec_ent() {
                     return Err(err);
@@ -842,4 +908,7 @@ impl Parser {
     fn error_no_quantifier_arguments(&self) -> syn::Error {
         syn::Error::new(self.input.span, "a quantifier must have at least one argument")
     }
+    fn error_no_unchanged_places(&self) -> syn::Error {
+        syn::Error::new(self.input.span, "`unchanged` must have at least one argument")
+    }
 }
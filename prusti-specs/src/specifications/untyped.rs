@@ -74,6 +74,14 @@ impl AnyFnItem {
         }
     }
 
+    pub fn sig_mut(&mut self) -> &mut syn::Signature {
+        match self {
+            AnyFnItem::Fn(item) => &mut item.sig,
+            AnyFnItem::TraitMethod(item) => &mut item.sig,
+            AnyFnItem::ImplMethod(item) => &mut item.sig,
+        }
+    }
+
     pub fn block(&self) -> Option<&syn::Block> {
         match self {
             AnyFnItem::Fn(item) => Some(&item.block),
@@ -81,6 +89,14 @@ impl AnyFnItem {
             AnyFnItem::TraitMethod(item) => item.default.as_ref(),
         }
     }
+
+    pub fn block_mut(&mut self) -> Option<&mut syn::Block> {
+        match self {
+            AnyFnItem::Fn(item) => Some(&mut item.block),
+            AnyFnItem::ImplMethod(item) => Some(&mut item.block),
+            AnyFnItem::TraitMethod(item) => item.default.as_mut(),
+        }
+    }
 }
 
 impl ToTokens for AnyFnItem {
@@ -0,0 +1,8 @@
+use prusti_contracts::*;
+
+#[ensures(result == x)]
+fn identity(x: u32, #[ghost] y: u32) -> u32 {
+    x + y //~ ERROR the ghost parameter `y` can only be used in specifications
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use prusti_contracts::*;
+
+fn test(b: bool) {
+    prusti_use_lemma!(square_nonneg(b)); //~ ERROR must be integers
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use prusti_contracts::*;
+
+fn main() {
+    let long = [1u8, 2, 3];
+    let mut short = [0u8; 2];
+    short.copy_from_slice(&long); //~ ERROR statement might panic
+}
@@ -0,0 +1,14 @@
+use prusti_contracts::*;
+
+fn main() {
+    let mut count = 0u32;
+    let mut inc = || {
+        if count < 10 {
+            count += 1;
+        }
+    };
+    inc();
+    // The closure has no postcondition, so the captured variable might have
+    // changed.
+    assert!(count == 0); //~ ERROR the asserted expression might not hold
+}
@@ -0,0 +1,23 @@
+// compile-flags: -Pgroup_errors_by_cause=true
+
+use prusti_contracts::*;
+
+#[requires(x > 0)]
+fn positive(x: u32) -> u32 {
+    x
+}
+
+fn root_cause(x: u32) {
+    assert!(x > 5); //~ ERROR the asserted expression might not hold
+    positive(x);
+}
+
+fn independent(x: u32, b: bool) {
+    if b {
+        assert!(x > 5); //~ ERROR the asserted expression might not hold
+    } else {
+        assert!(x < 5); //~ ERROR the asserted expression might not hold
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+#[prusti::check_panics(maybe)]
+fn invalid_flag(x: u32) -> u32 { //~ ERROR `#[prusti::check_panics(..)]` expects `true` or `false`, not `maybe`
+    x
+}
+
+#[prusti::check_panics(true)]
+fn checked(x: u32) -> u32 {
+    if x == 0 {
+        panic!() //~ ERROR panic!(..) statement might be reachable
+    }
+    x
+}
+
+fn main() {}
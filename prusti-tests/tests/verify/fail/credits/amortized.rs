@@ -0,0 +1,23 @@
+use prusti_contracts::*;
+
+pub struct Tick;
+
+#[requires(credits::<Tick>(1))]
+fn step() {
+    consume_credits!(Tick, 1);
+}
+
+#[requires(credits::<Tick>(1))]
+fn two_steps() {
+    step();
+    step(); //~ ERROR there might not be enough credits of kind `Tick`
+}
+
+#[requires(credits::<Tick>(2))]
+#[ensures(credits::<Tick>(1))]
+fn returns_too_many() { //~ ERROR there might not be enough credits of kind `Tick`
+    step();
+    step();
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+// compile-flags: -Pcheck_drop_obligations=true
+
+use prusti_contracts::*;
+
+struct Guard {
+    released: bool,
+}
+
+impl Guard {
+    #[ensures(!result.released)]
+    fn acquire() -> Self {
+        Guard { released: false }
+    }
+}
+
+impl Drop for Guard {
+    #[requires(self.released)]
+    fn drop(&mut self) {}
+}
+
+fn main() {
+    let _guard = Guard::acquire();
+} //~ ERROR the precondition of `drop` might not hold
@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+
+/// `clone` of this type does not return an equal value.
+struct Counter {
+    value: u32,
+}
+
+impl Clone for Counter {
+    #[trusted]
+    fn clone(&self) -> Self {
+        Counter { value: 0 }
+    }
+}
+
+#[derive(Clone)]
+struct Wrapper {
+    counter: Counter,
+    id: u32,
+}
+
+fn main() {
+    let w = Wrapper { counter: Counter { value: 5 }, id: 1 };
+    let v = w.clone();
+    // The derived `clone` calls the `clone` of `Counter`, so nothing is
+    // known about its result.
+    assert!(v.id == 1); //~ ERROR the asserted expression might not hold
+    assert!(v.counter.value == 5); //~ ERROR the asserted expression might not hold
+}
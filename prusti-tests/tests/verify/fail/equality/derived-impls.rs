@@ -0,0 +1,14 @@
+#[derive(Clone, PartialEq, Default)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {
+    let p = Point { x: 1, y: 2 };
+    let q = p.clone();
+    assert!(q.x == 2); //~ ERROR the asserted expression might not hold
+
+    let origin = Point::default();
+    assert!(origin.y == 1); //~ ERROR the asserted expression might not hold
+}
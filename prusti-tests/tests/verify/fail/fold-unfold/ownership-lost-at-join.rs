@@ -0,0 +1,20 @@
+use prusti_contracts::*;
+
+struct T {
+    f: Box<u32>,
+}
+
+// The loop needs the ownership of `x.f`, which is only kept by the branch in
+// which it is not moved.
+fn test(c: bool, mut x: T) {
+    if c { let _b = x.f; } //~ ERROR the ownership of `x` (or of a part of it) is lost where the branches join
+    //~| NOTE ownership lost here
+    //~| NOTE still owned in this branch
+    let mut i = 0;
+    while i < 10 {
+        x.f = Box::new(i);
+        i += 1;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+// compile-flags: -Phavoc_foreign_calls=true
+
+use prusti_contracts::*;
+
+extern "C" {
+    fn abs(x: i32) -> i32;
+    fn time(t: *mut i64) -> i64;
+}
+
+fn result_is_unknown() {
+    let x = unsafe { abs(-3) };
+    assert!(x == 3); //~ ERROR the asserted expression might not hold
+}
+
+fn target_is_unknown() {
+    let mut now = 0;
+    unsafe { time(&mut now) };
+    assert!(now == 0); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use prusti_contracts::*;
+
+trait Bounded {}
+
+#[refine_ensures(T: Bounded => result == 0)]
+fn not_trusted<T>(_x: T) -> u32 { //~ ERROR `#[refine_ensures(..)]` is only supported on trusted functions
+    0
+}
+
+fn main() {}
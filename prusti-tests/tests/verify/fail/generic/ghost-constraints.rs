@@ -0,0 +1,35 @@
+use prusti_contracts::*;
+
+trait Bounded {
+    fn max_value() -> u32;
+}
+
+impl Bounded for u8 {
+    fn max_value() -> u32 {
+        255
+    }
+}
+
+struct Wrapper<T> {
+    value: T,
+}
+
+impl<T> Wrapper<T> {
+    #[trusted]
+    #[refine_ensures(T: Bounded => result <= 255)]
+    fn width(&self) -> u32 {
+        unimplemented!()
+    }
+}
+
+fn unbounded(w: &Wrapper<u16>) {
+    let width = w.width();
+    assert!(width <= 255); //~ ERROR the asserted expression might not hold
+}
+
+fn generic<T>(w: &Wrapper<T>) {
+    let width = w.width();
+    assert!(width <= 255); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
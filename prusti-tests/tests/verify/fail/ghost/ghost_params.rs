@@ -0,0 +1,10 @@
+use prusti_contracts::*;
+
+#[requires(bound >= 10)]
+fn check(x: u32, #[ghost] bound: u32) {}
+
+fn client() {
+    check(1, ghost!(5)); //~ ERROR precondition might not hold
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use prusti_contracts::*;
+
+#[history_invariant(old(self.value) <= self.value)] //~ ERROR postcondition might not hold
+struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[requires(self.value > 0)]
+    fn decrement(&mut self) {
+        self.value -= 1;
+    }
+}
+
+fn main() {}
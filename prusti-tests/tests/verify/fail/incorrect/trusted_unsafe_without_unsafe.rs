@@ -0,0 +1,15 @@
+use prusti_contracts::*;
+
+#[trusted_unsafe(ensures = result == x)]
+fn identity(x: i32) -> i32 { //~ ERROR can only be used on functions that contain unsafe code
+    x
+}
+
+// A raw pointer in the signature or the word `unsafe` in a string is not
+// unsafe code.
+#[trusted_unsafe]
+fn describe(_pointer: *const i32) -> &'static str { //~ ERROR can only be used on functions that contain unsafe code
+    "unsafe"
+}
+
+fn main() {}
@@ -0,0 +1,38 @@
+// compile-flags: -Plint_specs=true
+
+use prusti_contracts::*;
+
+struct Wrapper {
+    value: i32,
+}
+
+#[pure]
+fn value(w: &Wrapper) -> i32 {
+    w.value
+}
+
+#[ensures(value(&w) > 0)] //~ WARNING only mentions arguments that are moved into the function
+//~| ERROR postcondition might not hold
+fn consume(w: Wrapper) {}
+
+#[requires(x > 0)]
+#[ensures(x > 0)] //~ WARNING repeats a precondition
+fn repeat(x: i32) {}
+
+#[pure]
+#[requires(x > 0)]
+#[ensures(x >= 0)] //~ WARNING does not mention `result`
+fn identity(x: i32) -> i32 {
+    x
+}
+
+#[ensures(old(x) == x)] //~ WARNING `old(..)` has no effect
+fn unchanged(x: i32) {}
+
+#[ensures(*x == old(*x))]
+#[ensures(value(&result) == value(&w))]
+fn no_warnings(x: &mut i32, w: Wrapper) -> Wrapper {
+    w
+}
+
+fn main() {}
@@ -0,0 +1,44 @@
+use prusti_contracts::*;
+use std::ops::{Add, AddAssign};
+
+#[derive(Clone, Copy)]
+struct Money {
+    cents: u32,
+}
+
+impl Add for Money {
+    type Output = u32;
+
+    #[pure]
+    #[requires(self.cents <= 1000 && other.cents <= 1000)]
+    fn add(self, other: Money) -> u32 {
+        self.cents + other.cents
+    }
+}
+
+struct Counter {
+    value: u32,
+}
+
+impl AddAssign<u32> for Counter {
+    #[requires(self.value <= 100 && amount <= 100)]
+    #[ensures(self.value == old(self.value) + amount)]
+    fn add_assign(&mut self, amount: u32) {
+        self.value += amount;
+    }
+}
+
+fn unbounded_sum(a: Money, b: Money) -> u32 {
+    a + b //~ ERROR precondition of pure function call might not hold
+}
+
+#[requires(a.cents == 1 && b.cents == 2)]
+fn sum(a: Money, b: Money) {
+    assert!(a + b == 4); //~ ERROR the asserted expression might not hold
+}
+
+fn increment(counter: &mut Counter) {
+    *counter += 1; //~ ERROR precondition might not hold
+}
+
+fn main() {}
@@ -0,0 +1,42 @@
+use prusti_contracts::*;
+use std::ops::{Deref, Index};
+
+struct Pair {
+    first: u32,
+    second: u32,
+}
+
+impl Index<bool> for Pair {
+    type Output = u32;
+
+    #[pure]
+    fn index(&self, first: bool) -> &u32 {
+        if first { &self.first } else { &self.second }
+    }
+}
+
+struct Wrapper {
+    value: u32,
+}
+
+impl Deref for Wrapper {
+    type Target = u32;
+
+    #[ensures(*result == self.value)]
+    fn deref(&self) -> &u32 {
+        &self.value
+    }
+}
+
+#[requires(pair[true] == 1)]
+#[ensures(result == 1)] //~ ERROR postcondition might not hold
+fn read_second(pair: &Pair) -> u32 {
+    pair[false]
+}
+
+fn deref(wrapper: &Wrapper) {
+    let value = *wrapper;
+    assert!(value == wrapper.value + 1); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+// compile-flags: -Ppanic_freedom=true
+
+fn parse(value: Option<u32>) -> u32 {
+    value.unwrap() //~ ERROR unwrap()/expect(..) might be called on a None or an Err
+}
+
+fn parse_or_zero(value: Option<u32>) -> u32 {
+    match value {
+        Some(_) => value.unwrap(),
+        None => 0,
+    }
+}
+
+fn increment(value: u32) -> u32 {
+    value + 1 //~ ERROR assertion might fail with "attempt to add with overflow"
+}
+
+fn main() {}
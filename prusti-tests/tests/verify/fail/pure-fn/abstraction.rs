@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+mod stack {
+    use prusti_contracts::*;
+
+    pub struct Stack {
+        len: usize,
+    }
+
+    impl Stack {
+        #[pure]
+        pub fn len(&self) -> usize {
+            self.len
+        }
+
+        #[pure]
+        #[abstraction]
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        #[requires(self.len() == 0)]
+        #[ensures(self.is_empty())]
+        pub fn assert_empty(&self) {}
+    }
+}
+
+use stack::Stack;
+
+#[requires(stack.len() == 0)]
+#[ensures(stack.is_empty())] //~ ERROR postcondition might not hold
+fn client(stack: &Stack) {}
+
+#[requires(stack.len() == 0)]
+#[ensures(stack.is_empty())]
+fn client_using_contract(stack: &Stack) {
+    stack.assert_empty();
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use prusti_contracts::*;
+
+const fn triangle(n: u32) -> u32 {
+    let mut sum = 0;
+    let mut i = 0;
+    while i < n {
+        i += 1;
+        sum += i;
+    }
+    sum
+}
+
+const fn double(x: u32) -> u32 {
+    x + x
+}
+
+#[ensures(result == double(x))] //~ ERROR postcondition might not hold
+fn twice(x: u32) -> u32 {
+    x + x + 1
+}
+
+#[ensures(result == triangle(n))] //~ ERROR contains a loop and cannot be used in specifications
+fn sum_up_to(n: u32) -> u32 {
+    n * (n + 1) / 2
+}
+
+fn main() {}
@@ -0,0 +1,40 @@
+// compile-flags: -Pcheck_pure_function_totality=true
+
+use prusti_contracts::*;
+
+#[pure]
+fn loops(n: u32) -> u32 {
+    if n == 0 { 0 } else { loops(n) } //~ ERROR recursive call in pure function might not terminate
+}
+
+#[pure]
+fn grows(n: u32) -> u32 {
+    if n > 100 { n } else { grows(n + 1) } //~ ERROR recursive call in pure function might not terminate
+}
+
+#[pure]
+fn is_even(n: u32) -> bool {
+    if n == 0 { true } else { is_odd(n - 1) } //~ ERROR recursive call in pure function might not terminate
+}
+
+#[pure]
+fn is_odd(n: u32) -> bool {
+    if n == 0 { false } else { is_even(n - 1) } //~ ERROR recursive call in pure function might not terminate
+}
+
+#[pure]
+fn first(n: u32) -> u32 {
+    if n == 0 { 0 } else { second(n - 1) } //~ ERROR recursive call in pure function might not terminate
+}
+
+#[pure]
+fn second(n: u32) -> u32 {
+    if n == 0 { 0 } else { third(n - 1) } //~ ERROR recursive call in pure function might not terminate
+}
+
+#[pure]
+fn third(n: u32) -> u32 {
+    if n == 0 { 0 } else { first(n - 1) } //~ ERROR recursive call in pure function might not terminate
+}
+
+fn main() {}
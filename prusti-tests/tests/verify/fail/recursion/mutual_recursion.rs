@@ -0,0 +1,16 @@
+use prusti_contracts::*;
+
+#[requires(n <= 100)]
+#[ensures(result == (n % 2 == 0))]
+fn is_even(n: u32) -> bool { //~ WARNING assumes the contracts of `is_odd`
+    if n == 0 { true } else { is_odd(n - 1) }
+}
+
+#[requires(n <= 100)]
+#[ensures(result == (n % 2 == 1))] //~ ERROR postcondition might not hold
+fn is_odd(n: u32) -> bool {
+    // Wrong for zero only, so `is_even` verifies assuming this contract.
+    if n == 0 { true } else { is_even(n - 1) }
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use prusti_contracts::*;
+
+#[requires(x < 100, "the value must be small")]
+fn small(x: u32) -> u32 { x }
+
+#[ensures(result >= x)]
+#[ensures(result < 10, "the result must be a digit")] //~ ERROR the result must be a digit
+fn digit(x: u32) -> u32 {
+    x
+}
+
+#[ensures(result > x && result < 10, "the successor must be a digit")] //~ ERROR the successor must be a digit
+fn successor(x: u32) -> u32 {
+    x + 1
+}
+
+fn test() {
+    small(200); //~ ERROR the value must be small
+}
+
+fn main() {}
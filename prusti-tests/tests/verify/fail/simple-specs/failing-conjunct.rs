@@ -0,0 +1,19 @@
+use prusti_contracts::*;
+
+#[ensures(
+    result >= 0
+    && result < 10 //~ ERROR postcondition might not hold
+    && result != 5
+)]
+fn conjunct(x: i32) -> i32 {
+    if x > 0 { 10 } else { 0 }
+}
+
+#[requires(x > 0 && x < 10)]
+#[ensures(
+    forall(|i: u32| i < 3 ==> x + i > 0
+        && x + i < 3) //~ ERROR postcondition might not hold
+)]
+fn quantifier_body(x: u32) {}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use prusti_contracts::*;
+
+#[ensures(result > 0)] //~ ERROR postcondition might not hold
+fn guarded(x: Option<i32>) -> i32 {
+    match x {
+        Some(v) if v > 0 => v,
+        Some(v) => v, // `v` might be negative
+        None => 1,
+    }
+}
+
+#[ensures(result == (x == 1 || x == 2))] //~ ERROR postcondition might not hold
+fn or_pattern(x: u32) -> bool {
+    match x {
+        1 | 2 | 3 => true,
+        _ => false,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use prusti_contracts::*;
+
+struct Account {
+    balance: u32,
+    deposits: u32,
+}
+
+impl Account {
+    #[requires(self.deposits < 1000)]
+    #[ensures(unchanged(self.balance, self.deposits))] //~ ERROR postcondition might not hold
+    fn deposit(&mut self) {
+        self.deposits += 1;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use std::thread;
+
+struct SharedCounter(*mut u32);
+
+unsafe impl Send for SharedCounter {}
+
+struct Job {
+    counter: SharedCounter,
+}
+
+fn send_job(job: Job, x: u32) {
+    let handle = thread::spawn(move || { //~ WARNING potential data race
+        let _job = job;
+    });
+    let _ = handle.join();
+    // The potential data race does not stop the verification of the rest
+    // of the function.
+    assert!(x > 0); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
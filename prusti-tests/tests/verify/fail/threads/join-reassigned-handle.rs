@@ -0,0 +1,21 @@
+#![feature(stmt_expr_attributes)]
+
+use prusti_contracts::*;
+use std::thread;
+
+fn join_one_of_two(c: bool) {
+    let handle = if c {
+        thread::spawn(closure!(
+            ensures(result == 1),
+            || -> i32 { 1 }
+        ))
+    } else {
+        thread::spawn(closure!(
+            ensures(result == 2),
+            || -> i32 { 2 }
+        ))
+    };
+    let _ = handle.join(); //~ ERROR joining a thread whose handle is assigned at several places is not supported
+}
+
+fn main() {}
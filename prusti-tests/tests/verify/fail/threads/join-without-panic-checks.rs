@@ -0,0 +1,22 @@
+// compile-flags: -Pcheck_panics=false
+#![feature(stmt_expr_attributes)]
+
+use prusti_contracts::*;
+use std::thread;
+
+// The closure is not verified to be panic-free, so the thread might have
+// panicked and `join` might return `Err`.
+#[ensures(result == 42)] //~ ERROR postcondition might not hold
+fn join_may_fail() -> i32 {
+    let worker = closure!(
+        ensures(result == 42),
+        || -> i32 { 42 }
+    );
+    let handle = thread::spawn(worker);
+    match handle.join() {
+        Ok(value) => value,
+        Err(_) => 0,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+#![feature(stmt_expr_attributes)]
+
+use prusti_contracts::*;
+use std::thread;
+
+fn main() {
+    let worker = closure!(
+        requires(false),
+        || -> i32 { 42 }
+    );
+    let handle = thread::spawn(worker); //~ ERROR the precondition of the spawned thread might not hold
+    let _ = handle.join();
+}
+
+fn join_without_postcondition() {
+    let worker = closure!(
+        ensures(result > 0),
+        || -> i32 { 42 }
+    );
+    let handle = thread::spawn(worker);
+    if let Ok(value) = handle.join() {
+        assert!(value == 42); //~ ERROR the asserted expression might not hold
+    }
+}
@@ -0,0 +1,24 @@
+use prusti_contracts::*;
+
+trait Counter {
+    #[ensures(result <= 10)]
+    fn get(&self) -> u32;
+
+    // The only impl refines `get`, but the default body has to hold for
+    // every implementation.
+    #[ensures(result == 10)] //~ ERROR postcondition might not hold
+    fn doubled(&self) -> u32 {
+        self.get() * 2
+    }
+}
+
+struct Five;
+
+impl Counter for Five {
+    #[ensures(result == 5)]
+    fn get(&self) -> u32 {
+        5
+    }
+}
+
+fn main() {}
@@ -0,0 +1,35 @@
+use prusti_contracts::*;
+
+trait Counter {
+    #[ensures(result > 0)]
+    fn count(&self) -> u32;
+}
+
+struct Wrapper<T>(T);
+
+#[refine_trait_spec]
+impl Counter for Wrapper<u8> {
+    #[ensures(result == 8)]
+    fn count(&self) -> u32 {
+        8
+    }
+}
+
+#[refine_trait_spec]
+impl Counter for Wrapper<u16> {
+    #[ensures(result == 16)]
+    fn count(&self) -> u32 {
+        16
+    }
+}
+
+fn generic<T: Counter>(x: &T) {
+    // Only the contract of the trait is known.
+    assert!(x.count() == 8); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {
+    let small = Wrapper(0u8);
+    assert!(small.count() == 16); //~ ERROR the asserted expression might not hold
+    generic(&small);
+}
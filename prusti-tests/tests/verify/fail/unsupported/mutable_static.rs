@@ -0,0 +1,12 @@
+static mut COUNTER: u32 = 0;
+
+fn increment() -> u32 {
+    unsafe {
+        COUNTER += 1; //~ ERROR accessing a mutable static is not supported
+        COUNTER
+    }
+}
+
+fn main() {
+    increment();
+}
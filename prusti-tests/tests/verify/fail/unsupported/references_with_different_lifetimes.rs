@@ -0,0 +1,5 @@
+fn both<'a, 'b>(x: &'a mut u32, y: &'b mut u32) -> (&'a mut u32, &'b mut u32) { //~ ERROR borrow from arguments with different lifetimes ('a, 'b)
+    (x, y)
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+// compile-flags: -Pmax_type_depth=3
+
+struct Leaf {
+    value: u32,
+}
+
+struct Nested {
+    inner: Option<Box<Leaf>>,
+}
+
+fn shallow(leaf: Leaf) -> u32 {
+    leaf.value
+}
+
+fn deep(nested: Nested) {} //~ ERROR is nested 4 levels deep, which exceeds the limit of 3
+
+fn main() {}
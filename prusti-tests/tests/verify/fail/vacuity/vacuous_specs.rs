@@ -0,0 +1,32 @@
+// compile-flags: -Pcheck_vacuous_specs=true
+
+use prusti_contracts::*;
+
+#[requires(x > 0 && x < 0)] //~ WARNING precondition is unsatisfiable
+#[ensures(result == 42)]
+fn vacuous(x: i32) -> i32 {
+    x
+}
+
+#[requires(x > 0)]
+#[ensures(result > 0)]
+fn satisfiable(x: i32) -> i32 {
+    x
+}
+
+// The vacuity check of the precondition does not hide the errors of the body.
+#[requires(x > 0)]
+fn satisfiable_with_error(x: i32) {
+    assert!(x > 1); //~ ERROR the asserted expression might not hold
+}
+
+#[ensures(1 + 1 == 2)] //~ WARNING does not mention the result or the arguments
+fn unconstrained(x: i32) -> i32 {
+    x
+}
+
+fn failing() {
+    assert!(false); //~ ERROR the asserted expression might not hold
+}
+
+fn main() {}
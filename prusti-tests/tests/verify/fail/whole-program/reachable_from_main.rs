@@ -0,0 +1,15 @@
+// compile-flags: -Pwhole_program=true
+
+fn unreachable_helper() {
+    // Not reachable from `main`, so it is not verified.
+    panic!();
+}
+
+fn divide(a: u32, b: u32) -> u32 {
+    a / b //~ ERROR attempt to divide by zero
+}
+
+fn main() {
+    let x = divide(10, 2);
+    assert!(x == 5); //~ ERROR the asserted expression might not hold
+}
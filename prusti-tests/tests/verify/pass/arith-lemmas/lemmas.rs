@@ -0,0 +1,27 @@
+use prusti_contracts::*;
+
+#[requires(a <= b)]
+#[ensures(a * c <= b * c)]
+fn scale(a: u32, b: u32, c: u32) {
+    prusti_use_lemma!(mul_mono(a, b, c));
+}
+
+#[requires(0 < b)]
+#[ensures(result == a)]
+fn recombine(a: u32, b: u32) -> u32 {
+    prusti_use_lemma!(div_mod(a, b));
+    (a / b) * b + a % b
+}
+
+#[requires(a <= b && 0 < c)]
+#[ensures(a / c <= b / c)]
+fn halve(a: u64, b: u64, c: u64) {
+    prusti_use_lemma!(div_mono(a, b, c));
+}
+
+#[ensures(0 <= x * x)]
+fn square(x: i64) {
+    prusti_use_lemma!(square_nonneg(x));
+}
+
+fn main() {}
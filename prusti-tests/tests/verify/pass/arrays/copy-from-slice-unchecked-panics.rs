@@ -0,0 +1,13 @@
+// compile-flags: -Pcheck_panics=false
+
+use prusti_contracts::*;
+
+// The copy panics, so the end of the function is unreachable.
+#[ensures(false)]
+fn copy_different_lengths() {
+    let long = [1u8, 2, 3];
+    let mut short = [0u8; 2];
+    short.copy_from_slice(&long);
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use prusti_contracts::*;
+
+fn main() {
+    let buf = [0u8; 4096];
+    assert!(buf[4095] == 0);
+
+    let header = [1u8, 2, 3, 4];
+    let mut copy = [0u8; 4];
+    copy.copy_from_slice(&header);
+    assert!(copy[0] == 1);
+    assert!(copy[3] == 4);
+
+    let values = [7u32, 8];
+    let mut clone = [0u32; 2];
+    clone.clone_from_slice(&values);
+    assert!(clone[1] == 8);
+}
@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+
+fn main() {
+    let mut count = 0u32;
+    let other = 5u32;
+    let mut inc = || {
+        if count < 10 {
+            count += 1;
+        }
+    };
+    inc();
+    inc();
+    // `other` is not captured, so the calls cannot modify it.
+    assert!(other == 5);
+    // The closure is no longer used, so `count` can be accessed again.
+    count = 0;
+    assert!(count == 0);
+}
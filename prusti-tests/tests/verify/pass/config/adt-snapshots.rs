@@ -0,0 +1,57 @@
+// compile-flags: -Puse_adt_snapshots=true
+
+use prusti_contracts::*;
+
+// Neither type implements `PartialEq`, so without the flag they would not
+// be encoded as snapshots.
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+enum Shape {
+    Circle(u32),
+    Square(u32),
+}
+
+#[pure]
+fn sum(p: &Point) -> i32 {
+    p.x + p.y
+}
+
+#[pure]
+fn size(s: &Shape) -> u32 {
+    match s {
+        Shape::Circle(r) => *r,
+        Shape::Square(a) => *a,
+    }
+}
+
+#[ensures(sum(&result) == x + y)]
+fn make(x: i32, y: i32) -> Point {
+    Point { x, y }
+}
+
+#[requires(size(s) < 100)]
+#[ensures(size(s) == old(size(s)) + 1)]
+fn grow(s: &mut Shape) {
+    match s {
+        Shape::Circle(r) => *r += 1,
+        Shape::Square(a) => *a += 1,
+    }
+}
+
+fn client() {
+    let p = make(1, 2);
+    let q = make(1, 2);
+    assert!(sum(&p) == sum(&q));
+    let mut r = p;
+    r.x = 3;
+    assert!(sum(&r) == 5);
+
+    let mut s = Shape::Square(4);
+    grow(&mut s);
+    assert!(size(&s) == 5);
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+// compile-flags: -Pcheck_preamble=true
+
+use prusti_contracts::*;
+
+#[pure]
+#[requires(x < 100)]
+fn double(x: u32) -> u32 {
+    x * 2
+}
+
+#[requires(x < 10)]
+#[ensures(result == double(x))]
+fn wrong(x: u32) -> u32 {
+    // The body is not encoded, so this error is not reported.
+    x
+}
+
+fn main() {}
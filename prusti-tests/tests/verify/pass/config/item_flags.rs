@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+
+#[prusti::check_panics(false)]
+fn unchecked(x: u32) -> u32 {
+    if x == 0 {
+        panic!()
+    }
+    x
+}
+
+#[prusti::simplify_encoding(false)]
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+fn unoptimized(x: u32) -> u32 {
+    x + 1
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+// compile-flags: -Pseed_sweep_runs=2
+
+use prusti_contracts::*;
+
+#[requires(x < 100)]
+#[ensures(result == x + 1)]
+fn inc(x: u32) -> u32 {
+    x + 1
+}
+
+fn main() {
+    assert!(inc(1) == 2);
+}
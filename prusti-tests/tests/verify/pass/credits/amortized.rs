@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+/// One unit of work.
+pub struct Tick;
+
+#[requires(credits::<Tick>(1))]
+fn step() {
+    consume_credits!(Tick, 1);
+}
+
+#[requires(credits::<Tick>(3))]
+#[ensures(credits::<Tick>(1))]
+fn two_steps() {
+    step();
+    step();
+}
+
+#[requires(credits::<Tick>(n))]
+fn steps(n: usize) {
+    if n > 0 {
+        step();
+        steps(n - 1);
+    }
+}
+
+fn main() {
+    obtain_credits!(Tick, 5);
+    two_steps();
+    steps(3);
+}
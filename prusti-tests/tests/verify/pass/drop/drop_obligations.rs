@@ -0,0 +1,29 @@
+// compile-flags: -Pcheck_drop_obligations=true
+
+use prusti_contracts::*;
+
+struct Guard {
+    released: bool,
+}
+
+impl Guard {
+    #[ensures(!result.released)]
+    fn acquire() -> Self {
+        Guard { released: false }
+    }
+
+    #[ensures(self.released)]
+    fn release(&mut self) {
+        self.released = true;
+    }
+}
+
+impl Drop for Guard {
+    #[requires(self.released)]
+    fn drop(&mut self) {}
+}
+
+fn main() {
+    let mut guard = Guard::acquire();
+    guard.release();
+}
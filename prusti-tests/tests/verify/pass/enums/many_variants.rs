@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+enum Shape {
+    Point,
+    Circle(u32),
+    Square(u32),
+    Rect(u32, u32),
+    Triangle(u32, u32, u32),
+    Line(u32),
+}
+
+#[pure]
+fn corners(shape: &Shape) -> u32 {
+    match shape {
+        Shape::Point | Shape::Circle(_) | Shape::Line(_) => 0,
+        Shape::Square(_) | Shape::Rect(_, _) => 4,
+        Shape::Triangle(_, _, _) => 3,
+    }
+}
+
+#[ensures(corners(&result) == 4)]
+fn make_rect(w: u32, h: u32) -> Shape {
+    Shape::Rect(w, h)
+}
+
+#[requires(corners(shape) == 3)]
+fn scale(shape: &mut Shape) {
+    if let Shape::Triangle(a, b, c) = shape {
+        *a = *a / 2;
+        *b = *b / 2;
+        *c = *c / 2;
+    }
+}
+
+fn main() {
+    let mut shape = Shape::Triangle(2, 4, 6);
+    scale(&mut shape);
+    let rect = make_rect(1, 2);
+    assert!(corners(&rect) == 4);
+}
@@ -0,0 +1,41 @@
+use prusti_contracts::*;
+
+#[ensures(result >= 0)]
+fn positive_or_zero(x: Option<i32>) -> i32 {
+    match x {
+        Some(v) if v > 0 => v,
+        Some(v) if v == 0 => 0,
+        Some(_) => 0,
+        None => 0,
+    }
+}
+
+#[ensures(result == (x == 1 || x == 2 || x == 3))]
+fn is_small(x: u32) -> bool {
+    match x {
+        1 | 2 | 3 => true,
+        _ => false,
+    }
+}
+
+#[ensures(result <= 2)]
+fn classify(x: u32, y: u32) -> u32 {
+    match x {
+        0 | 1 if y > 10 => 0,
+        0 | 1 => 1,
+        _ => 2,
+    }
+}
+
+/// From the serde crate
+fn iterator_len_hint<I>(iter: &I) -> Option<usize>
+    where
+        I: Iterator,
+{
+    match iter.size_hint() {
+        (lo, Some(hi)) if lo == hi => Some(lo),
+        _ => None,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,42 @@
+use prusti_contracts::*;
+
+#[derive(Clone, PartialEq, Default)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, PartialEq)]
+enum Shape {
+    Dot(Point),
+    Line(Point, Point),
+}
+
+#[derive(Default)]
+struct Config {
+    retries: u32,
+    verbose: bool,
+    origin: Point,
+}
+
+#[requires(a == b)]
+#[ensures(result)]
+fn same_shape(a: &Shape, b: &Shape) -> bool {
+    a == b
+}
+
+fn main() {
+    let p = Point { x: 1, y: 2 };
+    let q = p.clone();
+    assert!(q.x == 1 && q.y == 2);
+    assert!(p == q);
+
+    let shape = Shape::Line(p, q);
+    let copy = shape.clone();
+    assert!(same_shape(&shape, &copy));
+
+    let config = Config::default();
+    assert!(config.retries == 0);
+    assert!(!config.verbose);
+    assert!(config.origin.x == 0 && config.origin.y == 0);
+}
@@ -0,0 +1,23 @@
+// compile-flags: -Phavoc_foreign_calls=true
+
+use prusti_contracts::*;
+
+extern "C" {
+    fn abs(x: i32) -> i32;
+    fn time(t: *mut i64) -> i64;
+}
+
+#[ensures(result == x + 1)]
+fn pure_rust_part(x: i32, y: i32) -> i32 {
+    let _ = unsafe { abs(y) };
+    x + 1
+}
+
+fn main() {
+    let a = 5;
+    let mut now = 0;
+    unsafe { time(&mut now) };
+    let b = pure_rust_part(a, -3);
+    assert!(a == 5);
+    assert!(b == 6);
+}
@@ -0,0 +1,32 @@
+use prusti_contracts::*;
+
+trait HasItem {
+    type Item;
+
+    fn get(&self) -> Self::Item;
+}
+
+struct Counter {
+    count: u32,
+}
+
+impl HasItem for Counter {
+    type Item = u32;
+
+    #[trusted]
+    #[ensures(result == self.count)]
+    fn get(&self) -> u32 {
+        self.count
+    }
+}
+
+#[ensures(result == c.count)]
+fn first_item(c: &Counter) -> <Counter as HasItem>::Item {
+    c.get()
+}
+
+fn main() {
+    let c = Counter { count: 3 };
+    let i = first_item(&c);
+    assert!(i == 3);
+}
@@ -0,0 +1,30 @@
+use prusti_contracts::*;
+
+trait Bounded {
+    fn max_value() -> u32;
+}
+
+impl Bounded for u8 {
+    fn max_value() -> u32 {
+        255
+    }
+}
+
+struct Wrapper<T> {
+    value: T,
+}
+
+impl<T> Wrapper<T> {
+    #[trusted]
+    #[refine_ensures(T: Bounded => result <= 255)]
+    fn width(&self) -> u32 {
+        unimplemented!()
+    }
+}
+
+fn bounded(w: &Wrapper<u8>) {
+    let width = w.width();
+    assert!(width <= 255);
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+use prusti_contracts::*;
+
+pub struct Stack {
+    items: Vec<u32>,
+}
+
+impl Stack {
+    #[trusted]
+    #[pure]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[trusted]
+    #[ensures(self.len() == old(self.len()) + 1)]
+    fn push_item(&mut self, x: u32) {
+        self.items.push(x);
+    }
+
+    #[requires(self.len() == old_len)]
+    #[ensures(self.len() == old_len + 1)]
+    pub fn push(&mut self, x: u32, #[ghost] old_len: usize) {
+        self.push_item(x);
+    }
+}
+
+#[requires(stack.len() == 3)]
+#[ensures(stack.len() == 4)]
+fn client(stack: &mut Stack) {
+    stack.push(7, ghost!(stack.len()));
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+
+#[history_invariant(old(self.value) <= self.value)]
+struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[requires(self.value < u32::MAX)]
+    fn increment(&mut self) {
+        self.value += 1;
+    }
+
+    fn keep(&mut self) {}
+
+    #[pure]
+    fn get(&self) -> u32 {
+        self.value
+    }
+}
+
+fn test(c: &mut Counter) {
+    let before = c.get();
+    c.keep();
+    assert!(before <= c.get());
+}
+
+fn main() {}
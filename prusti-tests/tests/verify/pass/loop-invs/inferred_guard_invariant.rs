@@ -0,0 +1,24 @@
+// compile-flags: -Pinfer_loop_invariants=true
+use prusti_contracts::*;
+
+#[requires(n >= 0)]
+#[ensures(result == n)]
+fn count_up(n: i32) -> i32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+#[requires(n >= 0)]
+#[ensures(result == 0)]
+fn count_down(n: i32) -> i32 {
+    let mut i = n;
+    while 0 < i {
+        i -= 1;
+    }
+    i
+}
+
+fn main() {}
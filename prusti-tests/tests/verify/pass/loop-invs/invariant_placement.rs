@@ -0,0 +1,36 @@
+use prusti_contracts::*;
+
+fn invariant_in_branch(n: u32, flag: bool) {
+    let mut i = 0;
+    while i < n {
+        if flag {
+            body_invariant!(i < n);
+        }
+        i += 1;
+    }
+}
+
+fn invariant_in_guard_branch(n: u32, flag: bool) {
+    let mut i = 0;
+    while {
+        if flag {
+            body_invariant!(i <= n);
+        }
+        i < n
+    } {
+        i += 1;
+    }
+}
+
+fn multiple_invariants(n: u32) {
+    let mut i = 0;
+    let mut j = 0;
+    while i < n {
+        body_invariant!(i < n);
+        j += 1;
+        body_invariant!(j == i + 1);
+        i += 1;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,44 @@
+use prusti_contracts::*;
+
+pub struct Counter {
+    value: u32,
+    limit: u32,
+}
+
+impl Counter {
+    #[trusted]
+    #[ensures(self.limit == old(self.limit))]
+    pub fn next(&mut self) -> Option<u32> {
+        if self.value < self.limit {
+            self.value += 1;
+            Some(self.value)
+        } else {
+            None
+        }
+    }
+}
+
+fn while_let(counter: &mut Counter) {
+    let limit = counter.limit;
+    let mut count = 0;
+    while let Some(_value) = counter.next() {
+        body_invariant!(counter.limit == limit);
+        count += 1;
+    }
+    assert!(counter.limit == limit);
+}
+
+fn break_value(limit: u32) -> u32 {
+    let mut i = 0;
+    let result = loop {
+        if i > limit {
+            break i;
+        }
+        body_invariant!(i <= limit);
+        i += 1;
+    };
+    assert!(result > limit);
+    result
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use prusti_contracts::*;
+
+trait Counter {
+    #[ensures(result > 0)]
+    fn count(&self) -> u32;
+}
+
+struct One;
+
+impl Counter for One {
+    fn count(&self) -> u32 {
+        1
+    }
+}
+
+#[trusted]
+fn make_counter() -> impl Counter {
+    One
+}
+
+fn use_counter() {
+    let counter = make_counter();
+    // Only the contract of the trait is known about the hidden type.
+    assert!(counter.count() > 0);
+}
+
+fn main() {}
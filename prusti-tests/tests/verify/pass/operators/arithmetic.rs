@@ -0,0 +1,48 @@
+use prusti_contracts::*;
+use std::ops::{Add, AddAssign};
+
+#[derive(Clone, Copy)]
+struct Money {
+    cents: u32,
+}
+
+impl Add for Money {
+    type Output = u32;
+
+    #[pure]
+    #[requires(self.cents <= 1000 && other.cents <= 1000)]
+    fn add(self, other: Money) -> u32 {
+        self.cents + other.cents
+    }
+}
+
+struct Counter {
+    value: u32,
+}
+
+impl AddAssign<u32> for Counter {
+    #[requires(self.value <= 100 && amount <= 100)]
+    #[ensures(self.value == old(self.value) + amount)]
+    fn add_assign(&mut self, amount: u32) {
+        self.value += amount;
+    }
+}
+
+#[requires(a.cents <= 1000 && b.cents <= 1000)]
+#[ensures(result == a + b)]
+fn total(a: Money, b: Money) -> u32 {
+    a.cents + b.cents
+}
+
+#[requires(a.cents == 1 && b.cents == 2)]
+fn sum(a: Money, b: Money) {
+    assert!(a + b == 3);
+}
+
+#[requires(counter.value <= 100)]
+#[ensures(counter.value == old(counter.value) + 1)]
+fn increment(counter: &mut Counter) {
+    *counter += 1;
+}
+
+fn main() {}
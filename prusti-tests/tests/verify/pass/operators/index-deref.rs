@@ -0,0 +1,73 @@
+use prusti_contracts::*;
+use std::ops::{Deref, DerefMut, Index, IndexMut};
+
+struct Pair {
+    first: u32,
+    second: u32,
+}
+
+impl Index<bool> for Pair {
+    type Output = u32;
+
+    #[pure]
+    fn index(&self, first: bool) -> &u32 {
+        if first { &self.first } else { &self.second }
+    }
+}
+
+impl IndexMut<bool> for Pair {
+    #[after_expiry(if first {
+            (self.first == before_expiry(*result) && self.second == old(self.second))
+        } else {
+            (self.first == old(self.first) && self.second == before_expiry(*result))
+        })]
+    fn index_mut(&mut self, first: bool) -> &mut u32 {
+        if first { &mut self.first } else { &mut self.second }
+    }
+}
+
+struct Wrapper {
+    value: u32,
+}
+
+impl Deref for Wrapper {
+    type Target = u32;
+
+    #[ensures(*result == self.value)]
+    fn deref(&self) -> &u32 {
+        &self.value
+    }
+}
+
+impl DerefMut for Wrapper {
+    #[ensures(*result == old(self.value))]
+    #[after_expiry(self.value == before_expiry(*result))]
+    fn deref_mut(&mut self) -> &mut u32 {
+        &mut self.value
+    }
+}
+
+#[requires(pair[true] == 1)]
+#[ensures(result == 1)]
+fn read_first(pair: &Pair) -> u32 {
+    pair[true]
+}
+
+fn write_second(pair: &mut Pair) {
+    let old_first = pair.first;
+    pair[false] = 5;
+    assert!(pair.second == 5);
+    assert!(pair.first == old_first);
+}
+
+fn deref(wrapper: &Wrapper) {
+    let value = *wrapper;
+    assert!(value == wrapper.value);
+}
+
+fn deref_mut(wrapper: &mut Wrapper) {
+    *wrapper = 7;
+    assert!(wrapper.value == 7);
+}
+
+fn main() {}
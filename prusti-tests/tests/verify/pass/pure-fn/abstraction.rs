@@ -0,0 +1,40 @@
+use prusti_contracts::*;
+
+mod account {
+    use prusti_contracts::*;
+
+    pub struct Account {
+        balance: u32,
+    }
+
+    impl Account {
+        #[pure]
+        #[abstraction]
+        pub fn balance(&self) -> u32 {
+            self.balance
+        }
+
+        #[ensures(result.balance() == 0)]
+        pub fn new() -> Self {
+            Account { balance: 0 }
+        }
+
+        #[requires(self.balance() <= 1000 && amount <= 1000)]
+        #[ensures(self.balance() == old(self.balance()) + amount)]
+        pub fn deposit(&mut self, amount: u32) {
+            self.balance += amount;
+        }
+    }
+}
+
+use account::Account;
+
+#[ensures(result.balance() == 15)]
+fn open_account() -> Account {
+    let mut account = Account::new();
+    account.deposit(10);
+    account.deposit(5);
+    account
+}
+
+fn main() {}
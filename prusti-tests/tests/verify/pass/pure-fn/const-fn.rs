@@ -0,0 +1,38 @@
+// compile-flags: -Pcheck_const_evaluation=true
+
+use prusti_contracts::*;
+
+#[requires(x <= 1000)]
+#[ensures(result == x * x)]
+const fn square(x: u32) -> u32 {
+    x * x
+}
+
+const fn max(a: i32, b: i32) -> i32 {
+    if a >= b { a } else { b }
+}
+
+const fn is_digit(c: char) -> bool {
+    c >= '0' && c <= '9'
+}
+
+const SQUARE: u32 = square(12);
+const MAX: i32 = max(-3, 7);
+const DIGIT: bool = is_digit('7');
+
+#[requires(x <= 10)]
+#[ensures(result == square(x) + 1)]
+fn next_square(x: u32) -> u32 {
+    square(x) + 1
+}
+
+#[ensures(result == max(a, b))]
+fn larger(a: i32, b: i32) -> i32 {
+    if a < b { b } else { a }
+}
+
+fn main() {
+    assert!(SQUARE == 144);
+    assert!(MAX == 7);
+    assert!(DIGIT);
+}
@@ -0,0 +1,27 @@
+// compile-flags: -Pcheck_pure_function_totality=true
+
+use prusti_contracts::*;
+
+#[pure]
+fn count_down(n: u32) -> u32 {
+    if n == 0 { 0 } else { count_down(n - 1) }
+}
+
+#[pure]
+fn lexicographic(m: u32, n: u32) -> bool {
+    if m == 0 {
+        true
+    } else if n == 0 {
+        lexicographic(m - 1, 1000)
+    } else {
+        lexicographic(m, n - 1)
+    }
+}
+
+#[pure]
+#[requires(n >= 0)]
+fn reaches_zero(n: i32) -> bool {
+    if n == 0 { true } else { reaches_zero(n - 1) }
+}
+
+fn main() {}
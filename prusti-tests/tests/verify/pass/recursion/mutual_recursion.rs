@@ -0,0 +1,18 @@
+use prusti_contracts::*;
+
+#[requires(n <= 100)]
+#[ensures(result == (n % 2 == 0))]
+fn is_even(n: u32) -> bool {
+    if n == 0 { true } else { is_odd(n - 1) }
+}
+
+#[requires(n <= 100)]
+#[ensures(result == (n % 2 == 1))]
+fn is_odd(n: u32) -> bool {
+    if n == 0 { false } else { is_even(n - 1) }
+}
+
+fn main() {
+    assert!(is_even(10));
+    assert!(is_odd(7));
+}
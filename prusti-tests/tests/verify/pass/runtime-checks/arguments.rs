@@ -0,0 +1,28 @@
+// compile-flags: -Pruntime_checks=true
+
+use prusti_contracts::*;
+
+struct Buffer {
+    len: usize,
+}
+
+// The check of the postcondition uses the value of `n` at the call.
+#[requires(n < 100)]
+#[ensures(result == n + 1)]
+fn increment(mut n: u32) -> u32 {
+    n += 1;
+    n
+}
+
+// `buffer` is moved by the body, so the postcondition is not checked.
+#[ensures(result == buffer.len)]
+fn consume(buffer: Buffer) -> usize {
+    let moved = buffer;
+    moved.len
+}
+
+fn main() {
+    assert!(increment(1) == 2);
+    let buffer = Buffer { len: 3 };
+    assert!(consume(buffer) == 3);
+}
@@ -0,0 +1,33 @@
+// compile-flags: -Ptrusted_runtime_checks=true
+
+use prusti_contracts::*;
+use std::panic;
+
+struct Buffer {
+    len: usize,
+}
+
+#[trusted]
+#[ensures(result == x + 1)]
+fn wrong_increment(x: u32) -> u32 {
+    x + 2
+}
+
+// `buffer` is moved by the body, so the postcondition is not checked.
+#[trusted]
+#[ensures(result == buffer.len)]
+fn consume(buffer: Buffer) -> usize {
+    if buffer.len == 0 {
+        return 0;
+    }
+    let moved = buffer;
+    moved.len
+}
+
+#[trusted]
+fn main() {
+    // The postcondition of the trusted function is violated when it runs.
+    let outcome = panic::catch_unwind(|| wrong_increment(1));
+    assert!(outcome.is_err());
+    assert!(consume(Buffer { len: 3 }) == 3);
+}
@@ -0,0 +1,23 @@
+use prusti_contracts::*;
+
+#[pure]
+fn first(pair: (u32, u32)) -> u32 {
+    pair.0
+}
+
+#[requires(x.1.0 < x.1.1)]
+#[ensures(result.0 == x.0 && result.1 == x.1.1 - x.1.0)]
+#[ensures(first(result.2) == result.2.0 && result.2.1 == x.1.1)]
+fn test_nested(x: (bool, (u32, u32))) -> (bool, u32, (u32, u32)) {
+    (x.0, x.1.1 - x.1.0, x.1)
+}
+
+#[ensures(first((result, a)) == result)]
+fn test_tuple_in_spec(a: u32) -> u32 {
+    a
+}
+
+fn main() {
+    let r = test_nested((true, (1, 3)));
+    assert!(r.0 && r.1 == 2);
+}
@@ -0,0 +1,31 @@
+use prusti_contracts::*;
+
+struct Account {
+    balance: u32,
+    deposits: u32,
+    withdrawals: u32,
+}
+
+impl Account {
+    #[requires(self.balance <= 1000 && self.deposits < 1000 && amount <= 1000)]
+    #[ensures(self.balance == old(self.balance) + amount)]
+    #[ensures(self.deposits == old(self.deposits) + 1)]
+    #[ensures(unchanged(self.withdrawals))]
+    fn deposit(&mut self, amount: u32) {
+        self.balance += amount;
+        self.deposits += 1;
+    }
+
+    #[ensures(unchanged(self.balance, self.deposits, self.withdrawals))]
+    fn audit(&mut self) {}
+}
+
+#[ensures(old(*x) >= 10 ==> unchanged(*x))]
+#[ensures(unchanged(*y) && *x <= old(*x))]
+fn reset_if_small(x: &mut u32, y: &mut u32) {
+    if *x < 10 {
+        *x = 0;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,44 @@
+use prusti_contracts::*;
+
+// A crate that only contains specifications; other crates expand them with
+// `<crate>::import_specs!()`, or with the name given by `macro <name>;`.
+spec_library! {
+    #[pure]
+    pub fn max(a: i32, b: i32) -> i32 {
+        if a > b { a } else { b }
+    }
+
+    #[predicate]
+    pub fn sorted_pair(a: i32, b: i32) -> bool {
+        a <= b
+    }
+}
+
+spec_library! {
+    macro import_min_specs;
+
+    #[pure]
+    pub fn min(a: i32, b: i32) -> i32 {
+        if a < b { a } else { b }
+    }
+}
+
+mod imported {
+    use prusti_contracts::*;
+    import_specs!();
+    import_min_specs!();
+}
+
+#[requires(imported::sorted_pair(a, b))]
+#[ensures(result == b)]
+fn client(a: i32, b: i32) -> i32 {
+    imported::max(a, b)
+}
+
+#[requires(imported::sorted_pair(a, b))]
+#[ensures(result == a)]
+fn other_client(a: i32, b: i32) -> i32 {
+    imported::min(a, b)
+}
+
+fn main() {}
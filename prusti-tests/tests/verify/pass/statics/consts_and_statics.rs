@@ -0,0 +1,28 @@
+use prusti_contracts::*;
+
+static LIMIT: u32 = 100;
+
+struct Buffer;
+
+impl Buffer {
+    const CAPACITY: usize = 16;
+}
+
+#[ensures(result <= Buffer::CAPACITY)]
+fn clamp(x: usize) -> usize {
+    if x > Buffer::CAPACITY {
+        Buffer::CAPACITY
+    } else {
+        x
+    }
+}
+
+fn read_limit() -> u32 {
+    LIMIT
+}
+
+fn main() {
+    let c = clamp(20);
+    assert!(c <= 16);
+    let _ = read_limit();
+}
@@ -0,0 +1,16 @@
+#![feature(stmt_expr_attributes)]
+
+use prusti_contracts::*;
+use std::thread;
+
+fn main() {
+    let worker = closure!(
+        ensures(result == 42),
+        || -> i32 { 42 }
+    );
+    let handle = thread::spawn(worker);
+    match handle.join() {
+        Ok(value) => assert!(value == 42),
+        Err(_) => unreachable!(),
+    }
+}
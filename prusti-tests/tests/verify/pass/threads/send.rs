@@ -0,0 +1,14 @@
+use std::thread;
+
+struct Job {
+    id: u32,
+    data: Vec<u32>,
+}
+
+fn main() {
+    let job = Job { id: 1, data: Vec::new() };
+    let handle = thread::spawn(move || {
+        let _job = job;
+    });
+    let _ = handle.join();
+}
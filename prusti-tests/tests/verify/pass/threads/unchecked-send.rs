@@ -0,0 +1,16 @@
+use std::thread;
+
+/// Uniquely owns the value behind the pointer, like a `Box`, so sending it
+/// to another thread is sound.
+struct Unique(*mut u32);
+
+unsafe impl Send for Unique {}
+
+fn send_unique(value: Unique) {
+    let handle = thread::spawn(move || {
+        let _value = value;
+    });
+    let _ = handle.join();
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use prusti_contracts::*;
+
+trait Double {
+    type Output;
+
+    fn double(&self) -> Self::Output;
+}
+
+struct Wrapper {
+    value: u8,
+}
+
+#[refine_trait_spec]
+impl Double for Wrapper {
+    type Output = u32;
+
+    #[ensures(result == 2 * (self.value as u32))]
+    fn double(&self) -> Self::Output {
+        2 * (self.value as u32)
+    }
+}
+
+fn main() {
+    let w = Wrapper { value: 21 };
+    let d = w.double();
+    assert!(d == 42);
+}
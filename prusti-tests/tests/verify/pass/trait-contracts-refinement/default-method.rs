@@ -0,0 +1,27 @@
+use prusti_contracts::*;
+
+trait Counter {
+    #[ensures(result <= 10)]
+    fn get(&self) -> u32;
+
+    #[ensures(result <= 20)]
+    fn doubled(&self) -> u32 {
+        self.get() * 2
+    }
+}
+
+struct Five;
+
+impl Counter for Five {
+    #[ensures(result == 5)]
+    fn get(&self) -> u32 {
+        5
+    }
+}
+
+#[ensures(result <= 20)]
+fn client(c: &Five) -> u32 {
+    c.doubled()
+}
+
+fn main() {}
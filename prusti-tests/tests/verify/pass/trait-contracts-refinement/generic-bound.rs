@@ -0,0 +1,37 @@
+use prusti_contracts::*;
+
+trait Counter {
+    #[ensures(result > 0)]
+    fn count(&self) -> u32;
+}
+
+struct One;
+
+impl Counter for One {
+    fn count(&self) -> u32 {
+        1
+    }
+}
+
+struct Two;
+
+#[refine_trait_spec]
+impl Counter for Two {
+    #[ensures(result == 2)]
+    fn count(&self) -> u32 {
+        2
+    }
+}
+
+fn positive<T: Counter>(x: &T) -> u32 {
+    let c = x.count();
+    assert!(c > 0);
+    c
+}
+
+fn main() {
+    let one = One;
+    positive(&one);
+    let two = Two;
+    assert!(two.count() == 2);
+}
@@ -0,0 +1,33 @@
+use prusti_contracts::*;
+
+trait Counter {
+    #[ensures(result > 0)]
+    fn count(&self) -> u32;
+}
+
+struct Wrapper<T>(T);
+
+// Both implementations are relevant for the type `Wrapper<_>`, so the
+// contract of the call has to come from the selected implementation.
+#[refine_trait_spec]
+impl Counter for Wrapper<u8> {
+    #[ensures(result == 8)]
+    fn count(&self) -> u32 {
+        8
+    }
+}
+
+#[refine_trait_spec]
+impl Counter for Wrapper<u16> {
+    #[ensures(result == 16)]
+    fn count(&self) -> u32 {
+        16
+    }
+}
+
+fn main() {
+    let small = Wrapper(0u8);
+    assert!(small.count() == 8);
+    let large = Wrapper(0u16);
+    assert!(large.count() == 16);
+}
@@ -0,0 +1,20 @@
+use prusti_contracts::*;
+
+#[trusted_unsafe(requires = x > 0, ensures = result == x)]
+fn read_through_pointer(x: i32) -> i32 {
+    let pointer = &x as *const i32;
+    unsafe { *pointer }
+}
+
+#[trusted_unsafe]
+#[ensures(result == 42)]
+unsafe fn answer() -> i32 {
+    42
+}
+
+fn main() {
+    let value = read_through_pointer(5);
+    assert!(value == 5);
+    let answer = unsafe { answer() };
+    assert!(answer == 42);
+}
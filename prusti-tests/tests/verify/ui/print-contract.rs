@@ -0,0 +1,21 @@
+// compile-flags: -Pquiet=false -Pprint_contract=area
+
+use prusti_contracts::*;
+
+trait Shape {
+    #[ensures(result >= 0)]
+    fn area(&self) -> i32;
+}
+
+struct Square {
+    side: i32,
+}
+
+impl Shape for Square {
+    #[requires(self.side >= 0 && self.side <= 100)]
+    fn area(&self) -> i32 {
+        self.side * self.side
+    }
+}
+
+fn main() {}
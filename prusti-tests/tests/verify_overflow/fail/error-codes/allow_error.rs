@@ -0,0 +1,20 @@
+use prusti_contracts::*;
+
+#[prusti::allow_error(P0301)]
+fn allowed_overflow(x: u8) -> u8 {
+    x + 1
+}
+
+#[prusti::allow_error(P0101)]
+#[ensures(result > x)]
+fn allowed_postcondition(x: u32) -> u32 {
+    x
+}
+
+#[prusti::allow_error(P0101)]
+fn other_class_is_reported(x: u32) -> u32 {
+    assert!(x > 0); //~ ERROR the asserted expression might not hold
+    x
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+extern crate prusti_contracts;
+
+#[prusti::overflow_checks(off)]
+fn wrapping_hash(x: u32, y: u32) -> u32 {
+    x * 31 + y
+}
+
+#[prusti::overflow_checks(on)]
+fn checked_sum(x: u32, y: u32) -> u32 {
+    x + y //~ ERROR
+}
+
+fn default_sum(x: u32, y: u32) -> u32 {
+    x + y //~ ERROR
+}
+
+fn main() {}
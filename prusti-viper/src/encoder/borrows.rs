@@ -44,16 +44,22 @@ impl<P: fmt::Debug> BorrowInfo<P> {
             blocked_paths: Vec::new(),
         }
     }
+
+    fn lifetime_name(&self) -> EncodingResult<String> {
+        match self.region {
+            None => Ok(format!("static")),
+            Some(ty::BoundRegionKind::BrAnon(id)) => Ok(format!("#{}", id)),
+            Some(ty::BoundRegionKind::BrNamed(_, name)) => Ok(name.to_string()),
+            Some(ty::BoundRegionKind::BrEnv) => Err(EncodingError::unsupported(
+                "references that borrow from the environment of a closure are not supported"
+            )),
+        }
+    }
 }
 
 impl<P: fmt::Debug> fmt::Display for BorrowInfo<P> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let lifetime = match self.region {
-            None => format!("static"),
-            Some(ty::BoundRegionKind::BrAnon(id)) => format!("#{}", id),
-            Some(ty::BoundRegionKind::BrNamed(_, name)) => name.to_string(),
-            _ => unimplemented!(),
-        };
+        let lifetime = self.lifetime_name().unwrap_or_else(|_| format!("{:?}", self.region));
         writeln!(f, "BorrowInfo<{}> {{", lifetime)?;
         for path in self.blocking_paths.iter() {
             writeln!(f, "  {:?}", path)?;
@@ -472,6 +478,19 @@ where
         .into_iter()
         .filter(|info| !info.blocked_paths.is_empty() && !info.blocking_paths.is_empty())
         .collect();
+    // The encoding uses a single magic wand to give back the blocked
+    // arguments when the returned references expire, which requires them all
+    // to expire at the same time.
+    if borrow_infos.len() > 1 {
+        let lifetimes = borrow_infos.iter()
+            .map(|info| info.lifetime_name())
+            .collect::<EncodingResult<Vec<_>>>()?;
+        return Err(EncodingError::unsupported(format!(
+            "returning references that borrow from arguments with different lifetimes ({}) \
+            is not supported",
+            lifetimes.join(", "),
+        )));
+    }
     let is_not_blocked = |place: &mir::Place<'tcx>| {
         !borrow_infos.iter().any(|info| {
             info.blocked_paths
@@ -28,12 +28,183 @@ pub enum BuiltinFunctionKind {
         array_len: usize,
         return_ty: vir::Type,
     },
+    /// The credits of the given kind in a specification, e.g.
+    /// `builtin$credits$Tick(amount)`
+    Credits(String),
+}
+
+pub const CREDITS_FUNCTION_PREFIX: &str = "builtin$credits$";
+
+/// The identifier of a kind of credits in Viper names.
+pub fn credit_kind_ident(kind: &str) -> String {
+    kind.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '$' }).collect()
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
 pub enum BuiltinDomainKind {
     Nat,
     Primitive,
+    ArithLemmas,
+}
+
+pub const ARITH_LEMMAS_DOMAIN_NAME: &str = "ArithLemmas";
+
+/// The arithmetic lemmas that can be instantiated in a proof with
+/// `prusti_use_lemma!`. Each lemma is a domain function whose axiom makes
+/// the lemma's property available whenever the function is applied.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum ArithLemma {
+    /// `0 <= c && a <= b ==> a * c <= b * c`
+    MulMono,
+    /// `0 < c && a < b ==> a * c < b * c`
+    MulStrictMono,
+    /// `0 <= a && 0 <= b ==> 0 <= a * b`
+    MulNonneg,
+    /// `0 <= a * a`
+    SquareNonneg,
+    /// `0 <= a && 0 < b ==> a == (a / b) * b + a % b && 0 <= a % b < b`
+    DivMod,
+    /// `0 <= a && a <= b && 0 < c ==> a / c <= b / c`
+    DivMono,
+    /// `0 < b ==> (a * b) / b == a`
+    MulDivCancel,
+}
+
+impl ArithLemma {
+    pub const ALL: [ArithLemma; 7] = [
+        ArithLemma::MulMono,
+        ArithLemma::MulStrictMono,
+        ArithLemma::MulNonneg,
+        ArithLemma::SquareNonneg,
+        ArithLemma::DivMod,
+        ArithLemma::DivMono,
+        ArithLemma::MulDivCancel,
+    ];
+
+    /// The name of the function in `prusti_contracts::lemmas`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ArithLemma::MulMono => "mul_mono",
+            ArithLemma::MulStrictMono => "mul_strict_mono",
+            ArithLemma::MulNonneg => "mul_nonneg",
+            ArithLemma::SquareNonneg => "square_nonneg",
+            ArithLemma::DivMod => "div_mod",
+            ArithLemma::DivMono => "div_mono",
+            ArithLemma::MulDivCancel => "mul_div_cancel",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ArithLemma::ALL.iter().copied().find(|lemma| lemma.name() == name)
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            ArithLemma::SquareNonneg => 1,
+            ArithLemma::MulNonneg
+            | ArithLemma::DivMod
+            | ArithLemma::MulDivCancel => 2,
+            ArithLemma::MulMono
+            | ArithLemma::MulStrictMono
+            | ArithLemma::DivMono => 3,
+        }
+    }
+
+    pub fn domain_func(self) -> vir::DomainFunc {
+        let formal_args = ["a", "b", "c"][..self.arity()]
+            .iter()
+            .map(|name| vir::LocalVar::new(*name, vir::Type::Int))
+            .collect();
+        vir::DomainFunc {
+            name: format!("lemma${}", self.name()),
+            formal_args,
+            return_type: vir::Type::Bool,
+            unique: false,
+            domain_name: ARITH_LEMMAS_DOMAIN_NAME.to_owned(),
+        }
+    }
+
+    /// The property stated by the lemma, over the (integer) arguments.
+    fn property(self, args: &[vir::Expr]) -> vir::Expr {
+        let zero: vir::Expr = 0.into();
+        let arg = |i: usize| args[i].clone();
+        match self {
+            ArithLemma::MulMono => vir::Expr::implies(
+                vir::Expr::and(
+                    vir::Expr::le_cmp(zero, arg(2)),
+                    vir::Expr::le_cmp(arg(0), arg(1)),
+                ),
+                vir::Expr::le_cmp(
+                    vir::Expr::mul(arg(0), arg(2)),
+                    vir::Expr::mul(arg(1), arg(2)),
+                ),
+            ),
+            ArithLemma::MulStrictMono => vir::Expr::implies(
+                vir::Expr::and(
+                    vir::Expr::lt_cmp(zero, arg(2)),
+                    vir::Expr::lt_cmp(arg(0), arg(1)),
+                ),
+                vir::Expr::lt_cmp(
+                    vir::Expr::mul(arg(0), arg(2)),
+                    vir::Expr::mul(arg(1), arg(2)),
+                ),
+            ),
+            ArithLemma::MulNonneg => vir::Expr::implies(
+                vir::Expr::and(
+                    vir::Expr::le_cmp(zero.clone(), arg(0)),
+                    vir::Expr::le_cmp(zero.clone(), arg(1)),
+                ),
+                vir::Expr::le_cmp(zero, vir::Expr::mul(arg(0), arg(1))),
+            ),
+            ArithLemma::SquareNonneg => {
+                vir::Expr::le_cmp(zero, vir::Expr::mul(arg(0), arg(0)))
+            }
+            ArithLemma::DivMod => {
+                // Viper's division and modulo are Euclidean, so they agree
+                // with Rust's only for non-negative dividends.
+                let rem = vir::Expr::rem(arg(0), arg(1));
+                vir::Expr::implies(
+                    vir::Expr::and(
+                        vir::Expr::le_cmp(zero.clone(), arg(0)),
+                        vir::Expr::lt_cmp(zero.clone(), arg(1)),
+                    ),
+                    vir::Expr::and(
+                        vir::Expr::eq_cmp(
+                            arg(0),
+                            vir::Expr::add(
+                                vir::Expr::mul(vir::Expr::div(arg(0), arg(1)), arg(1)),
+                                rem.clone(),
+                            ),
+                        ),
+                        vir::Expr::and(
+                            vir::Expr::le_cmp(zero, rem.clone()),
+                            vir::Expr::lt_cmp(rem, arg(1)),
+                        ),
+                    ),
+                )
+            }
+            ArithLemma::DivMono => vir::Expr::implies(
+                vir::Expr::and(
+                    vir::Expr::le_cmp(zero.clone(), arg(0)),
+                    vir::Expr::and(
+                        vir::Expr::le_cmp(arg(0), arg(1)),
+                        vir::Expr::lt_cmp(zero, arg(2)),
+                    ),
+                ),
+                vir::Expr::le_cmp(
+                    vir::Expr::div(arg(0), arg(2)),
+                    vir::Expr::div(arg(1), arg(2)),
+                ),
+            ),
+            ArithLemma::MulDivCancel => vir::Expr::implies(
+                vir::Expr::lt_cmp(zero, arg(1)),
+                vir::Expr::eq_cmp(
+                    vir::Expr::div(vir::Expr::mul(arg(0), arg(1)), arg(1)),
+                    arg(0),
+                ),
+            ),
+        }
+    }
 }
 
 pub struct BuiltinEncoder {}
@@ -81,6 +252,9 @@ impl BuiltinEncoder {
             BuiltinFunctionKind::ArrayLookupPure { elem_ty_pred, array_len, .. } => {
                 format!("Array${}${}$lookup_pure", array_len, elem_ty_pred)
             }
+            BuiltinFunctionKind::Credits(kind) => {
+                format!("{}{}", CREDITS_FUNCTION_PREFIX, credit_kind_ident(kind))
+            }
         }
     }
 
@@ -131,6 +305,17 @@ impl BuiltinEncoder {
                     body: None,
                 }
             },
+            // Credits are accounted for by the procedure encoder, which
+            // removes them from the contracts; the function only exists for
+            // the other (unsupported) positions.
+            BuiltinFunctionKind::Credits(_) => vir::Function {
+                name: fn_name,
+                formal_args: vec![vir_local!{ amount: Int }],
+                return_type: vir::Type::Bool,
+                pres: vec![],
+                posts: vec![],
+                body: None,
+            },
         }
     }
 
@@ -138,6 +323,7 @@ impl BuiltinEncoder {
         match kind {
             BuiltinDomainKind::Nat => self.encode_nat_builtin_domain(),
             BuiltinDomainKind::Primitive => self.encode_primitive_builtin_domain(),
+            BuiltinDomainKind::ArithLemmas => self.encode_arith_lemmas_builtin_domain(),
         }
     }
 
@@ -193,4 +379,34 @@ impl BuiltinEncoder {
             type_vars: vec![],
         }
     }
+
+    fn encode_arith_lemmas_builtin_domain(&self) -> vir::Domain {
+        let mut functions = vec![];
+        let mut axioms = vec![];
+        for &lemma in ArithLemma::ALL.iter() {
+            let f = lemma.domain_func();
+            let vars = f.formal_args.clone();
+            let args: Vec<_> = vars.iter().cloned().map(vir::Expr::local).collect();
+            let function_app = vir::Expr::domain_func_app(f.clone(), args.clone());
+            // The lemma only holds where it is explicitly instantiated.
+            let body = vir::Expr::forall(
+                vars,
+                vec![vir::Trigger::new(vec![function_app.clone()])],
+                vir::Expr::and(function_app, lemma.property(&args)),
+            );
+            axioms.push(vir::DomainAxiom {
+                name: format!("{}$axiom", f.get_identifier()),
+                expr: body,
+                domain_name: ARITH_LEMMAS_DOMAIN_NAME.to_string(),
+            });
+            functions.push(f);
+        }
+
+        vir::Domain {
+            name: ARITH_LEMMAS_DOMAIN_NAME.to_owned(),
+            functions,
+            axioms,
+            type_vars: vec![],
+        }
+    }
 }
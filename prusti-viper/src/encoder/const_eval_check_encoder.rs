@@ -0,0 +1,163 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::encoder::errors::{ErrorCtxt, SpannedEncodingResult, WithSpan};
+use crate::encoder::Encoder;
+use prusti_common::vir;
+use prusti_common::vir::Successor;
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir;
+use rustc_middle::ty;
+use rustc_span::Span;
+use ::log::debug;
+
+/// A call of a `const fn` with constant arguments.
+struct ConstFnCall<'tcx> {
+    callee: DefId,
+    args: Vec<(ty::Ty<'tcx>, ty::ConstKind<'tcx>)>,
+    span: Span,
+}
+
+/// Encodes a method that checks that the encoding of the `const fn` call that
+/// initializes a constant evaluates to the value computed by the compiler.
+pub struct ConstEvalCheckEncoder<'p, 'v: 'p, 'tcx: 'v> {
+    encoder: &'p Encoder<'v, 'tcx>,
+    const_def_id: DefId,
+}
+
+impl<'p, 'v: 'p, 'tcx: 'v> ConstEvalCheckEncoder<'p, 'v, 'tcx> {
+    pub fn new(encoder: &'p Encoder<'v, 'tcx>, const_def_id: DefId) -> Self {
+        ConstEvalCheckEncoder {
+            encoder,
+            const_def_id,
+        }
+    }
+
+    /// Returns `None` if the constant is not initialized by a call of a
+    /// non-generic `const fn` on primitive values.
+    pub fn encode(&self) -> SpannedEncodingResult<Option<vir::CfgMethod>> {
+        let tcx = self.encoder.env().tcx();
+        let call = match self.find_const_fn_call() {
+            Some(call) => call,
+            None => return Ok(None),
+        };
+        let value = match tcx.const_eval_poly(self.const_def_id) {
+            Ok(value) => value,
+            Err(_) => return Ok(None),
+        };
+        let procedure = self.encoder.env().get_procedure(call.callee);
+        if !procedure.loop_info().loop_heads.is_empty() {
+            debug!("The const fn {:?} contains a loop and is not checked", call.callee);
+            return Ok(None);
+        }
+
+        let encoded_value = self.encoder
+            .encode_const_expr(tcx.type_of(self.const_def_id), &ty::ConstKind::Value(value))
+            .with_span(call.span)?;
+        let mut encoded_args = vec![];
+        let mut formal_args = vec![];
+        for (index, (arg_ty, arg)) in call.args.iter().enumerate() {
+            encoded_args.push(self.encoder.encode_const_expr(arg_ty, arg).with_span(call.span)?);
+            let arg_type = self.encoder.encode_value_or_ref_type(arg_ty).with_span(call.span)?;
+            formal_args.push(vir::LocalVar::new(format!("x{}", index), arg_type));
+        }
+        let (function_name, return_type) = self.encoder.encode_pure_function_use(call.callee)?;
+
+        let call_pos = self.encoder
+            .error_manager()
+            .register(call.span, ErrorCtxt::PureFunctionCall);
+        let assert_pos = self.encoder.error_manager().register(
+            call.span,
+            ErrorCtxt::AssertConstEvaluation(encoded_value.to_string()),
+        );
+        let encoded_call = vir::Expr::func_app(
+            function_name,
+            encoded_args,
+            formal_args,
+            return_type,
+            call_pos,
+        );
+
+        let mut cfg_method = vir::CfgMethod::new(
+            // method name
+            self.encoder.encode_item_name(self.const_def_id),
+            // formal args
+            0,
+            // formal returns
+            vec![],
+            // local vars
+            vec![],
+            // reserved labels
+            vec![],
+        );
+        let check_block = cfg_method.add_block(
+            "check",
+            vec![
+                vir::Stmt::comment("========== const evaluation check =========="),
+                vir::Stmt::comment(format!("Def path: {:?}", tcx.def_path_str(self.const_def_id))),
+                vir::Stmt::Assert(vir::Expr::eq_cmp(encoded_call, encoded_value), assert_pos),
+            ],
+        );
+        cfg_method.set_successor(check_block, Successor::Return);
+        Ok(Some(cfg_method))
+    }
+
+    /// Find the call whose result is the value of the constant.
+    fn find_const_fn_call(&self) -> Option<ConstFnCall<'tcx>> {
+        let tcx = self.encoder.env().tcx();
+        let body = tcx.mir_for_ctfe(self.const_def_id);
+        for block in body.basic_blocks() {
+            let terminator = block.terminator();
+            let (func, args, place) = match &terminator.kind {
+                mir::TerminatorKind::Call { func, args, destination: Some((place, _)), .. } => {
+                    (func, args, place)
+                }
+                _ => continue,
+            };
+            if place.as_local() != Some(mir::RETURN_PLACE) {
+                continue;
+            }
+            let (callee, substs) = match constant(func).map(|(ty, _)| ty.kind()) {
+                Some(ty::TyKind::FnDef(callee, substs)) => (*callee, *substs),
+                _ => continue,
+            };
+            if !substs.is_empty()
+                || !callee.is_local()
+                || !(self.encoder.is_pure(callee) || self.encoder.is_const_fn(callee))
+                || !is_primitive(body.return_ty())
+            {
+                return None;
+            }
+            let args: Option<Vec<_>> = args.iter().map(constant).collect();
+            return match args {
+                Some(args) if args.iter().all(|(ty, _)| is_primitive(ty)) => Some(ConstFnCall {
+                    callee,
+                    args,
+                    span: terminator.source_info.span,
+                }),
+                _ => None,
+            };
+        }
+        None
+    }
+}
+
+fn constant<'tcx>(operand: &mir::Operand<'tcx>) -> Option<(ty::Ty<'tcx>, ty::ConstKind<'tcx>)> {
+    match operand {
+        mir::Operand::Constant(box mir::Constant { literal, .. }) => Some(match literal {
+            mir::ConstantKind::Ty(ty::Const { ty, val }) => (*ty, *val),
+            mir::ConstantKind::Val(val, ty) => (*ty, ty::ConstKind::Value(*val)),
+        }),
+        _ => None,
+    }
+}
+
+fn is_primitive(ty: ty::Ty) -> bool {
+    matches!(
+        ty.kind(),
+        ty::TyKind::Bool | ty::TyKind::Char | ty::TyKind::Int(_) | ty::TyKind::Uint(_)
+    )
+}
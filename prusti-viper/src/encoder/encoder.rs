@@ -9,7 +9,8 @@ use crate::encoder::borrows::{compute_procedure_contract, ProcedureContract, Pro
 use crate::encoder::builtin_encoder::BuiltinEncoder;
 use crate::encoder::builtin_encoder::BuiltinFunctionKind;
 use crate::encoder::builtin_encoder::BuiltinMethodKind;
-use crate::encoder::builtin_encoder::BuiltinDomainKind;
+use crate::encoder::builtin_encoder::{ArithLemma, BuiltinDomainKind};
+use crate::encoder::const_eval_check_encoder::ConstEvalCheckEncoder;
 use crate::encoder::errors::{ErrorCtxt, ErrorManager, SpannedEncodingError, EncodingError, WithSpan};
 use crate::encoder::foldunfold;
 use crate::encoder::places;
@@ -26,9 +27,9 @@ use crate::encoder::spec_function_encoder::SpecFunctionEncoder;
 use prusti_common::{vir, vir_local};
 use prusti_common::vir::{WithIdentifier, ExprIterator};
 use prusti_common::config;
-use prusti_common::report::log;
+use prusti_common::report::{log, progress};
 use prusti_interface::data::ProcedureDefId;
-use prusti_interface::environment::Environment;
+use prusti_interface::environment::{Environment, Procedure};
 use prusti_interface::specs::typed;
 use prusti_interface::specs::typed::SpecificationId;
 use prusti_interface::utils::{has_spec_only_attr, read_prusti_attrs};
@@ -40,8 +41,9 @@ use rustc_hir::def_id::DefId;
 use rustc_middle::mir;
 // use rustc::mir::interpret::GlobalId;
 use rustc_middle::ty;
+use rustc_middle::ty::TypeFoldable;
 use std::cell::{RefCell, RefMut};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use std::mem;
 // use viper;
@@ -84,15 +86,34 @@ pub struct Encoder<'v, 'tcx: 'v> {
     builtin_methods: RefCell<HashMap<BuiltinMethodKind, vir::BodylessMethod>>,
     builtin_functions: RefCell<HashMap<BuiltinFunctionKind, vir::Function>>,
     procedures: RefCell<HashMap<ProcedureDefId, vir::CfgMethod>>,
+    /// The methods that check whether the precondition of a procedure is
+    /// satisfiable, which are verified in a separate program.
+    vacuity_checks: RefCell<HashMap<ProcedureDefId, vir::CfgMethod>>,
+    /// The methods that are excluded from the optimizations with
+    /// `#[prusti::simplify_encoding(false)]`.
+    unoptimized_methods: RefCell<HashSet<String>>,
+    /// For each called trusted function, the callers and how many
+    /// postconditions of the trusted function they assume.
+    trusted_calls: RefCell<HashMap<ProcedureDefId, HashMap<ProcedureDefId, usize>>>,
+    /// The items whose encoding failed.
+    encoding_failures: RefCell<HashSet<ProcedureDefId>>,
     pure_function_bodies: RefCell<HashMap<(ProcedureDefId, String), vir::Expr>>,
     pure_functions: RefCell<HashMap<(ProcedureDefId, String), vir::Function>>,
+    /// Bodyless variants of the `#[abstraction]` pure functions, used outside
+    /// of the modules that define them.
+    abstract_pure_functions: RefCell<HashMap<(ProcedureDefId, String), vir::Function>>,
     /// Stub pure functions. Generated when an impure Rust function is invoked
     /// where a pure function is required.
     stub_pure_functions: RefCell<HashMap<(ProcedureDefId, String), vir::Function>>,
     spec_functions: RefCell<HashMap<ProcedureDefId, Vec<vir::Function>>>,
-    type_predicate_names: RefCell<HashMap<ty::TyKind<'tcx>, String>>,
-    type_invariant_names: RefCell<HashMap<ty::TyKind<'tcx>, String>>,
-    type_tag_names: RefCell<HashMap<ty::TyKind<'tcx>, String>>,
+    /// The cycles of mutually recursive local pure functions, computed when
+    /// the termination of a pure function is first checked.
+    pure_function_cycles: RefCell<Option<Vec<Vec<ProcedureDefId>>>>,
+    /// The names of the encoded types, keyed by `type_cache_key`. The
+    /// definitions are shared by all the procedures of the crate.
+    type_predicate_names: RefCell<HashMap<ty::Ty<'tcx>, String>>,
+    type_invariant_names: RefCell<HashMap<ty::Ty<'tcx>, String>>,
+    type_tag_names: RefCell<HashMap<ty::Ty<'tcx>, String>>,
     predicate_types: RefCell<HashMap<String, ty::Ty<'tcx>>>,
     type_predicates: RefCell<HashMap<String, vir::Predicate>>,
     type_invariants: RefCell<HashMap<String, vir::Function>>,
@@ -105,7 +126,9 @@ pub struct Encoder<'v, 'tcx: 'v> {
     type_snapshots: RefCell<HashMap<String, String>>, // maps snapshot names to predicate names
     snap_mirror_funcs: RefCell<HashMap<String, Option<vir::DomainFunc>>>,
     closures_collector: RefCell<SpecsClosuresCollector<'tcx>>,
-    encoding_queue: RefCell<Vec<(ProcedureDefId, Vec<(ty::Ty<'tcx>, ty::Ty<'tcx>)>)>>,
+    /// The items to encode: the procedures (with `None`) and the pure
+    /// functions (with their type substitutions).
+    encoding_queue: RefCell<Vec<(ProcedureDefId, Option<Vec<(ty::Ty<'tcx>, ty::Ty<'tcx>)>>)>>,
     vir_program_before_foldunfold_writer: RefCell<Box<dyn Write>>,
     vir_program_before_viper_writer: RefCell<Box<dyn Write>>,
     pub typaram_repl: RefCell<Vec<HashMap<ty::Ty<'tcx>, ty::Ty<'tcx>>>>,
@@ -113,6 +136,8 @@ pub struct Encoder<'v, 'tcx: 'v> {
     name_interner: RefCell<NameInterner>,
     mirror_function_domain: RefCell<vir::Domain>,
     mirror_caller_functions: RefCell<Vec<vir::Function>>,
+    /// Whether some procedure instantiates an arithmetic lemma.
+    uses_arith_lemmas: RefCell<bool>,
     /// The procedure that is currently being encoded.
     pub current_proc: RefCell<Option<ProcedureDefId>>
 }
@@ -156,10 +181,16 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             builtin_methods: RefCell::new(HashMap::new()),
             builtin_functions: RefCell::new(HashMap::new()),
             procedures: RefCell::new(HashMap::new()),
+            vacuity_checks: RefCell::new(HashMap::new()),
+            unoptimized_methods: RefCell::new(HashSet::new()),
+            trusted_calls: RefCell::new(HashMap::new()),
+            encoding_failures: RefCell::new(HashSet::new()),
             pure_function_bodies: RefCell::new(HashMap::new()),
             pure_functions: RefCell::new(HashMap::new()),
+            abstract_pure_functions: RefCell::new(HashMap::new()),
             stub_pure_functions: RefCell::new(HashMap::new()),
             spec_functions: RefCell::new(HashMap::new()),
+            pure_function_cycles: RefCell::new(None),
             type_predicate_names: RefCell::new(HashMap::new()),
             type_invariant_names: RefCell::new(HashMap::new()),
             type_tag_names: RefCell::new(HashMap::new()),
@@ -183,6 +214,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             name_interner: RefCell::new(NameInterner::new()),
             mirror_function_domain: RefCell::new(axiomatized_functions_domain),
             mirror_caller_functions: RefCell::new(vec![]),
+            uses_arith_lemmas: RefCell::new(false),
             current_proc:  RefCell::new(None),
         }
     }
@@ -243,14 +275,73 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     }
 
     pub fn get_viper_program(&self) -> vir::Program {
-        vir::Program {
+        let mut program = vir::Program {
             domains: self.get_used_viper_domains(),
             fields: self.get_used_viper_fields(),
             builtin_methods: self.get_used_builtin_methods(),
             methods: self.get_used_viper_methods(),
             functions: self.get_used_viper_functions(),
             viper_predicates: self.get_used_viper_predicates(),
+        };
+        program.sort_items();
+        program
+    }
+
+    /// The program that checks whether the preconditions of the procedures
+    /// are satisfiable, if `config::check_vacuous_specs()` registered any
+    /// check. It is verified separately from the main program, because its
+    /// assertions are expected to fail.
+    pub fn get_vacuity_check_program(&self) -> Option<vir::Program> {
+        let vacuity_checks = self.vacuity_checks.borrow();
+        if vacuity_checks.is_empty() {
+            return None;
         }
+        let mut program = vir::Program {
+            domains: self.get_used_viper_domains(),
+            fields: self.get_used_viper_fields(),
+            builtin_methods: self.get_used_builtin_methods(),
+            methods: vacuity_checks.values().cloned().collect(),
+            functions: self.get_used_viper_functions(),
+            viper_predicates: self.get_used_viper_predicates(),
+        };
+        program.sort_items();
+        Some(program)
+    }
+
+    pub(in crate::encoder) fn register_vacuity_check(
+        &self,
+        proc_def_id: ProcedureDefId,
+        method: vir::CfgMethod,
+    ) {
+        self.log_vir_program_before_viper(method.to_string());
+        self.vacuity_checks.borrow_mut().insert(proc_def_id, method);
+    }
+
+    /// The methods that should not be optimized.
+    pub fn get_unoptimized_methods(&self) -> HashSet<String> {
+        self.unoptimized_methods.borrow().clone()
+    }
+
+    /// Record that `caller` assumes `assumed_posts` postconditions of the
+    /// trusted function `callee`.
+    pub(in crate::encoder) fn register_trusted_call(
+        &self,
+        callee: ProcedureDefId,
+        caller: ProcedureDefId,
+        assumed_posts: usize,
+    ) {
+        *self.trusted_calls
+            .borrow_mut()
+            .entry(callee)
+            .or_default()
+            .entry(caller)
+            .or_insert(0) += assumed_posts;
+    }
+
+    /// The callers of each trusted function, with the number of assumed
+    /// postconditions.
+    pub fn get_trusted_calls(&self) -> HashMap<ProcedureDefId, HashMap<ProcedureDefId, usize>> {
+        self.trusted_calls.borrow().clone()
     }
 
     pub(in crate::encoder) fn register_encoding_error(&self, encoding_error: SpannedEncodingError) {
@@ -299,6 +390,11 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             domains.push(builtin_encoder.encode_builtin_domain(BuiltinDomainKind::Primitive));
         }
 
+        if *self.uses_arith_lemmas.borrow() {
+            let builtin_encoder = BuiltinEncoder::new();
+            domains.push(builtin_encoder.encode_builtin_domain(BuiltinDomainKind::ArithLemmas));
+        }
+
         domains.sort_by_key(|d| d.get_identifier());
         domains
     }
@@ -310,6 +406,18 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             function);
     }
 
+    /// The kind of credits that is identified by the (marker) type `ty`.
+    pub fn encode_credit_kind(&self, ty: ty::Ty<'tcx>) -> String {
+        format!("{}", ty)
+    }
+
+    /// The domain function that instantiates `lemma`, making sure that the
+    /// domain of the arithmetic lemmas is part of the program.
+    pub fn encode_arith_lemma_use(&self, lemma: ArithLemma) -> vir::DomainFunc {
+        *self.uses_arith_lemmas.borrow_mut() = true;
+        lemma.domain_func()
+    }
+
     pub fn insert_mirror_caller(&self, function: vir::Function) {
         self.mirror_caller_functions.borrow_mut().push(function);
     }
@@ -328,6 +436,9 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         for function in self.pure_functions.borrow().values() {
             functions.push(function.clone());
         }
+        for function in self.abstract_pure_functions.borrow().values() {
+            functions.push(function.clone());
+        }
         for function in self.stub_pure_functions.borrow().values() {
             functions.push(function.clone());
         }
@@ -429,11 +540,37 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     fn get_procedure_contract(&self, proc_def_id: ProcedureDefId)
         -> EncodingResult<ProcedureContractMirDef<'tcx>>
     {
-        let spec = typed::SpecificationSet::Procedure(
-            self.get_procedure_specs(proc_def_id)
-                .unwrap_or_else(|| typed::ProcedureSpecification::empty())
-        );
-        compute_procedure_contract(proc_def_id, self.env().tcx(), spec, None)
+        let mut spec = self.get_procedure_specs(proc_def_id)
+            .unwrap_or_else(|| typed::ProcedureSpecification::empty());
+        self.add_history_invariants(proc_def_id, &mut spec);
+        compute_procedure_contract(
+            proc_def_id,
+            self.env().tcx(),
+            typed::SpecificationSet::Procedure(spec),
+            None
+        )
+    }
+
+    /// Conjoin the history invariants of the receiver type to the
+    /// postconditions of a method that takes `&mut self`.
+    fn add_history_invariants(
+        &self,
+        proc_def_id: ProcedureDefId,
+        spec: &mut typed::ProcedureSpecification<'tcx>,
+    ) {
+        let tcx = self.env().tcx();
+        let has_self = tcx.opt_associated_item(proc_def_id)
+            .map_or(false, |item| item.fn_has_self_parameter);
+        if has_self {
+            let sig = tcx.fn_sig(proc_def_id).skip_binder();
+            if let ty::TyKind::Ref(_, ty, hir::Mutability::Mut) = sig.inputs()[0].kind() {
+                if let ty::TyKind::Adt(adt_def, _) = ty.kind() {
+                    spec.posts.extend(
+                        self.def_spec.get_history_invariants(&adt_def.did).iter().cloned()
+                    );
+                }
+            }
+        }
     }
 
     /// Extract scalar value, invoking const evaluation if necessary.
@@ -511,23 +648,36 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
 
         if let Some(ty) = self_ty {
             if let Some(id) = self.env().tcx().trait_of_item(proc_def_id) {
-                let proc_name = self.env().tcx().item_name(proc_def_id);
-                let procs = self.env().get_trait_method_decl_for_type(ty, id, proc_name);
-                if procs.len() == 1 {
+                // Inside a default method body the receiver is `Self`, which
+                // stands for any implementation; only the trait contract applies.
+                let impl_method = self.resolve_trait_method(proc_def_id, id, ty).or_else(|| {
+                    if ty.needs_subst() {
+                        return None;
+                    }
+                    let proc_name = self.env().tcx().item_name(proc_def_id);
+                    let procs = self.env().get_trait_method_decl_for_type(ty, id, proc_name);
                     // FIXME(@jakob): if several methods are found, we currently don't know which
                     // one to pick.
-                    let item = procs[0];
-                    if let Some(spec) = self.get_procedure_specs(item.def_id) {
+                    if procs.len() == 1 { Some(procs[0].def_id) } else { None }
+                });
+                if let Some(impl_method) = impl_method {
+                    if let Some(spec) = self.get_procedure_specs(impl_method) {
                         impl_spec = spec;
                     } else {
-                        debug!("Procedure {:?} has no specification", item.def_id);
+                        debug!("Procedure {:?} has no specification", impl_method);
                     }
                 }
             }
         }
 
         // merge specifications
-        let final_spec = trait_spec.refine(&impl_spec);
+        let mut final_spec = trait_spec.refine(&impl_spec);
+        self.add_history_invariants(proc_def_id, &mut final_spec);
+        for (spec_item, post) in mem::take(&mut final_spec.refined_posts) {
+            if self.ghost_constraints_hold(proc_def_id, spec_item.to_def_id()) {
+                final_spec.posts.push(post);
+            }
+        }
 
         let contract = compute_procedure_contract(
             proc_def_id,
@@ -538,6 +688,215 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         Ok(contract.to_call_site_contract(args, target))
     }
 
+    /// Check whether the bounds of the spec item of a refined postcondition
+    /// that `proc_def_id` does not have (e.g. `T: Ord`) hold for the types of
+    /// the call that is being encoded. A bound on a type that is still
+    /// generic at the call does not hold.
+    fn ghost_constraints_hold(&self, proc_def_id: ProcedureDefId, spec_item: DefId) -> bool {
+        let tcx = self.env().tcx();
+        let proc_predicates = tcx.predicates_of(proc_def_id).instantiate_identity(tcx).predicates;
+        tcx.predicates_of(spec_item).instantiate_identity(tcx).predicates
+            .into_iter()
+            .filter(|predicate| !proc_predicates.contains(predicate))
+            .all(|predicate| match predicate.kind().skip_binder() {
+                ty::PredicateKind::Trait(trait_predicate, _) => {
+                    let trait_ref = trait_predicate.trait_ref;
+                    let self_ty = self.resolve_typaram(trait_ref.self_ty());
+                    !self_ty.needs_subst() && tcx.type_implements_trait((
+                        trait_ref.def_id,
+                        self_ty,
+                        tcx.mk_substs(trait_ref.substs.iter().skip(1)),
+                        ty::ParamEnv::empty(),
+                    ))
+                }
+                _ => false,
+            })
+    }
+
+    /// Describe the contract that is used for the procedure `proc_def_id`,
+    /// and where each of its parts comes from. Calls on a receiver whose
+    /// implementation is known use this contract, while calls through the
+    /// trait only use the trait contract.
+    pub fn describe_procedure_contract(&self, proc_def_id: ProcedureDefId) -> String {
+        let tcx = self.env().tcx();
+        let trait_method = tcx.opt_associated_item(proc_def_id)
+            .and_then(|item| item.trait_item_def_id);
+        let trait_spec = trait_method.and_then(|def_id| self.get_procedure_specs(def_id));
+        let own_spec = self.get_procedure_specs(proc_def_id);
+        let empty_spec = typed::ProcedureSpecification::empty();
+        let own = own_spec.as_ref().unwrap_or(&empty_spec);
+        let inherited = trait_spec.as_ref().unwrap_or(&empty_spec);
+        let trait_method_name = trait_method
+            .map(|def_id| self.env().get_item_name(def_id))
+            .unwrap_or_default();
+
+        let origin = |own_is_empty: bool, inherited_is_empty: bool| {
+            if !own_is_empty {
+                "declared on the item".to_string()
+            } else if !inherited_is_empty {
+                format!("inherited from `{}`", trait_method_name)
+            } else {
+                "none, defaults to `true`".to_string()
+            }
+        };
+        let mut spec = inherited.refine(own);
+        let mut lines = vec![format!("Contract of `{}`:", self.env().get_item_name(proc_def_id))];
+        if let Some(trait_method) = trait_method {
+            lines.push(format!("  implements `{}`", self.env().get_item_name(trait_method)));
+        }
+        lines.push(format!("  pure: {}, trusted: {}", spec.pure, spec.trusted));
+        lines.push(format!(
+            "  preconditions ({}):",
+            origin(own.pres.is_empty(), inherited.pres.is_empty()),
+        ));
+        lines.extend(spec.pres.iter().map(|pre| format!("    requires {}", self.describe_assertion(pre))));
+        lines.push(format!(
+            "  postconditions ({}):",
+            origin(own.posts.is_empty(), inherited.posts.is_empty()),
+        ));
+        lines.extend(spec.posts.iter().map(|post| format!("    ensures {}", self.describe_assertion(post))));
+        let posts_len = spec.posts.len();
+        self.add_history_invariants(proc_def_id, &mut spec);
+        if spec.posts.len() > posts_len {
+            lines.push("  history invariants of the receiver (added to the postconditions):".to_string());
+            lines.extend(spec.posts[posts_len..].iter()
+                .map(|inv| format!("    ensures {}", self.describe_assertion(inv))));
+        }
+        if !spec.refined_posts.is_empty() {
+            lines.push("  refined postconditions (at calls that satisfy the bounds):".to_string());
+            lines.extend(spec.refined_posts.iter()
+                .map(|(_, post)| format!("    ensures {}", self.describe_assertion(post))));
+        }
+        if !spec.pledges.is_empty() {
+            lines.push(format!("  pledges: {}", spec.pledges.len()));
+        }
+        lines.join("\n")
+    }
+
+    /// The source code of a specification assertion.
+    fn describe_assertion(&self, assertion: &typed::Assertion<'tcx>) -> String {
+        let tcx = self.env().tcx();
+        let snippet = |expr: &typed::Expression| {
+            let span = tcx.def_span(expr.expr.to_def_id());
+            tcx.sess.source_map().span_to_snippet(span).unwrap_or_else(|_| format!("{:?}", span))
+        };
+        match *assertion.kind {
+            typed::AssertionKind::Expr(ref expr) => snippet(expr),
+            typed::AssertionKind::And(ref conjuncts) => conjuncts.iter()
+                .map(|conjunct| format!("({})", self.describe_assertion(conjunct)))
+                .collect::<Vec<_>>()
+                .join(" && "),
+            typed::AssertionKind::Implies(ref lhs, ref rhs) => format!(
+                "({}) ==> ({})", self.describe_assertion(lhs), self.describe_assertion(rhs)
+            ),
+            typed::AssertionKind::ForAll(ref vars, _, ref body) => {
+                let tys: Vec<_> = vars.vars.iter().map(|(_, ty)| ty.to_string()).collect();
+                format!("forall over ({}): {}", tys.join(", "), self.describe_assertion(body))
+            }
+            typed::AssertionKind::TypeCond(ref vars, ref body) => format!(
+                "(type {} == type {}) ==> ({})",
+                vars.vars[0].1, vars.vars[1].1, self.describe_assertion(body)
+            ),
+            typed::AssertionKind::SpecEntailment { ref closure, .. } => {
+                format!("{} |= ..", snippet(closure))
+            }
+        }
+    }
+
+    /// Calls of the methods of the operator traits (e.g. `Add::add`,
+    /// `PartialOrd::lt`, `Index::index` or `Deref::deref`) are the desugaring
+    /// of `a + b`, `a < b`, `container[index]`, `*pointer` etc. on
+    /// user-defined types. If the implementation of the operator that is
+    /// called from `caller` has a specification, return it with its
+    /// substitutions, so that the call uses its contract (and its body, if it
+    /// is pure) like the call of a regular method.
+    pub fn resolve_operator_impl(
+        &self,
+        caller: DefId,
+        callee: DefId,
+        substs: ty::subst::SubstsRef<'tcx>,
+    ) -> Option<(DefId, ty::subst::SubstsRef<'tcx>)> {
+        let tcx = self.env().tcx();
+        let lang_items = tcx.lang_items();
+        let trait_def_id = tcx.trait_of_item(callee)?;
+        let operator_traits = [
+            lang_items.add_trait(),
+            lang_items.sub_trait(),
+            lang_items.mul_trait(),
+            lang_items.div_trait(),
+            lang_items.rem_trait(),
+            lang_items.neg_trait(),
+            lang_items.not_trait(),
+            lang_items.bitxor_trait(),
+            lang_items.bitand_trait(),
+            lang_items.bitor_trait(),
+            lang_items.shl_trait(),
+            lang_items.shr_trait(),
+            lang_items.add_assign_trait(),
+            lang_items.sub_assign_trait(),
+            lang_items.mul_assign_trait(),
+            lang_items.div_assign_trait(),
+            lang_items.rem_assign_trait(),
+            lang_items.bitxor_assign_trait(),
+            lang_items.bitand_assign_trait(),
+            lang_items.bitor_assign_trait(),
+            lang_items.shl_assign_trait(),
+            lang_items.shr_assign_trait(),
+            lang_items.eq_trait(),
+            lang_items.partial_ord_trait(),
+            lang_items.index_trait(),
+            lang_items.index_mut_trait(),
+            lang_items.deref_trait(),
+            lang_items.deref_mut_trait(),
+        ];
+        if !operator_traits.contains(&Some(trait_def_id)) {
+            return None;
+        }
+        let instance = ty::Instance::resolve(tcx, tcx.param_env(caller), callee, substs)
+            .ok()
+            .flatten()?;
+        let impl_def_id = instance.def_id();
+        if impl_def_id == callee || self.get_procedure_specs(impl_def_id).is_none() {
+            return None;
+        }
+        debug!("Operator call {:?} resolved to {:?}", callee, impl_def_id);
+        Some((impl_def_id, instance.substs))
+    }
+
+    /// Find the implementation of the trait method that is called on a
+    /// receiver of type `self_ty`. Returns `None` if the implementation
+    /// depends on type parameters (e.g. `T: Trait`), in which case only the
+    /// contract of the trait applies.
+    fn resolve_trait_method(
+        &self,
+        trait_method_def_id: ProcedureDefId,
+        trait_def_id: DefId,
+        self_ty: ty::Ty<'tcx>,
+    ) -> Option<ProcedureDefId> {
+        let tcx = self.env().tcx();
+        // The trait reference must be fully known to select an impl. The
+        // implementation behind an `impl Trait` type is hidden on purpose.
+        if self_ty.needs_subst() || tcx.generics_of(trait_def_id).count() != 1 ||
+            matches!(self_ty.kind(), ty::TyKind::Opaque(..))
+        {
+            return None;
+        }
+        let identity_substs = ty::List::identity_for_item(tcx, trait_method_def_id);
+        let substs = tcx.mk_substs(
+            std::iter::once(self_ty.into()).chain(identity_substs.iter().skip(1))
+        );
+        let param_env = tcx.param_env(trait_method_def_id);
+        let instance = ty::Instance::resolve(tcx, param_env, trait_method_def_id, substs)
+            .ok()
+            .flatten()?;
+        let resolved_def_id = instance.def_id();
+        if resolved_def_id != trait_method_def_id {
+            Some(resolved_def_id)
+        } else {
+            None
+        }
+    }
+
     /// Encodes a value in a field if the base expression is a reference or
     /// a primitive types.
     /// For composed data structures, the base expression is returned.
@@ -818,6 +1177,17 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             if config::enable_purification_optimization() {
                 crate::encoder::purify_shared_borrows(&self, &mut method);
             }
+            let simplify_encoding = self.env.get_item_config_flag(def_id, "simplify_encoding")
+                .map_err(|value| SpannedEncodingError::incorrect(
+                    format!(
+                        "`#[prusti::simplify_encoding(..)]` expects `true` or `false`, not `{}`",
+                        value,
+                    ),
+                    procedure.get_span(),
+                ))?;
+            if simplify_encoding == Some(false) {
+                self.unoptimized_methods.borrow_mut().insert(method.name());
+            }
             self.procedures.borrow_mut().insert(def_id, method);
         }
 
@@ -903,22 +1273,33 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         ))
     }
 
+    /// The key under which the encoding of `ty` is cached. The encoding of a
+    /// type does not depend on its lifetimes, so types that differ only in
+    /// their lifetimes (e.g. the same reference type in two procedures)
+    /// share one entry.
+    fn type_cache_key(&self, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+        self.env().tcx().erase_regions(ty)
+    }
+
     pub fn encode_type_predicate_use(&self, ty: ty::Ty<'tcx>)
         -> EncodingResult<String>
     {
-        if !self.type_predicate_names.borrow().contains_key(ty.kind()) {
+        let ty = self.normalize_projections(ty);
+        let key = self.type_cache_key(ty);
+        if !self.type_predicate_names.borrow().contains_key(&key) {
             let type_encoder = TypeEncoder::new(self, ty);
+            type_encoder.check_size_limits()?;
             let name = type_encoder.encode_predicate_use()?;
             self.type_predicate_names
                 .borrow_mut()
-                .insert(ty.kind().clone(), name.clone());
+                .insert(key, name.clone());
             self.predicate_types
                 .borrow_mut()
                 .insert(name, ty);
             // Trigger encoding of definition
             self.encode_type_predicate_def(ty)?;
         }
-        let predicate_name = self.type_predicate_names.borrow()[&ty.kind()].clone();
+        let predicate_name = self.type_predicate_names.borrow()[&key].clone();
         Ok(predicate_name)
     }
 
@@ -991,7 +1372,24 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
             | ty::TyKind::Never
             | ty::TyKind::Param(_) => true,
             ty::TyKind::Adt(_, _) => {
-                self.env().tcx().has_structural_eq_impls(ty)
+                self.env().tcx().has_structural_eq_impls(ty) || self.has_derived_eq_impl(ty)
+            }
+            _ => false,
+        }
+    }
+
+    /// A derived `PartialEq` compares all fields, so it is structural if the
+    /// equality of the types of all fields is structural.
+    fn has_derived_eq_impl(&self, ty: ty::Ty<'tcx>) -> bool {
+        let tcx = self.env().tcx();
+        let eq_trait = match tcx.lang_items().eq_trait() {
+            Some(def_id) => def_id,
+            None => return false,
+        };
+        match ty.kind() {
+            ty::TyKind::Adt(adt_def, substs) if !adt_def.is_box() => {
+                self.env().has_derived_impl(eq_trait, ty) && adt_def.all_fields()
+                    .all(|field| self.has_structural_eq_impl(field.ty(tcx, substs)))
             }
             _ => false,
         }
@@ -1026,17 +1424,18 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         -> EncodingResult<String>
     {
         // TODO we could use type_predicate_names instead (see TypeEncoder::encode_invariant_use)
-        if !self.type_invariant_names.borrow().contains_key(ty.kind()) {
+        let key = self.type_cache_key(ty);
+        if !self.type_invariant_names.borrow().contains_key(&key) {
             let type_encoder = TypeEncoder::new(self, ty);
             let invariant_name = type_encoder.encode_invariant_use()
                 .expect("failed to encode unsupported type");
             self.type_invariant_names
                 .borrow_mut()
-                .insert(ty.kind().clone(), invariant_name);
+                .insert(key, invariant_name);
             // Trigger encoding of definition
             self.encode_type_invariant_def(ty)?;
         }
-        let invariant_name = self.type_invariant_names.borrow()[&ty.kind()].clone();
+        let invariant_name = self.type_invariant_names.borrow()[&key].clone();
         Ok(invariant_name)
     }
 
@@ -1055,17 +1454,18 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     }
 
     pub fn encode_type_tag_use(&self, ty: ty::Ty<'tcx>) -> String {
-        if !self.type_tag_names.borrow().contains_key(&ty.kind()) {
+        let key = self.type_cache_key(ty);
+        if !self.type_tag_names.borrow().contains_key(&key) {
             let type_encoder = TypeEncoder::new(self, ty);
             let tag_name = type_encoder.encode_tag_use()
                 .expect("failed to encode unsupported type");
             self.type_tag_names
                 .borrow_mut()
-                .insert(ty.kind().clone(), tag_name);
+                .insert(key, tag_name);
             // Trigger encoding of definition
             self.encode_type_tag_def(ty);
         }
-        let tag_name = self.type_tag_names.borrow()[&ty.kind()].clone();
+        let tag_name = self.type_tag_names.borrow()[&key].clone();
         tag_name
     }
 
@@ -1206,7 +1606,7 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     ) -> SpannedEncodingResult<()> {
         trace!("[enter] encode_pure_function_def({:?})", proc_def_id);
         assert!(
-            self.is_pure(proc_def_id),
+            self.is_pure(proc_def_id) || self.is_const_fn(proc_def_id),
             "procedure is not marked as pure: {:?}",
             proc_def_id
         );
@@ -1259,6 +1659,14 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
                 function
             };
 
+            if self.is_abstraction(proc_def_id) {
+                let abstract_function = vir::Function {
+                    name: abstract_function_name(&final_function.name),
+                    ..pure_function_encoder.encode_bodyless_function()?
+                };
+                self.abstract_pure_functions.borrow_mut().insert(key.clone(), abstract_function);
+            }
+
             self.log_vir_program_before_viper(final_function.to_string());
             self.pure_functions.borrow_mut().insert(key, final_function);
         }
@@ -1414,8 +1822,8 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
     /// Encode the use (call) of a pure function, returning the name of the
     /// function and its type.
     ///
-    /// The called function must be marked as pure. It should be local unless
-    /// there is an external specification defined.
+    /// The called function must be marked as pure or be a `const fn`. It
+    /// should be local unless there is an external specification defined.
     pub fn encode_pure_function_use(
         &self,
         proc_def_id: ProcedureDefId,
@@ -1424,22 +1832,43 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         let procedure = self.env.get_procedure(wrapper_def_id);
 
         assert!(
-            self.is_pure(proc_def_id),
+            self.is_pure(proc_def_id) || self.is_const_fn(proc_def_id),
             "procedure is not marked as pure: {:?}",
             proc_def_id
         );
+        if self.is_const_fn(proc_def_id) {
+            self.check_const_fn_is_pure(&procedure)?;
+        }
 
         let pure_function_encoder =
             PureFunctionEncoder::new(self, proc_def_id, procedure.get_mir(), false);
 
         self.queue_pure_function_encoding(proc_def_id);
 
+        let function_name = pure_function_encoder.encode_function_name();
         Ok((
-            pure_function_encoder.encode_function_name(),
+            if self.is_abstraction(proc_def_id) && !self.sees_definition_of(proc_def_id) {
+                abstract_function_name(&function_name)
+            } else {
+                function_name
+            },
             pure_function_encoder.encode_function_return_type()?,
         ))
     }
 
+    /// Is the procedure that is being encoded in the module that defines the
+    /// `#[abstraction]` function (or in one of its submodules)?
+    fn sees_definition_of(&self, abstraction_def_id: ProcedureDefId) -> bool {
+        let tcx = self.env.tcx();
+        match (abstraction_def_id.as_local(), *self.current_proc.borrow()) {
+            (Some(local_def_id), Some(current_proc)) => {
+                let module = tcx.parent_module_from_def_id(local_def_id);
+                tcx.is_descendant_of(current_proc, module.to_def_id())
+            }
+            _ => false,
+        }
+    }
+
     /// Encode the use (call) of either a comparison (equality or disequality)
     /// returning the name of the encoded function and its type.
     /// If the comparison is not supported, a stub function will be encoded
@@ -1496,22 +1925,44 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         ))
     }
 
+    /// Encode the checks that the `const fn` calls that initialize the
+    /// constants of the crate agree with the values computed by the compiler.
+    pub fn encode_const_eval_checks(&self) {
+        let tcx = self.env.tcx();
+        for local_def_id in tcx.hir().body_owners() {
+            let def_id = local_def_id.to_def_id();
+            if !matches!(tcx.def_kind(def_id), hir::def::DefKind::Const | hir::def::DefKind::AssocConst) {
+                continue;
+            }
+            match ConstEvalCheckEncoder::new(self, def_id).encode() {
+                Ok(Some(method)) => {
+                    self.log_vir_program_before_viper(method.to_string());
+                    self.procedures.borrow_mut().insert(def_id, method);
+                }
+                Ok(None) => debug!("The constant {:?} is not checked", def_id),
+                Err(error) => self.register_encoding_error(error),
+            }
+        }
+    }
+
     pub fn queue_procedure_encoding(&self, proc_def_id: ProcedureDefId) {
         self.encoding_queue
             .borrow_mut()
-            .push((proc_def_id, Vec::new()));
+            .push((proc_def_id, None));
     }
 
     pub fn queue_pure_function_encoding(&self, proc_def_id: ProcedureDefId) {
         let substs = self.current_tymap().into_iter().collect();
-        self.encoding_queue.borrow_mut().push((proc_def_id, substs));
+        self.encoding_queue.borrow_mut().push((proc_def_id, Some(substs)));
     }
 
     pub fn process_encoding_queue(&mut self) {
         self.initialize();
+        let mut started = 0;
         while !self.encoding_queue.borrow().is_empty() {
             let (proc_def_id, substs) = self.encoding_queue.borrow_mut().pop().unwrap();
             self.current_proc.replace(Some(proc_def_id.clone()));
+            self.error_manager().set_current_item(Some(proc_def_id));
 
             let proc_name = self.env.get_absolute_item_name(proc_def_id);
             let proc_def_path = self.env.get_item_def_path(proc_def_id);
@@ -1521,31 +1972,95 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
                 "Encoding: {} from {:?} ({})",
                 proc_name, proc_span, proc_def_path
             );
-            let is_pure_function = self.is_pure(proc_def_id);
-            if is_pure_function {
+            let _span = tracing::info_span!("encode", item = %proc_def_path).entered();
+            started += 1;
+            let total = started + self.encoding_queue.borrow().len();
+            self.report_progress(proc_def_id, progress::ItemStatus::Started, started, total);
+            // A `const fn` is verified as a procedure, and also encoded as a
+            // pure function when it is used in a specification.
+            let pure_function_substs = match substs {
+                None if self.is_pure(proc_def_id) => Some(vec![]),
+                substs => substs,
+            };
+            let encoded = if let Some(substs) = pure_function_substs {
                 if let Err(error) = self.encode_pure_function_def(proc_def_id, substs) {
                     self.register_encoding_error(error);
                     debug!("Error encoding pure function: {:?}", proc_def_id);
+                    false
+                } else {
+                    true
                 }
             } else {
-                assert!(substs.is_empty());
                 if self.is_trusted(proc_def_id) {
                     debug!(
                         "Trusted procedure will not be encoded or verified: {:?}",
                         proc_def_id
                     );
+                    true
+                } else if config::check_preamble() {
+                    debug!("Only the contract of the procedure will be encoded: {:?}", proc_def_id);
+                    if let Err(error) = self.encode_spec_funcs(proc_def_id) {
+                        self.register_encoding_error(error);
+                        debug!("Error encoding the contract of function: {:?}", proc_def_id);
+                        false
+                    } else {
+                        true
+                    }
                 } else {
                     if let Err(error) = self.encode_procedure(proc_def_id) {
                         self.register_encoding_error(error);
                         debug!("Error encoding function: {:?}", proc_def_id);
+                        false
+                    } else {
+                        true
                     }
                 }
+            };
+            if encoded {
+                self.report_progress(proc_def_id, progress::ItemStatus::Encoded, started, total);
+            } else {
+                self.encoding_failures.borrow_mut().insert(proc_def_id);
+                self.report_progress(proc_def_id, progress::ItemStatus::Failed, started, total);
             }
 
+            self.error_manager().set_current_item(None);
             self.current_proc.replace(None);
         }
     }
 
+    /// Report a progress event about the item `def_id`.
+    pub fn report_progress(
+        &self,
+        def_id: ProcedureDefId,
+        status: progress::ItemStatus,
+        done: usize,
+        total: usize,
+    ) {
+        let source_map = self.env.tcx().sess.source_map();
+        progress::report(progress::ProgressEvent {
+            status,
+            item: self.env.get_absolute_item_name(def_id),
+            location: source_map.span_to_string(self.env.get_item_span(def_id)),
+            done,
+            total,
+        });
+    }
+
+    /// Did the encoding of the item fail?
+    pub fn has_encoding_failed(&self, def_id: ProcedureDefId) -> bool {
+        self.encoding_failures.borrow().contains(&def_id)
+    }
+
+    /// Is the pure function marked with `#[abstraction]`? Its body is only
+    /// visible to the procedures of the module that defines it, so that
+    /// public contracts can use it to talk about private fields without
+    /// revealing them.
+    pub fn is_abstraction(&self, def_id: ProcedureDefId) -> bool {
+        let result = self.is_pure(def_id) && self.env.has_prusti_attribute(def_id, "abstraction");
+        trace!("is_abstraction {:?} = {}", def_id, result);
+        result
+    }
+
     pub fn is_trusted(&self, def_id: ProcedureDefId) -> bool {
         let result = self.def_spec.get(&def_id).map_or(false, |spec| spec.expect_procedure().trusted);
         trace!("is_trusted {:?} = {}", def_id, result);
@@ -1558,6 +2073,64 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         result
     }
 
+    /// Are `caller` and `callee` in the same cycle of mutually recursive pure
+    /// functions? The cycles are the strongly connected components of the
+    /// call graph of all the local pure functions, so that cycles through
+    /// any number of functions are found.
+    pub fn is_mutually_recursive_pure_call(
+        &self,
+        caller: ProcedureDefId,
+        callee: ProcedureDefId,
+    ) -> bool {
+        let mut cycles = self.pure_function_cycles.borrow_mut();
+        let cycles = cycles.get_or_insert_with(|| {
+            let pure_functions: Vec<_> = self.env.get_annotated_procedures()
+                .into_iter()
+                .filter(|&def_id| self.is_pure(def_id) || self.is_const_fn(def_id))
+                .collect();
+            self.env.get_recursive_cycles(&pure_functions)
+        });
+        cycles.iter().any(|cycle| {
+            cycle.len() > 1 && cycle.contains(&caller) && cycle.contains(&callee)
+        })
+    }
+
+    /// Is the item a local `const fn` that is not marked as pure? Such a
+    /// function is verified like any other procedure, but it can also be
+    /// called in specifications, where it is encoded as a pure function.
+    pub fn is_const_fn(&self, def_id: ProcedureDefId) -> bool {
+        let result = def_id.is_local()
+            && self.env.tcx().is_const_fn_raw(def_id)
+            && !self.is_pure(def_id)
+            && !self.is_trusted(def_id);
+        trace!("is_const_fn {:?} = {}", def_id, result);
+        result
+    }
+
+    /// Check that the body of the `const fn` can be encoded as a pure function.
+    fn check_const_fn_is_pure(&self, procedure: &Procedure<'_, 'tcx>) -> SpannedEncodingResult<()> {
+        let name = self.env.get_item_name(procedure.get_id());
+        if !procedure.loop_info().loop_heads.is_empty() {
+            return Err(SpannedEncodingError::unsupported(
+                format!("const fn {} contains a loop and cannot be used in specifications", name),
+                procedure.get_span(),
+            ));
+        }
+        let mir = procedure.get_mir();
+        for arg in mir.args_iter() {
+            if let ty::TyKind::Ref(_, _, hir::Mutability::Mut) = mir.local_decls[arg].ty.kind() {
+                return Err(SpannedEncodingError::unsupported(
+                    format!(
+                        "const fn {} takes a mutable reference and cannot be used in specifications",
+                        name,
+                    ),
+                    procedure.get_span(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     pub fn get_predicate_body(&self, def_id: ProcedureDefId) -> Option<&typed::Assertion<'tcx>> {
         let result = self.def_spec.get(&def_id).map_or(None, |spec| spec.expect_procedure().predicate_body.as_ref());
         trace!("get_predicate_body {:?} = {:?}", def_id, result);
@@ -1585,6 +2158,34 @@ impl<'v, 'tcx> Encoder<'v, 'tcx> {
         ty
     }
 
+    /// Normalize the associated type projections in `ty` (e.g. `T::Output` or
+    /// `<I as Iterator>::Item`) to the types that they are defined to be,
+    /// after replacing the type parameters with their current substitution.
+    /// Projections that still depend on type parameters are left unchanged,
+    /// and are encoded as abstract types.
+    pub fn normalize_projections(&self, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+        if !ty.has_projections() {
+            return ty;
+        }
+        let tcx = self.env().tcx();
+        let tymap = self.current_tymap();
+        let substituted_ty = ty.fold_with(&mut ty::fold::BottomUpFolder {
+            tcx,
+            ty_op: |ty| tymap.get(&ty).cloned().unwrap_or(ty),
+            lt_op: |region| region,
+            ct_op: |constant| constant,
+        });
+        if substituted_ty.needs_subst() {
+            return ty;
+        }
+        let normalized_ty = tcx.normalize_erasing_regions(
+            ty::ParamEnv::reveal_all(),
+            substituted_ty,
+        );
+        trace!("normalize_projections({:?}) ==> {:?}", ty, normalized_ty);
+        normalized_ty
+    }
+
     /// Merges the stack of type maps into a single map.
     pub fn current_tymap(&self) -> HashMap<ty::Ty<'tcx>, ty::Ty<'tcx>> {
         let mut map = HashMap::new();
@@ -1693,3 +2294,8 @@ fn encode_identifier(ident: String) -> String {
         .replace(" ", "$space$")
 }
 
+
+/// The name of the bodyless variant of an `#[abstraction]` pure function.
+fn abstract_function_name(function_name: &str) -> String {
+    format!("{}$abstract$", function_name)
+}
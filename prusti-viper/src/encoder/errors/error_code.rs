@@ -0,0 +1,155 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::{ErrorCtxt, PanicCause};
+use std::fmt;
+
+/// The stable code of a class of verification errors. The codes are part of
+/// the user interface: they are reported with the errors, and they can be
+/// used to allow a class of errors in a function with
+/// `#[prusti::allow_error(<code>)]`. Existing codes must not be renumbered.
+///
+/// * `P01xx`: contracts of methods and loops
+/// * `P02xx`: pledges
+/// * `P03xx`: panics and arithmetic
+/// * `P04xx`: pure functions
+/// * `P05xx`: unsupported features
+/// * `P06xx`: concurrency
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// A postcondition might not hold (P0101).
+    Postcondition,
+    /// The precondition of a called method might not hold (P0102).
+    Precondition,
+    /// A loop invariant might not hold in the first iteration (P0103).
+    LoopInvariantOnEntry,
+    /// A loop invariant might not be preserved by an iteration (P0104).
+    LoopInvariantAfterIteration,
+    /// A method precondition is not a weakening of the trait's (P0105).
+    PreconditionWeakening,
+    /// A method postcondition is not a strengthening of the trait's (P0106).
+    PostconditionStrengthening,
+    /// A type invariant might not hold at the end of a method (P0107).
+    TypeInvariant,
+    /// A pledge might not hold when the function returns (P0201).
+    Pledge,
+    /// A pledge might not hold when the borrow expires (P0203).
+    PledgeAtExpiry,
+    /// An arithmetic operation might overflow (P0301).
+    Overflow,
+    /// A built-in assertion, such as a bounds or a division check, might
+    /// fail (P0302).
+    BuiltinAssertion,
+    /// A `panic!(..)` might be reachable (P0303).
+    Panic,
+    /// An `assert!(..)` might fail (P0304).
+    Assertion,
+    /// An `unreachable!(..)` or `unimplemented!(..)` might be reachable
+    /// (P0305).
+    Unreachable,
+    /// An `unwrap()` or `expect(..)` might fail (P0306).
+    Unwrap,
+    /// A statement might abort (P0307).
+    Abort,
+    /// The precondition of a called pure function might not hold (P0401).
+    PureFunctionPrecondition,
+    /// A pure function might panic (P0402).
+    PureFunctionPanic,
+    /// A postcondition of a pure function might not hold (P0403).
+    PureFunctionPostcondition,
+    /// A numeric cast might lose information (P0404).
+    TypeCast,
+    /// A recursive call of a pure function might not terminate (P0405).
+    PureFunctionTermination,
+    /// An unsupported feature might be reachable (P0501).
+    Unsupported,
+}
+
+impl ErrorCode {
+    /// The code of an error with the given context, or `None` for
+    /// contexts whose errors are internal errors of Prusti.
+    pub fn of(error_ctxt: &ErrorCtxt) -> Option<Self> {
+        Some(match error_ctxt {
+            ErrorCtxt::ExhaleMethodPostcondition |
+            ErrorCtxt::AssertMethodPostcondition => ErrorCode::Postcondition,
+            ErrorCtxt::ExhaleMethodPrecondition |
+            ErrorCtxt::AssertSpawnedThreadPrecondition |
+            ErrorCtxt::AssertDropPrecondition => ErrorCode::Precondition,
+            ErrorCtxt::ExhaleLoopInvariantOnEntry |
+            ErrorCtxt::AssertLoopInvariantOnEntry => ErrorCode::LoopInvariantOnEntry,
+            ErrorCtxt::ExhaleLoopInvariantAfterIteration |
+            ErrorCtxt::AssertLoopInvariantAfterIteration => {
+                ErrorCode::LoopInvariantAfterIteration
+            }
+            ErrorCtxt::AssertMethodPreconditionWeakening(_) => ErrorCode::PreconditionWeakening,
+            ErrorCtxt::AssertMethodPostconditionStrengthening(_) => {
+                ErrorCode::PostconditionStrengthening
+            }
+            ErrorCtxt::AssertMethodPostconditionTypeInvariants => ErrorCode::TypeInvariant,
+            ErrorCtxt::PackageMagicWandForPostcondition => ErrorCode::Pledge,
+            ErrorCtxt::ApplyMagicWandOnExpiry => ErrorCode::PledgeAtExpiry,
+            ErrorCtxt::AssertOverflowTerminator(_) => ErrorCode::Overflow,
+            ErrorCtxt::AssertTerminator(_) => ErrorCode::BuiltinAssertion,
+            ErrorCtxt::Panic(PanicCause::Generic) |
+            ErrorCtxt::Panic(PanicCause::Panic) => ErrorCode::Panic,
+            ErrorCtxt::Panic(PanicCause::Assert) |
+            ErrorCtxt::Panic(PanicCause::DebugAssert) => ErrorCode::Assertion,
+            ErrorCtxt::Panic(PanicCause::Unreachable) |
+            ErrorCtxt::Panic(PanicCause::Unimplemented) => ErrorCode::Unreachable,
+            ErrorCtxt::Panic(PanicCause::Unwrap) => ErrorCode::Unwrap,
+            ErrorCtxt::AbortTerminator => ErrorCode::Abort,
+            ErrorCtxt::PureFunctionCall => ErrorCode::PureFunctionPrecondition,
+            ErrorCtxt::PanicInPureFunction(_) |
+            ErrorCtxt::PureFunctionAssertTerminator(_) |
+            ErrorCtxt::DivergingCallInPureFunction => ErrorCode::PureFunctionPanic,
+            ErrorCtxt::PureFunctionDefinition |
+            ErrorCtxt::PureFunctionPostconditionValueRangeOfResult => {
+                ErrorCode::PureFunctionPostcondition
+            }
+            ErrorCtxt::TypeCast => ErrorCode::TypeCast,
+            ErrorCtxt::PureFunctionTermination => ErrorCode::PureFunctionTermination,
+            ErrorCtxt::Unsupported(_) => ErrorCode::Unsupported,
+            ErrorCtxt::UnreachableTerminator |
+            ErrorCtxt::Unexpected |
+            ErrorCtxt::GenericExpression |
+            ErrorCtxt::GenericStatement |
+            ErrorCtxt::VacuityCheck => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Postcondition => "P0101",
+            ErrorCode::Precondition => "P0102",
+            ErrorCode::LoopInvariantOnEntry => "P0103",
+            ErrorCode::LoopInvariantAfterIteration => "P0104",
+            ErrorCode::PreconditionWeakening => "P0105",
+            ErrorCode::PostconditionStrengthening => "P0106",
+            ErrorCode::TypeInvariant => "P0107",
+            ErrorCode::Pledge => "P0201",
+            ErrorCode::PledgeAtExpiry => "P0203",
+            ErrorCode::Overflow => "P0301",
+            ErrorCode::BuiltinAssertion => "P0302",
+            ErrorCode::Panic => "P0303",
+            ErrorCode::Assertion => "P0304",
+            ErrorCode::Unreachable => "P0305",
+            ErrorCode::Unwrap => "P0306",
+            ErrorCode::Abort => "P0307",
+            ErrorCode::PureFunctionPrecondition => "P0401",
+            ErrorCode::PureFunctionPanic => "P0402",
+            ErrorCode::PureFunctionPostcondition => "P0403",
+            ErrorCode::TypeCast => "P0404",
+            ErrorCode::PureFunctionTermination => "P0405",
+            ErrorCode::Unsupported => "P0501",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
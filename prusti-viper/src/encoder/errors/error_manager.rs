@@ -7,10 +7,13 @@
 use prusti_common::vir::Position;
 use std::collections::HashMap;
 use rustc_span::source_map::SourceMap;
-use rustc_span::MultiSpan;
+use rustc_span::{MultiSpan, Span};
+use rustc_span::hygiene::{ExpnKind, MacroKind};
 use viper::VerificationError;
 use prusti_interface::PrustiError;
+use prusti_interface::data::ProcedureDefId;
 use log::debug;
+use super::ErrorCode;
 
 /// The cause of a panic!()
 #[derive(Clone, Debug)]
@@ -27,6 +30,8 @@ pub enum PanicCause {
     Unreachable,
     /// Caused by an unimplemented!()
     Unimplemented,
+    /// Caused by calling `unwrap()` or `expect(..)` on a `None` or an `Err`
+    Unwrap,
 }
 
 /// In case of verification error, this enum will contain additional information
@@ -37,6 +42,12 @@ pub enum ErrorCtxt {
     Panic(PanicCause),
     /// A Viper `exhale expr` that encodes the call of a Rust procedure with precondition `expr`
     ExhaleMethodPrecondition,
+    /// A Viper `assert expr` that checks the precondition `expr` of a closure passed to
+    /// `std::thread::spawn`
+    AssertSpawnedThreadPrecondition,
+    /// A Viper `assert expr` that checks the precondition `expr` of the `Drop::drop` implementation
+    /// called when a value goes out of scope
+    AssertDropPrecondition,
     /// A Viper `assert expr` that encodes the call of a Rust procedure with precondition `expr`
     AssertMethodPostcondition,
     /// A Viper `assert expr` that encodes the call of a Rust procedure with precondition `expr`
@@ -52,6 +63,9 @@ pub enum ErrorCtxt {
     /// A Viper `assert false` that encodes the failure (panic) of an `assert` Rust terminator
     /// Arguments: the message of the Rust assertion
     AssertTerminator(String),
+    /// Like `AssertTerminator`, for the overflow checks of arithmetic operations
+    /// Arguments: the message of the Rust assertion
+    AssertOverflowTerminator(String),
     /// A Viper `assert false` that encodes an `abort` Rust terminator
     AbortTerminator,
     /// A Viper `assert false` that encodes an `unreachable` Rust terminator
@@ -80,6 +94,9 @@ pub enum ErrorCtxt {
     ApplyMagicWandOnExpiry,
     /// A diverging function call performed in a pure function
     DivergingCallInPureFunction,
+    /// A Viper pure function call with `false` precondition that encodes a
+    /// recursive call of a Rust pure function that might not terminate
+    PureFunctionTermination,
     /// A Viper pure function call with `false` precondition that encodes a Rust panic in a pure function
     PanicInPureFunction(PanicCause),
     /// A Viper `assert e1 ==> e2` that encodes a weakening of the precondition
@@ -92,6 +109,49 @@ pub enum ErrorCtxt {
     TypeCast,
     /// A Viper `assert false` that encodes an unsupported feature
     Unsupported(String),
+    /// A Viper `assert e` that checks that there are enough credits of the
+    /// given kind to be consumed or passed to a callee
+    AssertEnoughCredits(String),
+    /// A Viper `assert false` of a separate method that only inhales the
+    /// precondition of a procedure, which is expected to fail unless the
+    /// precondition is unsatisfiable
+    VacuityCheck,
+    /// A Viper `assert e` that checks that the encoding of a `const fn` call
+    /// agrees with the given value, computed by the compiler
+    AssertConstEvaluation(String),
+}
+
+impl ErrorCtxt {
+    /// The kind of proof obligation that is checked by a Viper `assert` or
+    /// `exhale` with this context, or `None` for the checks that are internal
+    /// to the encoding. See `config::export_obligations()`.
+    pub fn obligation_kind(&self) -> Option<&'static str> {
+        Some(match self {
+            ErrorCtxt::ExhaleMethodPrecondition
+            | ErrorCtxt::AssertSpawnedThreadPrecondition
+            | ErrorCtxt::AssertDropPrecondition => "precondition",
+            ErrorCtxt::ExhaleMethodPostcondition
+            | ErrorCtxt::AssertMethodPostcondition
+            | ErrorCtxt::AssertMethodPostconditionTypeInvariants
+            | ErrorCtxt::PureFunctionPostconditionValueRangeOfResult => "postcondition",
+            ErrorCtxt::ExhaleLoopInvariantOnEntry
+            | ErrorCtxt::ExhaleLoopInvariantAfterIteration
+            | ErrorCtxt::AssertLoopInvariantOnEntry
+            | ErrorCtxt::AssertLoopInvariantAfterIteration => "loop_invariant",
+            ErrorCtxt::AssertOverflowTerminator(_) => "overflow",
+            ErrorCtxt::AssertTerminator(_) => "assertion",
+            ErrorCtxt::Panic(_)
+            | ErrorCtxt::AbortTerminator
+            | ErrorCtxt::UnreachableTerminator => "panic",
+            ErrorCtxt::AssertMethodPreconditionWeakening(_)
+            | ErrorCtxt::AssertMethodPostconditionStrengthening(_) => "refinement",
+            ErrorCtxt::Unexpected
+            | ErrorCtxt::GenericExpression
+            | ErrorCtxt::GenericStatement
+            | ErrorCtxt::VacuityCheck => return None,
+            _ => "other",
+        })
+    }
 }
 
 /// The error manager
@@ -100,6 +160,18 @@ pub struct ErrorManager<'tcx> {
     codemap: &'tcx SourceMap,
     source_span: HashMap<u64, MultiSpan>,
     error_contexts: HashMap<u64, ErrorCtxt>,
+    /// For positions whose span was moved out of the expansion of an
+    /// external macro: the name of the macro and the original span.
+    macro_expansions: HashMap<u64, (String, Span)>,
+    /// The item that was being encoded when a position was registered.
+    position_items: HashMap<u64, ProcedureDefId>,
+    /// For the positions of the sub-expressions of a contract: the position
+    /// of the innermost conjunct or quantifier body that contains them.
+    spec_parts: HashMap<u64, u64>,
+    /// The messages given by the user to the clauses of a contract, keyed by
+    /// the position of the conjunct or quantifier body.
+    spec_messages: HashMap<u64, String>,
+    current_item: Option<ProcedureDefId>,
     next_pos_id: u64,
 }
 
@@ -110,6 +182,11 @@ impl<'tcx> ErrorManager<'tcx>
             codemap,
             source_span: HashMap::new(),
             error_contexts: HashMap::new(),
+            macro_expansions: HashMap::new(),
+            position_items: HashMap::new(),
+            spec_parts: HashMap::new(),
+            spec_messages: HashMap::new(),
+            current_item: None,
             next_pos_id: 1,
         }
     }
@@ -125,6 +202,10 @@ impl<'tcx> ErrorManager<'tcx>
         let pos_id = self.next_pos_id;
         self.next_pos_id += 1;
         debug!("Register position {:?} at span {:?}", pos_id, span);
+        if let Some(item) = self.current_item {
+            self.position_items.insert(pos_id, item);
+        }
+        let span = self.map_external_macro_spans(pos_id, span);
         let pos = if let Some(primary_span) = span.primary_span() {
             let lines_info_res = self
                 .codemap
@@ -153,7 +234,116 @@ impl<'tcx> ErrorManager<'tcx>
         self.error_contexts.insert(pos.id(), error_ctxt);
     }
 
+    /// Record that the given positions belong to the conjunct or quantifier
+    /// body of a contract at `part_pos`. Positions that already belong to an
+    /// inner part are left unchanged.
+    pub fn register_spec_part<I>(&mut self, part_pos: &Position, positions: I)
+        where I: IntoIterator<Item = Position>
+    {
+        for pos in positions {
+            if !pos.is_default() {
+                self.spec_parts.entry(pos.id()).or_insert(part_pos.id());
+            }
+        }
+    }
+
+    /// The span and the error context registered for `pos`, if any.
+    pub fn get_registered_error(&self, pos: &Position) -> Option<(&MultiSpan, &ErrorCtxt)> {
+        let error_ctxt = self.error_contexts.get(&pos.id())?;
+        let span = self.source_span.get(&pos.id())?;
+        Some((span, error_ctxt))
+    }
+
+    /// Report `message` instead of the generic message when the conjunct or
+    /// quantifier body of a contract at `part_pos` does not hold.
+    pub fn register_spec_message(&mut self, part_pos: &Position, message: String) {
+        self.spec_messages.insert(part_pos.id(), message);
+    }
+
+    /// Errors in code generated by macros of other crates (e.g. `assert!`
+    /// or the Prusti specification macros) should point at the macro
+    /// invocation written by the user. The first original span is kept to
+    /// report it in a note.
+    fn map_external_macro_spans(&mut self, pos_id: u64, span: MultiSpan) -> MultiSpan {
+        if !span.primary_spans().iter().any(|span| span.from_expansion()) {
+            return span;
+        }
+        let mapped_spans = span.primary_spans().iter().map(|&original_span| {
+            let mut span = original_span;
+            while span.from_expansion() {
+                let expn_data = span.ctxt().outer_expn_data();
+                if expn_data.macro_def_id.map_or(false, |def_id| def_id.is_local()) {
+                    break;
+                }
+                if !self.macro_expansions.contains_key(&pos_id) {
+                    let macro_name = match expn_data.kind {
+                        ExpnKind::Macro(MacroKind::Bang, name) => format!("{}!", name),
+                        ExpnKind::Macro(_, name) => name.to_string(),
+                        _ => expn_data.kind.descr().to_string(),
+                    };
+                    self.macro_expansions.insert(pos_id, (macro_name, original_span));
+                }
+                span = expn_data.call_site;
+            }
+            span
+        }).collect();
+        MultiSpan::from_spans(mapped_spans)
+    }
+
+    /// Set the item whose encoding registers the next positions.
+    pub fn set_current_item(&mut self, item: Option<ProcedureDefId>) {
+        self.current_item = item;
+    }
+
+    /// The item whose encoding produced the failing assertion.
+    pub fn get_item(&self, ver_error: &VerificationError) -> Option<ProcedureDefId> {
+        let pos_id: u64 = ver_error.pos_id.as_ref()?.parse().ok()?;
+        self.position_items.get(&pos_id).cloned()
+    }
+
+    /// The positions of the registered `ErrorCtxt::VacuityCheck` assertions.
+    pub fn vacuity_checks(&self) -> Vec<(u64, MultiSpan)> {
+        self.error_contexts
+            .iter()
+            .filter(|(_, error_ctxt)| matches!(error_ctxt, ErrorCtxt::VacuityCheck))
+            .map(|(&pos_id, _)| (pos_id, self.source_span[&pos_id].clone()))
+            .collect()
+    }
+
+    /// If the verification error is the expected failure of a vacuity check,
+    /// return the position of the check.
+    pub fn get_vacuity_check(&self, ver_error: &VerificationError) -> Option<u64> {
+        let pos_id: u64 = ver_error.pos_id.as_ref()?.parse().ok()?;
+        match self.error_contexts.get(&pos_id) {
+            Some(ErrorCtxt::VacuityCheck) => Some(pos_id),
+            _ => None,
+        }
+    }
+
+    /// The stable code of the class of the verification error.
+    pub fn get_error_code(&self, ver_error: &VerificationError) -> Option<ErrorCode> {
+        ver_error.pos_id.as_ref()
+            .and_then(|pos_id| pos_id.parse::<u64>().ok())
+            .and_then(|pos_id| self.error_contexts.get(&pos_id))
+            .and_then(ErrorCode::of)
+    }
+
     pub fn translate_verification_error(&self, ver_error: &VerificationError) -> PrustiError {
+        let mut prusti_error = self.translate_verification_error_in_source(ver_error);
+        if let Some(code) = self.get_error_code(ver_error) {
+            prusti_error = prusti_error.set_code(code);
+        }
+        let opt_expansion = ver_error.pos_id.as_ref()
+            .and_then(|pos_id| pos_id.parse::<u64>().ok())
+            .and_then(|pos_id| self.macro_expansions.get(&pos_id));
+        if let Some((macro_name, original_span)) = opt_expansion {
+            prusti_error.set_macro_expansion_note(macro_name, *original_span)
+        } else {
+            prusti_error
+        }
+    }
+
+    fn translate_verification_error_in_source(&self, ver_error: &VerificationError) -> PrustiError {
         debug!("Verification error: {:?}", ver_error);
         let opt_pos_id: Option<u64> = match ver_error.pos_id {
             Some(ref viper_pos_id) => {
@@ -189,6 +379,11 @@ impl<'tcx> ErrorManager<'tcx>
             }
             None => None
         };
+        // Point to the failing conjunct or quantifier body of a contract,
+        // rather than to the whole contract or to one of its sub-expressions.
+        let opt_reason_pos_id = opt_reason_pos_id.map(|reason_pos_id| {
+            self.spec_parts.get(&reason_pos_id).cloned().unwrap_or(reason_pos_id)
+        });
 
         let opt_error_ctxt = opt_pos_id.and_then(|pos_id| self.error_contexts.get(&pos_id));
         let opt_error_span = opt_pos_id.and_then(|pos_id| self.source_span.get(&pos_id));
@@ -199,6 +394,9 @@ impl<'tcx> ErrorManager<'tcx>
             }
             res
         });
+        let opt_spec_message = opt_reason_pos_id
+            .and_then(|reason_pos_id| self.spec_messages.get(&reason_pos_id))
+            .map(|message| message.as_str());
 
         let (error_span, error_ctxt) = if let Some(error_ctxt) = opt_error_ctxt {
             debug_assert!(opt_error_span.is_some());
@@ -269,11 +467,31 @@ impl<'tcx> ErrorManager<'tcx>
                     .set_failing_assertion(opt_cause_span)
             }
 
-            ("assert.failed:assertion.false", ErrorCtxt::AssertTerminator(ref message)) => {
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Unwrap)) => {
+                PrustiError::verification(
+                    "unwrap()/expect(..) might be called on a None or an Err",
+                    error_span
+                ).set_failing_assertion(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertTerminator(ref message)) |
+            ("assert.failed:assertion.false", ErrorCtxt::AssertOverflowTerminator(ref message)) => {
                 PrustiError::verification(format!("assertion might fail with \"{}\"", message), error_span)
                     .set_failing_assertion(opt_cause_span)
             }
 
+            ("assert.failed:assertion.false", ErrorCtxt::AssertConstEvaluation(ref value)) => {
+                PrustiError::internal(
+                    format!(
+                        "the encoding of the const fn call might not agree with its \
+                        compile-time value {}",
+                        value,
+                    ),
+                    error_span
+                ).set_failing_assertion(opt_cause_span)
+                    .set_help("This is a bug in the encoding of the const fn.")
+            }
+
             ("assert.failed:assertion.false", ErrorCtxt::AbortTerminator) => {
                 PrustiError::verification("statement might abort", error_span)
                     .set_failing_assertion(opt_cause_span)
@@ -288,7 +506,10 @@ impl<'tcx> ErrorManager<'tcx>
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::ExhaleMethodPrecondition) => {
-                PrustiError::verification("precondition might not hold.", error_span)
+                PrustiError::verification(
+                    opt_spec_message.unwrap_or("precondition might not hold."),
+                    error_span
+                )
                     .set_failing_assertion(opt_cause_span)
             }
 
@@ -299,8 +520,32 @@ impl<'tcx> ErrorManager<'tcx>
                 ).set_failing_assertion(opt_cause_span)
             }
 
+            ("assert.failed:assertion.false", ErrorCtxt::AssertEnoughCredits(ref kind)) => {
+                PrustiError::verification(
+                    format!("there might not be enough credits of kind `{}`.", kind),
+                    error_span
+                ).set_failing_assertion(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertDropPrecondition) => {
+                PrustiError::verification(
+                    "the precondition of `drop` might not hold when the value is dropped.",
+                    error_span
+                ).set_failing_assertion(opt_cause_span)
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertSpawnedThreadPrecondition) => {
+                PrustiError::verification(
+                    "the precondition of the spawned thread might not hold.",
+                    error_span
+                ).set_failing_assertion(opt_cause_span)
+            }
+
             ("assert.failed:assertion.false", ErrorCtxt::ExhaleMethodPostcondition) => {
-                PrustiError::verification("postcondition might not hold.", error_span)
+                PrustiError::verification(
+                    opt_spec_message.unwrap_or("postcondition might not hold."),
+                    error_span
+                )
                     .push_primary_span(opt_cause_span)
             }
 
@@ -424,6 +669,20 @@ impl<'tcx> ErrorManager<'tcx>
                 ).set_failing_assertion(opt_cause_span)
             },
 
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PureFunctionTermination,
+            ) => {
+                PrustiError::verification(
+                    "recursive call in pure function might not terminate",
+                    error_span
+                ).set_help(
+                    "the integer arguments of a recursive call of a pure function have to \
+                    decrease lexicographically and stay non-negative, and pure functions \
+                    cannot be mutually recursive"
+                )
+            }
+
             ("application.precondition:assertion.false", ErrorCtxt::TypeCast) => {
                 PrustiError::verification(
                     "value might not fit into the target type.",
@@ -437,7 +696,10 @@ impl<'tcx> ErrorManager<'tcx>
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPostcondition) => {
-                PrustiError::verification(format!("postcondition might not hold."), error_span)
+                PrustiError::verification(
+                    opt_spec_message.unwrap_or("postcondition might not hold."),
+                    error_span
+                )
                     .push_primary_span(opt_cause_span)
             }
 
@@ -518,3 +780,17 @@ impl<'tcx> ErrorManager<'tcx>
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorCtxt;
+
+    #[test]
+    fn overflow_obligations_are_classified_by_assert_kind() {
+        let overflow = ErrorCtxt::AssertOverflowTerminator("attempt to add with overflow".to_string());
+        assert_eq!(overflow.obligation_kind(), Some("overflow"));
+        // The message of other assertions does not matter.
+        let bounds = ErrorCtxt::AssertTerminator("index out of bounds: overflow".to_string());
+        assert_eq!(bounds.obligation_kind(), Some("assertion"));
+    }
+}
@@ -7,6 +7,7 @@
 pub use self::conversions::*;
 pub use self::spanned_encoding_error::*;
 pub use self::error_manager::*;
+pub use self::error_code::*;
 pub use self::encoding_error::*;
 pub use self::encoding_error_kind::*;
 pub use self::with_span::*;
@@ -14,6 +15,7 @@ pub use self::with_span::*;
 mod conversions;
 mod spanned_encoding_error;
 mod error_manager;
+mod error_code;
 mod encoding_error;
 mod encoding_error_kind;
 mod with_span;
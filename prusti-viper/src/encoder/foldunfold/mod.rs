@@ -60,6 +60,15 @@ pub enum FoldUnfoldError {
     FailedToRemovePred(vir::Expr),
     /// The algorithm tried to lookup a never-seen-before label
     MissingLabel(String),
+    /// The algorithm failed to obtain a permission that was dropped where
+    /// branches join, because only some of the branches still owned it.
+    PermissionLost {
+        missing_perm: Perm,
+        /// The MIR blocks of the branches that still owned the permission.
+        kept_in: Vec<mir::BasicBlock>,
+        /// The MIR blocks of the branches in which the permission was lost.
+        lost_in: Vec<mir::BasicBlock>,
+    },
 }
 
 impl From<PermAmountError> for FoldUnfoldError {
@@ -169,15 +178,16 @@ pub fn add_fold_unfold<'p, 'v: 'p, 'tcx: 'v>(
         old_expr_collector.old_exprs
     };
     let initial_pctxt = PathCtxt::new(cfg_vars, &predicates, &old_exprs);
-    FoldUnfold::new(
+    let mut fold_unfold = FoldUnfold::new(
         encoder,
         initial_pctxt,
         &cfg,
         borrow_locations,
         cfg_map,
         method_pos,
-    )
-    .replace_cfg(&cfg)
+    );
+    fold_unfold.replace_cfg(&cfg)
+        .map_err(|error| fold_unfold.explain_lost_permission(error))
 }
 
 #[derive(Clone)]
@@ -196,6 +206,9 @@ struct FoldUnfold<'p, 'v: 'p, 'tcx: 'v> {
     borrow_locations: &'p HashMap<vir::borrows::Borrow, mir::Location>,
     cfg_map: &'p HashMap<mir::BasicBlock, HashSet<CfgBlockIndex>>,
     method_pos: vir::Position,
+    /// The permissions dropped on the edges that lead to a join point,
+    /// as `(source block, join block, permission)`.
+    dropped_at_join: Vec<(CfgBlockIndex, CfgBlockIndex, Perm)>,
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> FoldUnfold<'p, 'v, 'tcx> {
@@ -218,9 +231,49 @@ impl<'p, 'v: 'p, 'tcx: 'v> FoldUnfold<'p, 'v, 'tcx> {
             borrow_locations,
             cfg_map,
             method_pos,
+            dropped_at_join: vec![],
         }
     }
 
+    /// If `error` is caused by a permission that was dropped at a join point,
+    /// report the branches in which it was still owned and the branches in
+    /// which it was lost.
+    fn explain_lost_permission(&self, error: FoldUnfoldError) -> FoldUnfoldError {
+        let missing_perm = match error {
+            FoldUnfoldError::FailedToObtain(ref perm) => perm.clone(),
+            _ => return error,
+        };
+        let missing_place = missing_perm.get_place();
+        let is_related = |perm: &Perm| {
+            missing_place.has_prefix(perm.get_place()) || perm.get_place().has_prefix(missing_place)
+        };
+        let join = match self.dropped_at_join.iter().rev().find(|(_, _, perm)| is_related(perm)) {
+            Some(&(_, join, _)) => join,
+            None => return error,
+        };
+        let (kept_blocks, lost_blocks): (Vec<_>, Vec<_>) = self.cfg.get_preceding(join)
+            .into_iter()
+            .partition(|src| self.dropped_at_join.iter().any(|(dropped_src, dropped_join, perm)| {
+                dropped_src.weak_eq(src) && dropped_join.weak_eq(&join) && is_related(perm)
+            }));
+        let to_mir_blocks = |cfg_blocks: Vec<CfgBlockIndex>| -> Vec<mir::BasicBlock> {
+            let mut mir_blocks: Vec<_> = self.cfg_map.iter()
+                .filter(|(_, blocks)| {
+                    blocks.iter().any(|block| cfg_blocks.iter().any(|b| b.weak_eq(block)))
+                })
+                .map(|(&bb, _)| bb)
+                .collect();
+            mir_blocks.sort();
+            mir_blocks
+        };
+        let kept_in = to_mir_blocks(kept_blocks);
+        let lost_in = to_mir_blocks(lost_blocks);
+        if kept_in.is_empty() || lost_in.is_empty() {
+            return error;
+        }
+        FoldUnfoldError::PermissionLost { missing_perm, kept_in, lost_in }
+    }
+
     fn replace_expr(
         &self,
         expr: &vir::Expr,
@@ -457,6 +510,54 @@ impl<'p, 'v: 'p, 'tcx: 'v> FoldUnfold<'p, 'v, 'tcx> {
     }
 }
 
+impl<'p, 'v: 'p, 'tcx: 'v> FoldUnfold<'p, 'v, 'tcx> {
+    /// Join the given path contexts, which are expected to have pairwise
+    /// different states, by recursively joining two halves of them.
+    fn join_path_ctxts(
+        &mut self,
+        bcs: Vec<&PathCtxt<'p>>,
+    ) -> Result<(Vec<ActionVec>, PathCtxt<'p>), FoldUnfoldError> {
+        trace!("[enter] join_path_ctxts(..{})", &bcs.len());
+        assert!(bcs.len() > 0);
+        if bcs.len() == 1 {
+            Ok((vec![ActionVec(vec![])], bcs[0].clone()))
+        } else {
+            // Define two subgroups
+            let mid = bcs.len() / 2;
+            let left_pctxts = &bcs[..mid];
+            let right_pctxts = &bcs[mid..];
+
+            // Join the subgroups
+            let (left_actions_vec, mut left_pctxt) = self.join_path_ctxts(left_pctxts.to_vec())?;
+            let (right_actions_vec, right_pctxt) = self.join_path_ctxts(right_pctxts.to_vec())?;
+
+            // Join the recursive calls
+            let (merge_actions_left, merge_actions_right) = left_pctxt.join(right_pctxt)?;
+            let merged_pctxt = left_pctxt;
+
+            let mut branch_actions_vec: Vec<ActionVec> = vec![];
+            for mut left_actions in left_actions_vec {
+                left_actions.0.extend(merge_actions_left.iter().cloned());
+                branch_actions_vec.push(left_actions);
+            }
+            for mut right_actions in right_actions_vec {
+                right_actions.0.extend(merge_actions_right.iter().cloned());
+                branch_actions_vec.push(right_actions);
+            }
+
+            trace!(
+                "[exit] join_path_ctxts(..{}): {}",
+                &bcs.len(),
+                branch_actions_vec
+                    .iter()
+                    .map(|v| format!("[{}]", v.iter().to_sorted_multiline_string()))
+                    .to_string()
+            );
+            Ok((branch_actions_vec, merged_pctxt))
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ActionVec(pub Vec<Action>);
 
@@ -747,9 +848,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> vir::CfgReplacer<PathCtxt<'p>, ActionVec>
                 let (mut join_actions, mut joined_pctxt) = self.prepend_join(
                     vec![&then_pctxt, &else_pctxt])?;
                 else_stmts.extend(self.perform_prejoin_action(
-                    &mut joined_pctxt, curr_block_index, join_actions.remove(1))?);
+                    &mut joined_pctxt, curr_block_index, None, join_actions.remove(1))?);
                 then_stmts.extend(self.perform_prejoin_action(
-                    &mut joined_pctxt, curr_block_index, join_actions.remove(0))?);
+                    &mut joined_pctxt, curr_block_index, None, join_actions.remove(0))?);
                 *pctxt = joined_pctxt;
                 vir::Stmt::If(cond, then_stmts, else_stmts)
             },
@@ -1034,42 +1135,42 @@ impl<'p, 'v: 'p, 'tcx: 'v> vir::CfgReplacer<PathCtxt<'p>, ActionVec>
     ) -> Result<(Vec<ActionVec>, PathCtxt<'p>), Self::Error> {
         trace!("[enter] prepend_join(..{})", &bcs.len());
         assert!(bcs.len() > 0);
-        if bcs.len() == 1 {
-            Ok((vec![ActionVec(vec![])], bcs[0].clone()))
-        } else {
-            // Define two subgroups
-            let mid = bcs.len() / 2;
-            let left_pctxts = &bcs[..mid];
-            let right_pctxts = &bcs[mid..];
-
-            // Join the subgroups
-            let (left_actions_vec, mut left_pctxt) = self.prepend_join(left_pctxts.to_vec())?;
-            let (right_actions_vec, right_pctxt) = self.prepend_join(right_pctxts.to_vec())?;
-
-            // Join the recursive calls
-            let (merge_actions_left, merge_actions_right) = left_pctxt.join(right_pctxt)?;
-            let merged_pctxt = left_pctxt;
-
-            let mut branch_actions_vec: Vec<ActionVec> = vec![];
-            for mut left_actions in left_actions_vec {
-                left_actions.0.extend(merge_actions_left.iter().cloned());
-                branch_actions_vec.push(left_actions);
+        // Many branches (e.g. the arms of a `match` on an enum with many
+        // variants) reach a join point with the same fold-unfold state. Join
+        // each distinct state only once and reuse its actions for all the
+        // branches that share it.
+        let mut representatives: Vec<usize> = vec![];
+        let mut representative_of: Vec<usize> = Vec::with_capacity(bcs.len());
+        for (index, pctxt) in bcs.iter().enumerate() {
+            let found = representatives
+                .iter()
+                .position(|&repr| bcs[repr].state() == pctxt.state());
+            match found {
+                Some(pos) => representative_of.push(pos),
+                None => {
+                    representative_of.push(representatives.len());
+                    representatives.push(index);
+                }
             }
-            for mut right_actions in right_actions_vec {
-                right_actions.0.extend(merge_actions_right.iter().cloned());
-                branch_actions_vec.push(right_actions);
+        }
+        debug!(
+            "prepend_join: {} distinct states out of {} incoming branches",
+            representatives.len(),
+            bcs.len()
+        );
+        let unique_pctxts = representatives.iter().map(|&index| bcs[index]).collect();
+        let (unique_actions_vec, mut merged_pctxt) = self.join_path_ctxts(unique_pctxts)?;
+        for (index, pctxt) in bcs.iter().enumerate() {
+            if !representatives.contains(&index) {
+                merged_pctxt.log_mut().join(pctxt.log().clone())?;
             }
-
-            trace!(
-                "[exit] prepend_join(..{}): {}",
-                &bcs.len(),
-                branch_actions_vec
-                    .iter()
-                    .map(|v| format!("[{}]", v.iter().to_sorted_multiline_string()))
-                    .to_string()
-            );
-            Ok((branch_actions_vec, merged_pctxt))
         }
+        let branch_actions_vec = representative_of
+            .into_iter()
+            .map(|pos| ActionVec(unique_actions_vec[pos].0.clone()))
+            .collect();
+        trace!("[exit] prepend_join(..{})", &bcs.len());
+        Ok((branch_actions_vec, merged_pctxt))
     }
 
     /// Convert actions to statements and log them.
@@ -1077,10 +1178,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> vir::CfgReplacer<PathCtxt<'p>, ActionVec>
         &mut self,
         pctxt: &mut PathCtxt,
         block_index: CfgBlockIndex,
+        edge: Option<(CfgBlockIndex, CfgBlockIndex)>,
         actions: ActionVec,
     ) -> Result<Vec<vir::Stmt>, Self::Error> {
         let mut stmts = Vec::new();
         for action in actions.0 {
+            if let (Some((src, join)), Action::Drop(ref perm, _)) = (edge, &action) {
+                self.dropped_at_join.push((src, join, perm.clone()));
+            }
             stmts.push(action.to_stmt());
             pctxt.log_mut().log_prejoin_action(block_index, action);
         }
@@ -3,6 +3,7 @@ use std::ops::Deref;
 
 use log::*;
 
+use prusti_common::config;
 use prusti_common::report;
 use prusti_common::utils::to_string::ToString;
 use prusti_common::vir;
@@ -28,6 +29,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> FoldUnfold<'p, 'v, 'tcx> {
     ) -> Result<Vec<vir::Stmt>, FoldUnfoldError> {
         trace!("[enter] process_expire_borrows dag=[{:?}]", dag);
 
+        self.dump_dag(dag, surrounding_block_index, new_cfg);
+
         let mut cfg = build_initial_cfg(dag);
 
         let mut final_pctxt: Vec<Option<PathCtxt>> = vec![None; cfg.basic_blocks.len()];
@@ -375,6 +378,32 @@ impl<'p, 'v: 'p, 'tcx: 'v> FoldUnfold<'p, 'v, 'tcx> {
         stmts
     }
 
+    fn dump_dag(&self,
+        dag: &vir::borrows::DAG,
+        surrounding_block_index: vir::CfgBlockIndex,
+        new_cfg: &vir::CfgMethod,
+    ) {
+        if !config::dump_reborrowing_dags() { return; }
+        let source_path = self.encoder.env().source_path();
+        let source_filename = source_path.file_name().unwrap().to_str().unwrap();
+        let method_name = new_cfg.name();
+        report::log::report_with_writer(
+            "graphviz_reborrowing_dag",
+            format!(
+                "{}.{}.{}.{:?}.dot",
+                source_filename,
+                method_name,
+                surrounding_block_index.index(),
+                dag
+            ),
+            |writer| dag.to_graphviz(
+                writer,
+                &format!("Reborrowing DAG of {} expired in block {}",
+                    method_name, surrounding_block_index.index()),
+            ),
+        );
+    }
+
     fn dump_debug_info(&self,
         dag: &vir::borrows::DAG,
         cfg: &borrows::CFG,
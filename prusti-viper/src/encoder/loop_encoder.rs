@@ -17,6 +17,14 @@ pub enum LoopEncoderError {
     LoopInvariantInBranch(BasicBlockIndex),
 }
 
+/// A comparison that holds whenever the body of a loop is entered, inferred
+/// from the guard of the loop. The operands are constants or user variables.
+pub struct InferredLoopGuard<'tcx> {
+    pub op: mir::BinOp,
+    pub left: mir::Operand<'tcx>,
+    pub right: mir::Operand<'tcx>,
+}
+
 pub struct LoopEncoder<'p, 'tcx: 'p> {
     procedure: &'p Procedure<'p, 'tcx>,
     tcx: ty::TyCtxt<'tcx>,
@@ -129,7 +137,14 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
             .any(|def_init_place| utils::is_prefix(place, def_init_place))
     }
 
-    /// Return the block at whose end the loop invariant holds
+    /// Return the block at whose end the loop invariant holds.
+    ///
+    /// A `body_invariant!` in a conditional branch of the loop (e.g. inside
+    /// an `if`) is moved to the end of the last block that is executed in
+    /// every iteration before the branch. When the loop has several
+    /// `body_invariant!`, they all hold at the latest of these blocks. An
+    /// invariant is not moved across a condition that exits the loop,
+    /// because it could no longer rely on the loop guard.
     pub fn get_loop_invariant_block(
         &self,
         loop_head: BasicBlockIndex
@@ -147,26 +162,146 @@ impl<'p, 'tcx: 'p> LoopEncoder<'p, 'tcx> {
             .collect();
 
         let loop_exit_blocks = loop_info.get_loop_exit_blocks(loop_head);
-        let before_invariant_block: BasicBlockIndex = loop_body
-            .iter()
-            .find(|&&bb| {
-                loop_info.get_loop_depth(bb) == loop_depth
-                    && self.mir()[bb].terminator().successors().any(|&succ_bb| {
-                        self.procedure.is_reachable_block(succ_bb)
-                            && self.procedure.is_spec_block(succ_bb)
-                    })
-            })
-            .cloned()
-            .unwrap_or_else(|| loop_exit_blocks.get(0).cloned().unwrap_or(loop_head));
+        let position = |bb: BasicBlockIndex| loop_body.iter().position(|&body_bb| body_bb == bb);
+        let mut before_invariant_block: Option<BasicBlockIndex> = None;
+        for &bb in &loop_body {
+            let leads_to_invariant = loop_info.get_loop_depth(bb) == loop_depth
+                && self.mir()[bb].terminator().successors().any(|&succ_bb| {
+                    self.procedure.is_reachable_block(succ_bb)
+                        && self.procedure.is_spec_block(succ_bb)
+                });
+            if !leads_to_invariant {
+                continue;
+            }
+            let invariant_block = loop_info.get_nonconditional_dominator(loop_head, bb);
+            if invariant_block != bb {
+                let crossed_exit = loop_exit_blocks.iter().any(|&exit_bb| {
+                    position(exit_bb) >= position(invariant_block) && position(exit_bb) < position(bb)
+                });
+                if crossed_exit {
+                    debug!(
+                        "{:?} is conditional branch in loop {:?} after an exit",
+                        bb, loop_head
+                    );
+                    return Err(LoopEncoderError::LoopInvariantInBranch(loop_head));
+                }
+                debug!(
+                    "The invariant after the conditional block {:?} is moved to {:?}",
+                    bb, invariant_block
+                );
+            }
+            if before_invariant_block.map_or(true, |curr_bb| position(curr_bb) < position(invariant_block)) {
+                before_invariant_block = Some(invariant_block);
+            }
+        }
 
-        if loop_info.is_conditional_branch(loop_head, before_invariant_block) {
-            debug!(
-                "{:?} is conditional branch in loop {:?}",
-                before_invariant_block, loop_head
-            );
-            return Err(LoopEncoderError::LoopInvariantInBranch(loop_head));
+        Ok(before_invariant_block
+            .unwrap_or_else(|| loop_exit_blocks.get(0).cloned().unwrap_or(loop_head)))
+    }
+
+    /// Infer an invariant for a loop whose invariant block is the guard of the
+    /// loop, i.e. a loop without `body_invariant!`. There the comparison
+    /// evaluated by the guard holds both on entry and after each iteration,
+    /// but it is lost when the modified variables are havocked. Only
+    /// comparisons between constants and user variables that are not modified
+    /// by the guard block are inferred.
+    pub fn infer_guard_invariant(
+        &self,
+        loop_head: BasicBlockIndex,
+        loop_inv_block: BasicBlockIndex,
+    ) -> Option<InferredLoopGuard<'tcx>> {
+        let mir = self.mir();
+        let block_data = &mir[loop_inv_block];
+        let (cond_local, targets) = match block_data.terminator().kind {
+            mir::TerminatorKind::SwitchInt { ref discr, switch_ty, ref targets }
+                if switch_ty.is_bool() => (discr.place()?.as_local()?, targets),
+            _ => return None,
+        };
+        let loop_body = self.loops().get_loop_body(loop_head);
+        let (false_value, false_target) = targets.iter().next()?;
+        if false_value != 0 || targets.iter().count() != 1 {
+            return None;
+        }
+        let guard_holds = match (
+            loop_body.contains(&targets.otherwise()),
+            loop_body.contains(&false_target),
+        ) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => return None,
+        };
+
+        let assigned_local = |stmt: &mir::Statement<'tcx>| match stmt.kind {
+            mir::StatementKind::Assign(box (ref place, _)) => place.as_local(),
+            _ => None,
+        };
+        let (cond_index, op, left, right) = block_data.statements
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, stmt)| assigned_local(stmt) == Some(cond_local))
+            .and_then(|(index, stmt)| match stmt.kind {
+                mir::StatementKind::Assign(box (_, mir::Rvalue::BinaryOp(op, box (ref l, ref r)))) => {
+                    Some((index, op, l, r))
+                }
+                _ => None,
+            })?;
+        if !matches!(
+            op,
+            mir::BinOp::Lt | mir::BinOp::Le | mir::BinOp::Gt |
+            mir::BinOp::Ge | mir::BinOp::Eq | mir::BinOp::Ne
+        ) {
+            return None;
         }
 
-        Ok(before_invariant_block)
+        // Replace the temporaries that hold copies of user variables.
+        let resolve = |operand: &mir::Operand<'tcx>| -> Option<mir::Operand<'tcx>> {
+            let local = match operand {
+                mir::Operand::Constant(_) => return Some(operand.clone()),
+                mir::Operand::Copy(place) | mir::Operand::Move(place) => place.as_local()?,
+            };
+            let source = if mir.local_decls[local].is_user_variable() {
+                local
+            } else {
+                block_data.statements[..cond_index]
+                    .iter()
+                    .rev()
+                    .find(|stmt| assigned_local(stmt) == Some(local))
+                    .and_then(|stmt| match stmt.kind {
+                        mir::StatementKind::Assign(box (
+                            _,
+                            mir::Rvalue::Use(mir::Operand::Copy(ref source)),
+                        )) => source.as_local(),
+                        _ => None,
+                    })?
+            };
+            let source_ty = mir.local_decls[source].ty;
+            let is_supported_ty = source_ty.is_integral() || source_ty.is_bool() || source_ty.is_char();
+            let is_modified = block_data.statements
+                .iter()
+                .any(|stmt| assigned_local(stmt) == Some(source));
+            if mir.local_decls[source].is_user_variable() && is_supported_ty && !is_modified {
+                Some(mir::Operand::Copy(source.into()))
+            } else {
+                None
+            }
+        };
+        let left = resolve(left)?;
+        let right = resolve(right)?;
+
+        let op = if guard_holds {
+            op
+        } else {
+            match op {
+                mir::BinOp::Lt => mir::BinOp::Ge,
+                mir::BinOp::Le => mir::BinOp::Gt,
+                mir::BinOp::Gt => mir::BinOp::Le,
+                mir::BinOp::Ge => mir::BinOp::Lt,
+                mir::BinOp::Eq => mir::BinOp::Ne,
+                mir::BinOp::Ne => mir::BinOp::Eq,
+                _ => unreachable!(),
+            }
+        };
+        Some(InferredLoopGuard { op, left, right })
     }
 }
@@ -158,7 +158,7 @@ impl MemoryEqEncoder {
                     elems
                 )?)
             }
-            ty::TyKind::Param(_) => {
+            ty::TyKind::Param(_) | ty::TyKind::Opaque(..) => {
                 None
             }
             ty::TyKind::Ref(..) => {
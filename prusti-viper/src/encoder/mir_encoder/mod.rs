@@ -26,6 +26,7 @@ use std::{
     convert::TryInto,
 };
 use prusti_interface::environment::mir_utils::MirPlace;
+use prusti_interface::utils::read_prusti_attr_arg;
 
 use downcast_detector::detect_downcasts;
 pub use place_encoding::PlaceEncoding;
@@ -511,6 +512,22 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// Should overflows be checked in this procedure? The global setting can be
+    /// overridden with `#[prusti::overflow_checks(on)]` or
+    /// `#[prusti::overflow_checks(off)]`.
+    pub fn check_overflows(&self) -> EncodingResult<bool> {
+        let attrs = self.encoder.env().tcx().get_attrs(self.def_id);
+        match read_prusti_attr_arg("overflow_checks", attrs).as_deref() {
+            Some("on") => Ok(true),
+            Some("off") => Ok(false),
+            Some(value) => Err(EncodingError::incorrect(format!(
+                "`#[prusti::overflow_checks(..)]` expects `on` or `off`, not `{}`",
+                value,
+            ))),
+            None => Ok(config::check_overflows()),
+        }
+    }
+
     /// Returns `true` is an overflow happened
     pub fn encode_bin_op_check(
         &self,
@@ -519,7 +536,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
         right: vir::Expr,
         ty: ty::Ty<'tcx>,
     ) -> EncodingResult<vir::Expr> {
-        if !op.is_checkable() || !config::check_overflows() {
+        if !op.is_checkable() || !self.check_overflows()? {
             Ok(false.into())
         } else {
             let result = self.encode_bin_op_expr(op, left, right, ty)?;
@@ -659,7 +676,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> MirEncoder<'p, 'v, 'tcx> {
             | (ty::TyKind::Uint(_), ty::TyKind::Uint(_))
             => {
                 let encoded_operand = self.encode_operand_expr(operand).with_span(span)?;
-                if config::check_overflows() {
+                if self.check_overflows().with_span(span)? {
                     // Check the cast
                     let function_name = self.encoder.encode_cast_function_use(src_ty, dst_ty)
                         .with_span(span)?;
@@ -5,7 +5,8 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::encoder::borrows::ProcedureContract;
-use crate::encoder::builtin_encoder::{BuiltinMethodKind, BuiltinFunctionKind};
+use crate::encoder::builtin_encoder::{ArithLemma, BuiltinMethodKind, BuiltinFunctionKind};
+use crate::encoder::builtin_encoder::{credit_kind_ident, CREDITS_FUNCTION_PREFIX};
 use crate::encoder::errors::{
     SpannedEncodingError, ErrorCtxt, PanicCause, EncodingError, WithSpan,
     EncodingResult, SpannedEncodingResult
@@ -22,12 +23,12 @@ use crate::encoder::snapshot_spec_patcher::SnapshotSpecPatcher;
 use prusti_common::{
     config,
     report::log,
-    utils::to_string::ToString,
+    utils::{memory, to_string::ToString},
     vir,
     vir::{
         borrows::Borrow,
         collect_assigned_vars,
-        fixes::fix_ghost_vars,
+        fixes::{fix_ghost_vars, fix_package_footprints},
         CfgBlockIndex, Expr, ExprIterator, Successor, Type,
     },
 };
@@ -43,11 +44,14 @@ use prusti_interface::{
     },
 };
 use prusti_interface::utils;
+use prusti_interface::PrustiError;
 // use prusti_common::report::log;
 // use prusti_interface::specifications::*;
 use rustc_middle::mir::Mutability;
 use rustc_middle::mir;
 use rustc_middle::mir::{TerminatorKind, AssertKind};
+use rustc_middle::mir::interpret::{ConstValue, GlobalAlloc, Scalar};
+use rustc_hir::def_id::DefId;
 use rustc_middle::ty;
 use rustc_middle::ty::layout;
 use rustc_target::abi::Integer;
@@ -55,11 +59,12 @@ use rustc_middle::ty::layout::IntegerExt;
 use rustc_index::vec::Idx;
 // use rustc_data_structures::indexed_vec::Idx;
 // use std;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use rustc_attr::IntType::SignedInt;
 // use syntax::codemap::{MultiSpan, Span};
-use rustc_span::{MultiSpan, Span};
+use rustc_span::{DUMMY_SP, MultiSpan, Span, sym};
 use prusti_interface::specs::typed;
 use ::log::{trace, debug};
 use std::borrow::Borrow as StdBorrow;
@@ -113,6 +118,20 @@ pub struct ProcedureEncoder<'p, 'v: 'p, 'tcx: 'v> {
     old_ghost_vars: HashMap<String, vir::Type>,
     /// For each loop head, the block at whose end the loop invariant holds
     cached_loop_invariant_block: HashMap<BasicBlockIndex, BasicBlockIndex>,
+    /// The resident memory (in MB) when the encoding started, if it is
+    /// limited by `config::encoding_memory_limit()`.
+    memory_at_start: Option<usize>,
+    /// The ghost variables that count the credits of each kind.
+    credit_counters: BTreeMap<String, vir::LocalVar>,
+    /// The credits that the procedure obtains from its precondition.
+    precondition_credits: Vec<(String, vir::Expr)>,
+    /// The locals holding a slice obtained by unsizing a reference to an
+    /// array, with the place of that reference.
+    unsized_arrays: HashMap<mir::Local, mir::Place<'tcx>>,
+    /// The locals holding the `JoinHandle` of a spawned thread, with the
+    /// closure run by the thread, the local of the closure and the label of
+    /// the state at the spawn.
+    spawned_threads: HashMap<mir::Local, (ProcedureDefId, Local, String)>,
 }
 
 impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
@@ -129,6 +148,18 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         let init_info = InitInfo::new(mir, tcx, def_id, &mir_encoder)
             .with_span(procedure.get_span())?;
 
+        let item_flag = |name: &str, default: bool| -> SpannedEncodingResult<bool> {
+            encoder.env().get_item_config_flag(def_id, name)
+                .map(|flag| flag.unwrap_or(default))
+                .map_err(|value| SpannedEncodingError::incorrect(
+                    format!("`#[prusti::{}(..)]` expects `true` or `false`, not `{}`", name, value),
+                    procedure.get_span(),
+                ))
+        };
+        let check_panics = item_flag("check_panics", config::check_panics())?;
+        let check_foldunfold_state =
+            item_flag("check_foldunfold_state", config::check_foldunfold_state())?;
+
         let cfg_method = vir::CfgMethod::new(
             // method name
             encoder.encode_item_name(def_id),
@@ -152,8 +183,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             loop_encoder: LoopEncoder::new(procedure, tcx),
             auxiliary_local_vars: HashMap::new(),
             mir_encoder,
-            check_panics: config::check_panics(),
-            check_foldunfold_state: config::check_foldunfold_state(),
+            check_panics,
+            check_foldunfold_state,
             polonius_info: None,
             procedure_contract: None,
             label_after_location: HashMap::new(),
@@ -166,9 +197,22 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             old_to_ghost_var: HashMap::new(),
             old_ghost_vars: HashMap::new(),
             cached_loop_invariant_block: HashMap::new(),
+            memory_at_start: if config::encoding_memory_limit() > 0 {
+                memory::resident_memory_mb()
+            } else {
+                None
+            },
+            credit_counters: BTreeMap::new(),
+            precondition_credits: vec![],
+            unsized_arrays: HashMap::new(),
+            spawned_threads: HashMap::new(),
         })
     }
 
+    fn check_memory_usage(&self) -> SpannedEncodingResult<()> {
+        check_encoding_memory(self.memory_at_start, self.mir.span)
+    }
+
     fn translate_polonius_error(&self, error: PoloniusInfoError) -> SpannedEncodingError {
         match error {
             PoloniusInfoError::UnsupportedLoanInLoop {
@@ -433,10 +477,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
         // Encode preconditions
         self.encode_preconditions(start_cfg_block, precondition_weakening)?;
+        let vacuity_check_pos = self.register_vacuity_check();
 
         // Encode postcondition
         self.encode_postconditions(return_cfg_block, postcondition_strengthening)?;
 
+        // Initialise the credit counters
+        self.encode_credits_initialisation(start_cfg_block);
+
         let local_vars: Vec<_> = self
             .locals
             .iter()
@@ -468,6 +516,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
         self.encoder
             .log_vir_program_before_foldunfold(self.cfg_method.to_string());
+        prusti_common::report::log::report_vir_pass(&method_name, 1, "cfg", &self.cfg_method);
 
         // Dump initial CFG
         if config::dump_debug_info() {
@@ -485,10 +534,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             .iter()
             .map(|(loan, location)| (loan.into(), *location))
             .collect();
+        self.check_memory_usage()?;
         let method_pos = self
             .encoder
             .error_manager()
             .register(self.mir.span, ErrorCtxt::Unexpected);
+        let memory_at_start = self.memory_at_start;
+        let mir = self.mir;
+        let mir_encoder = &self.mir_encoder;
         let method_with_fold_unfold = foldunfold::add_fold_unfold(
             self.encoder,
             self.cfg_method,
@@ -496,19 +549,38 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             &self.cfg_blocks_map,
             method_pos,
         )
-        .map_err(|foldunfold_error| {
-            SpannedEncodingError::internal(
+        .map_err(|foldunfold_error| match foldunfold_error {
+            foldunfold::FoldUnfoldError::PermissionLost { missing_perm, kept_in, lost_in } => {
+                Self::encode_lost_permission_error(
+                    mir, mir_encoder, missing_perm.get_place(), &kept_in, &lost_in
+                )
+            }
+            _ => SpannedEncodingError::internal(
                 format!(
                     "generating fold-unfold Viper statements failed ({:?})",
                     foldunfold_error
                 ),
                 mir_span,
-            )
+            ),
         })?;
 
+        // The method is still to be converted to a Java object.
+        check_encoding_memory(memory_at_start, mir_span)?;
+
         // Fix variable declarations.
         let final_method = fix_ghost_vars(method_with_fold_unfold);
 
+        // Make the footprints of statements inside package statements explicit.
+        let final_method = fix_package_footprints(final_method);
+        prusti_common::report::log::report_vir_pass(&method_name, 2, "foldunfold", &final_method);
+
+        // The vacuity check is verified separately, since its assertion is
+        // expected to fail.
+        if let Some(pos) = vacuity_check_pos {
+            let vacuity_check = encode_vacuity_check(&final_method, start_cfg_block, pos);
+            self.encoder.register_vacuity_check(self.proc_def_id, vacuity_check);
+        }
+
         // Dump final CFG
         if config::dump_debug_info() {
             prusti_common::report::log::report_with_writer(
@@ -521,6 +593,45 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(final_method)
     }
 
+    /// Report that the ownership of `place` is lost where branches join,
+    /// because only the branches `kept_in` still own it.
+    fn encode_lost_permission_error(
+        mir: &mir::Body<'tcx>,
+        mir_encoder: &MirEncoder<'p, 'v, 'tcx>,
+        place: &vir::Expr,
+        kept_in: &[BasicBlockIndex],
+        lost_in: &[BasicBlockIndex],
+    ) -> SpannedEncodingError {
+        let base_name = place.get_base().name;
+        let var_name = mir.var_debug_info.iter()
+            .find_map(|info| match info.value {
+                mir::VarDebugInfoContents::Place(var_place)
+                    if format!("{:?}", var_place.local) == base_name => Some(info.name.to_string()),
+                _ => None,
+            });
+        let message = match var_name {
+            Some(name) => format!(
+                "the ownership of `{}` (or of a part of it) is lost where the branches join",
+                name
+            ),
+            None => "the ownership of a value is lost where the branches join".to_string(),
+        };
+        let lost_spans: Vec<_> = lost_in.iter()
+            .map(|&bb| mir_encoder.get_span_of_basic_block(bb))
+            .collect();
+        let mut span = MultiSpan::from_spans(lost_spans.clone());
+        for lost_span in lost_spans {
+            span.push_span_label(lost_span, "ownership lost here".to_string());
+        }
+        for &bb in kept_in {
+            span.push_span_label(
+                mir_encoder.get_span_of_basic_block(bb),
+                "still owned in this branch".to_string(),
+            );
+        }
+        SpannedEncodingError::unsupported(message, span)
+    }
+
     /// Encodes a topologically ordered group of blocks.
     ///
     /// Returns:
@@ -918,6 +1029,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         return_block: CfgBlockIndex,
     ) -> SpannedEncodingResult<(CfgBlockIndex, Vec<(CfgBlockIndex, BasicBlockIndex)>)> {
         debug_assert!(!self.procedure.is_spec_block(bbi));
+        self.check_memory_usage()?;
 
         let curr_block = self.cfg_method.add_block(
             &format!("{}{:?}", label_prefix, bbi),
@@ -1145,6 +1257,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             | mir::StatementKind::Coverage(..)
             | mir::StatementKind::Nop => vec![],
 
+            // Shallow borrows are created when lowering the guards of a
+            // `match`, only to prevent the guards from modifying the matched
+            // place. They are never dereferenced, so we can skip them.
+            mir::StatementKind::Assign(
+                box (_, mir::Rvalue::Ref(_, mir::BorrowKind::Shallow, _))
+            ) => vec![],
+
             mir::StatementKind::Assign(box (ref lhs, ref rhs)) => {
                 // FIXME: the following line will panic if attempting to encode unsupported types.
                 let (encoded_lhs, pre_stmts, ty, _) = self.encode_place(lhs).unwrap();
@@ -1226,6 +1345,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             stmt.source_info.span,
                         )?
                     }
+                    &mir::Rvalue::Cast(
+                        mir::CastKind::Pointer(ty::adjustment::PointerCast::Unsize),
+                        ref operand,
+                        dst_ty,
+                    ) => {
+                        self.encode_unsize_array_ref(lhs, operand, dst_ty, stmt.source_info.span)?
+                    }
                     &mir::Rvalue::Len(ref place) => {
                         self.encode_assign_array_len(
                             encoded_lhs,
@@ -1784,7 +1910,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
                         ref x => unreachable!("{:?}", x),
                     };
-                    cfg_targets.push((viper_guard, target))
+                    // Or-patterns such as `1 | 2 | 3` jump to the same block for
+                    // several values, so we encode the arm once with a disjunction.
+                    if let Some(entry) = cfg_targets.iter_mut().find(|(_, t)| *t == target) {
+                        entry.0 = vir::Expr::or(entry.0.clone(), viper_guard);
+                    } else {
+                        cfg_targets.push((viper_guard, target))
+                    }
                 }
                 let mut default_target = targets.otherwise();
                 let mut kill_default_target = false;
@@ -1860,7 +1992,12 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 (stmts, MirSuccessor::Kill)
             }
 
-            TerminatorKind::Drop { target, .. } => (stmts, MirSuccessor::Goto(target)),
+            TerminatorKind::Drop { target, ref place, .. } => {
+                if config::check_drop_obligations() {
+                    stmts.extend(self.encode_drop_obligation(place, location, term.source_info.span)?);
+                }
+                (stmts, MirSuccessor::Goto(target))
+            }
 
             TerminatorKind::FalseEdge { real_target, .. } => {
                 (stmts, MirSuccessor::Goto(real_target))
@@ -1901,10 +2038,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 ..
             } => {
                 if let ty::TyKind::FnDef(def_id, substs) = ty.kind() {
+                    // Operators on user-defined types use the contract of
+                    // the operator implementation.
+                    let (def_id, substs) = self.encoder
+                        .resolve_operator_impl(self.proc_def_id, *def_id, *substs)
+                        .unwrap_or((*def_id, *substs));
                     let self_ty = {
                         // If we are calling a trait method on a struct, self_ty
                         // is the struct.
-                        let generics = self.encoder.env().tcx().generics_of(*def_id);
+                        let generics = self.encoder.env().tcx().generics_of(def_id);
                         if generics.has_self {
                             Some(substs.type_at(0))
                         } else {
@@ -1912,7 +2054,6 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                         }
                     };
 
-                    let def_id = *def_id;
                     let full_func_proc_name: &str =
                         &self.encoder.env().tcx().def_path_str(def_id);
                         // &self.encoder.env().tcx().absolute_item_path_str(def_id);
@@ -2007,6 +2148,53 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             );
                         }
 
+                        "std::clone::Clone::clone" |
+                        "core::clone::Clone::clone"
+                            if self.has_structural_clone(substs.type_at(0)) => {
+                            stmts.extend(self.encode_impure_function_call(
+                                location,
+                                term.source_info.span,
+                                args,
+                                destination,
+                                def_id,
+                                self_ty,
+                            )?);
+                            stmts.extend(self.encode_derived_clone_postcondition(
+                                location,
+                                term.source_info.span,
+                                &args[0],
+                                destination,
+                                substs.type_at(0),
+                            )?);
+                        }
+
+                        "std::default::Default::default" |
+                        "core::default::Default::default"
+                            if self.is_derived_call(
+                                self.encoder.env().tcx().get_diagnostic_item(sym::Default),
+                                substs.type_at(0),
+                            )
+                        => {
+                            stmts.extend(self.encode_impure_function_call(
+                                location,
+                                term.source_info.span,
+                                args,
+                                destination,
+                                def_id,
+                                self_ty,
+                            )?);
+                            if let Some((target, _)) = destination {
+                                let (encoded_target, pre_stmts, _, _) = self.encode_place(target)
+                                    .with_span(term.source_info.span)?;
+                                stmts.extend(pre_stmts);
+                                let default_value = self.encode_derived_default_value(
+                                    encoded_target,
+                                    substs.type_at(0),
+                                ).with_span(term.source_info.span)?;
+                                stmts.push(vir::Stmt::Inhale(default_value));
+                            }
+                        }
+
                         "std::cmp::PartialEq::eq" |
                         "core::cmp::PartialEq::eq"
                             if args.len() == 2 &&
@@ -2047,7 +2235,115 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             );
                         }
 
-                        "std::ops::Fn::call" => {
+                        "std::option::Option::<T>::unwrap" |
+                        "std::option::Option::<T>::expect" |
+                        "std::result::Result::<T, E>::unwrap" |
+                        "std::result::Result::<T, E>::expect"
+                            if config::panic_freedom() && !self.encoder.is_pure(def_id)
+                        => {
+                            // The callee panics unless the receiver is `Some`/`Ok`.
+                            stmts.extend(self.encode_unwrap_obligation(
+                                term.source_info.span,
+                                &args[0],
+                            )?);
+                            stmts.extend(self.encode_impure_function_call(
+                                location,
+                                term.source_info.span,
+                                args,
+                                destination,
+                                def_id,
+                                self_ty,
+                            )?);
+                        }
+
+                        "std::thread::spawn" => {
+                            // The spawned closure has to be callable at the spawn point,
+                            // afterwards its ownership is transferred to the new thread.
+                            assert_eq!(args.len(), 1);
+                            // The postcondition of the closure, which is assumed
+                            // at the join, refers to the state at the spawn.
+                            let spawn_label = self.cfg_method.get_fresh_label_name();
+                            stmts.push(vir::Stmt::Label(spawn_label.clone()));
+                            self.warn_about_unchecked_send_types(
+                                term.source_info.span,
+                                &args[0],
+                            );
+                            stmts.extend(self.encode_spawned_closure_precondition(
+                                term.source_info.span,
+                                &args[0],
+                            )?);
+                            stmts.extend(self.encode_impure_function_call(
+                                location,
+                                term.source_info.span,
+                                args,
+                                destination,
+                                def_id,
+                                self_ty,
+                            )?);
+                            self.register_spawned_thread(&args[0], destination, spawn_label);
+                        }
+
+                        "std::thread::JoinHandle::<T>::join" => {
+                            stmts.extend(self.encode_impure_function_call(
+                                location,
+                                term.source_info.span,
+                                args,
+                                destination,
+                                def_id,
+                                self_ty,
+                            )?);
+                            stmts.extend(self.encode_joined_thread_postcondition(
+                                term.source_info.span,
+                                &args[0],
+                                destination,
+                            )?);
+                        }
+
+                        "prusti_contracts::obtain_credits" |
+                        "prusti_contracts::consume_credits" => {
+                            assert_eq!(args.len(), 1);
+                            let kind = credit_kind_ident(
+                                &self.encoder.encode_credit_kind(substs.type_at(0))
+                            );
+                            let amount = self.mir_encoder.encode_operand_expr(&args[0])
+                                .with_span(term.source_info.span)?;
+                            let credits = vec![(kind, amount)];
+                            if full_func_proc_name == "prusti_contracts::obtain_credits" {
+                                stmts.extend(self.encode_credits_gain(
+                                    location,
+                                    term.source_info.span,
+                                    credits,
+                                )?);
+                            } else {
+                                stmts.extend(self.encode_credits_loss(
+                                    location,
+                                    term.source_info.span,
+                                    credits,
+                                )?);
+                            }
+                        }
+
+                        "core::slice::<impl [T]>::copy_from_slice" |
+                        "core::slice::<impl [T]>::clone_from_slice" => {
+                            stmts.extend(self.encode_slice_copy(
+                                full_func_proc_name,
+                                args,
+                                term.source_info.span,
+                            )?);
+                        }
+
+                        lemma_name if lemma_name.starts_with("prusti_contracts::lemmas::") => {
+                            // An explicit instantiation of an arithmetic lemma
+                            // with `prusti_use_lemma!`.
+                            stmts.extend(self.encode_arith_lemma_use(
+                                &lemma_name["prusti_contracts::lemmas::".len()..],
+                                args,
+                                term.source_info.span,
+                            )?);
+                        }
+
+                        "std::ops::Fn::call" |
+                        "std::ops::FnMut::call_mut" => {
                             let cl_type: ty::Ty = substs[0].expect_ty();
                             match cl_type.kind() {
                                 ty::TyKind::Closure(cl_def_id, _) => {
@@ -2071,6 +2367,18 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                             }
                         }
 
+                        _ if config::havoc_foreign_calls()
+                            && self.encoder.env().tcx().is_foreign_item(def_id)
+                            && self.encoder.get_procedure_specs(def_id).is_none() => {
+                            stmts.extend(
+                                self.encode_havoc_foreign_call(
+                                    term.source_info.span,
+                                    args,
+                                    destination,
+                                )?
+                            );
+                        }
+
                         _ => {
                             let is_pure_function = self.encoder.is_pure(def_id);
                             if is_pure_function {
@@ -2167,12 +2475,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
 
                 stmts.push(vir::Stmt::comment(format!("Rust assertion: {}", assert_msg)));
                 if self.check_panics {
+                    let error_ctxt = match msg {
+                        mir::AssertKind::Overflow(..) | mir::AssertKind::OverflowNeg(..) => {
+                            ErrorCtxt::AssertOverflowTerminator(assert_msg)
+                        }
+                        _ => ErrorCtxt::AssertTerminator(assert_msg),
+                    };
                     stmts.push(vir::Stmt::Assert(
                         viper_guard,
-                        self.encoder.error_manager().register(
-                            term.source_info.span,
-                            ErrorCtxt::AssertTerminator(assert_msg),
-                        ),
+                        self.encoder.error_manager().register(term.source_info.span, error_ctxt),
                     ));
                 } else {
                     stmts.push(vir::Stmt::comment("This assertion will not be checked"));
@@ -2190,6 +2501,107 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(result)
     }
 
+    /// Is the implementation of the trait `trait_def_id` for `ty` generated by
+    /// `#[derive(..)]`?
+    fn is_derived_call(&self, trait_def_id: Option<DefId>, ty: ty::Ty<'tcx>) -> bool {
+        trait_def_id.map_or(false, |trait_def_id| {
+            self.encoder.env().has_derived_impl(trait_def_id, ty)
+        })
+    }
+
+    /// Does `clone` return a value equal to the receiver? This is the case
+    /// for a derived `Clone` if the fields of the type are `Copy` or have a
+    /// derived `Clone` with the same property, because a derived `clone`
+    /// clones each field.
+    fn has_structural_clone(&self, ty: ty::Ty<'tcx>) -> bool {
+        let tcx = self.encoder.env().tcx();
+        match ty.kind() {
+            ty::TyKind::Adt(adt_def, substs) if !adt_def.is_box()
+                && self.is_derived_call(tcx.lang_items().clone_trait(), ty) =>
+            {
+                let param_env = tcx.param_env(self.proc_def_id);
+                adt_def.all_fields().all(|field| {
+                    let field_ty = field.ty(tcx, substs);
+                    field_ty.is_copy_modulo_regions(tcx.at(DUMMY_SP), param_env)
+                        || self.has_structural_clone(field_ty)
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// A derived `clone` returns a value that is equal to the receiver.
+    fn encode_derived_clone_postcondition(
+        &mut self,
+        location: mir::Location,
+        call_site_span: Span,
+        receiver: &mir::Operand<'tcx>,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        ty: ty::Ty<'tcx>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let target = match destination {
+            Some((target, _)) => target,
+            None => return Ok(vec![]),
+        };
+        let snapshot = self.encoder.encode_snapshot(&ty).with_span(call_site_span)?;
+        if !snapshot.supports_equality() {
+            return Ok(vec![]);
+        }
+        let encoded_receiver = match self.mir_encoder.encode_operand_place(receiver)
+            .with_span(call_site_span)?
+        {
+            Some(place) => place,
+            None => return Ok(vec![]),
+        };
+        let (encoded_target, mut stmts, _, _) = self.encode_place(target)
+            .with_span(call_site_span)?;
+        // The receiver is a reference, whose target is compared in the pre-state.
+        let pre_label = self.get_label_after_location(location).to_string();
+        let (receiver_target, _, _) = self.mir_encoder
+            .encode_deref(encoded_receiver, self.mir_encoder.get_operand_ty(receiver))
+            .with_span(call_site_span)?;
+        stmts.push(vir::Stmt::Inhale(vir::Expr::eq_cmp(
+            snapshot.snap_call(encoded_target.into()),
+            vir::Expr::labelled_old(&pre_label, snapshot.snap_call(receiver_target)),
+        )));
+        Ok(stmts)
+    }
+
+    /// The value returned by a derived `default`: integers are zero, booleans
+    /// are false and the fields of structs with a derived `Default` are
+    /// their defaults. Nothing is known about the other fields.
+    fn encode_derived_default_value(
+        &self,
+        place: vir::Expr,
+        ty: ty::Ty<'tcx>,
+    ) -> EncodingResult<vir::Expr> {
+        let tcx = self.encoder.env().tcx();
+        Ok(match ty.kind() {
+            ty::TyKind::Int(_) | ty::TyKind::Uint(_) => vir::Expr::eq_cmp(
+                self.encoder.encode_value_expr(place, ty),
+                0.into(),
+            ),
+            ty::TyKind::Bool => vir::Expr::not(self.encoder.encode_value_expr(place, ty)),
+            ty::TyKind::Adt(adt_def, substs) if adt_def.is_struct()
+                && self.is_derived_call(tcx.get_diagnostic_item(sym::Default), ty) =>
+            {
+                let mut conjuncts = vec![];
+                for field in &adt_def.non_enum_variant().fields {
+                    let field_ty = field.ty(tcx, substs);
+                    let encoded_field = self.encoder.encode_struct_field(
+                        &field.ident.as_str(),
+                        field_ty,
+                    )?;
+                    conjuncts.push(
+                        self.encode_derived_default_value(place.clone().field(encoded_field), field_ty)?
+                    );
+                }
+                conjuncts.into_iter().conjoin()
+            }
+            _ => true.into(),
+        })
+    }
+
     fn encode_cmp_function_call(
         &mut self,
         called_def_id: ProcedureDefId,
@@ -2290,6 +2702,542 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// Assert that the receiver of an `unwrap` or `expect` call is a `Some` or an `Ok`.
+    fn encode_unwrap_obligation(
+        &mut self,
+        call_site_span: rustc_span::Span,
+        receiver: &mir::Operand<'tcx>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let receiver_ty = self.mir_encoder.get_operand_ty(receiver);
+        let adt_def = match receiver_ty.kind() {
+            ty::TyKind::Adt(adt_def, _) if adt_def.is_enum() => adt_def,
+            _ => return Ok(vec![]),
+        };
+        let encoded_receiver = match self.mir_encoder.encode_operand_place(receiver)
+            .with_span(call_site_span)?
+        {
+            Some(place) => place,
+            None => return Ok(vec![]),
+        };
+        let tcx = self.encoder.env().tcx();
+        let lang_items = tcx.lang_items();
+        let success_variants = [lang_items.option_some_variant(), lang_items.result_ok_variant()];
+        let success_variant = adt_def.variants.iter_enumerated()
+            .find(|(_, variant)| success_variants.contains(&Some(variant.def_id)))
+            .map(|(variant_index, _)| variant_index);
+        let success_variant = match success_variant {
+            Some(variant_index) => variant_index,
+            None => return Ok(vec![]),
+        };
+        let discr_value: vir::Expr = adt_def
+            .discriminant_for_variant(tcx, success_variant)
+            .val
+            .into();
+        let discriminant = self.encoder.encode_discriminant_func_app(encoded_receiver, adt_def);
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(call_site_span, ErrorCtxt::Panic(PanicCause::Unwrap));
+        Ok(vec![
+            vir::Stmt::comment("The receiver of unwrap/expect must not panic"),
+            vir::Stmt::Assert(vir::Expr::eq_cmp(discriminant, discr_value), pos),
+        ])
+    }
+
+    /// Assume the property of the arithmetic lemma `lemma_name` for the
+    /// given (integer) arguments.
+    fn encode_arith_lemma_use(
+        &self,
+        lemma_name: &str,
+        args: &[mir::Operand<'tcx>],
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let lemma = ArithLemma::from_name(lemma_name).ok_or_else(||
+            SpannedEncodingError::incorrect(
+                format!("'{}' is not a known arithmetic lemma", lemma_name),
+                span,
+            )
+        )?;
+        assert_eq!(args.len(), lemma.arity());
+        let mut arg_exprs = vec![];
+        for operand in args {
+            if !self.mir_encoder.get_operand_ty(operand).is_integral() {
+                return Err(SpannedEncodingError::incorrect(
+                    format!("the arguments of the lemma '{}' must be integers", lemma_name),
+                    span,
+                ));
+            }
+            arg_exprs.push(self.mir_encoder.encode_operand_expr(operand).with_span(span)?);
+        }
+        let lemma_func = self.encoder.encode_arith_lemma_use(lemma);
+        Ok(vec![
+            vir::Stmt::comment(format!("Use of the lemma '{}'", lemma_name)),
+            vir::Stmt::Inhale(vir::Expr::domain_func_app(lemma_func, arg_exprs)),
+        ])
+    }
+
+    /// The ghost variable that counts the credits of the given kind.
+    fn credit_counter(&mut self, kind: &str) -> vir::LocalVar {
+        if let Some(counter) = self.credit_counters.get(kind) {
+            return counter.clone();
+        }
+        let counter = vir::LocalVar::new(format!("credits${}", kind), vir::Type::Int);
+        self.cfg_method.add_local_var(&counter.name, vir::Type::Int);
+        self.credit_counters.insert(kind.to_string(), counter.clone());
+        counter
+    }
+
+    /// Split the `credits::<K>(amount)` conjuncts off a contract, returning
+    /// the kinds and amounts of the credits and the rest of the contract.
+    fn split_credits(
+        &self,
+        spec: vir::Expr,
+        span: Span,
+    ) -> SpannedEncodingResult<(Vec<(String, vir::Expr)>, vir::Expr)> {
+        if !mentions_credits(&spec) {
+            return Ok((vec![], spec));
+        }
+        let mut credits = vec![];
+        let mut conjuncts = vec![];
+        let mut pending = vec![spec];
+        while let Some(expr) = pending.pop() {
+            match expr {
+                vir::Expr::BinOp(vir::BinOpKind::And, box left, box right, _) => {
+                    pending.push(right);
+                    pending.push(left);
+                }
+                vir::Expr::FuncApp(name, mut args, ..)
+                    if name.starts_with(CREDITS_FUNCTION_PREFIX) =>
+                {
+                    let kind = name[CREDITS_FUNCTION_PREFIX.len()..].to_string();
+                    credits.push((kind, args.remove(0)));
+                }
+                expr => {
+                    if mentions_credits(&expr) {
+                        return Err(SpannedEncodingError::unsupported(
+                            "credits can only be used as top-level conjuncts of preconditions \
+                            and postconditions",
+                            span,
+                        ));
+                    }
+                    conjuncts.push(expr);
+                }
+            }
+        }
+        Ok((credits, conjuncts.into_iter().conjoin()))
+    }
+
+    /// The counters of credits are not preserved across loop iterations.
+    fn check_credits_outside_loops(
+        &self,
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<()> {
+        if self.loop_encoder.get_loop_depth(location.block) > 0 {
+            return Err(SpannedEncodingError::unsupported(
+                "credits cannot be obtained, consumed or passed to a call inside a loop",
+                span,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Obtain credits, e.g. from the postcondition of a call.
+    fn encode_credits_gain(
+        &mut self,
+        location: mir::Location,
+        span: Span,
+        credits: Vec<(String, vir::Expr)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = vec![];
+        for (kind, amount) in credits {
+            self.check_credits_outside_loops(location, span)?;
+            let counter = vir::Expr::local(self.credit_counter(&kind));
+            stmts.push(vir::Stmt::Assign(
+                counter.clone(),
+                vir::Expr::add(counter, amount),
+                vir::AssignKind::Copy,
+            ));
+        }
+        Ok(stmts)
+    }
+
+    /// Consume credits, e.g. to satisfy the precondition of a call.
+    fn encode_credits_loss(
+        &mut self,
+        location: mir::Location,
+        span: Span,
+        credits: Vec<(String, vir::Expr)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = vec![];
+        for (kind, amount) in credits {
+            self.check_credits_outside_loops(location, span)?;
+            let counter = vir::Expr::local(self.credit_counter(&kind));
+            let pos = self.encoder.error_manager().register(
+                span,
+                ErrorCtxt::AssertEnoughCredits(kind),
+            );
+            stmts.push(vir::Stmt::Assert(
+                vir::Expr::ge_cmp(counter.clone(), amount.clone()),
+                pos,
+            ));
+            stmts.push(vir::Stmt::Assign(
+                counter.clone(),
+                vir::Expr::sub(counter, amount),
+                vir::AssignKind::Copy,
+            ));
+        }
+        Ok(stmts)
+    }
+
+    /// Start with the credits given by the precondition, and none of the
+    /// other kinds.
+    fn encode_credits_initialisation(&mut self, start_cfg_block: CfgBlockIndex) {
+        let mut initial_amounts: BTreeMap<String, vir::Expr> = BTreeMap::new();
+        for (kind, amount) in std::mem::take(&mut self.precondition_credits) {
+            let initial_amount = match initial_amounts.remove(&kind) {
+                Some(other_amount) => vir::Expr::add(other_amount, amount),
+                None => amount,
+            };
+            initial_amounts.insert(kind, initial_amount);
+        }
+        for kind in initial_amounts.keys() {
+            self.credit_counter(kind);
+        }
+        for (kind, counter) in self.credit_counters.clone() {
+            let initial_amount = initial_amounts.remove(&kind).unwrap_or_else(|| 0.into());
+            self.cfg_method.add_stmt(
+                start_cfg_block,
+                vir::Stmt::Assign(
+                    vir::Expr::local(counter),
+                    initial_amount,
+                    vir::AssignKind::Copy,
+                ),
+            );
+        }
+    }
+
+    /// The values captured by a spawned closure are sent to the new thread.
+    /// Values whose `Send` implementation is hand-written might still be
+    /// aliased by the current thread. The encoding cannot tell whether such an
+    /// implementation is sound, so a spawn that sends them is reported with a
+    /// warning.
+    fn warn_about_unchecked_send_types(
+        &self,
+        call_site_span: rustc_span::Span,
+        closure_operand: &mir::Operand<'tcx>,
+    ) {
+        let cl_ty = self.mir_encoder.get_operand_ty(closure_operand);
+        let captured_tys: Vec<_> = match cl_ty.kind() {
+            ty::TyKind::Closure(_, cl_substs) => cl_substs.as_closure().upvar_tys().collect(),
+            _ => vec![cl_ty],
+        };
+        for captured_ty in captured_tys {
+            if let Some(unchecked_ty) = self.encoder.env().find_unchecked_send_type(captured_ty) {
+                PrustiError::warning(
+                    format!(
+                        "potential data race: a value of type `{}` might be accessed \
+                        concurrently by the spawned thread",
+                        unchecked_ty,
+                    ),
+                    MultiSpan::from_span(call_site_span),
+                ).set_help(
+                    "the type implements `Send` because of an `unsafe impl`, which Prusti \
+                    does not check; make sure that it does not alias with values of the \
+                    current thread"
+                ).emit(self.encoder.env());
+            }
+        }
+    }
+
+    /// Record the closure run by the thread whose `JoinHandle` is stored in
+    /// the local `destination` of a call of `std::thread::spawn`.
+    fn register_spawned_thread(
+        &mut self,
+        closure_operand: &mir::Operand<'tcx>,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+        spawn_label: String,
+    ) {
+        let cl_ty = self.mir_encoder.get_operand_ty(closure_operand);
+        let handle = destination.as_ref().and_then(|(place, _)| place.as_local());
+        if let (ty::TyKind::Closure(cl_def_id, _), Some(handle)) = (cl_ty.kind(), handle) {
+            let closure_local: Local = closure_operand
+                .place()
+                .and_then(|place| place.as_local())
+                .map_or_else(|| self.locals.get_fresh(cl_ty), |local| local.into());
+            self.spawned_threads.insert(handle, (*cl_def_id, closure_local, spawn_label));
+        }
+    }
+
+    /// Assume the functional postcondition of the closure run by a joined
+    /// thread. If the closure is verified not to panic, `join` returns `Ok`
+    /// with the result of the closure. Otherwise, the result of `join` is
+    /// left unconstrained.
+    fn encode_joined_thread_postcondition(
+        &mut self,
+        call_site_span: rustc_span::Span,
+        handle_operand: &mir::Operand<'tcx>,
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let handle = match handle_operand.place().and_then(|place| place.as_local()) {
+            Some(handle) => handle,
+            None => return Ok(vec![]),
+        };
+        let (cl_def_id, closure_local, spawn_label) = match self.spawned_threads.get(&handle) {
+            Some(spawned_thread) => spawned_thread.clone(),
+            None => return Ok(vec![]),
+        };
+        // The label of the spawn is the one of the last spawn that was
+        // encoded, which is not necessarily the one on the current path.
+        if count_assignments(self.mir, handle) > 1 {
+            return Err(SpannedEncodingError::unsupported(
+                "joining a thread whose handle is assigned at several places is not supported",
+                call_site_span,
+            ));
+        }
+        if self.encoder.get_procedure_specs(cl_def_id).is_none() ||
+            !self.is_checked_for_panics(cl_def_id)
+        {
+            return Ok(vec![]);
+        }
+        let target = match destination {
+            Some((place, _)) => *place,
+            None => return Ok(vec![]),
+        };
+        let tcx = self.encoder.env().tcx();
+        let (adt_def, substs) = match target.ty(self.mir, tcx).ty.kind() {
+            ty::TyKind::Adt(adt_def, substs) if adt_def.is_enum() => (*adt_def, *substs),
+            _ => return Ok(vec![]),
+        };
+        let ok_variant = adt_def.variants.iter_enumerated()
+            .find(|(_, variant)| Some(variant.def_id) == tcx.lang_items().result_ok_variant())
+            .map(|(variant_index, _)| variant_index);
+        let ok_variant = match ok_variant {
+            Some(variant_index) => variant_index,
+            None => return Ok(vec![]),
+        };
+        let return_ty = substs.type_at(0);
+        let return_place = tcx.mk_place_field(
+            tcx.mk_place_downcast(target, adt_def, ok_variant),
+            mir::Field::new(0),
+            return_ty,
+        );
+
+        let mut stmts = vec![vir::Stmt::comment("Postcondition of the joined thread")];
+        let (encoded_target, pre_stmts, _, _) = self.encode_place(&target)
+            .with_span(call_site_span)?;
+        stmts.extend(pre_stmts);
+        let discr_value: vir::Expr = adt_def
+            .discriminant_for_variant(tcx, ok_variant)
+            .val
+            .into();
+        let discriminant = self.encoder.encode_discriminant_func_app(encoded_target, adt_def);
+        stmts.push(vir::Stmt::Inhale(vir::Expr::eq_cmp(discriminant, discr_value)));
+
+        let (encoded_return, pre_stmts, _, _) = self.encode_place(&return_place)
+            .with_span(call_site_span)?;
+        stmts.extend(pre_stmts);
+        let return_local = self.locals.get_fresh(return_ty);
+        let contract = self.encoder.get_procedure_contract_for_call(
+            None,
+            cl_def_id,
+            &vec![closure_local],
+            return_local,
+        ).with_span(call_site_span)?;
+        let encoded_args: Vec<vir::Expr> = contract
+            .args
+            .iter()
+            .map(|local| self.encode_prusti_local(*local).into())
+            .collect();
+        for typed_assertion in contract.functional_postcondition() {
+            let assertion = self.encoder.encode_assertion(
+                &typed_assertion,
+                &self.mir,
+                Some(&spawn_label),
+                &encoded_args,
+                Some(&encoded_return),
+                false,
+                None,
+                ErrorCtxt::GenericExpression,
+            )?;
+            let assertion = self.wrap_arguments_into_old(
+                assertion,
+                &spawn_label,
+                &contract,
+                &encoded_args,
+            )?;
+            stmts.push(vir::Stmt::Inhale(assertion));
+        }
+        Ok(stmts)
+    }
+
+    /// Whether the panics of a procedure are reported when it is verified.
+    fn is_checked_for_panics(&self, def_id: ProcedureDefId) -> bool {
+        if self.encoder.is_trusted(def_id) {
+            return false;
+        }
+        match self.encoder.env().get_item_config_flag(def_id, "check_panics") {
+            Ok(Some(check_panics)) => check_panics,
+            Ok(None) => config::check_panics(),
+            Err(_) => false,
+        }
+    }
+
+    /// Check the functional precondition of a closure passed to
+    /// `std::thread::spawn`. The closure does not take arguments, so its
+    /// precondition can only talk about the captured state, which is moved
+    /// into the new thread.
+    fn encode_spawned_closure_precondition(
+        &mut self,
+        call_site_span: rustc_span::Span,
+        closure_operand: &mir::Operand<'tcx>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let cl_ty = self.mir_encoder.get_operand_ty(closure_operand);
+        let (cl_def_id, cl_substs) = match cl_ty.kind() {
+            ty::TyKind::Closure(cl_def_id, cl_substs) => (*cl_def_id, cl_substs),
+            _ => {
+                return Err(SpannedEncodingError::unsupported(
+                    format!("only closures can be spawned as threads, not {:?}", cl_ty.kind()),
+                    call_site_span,
+                ));
+            }
+        };
+        if self.encoder.get_procedure_specs(cl_def_id).is_none() {
+            return Ok(vec![]);
+        }
+        let closure_local: Local = closure_operand
+            .place()
+            .and_then(|place| place.as_local())
+            .map_or_else(|| self.locals.get_fresh(cl_ty), |local| local.into());
+        let encoded_operand = self.mir_encoder.encode_operand_place(closure_operand)
+            .with_span(call_site_span)?;
+        let return_ty = cl_substs.as_closure().sig().output().skip_binder();
+        let target_local = self.locals.get_fresh(return_ty);
+        let contract = self.encoder.get_procedure_contract_for_call(
+            None,
+            cl_def_id,
+            &vec![closure_local],
+            target_local,
+        ).with_span(call_site_span)?;
+        let (_, _, pre_invs_spec, pre_func_spec, _) =
+            self.encode_precondition_expr(&contract, None)?;
+        let mut precondition = vir::Expr::and(pre_invs_spec, pre_func_spec);
+        if let Some(place) = encoded_operand {
+            let closure_place = vir::Expr::local(self.encode_prusti_local(closure_local));
+            precondition = precondition.replace_place(&closure_place, &place);
+        }
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(call_site_span, ErrorCtxt::AssertSpawnedThreadPrecondition);
+        Ok(vec![
+            vir::Stmt::comment("Precondition of the spawned thread"),
+            vir::Stmt::Assert(precondition, pos),
+        ])
+    }
+
+    /// With `check_drop_obligations`, dropping a value whose type implements
+    /// `Drop` has to satisfy the precondition of its `drop` method.
+    fn encode_drop_obligation(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        location: mir::Location,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let tcx = self.encoder.env().tcx();
+        let place_ty = place.ty(self.mir, tcx).ty;
+        let drop_def_id = match self.encoder.env().get_drop_impl(place_ty) {
+            Some(def_id) => def_id,
+            None => return Ok(vec![]),
+        };
+        if self.encoder.get_procedure_specs(drop_def_id).is_none() {
+            return Ok(vec![]);
+        }
+        let (encoded_place, mut stmts, _, _) = self.encode_place(place).with_span(span)?;
+        // Values that have been moved out are not dropped.
+        if !self.init_info.is_vir_place_accessible(&encoded_place, location) {
+            return Ok(vec![]);
+        }
+        let self_ref_ty = tcx.mk_mut_ref(tcx.lifetimes.re_erased, place_ty);
+        let self_local = self.locals.get_fresh(self_ref_ty);
+        let return_local = self.locals.get_fresh(tcx.mk_unit());
+        let contract = self.encoder.get_procedure_contract_for_call(
+            None,
+            drop_def_id,
+            &vec![self_local],
+            return_local,
+        ).with_span(span)?;
+        let (_, _, pre_invs_spec, pre_func_spec, _) =
+            self.encode_precondition_expr(&contract, None)?;
+        let deref_field = self.encoder.encode_dereference_field(place_ty).with_span(span)?;
+        let self_place = vir::Expr::local(self.encode_prusti_local(self_local)).field(deref_field);
+        let precondition = vir::Expr::and(pre_invs_spec, pre_func_spec)
+            .replace_place(&self_place, &encoded_place);
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(span, ErrorCtxt::AssertDropPrecondition);
+        stmts.push(vir::Stmt::comment("Precondition of drop"));
+        stmts.push(vir::Stmt::Assert(precondition, pos));
+        Ok(stmts)
+    }
+
+    /// With `havoc_foreign_calls`, a call to a foreign function without a
+    /// specification may modify anything that it can reach through mutable
+    /// references and raw pointers, and may return any value.
+    fn encode_havoc_foreign_call(
+        &mut self,
+        span: Span,
+        args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, BasicBlockIndex)>,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let mut stmts = vec![vir::Stmt::comment("Call of a foreign function without specification")];
+        for arg in args {
+            let encoded_arg = match self.mir_encoder.encode_operand_place(arg).with_span(span)? {
+                Some(encoded_arg) => encoded_arg,
+                None => continue,
+            };
+            let arg_ty = self.mir_encoder.get_operand_ty(arg);
+            let is_mutable_pointer = match arg_ty.kind() {
+                ty::TyKind::Ref(_, _, hir::Mutability::Mut) |
+                ty::TyKind::RawPtr(ty::TypeAndMut { mutbl: hir::Mutability::Mut, .. }) => true,
+                _ => false,
+            };
+            if is_mutable_pointer {
+                // The borrowed value stays accessible, but with unknown content.
+                let (target, _, _) = self.mir_encoder.encode_deref(encoded_arg, arg_ty)
+                    .with_span(span)?;
+                if let Some(target_perm) = self.mir_encoder
+                    .encode_place_predicate_permission(target, vir::PermAmount::Write)
+                {
+                    let pos = self.encoder.error_manager()
+                        .register(span, ErrorCtxt::ExhaleMethodPrecondition);
+                    stmts.push(vir::Stmt::Exhale(target_perm.clone(), pos));
+                    stmts.push(vir::Stmt::Inhale(target_perm));
+                }
+            } else if let mir::Operand::Move(_) = arg {
+                // The ownership of moved arguments is transferred to the callee.
+                if let Some(arg_perm) = self.mir_encoder
+                    .encode_place_predicate_permission(encoded_arg, vir::PermAmount::Write)
+                {
+                    let pos = self.encoder.error_manager()
+                        .register(span, ErrorCtxt::ExhaleMethodPrecondition);
+                    stmts.push(vir::Stmt::Exhale(arg_perm, pos));
+                }
+            }
+        }
+        match destination {
+            Some((target_place, _)) => {
+                let (encoded_target, pre_stmts, _, _) = self.encode_place(target_place)
+                    .with_span(span)?;
+                stmts.extend(pre_stmts);
+                stmts.extend(self.encode_havoc_and_allocation(&encoded_target));
+            }
+            None => stmts.push(vir::Stmt::Inhale(false.into())),
+        }
+        Ok(stmts)
+    }
+
     fn encode_impure_function_call(
         &mut self,
         location: mir::Location,
@@ -2503,6 +3451,13 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 target_local,
             ).with_span(call_site_span)?
         };
+        if self.encoder.is_trusted(called_def_id) {
+            self.encoder.register_trusted_call(
+                called_def_id,
+                self.proc_def_id,
+                procedure_contract.functional_postcondition().len(),
+            );
+        }
 
         // Store a label for the pre state
         let pre_label = self.cfg_method.get_fresh_label_name();
@@ -2522,6 +3477,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             _, // We don't care about verifying that the weakening is valid,
                // since it isn't the task of the caller
         ) = self.encode_precondition_expr(&procedure_contract, None)?;
+        let (pre_credits, pre_func_spec) = self.split_credits(pre_func_spec, call_site_span)?;
         let pos = self
             .encoder
             .error_manager()
@@ -2530,6 +3486,14 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             replace_fake_exprs(pre_func_spec),
             pos,
         ));
+        stmts.extend(self.encode_credits_loss(
+            location,
+            call_site_span,
+            pre_credits
+                .into_iter()
+                .map(|(kind, amount)| (kind, replace_fake_exprs(amount)))
+                .collect(),
+        )?);
         stmts.push(vir::Stmt::Assert(
             replace_fake_exprs(pre_invs_spec),
             pos,
@@ -2624,9 +3588,18 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         stmts.push(vir::Stmt::Inhale(
             replace_fake_exprs(post_invs_spec),
         ));
+        let (post_credits, post_func_spec) = self.split_credits(post_func_spec, call_site_span)?;
         stmts.push(vir::Stmt::Inhale(
             replace_fake_exprs(post_func_spec),
         ));
+        stmts.extend(self.encode_credits_gain(
+            location,
+            call_site_span,
+            post_credits
+                .into_iter()
+                .map(|(kind, amount)| (kind, replace_fake_exprs(amount)))
+                .collect(),
+        )?);
 
         // Exhale the permissions that were moved into magic wands.
         assert!(!pos.is_default());
@@ -2898,6 +3871,56 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(())
     }
 
+    /// The frame condition of a closure: a call cannot change which places
+    /// the closure captured by reference. Together with the permissions of the
+    /// closure, this frames everything that the closure does not capture, and
+    /// lets the permissions of the captured places be restored when the
+    /// borrows held by the closure expire.
+    fn encode_closure_frame_condition(
+        &self,
+        contract: &ProcedureContract<'tcx>,
+        pre_label: &str,
+        encoded_args: &[vir::Expr],
+    ) -> SpannedEncodingResult<Vec<vir::Expr>> {
+        let tcx = self.encoder.env().tcx();
+        if !tcx.is_closure(contract.def_id) || contract.args.is_empty() {
+            return Ok(vec![]);
+        }
+        // The first argument is the closure, passed by reference to `call`
+        // and `call_mut`.
+        let (closure_ty, closure_place) = match self.locals.get_type(contract.args[0]).kind() {
+            ty::TyKind::Ref(_, closure_ty, _) => {
+                let deref_field = self.encoder.encode_dereference_field(closure_ty)
+                    .with_span(self.mir.span)?;
+                (*closure_ty, encoded_args[0].clone().field(deref_field))
+            }
+            _ => return Ok(vec![]),
+        };
+        let upvar_tys: Vec<_> = match closure_ty.kind() {
+            ty::TyKind::Closure(_, substs) => substs.as_closure().upvar_tys().collect(),
+            _ => return Ok(vec![]),
+        };
+        let mut frame = vec![];
+        for (field_num, upvar_ty) in upvar_tys.into_iter().enumerate() {
+            let target_ty = match upvar_ty.kind() {
+                ty::TyKind::Ref(_, target_ty, _) => target_ty,
+                // Captured by value, the closure may modify it.
+                _ => continue,
+            };
+            let field = self.encoder
+                .encode_raw_ref_field(format!("closure_{}", field_num), upvar_ty)
+                .with_span(self.mir.span)?;
+            let deref_field = self.encoder.encode_dereference_field(target_ty)
+                .with_span(self.mir.span)?;
+            let captured = closure_place.clone().field(field).field(deref_field);
+            frame.push(vir::Expr::eq_cmp(
+                captured.clone(),
+                captured.old(pre_label),
+            ));
+        }
+        Ok(frame)
+    }
+
     /// Encode permissions that are implicitly carried by the given local variable.
     fn encode_local_variable_permission(&self, local: Local)
         -> SpannedEncodingResult<vir::Expr>
@@ -3085,6 +4108,8 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 self.procedure_contract(),
                 precondition_weakening
             )?;
+        let (credits, func_spec) = self.split_credits(func_spec, self.mir.span)?;
+        self.precondition_credits = credits;
         self.cfg_method.add_stmt(
             start_cfg_block,
             vir::Stmt::Inhale(type_spec),
@@ -3119,6 +4144,29 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         Ok(())
     }
 
+    /// Register the position of the vacuity check of the precondition, if
+    /// `config::check_vacuous_specs()` is enabled and there is a functional
+    /// precondition.
+    fn register_vacuity_check(&self) -> Option<vir::Position> {
+        if !config::check_vacuous_specs() {
+            return None;
+        }
+        let precondition_spans: Vec<Span> = self.procedure_contract()
+            .functional_precondition()
+            .iter()
+            .flat_map(|typed_assertion| typed::Spanned::get_spans(
+                typed_assertion, &self.mir, self.encoder.env().tcx()
+            ))
+            .collect();
+        if precondition_spans.is_empty() {
+            return None;
+        }
+        Some(self.encoder.error_manager().register(
+            MultiSpan::from_spans(precondition_spans),
+            ErrorCtxt::VacuityCheck,
+        ))
+    }
+
     /// Encode the magic wand used in the postcondition with its
     /// functional specification. Returns (lhs, rhs).
     fn encode_postcondition_magic_wand(
@@ -3352,12 +4400,16 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 }
                 Mutability::Mut => {
                     add_type_spec(vir::PermAmount::Write);
-                    let inv = self
-                        .encoder
-                        .encode_invariant_func_app(place_ty, old_place_expr)
-                        // TODO: Use a better span
-                        .with_span(self.mir.span)?;
-                    invs_spec.push(inv);
+                    // The value is destroyed after `Drop::drop`, so its
+                    // invariant does not have to be re-established.
+                    if !self.encoder.env().is_drop_impl(contract.def_id) {
+                        let inv = self
+                            .encoder
+                            .encode_invariant_func_app(place_ty, old_place_expr)
+                            // TODO: Use a better span
+                            .with_span(self.mir.span)?;
+                        invs_spec.push(inv);
+                    }
                 }
             };
         }
@@ -3419,7 +4471,15 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 None,
                 ErrorCtxt::GenericExpression,
             )?;
-            func_spec_spans.extend(typed::Spanned::get_spans(typed_assertion, &self.mir, self.encoder.env().tcx()));
+            let assertion_spans = typed::Spanned::get_spans(typed_assertion, &self.mir, self.encoder.env().tcx());
+            if function_end && config::check_vacuous_specs() && !mentions_local_vars(&assertion) {
+                PrustiError::warning(
+                    "this postcondition does not mention the result or the arguments, \
+                    so it does not constrain the function",
+                    MultiSpan::from_spans(assertion_spans.clone()),
+                ).emit(self.encoder.env());
+            }
+            func_spec_spans.extend(assertion_spans);
             assertion = self.wrap_arguments_into_old(
                 assertion,
                 pre_label,
@@ -3428,6 +4488,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             )?;
             func_spec.push(assertion);
         }
+        func_spec.extend(self.encode_closure_frame_condition(contract, pre_label, &encoded_args)?);
         let postcondition_span = MultiSpan::from_spans(func_spec_spans);
         let func_spec_pos = self.encoder.error_manager()
             .register_span(postcondition_span.clone());
@@ -3875,11 +4936,27 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             .encoder
             .error_manager()
             .register(self.mir.span, ErrorCtxt::AssertMethodPostcondition);
+        let (credits, func_spec) = self.split_credits(func_spec, self.mir.span)?;
         let patched_func_spec = self.replace_old_places_with_ghost_vars(None, func_spec);
         self.cfg_method.add_stmt(
             return_cfg_block,
             vir::Stmt::Assert(patched_func_spec, func_pos),
         );
+        for (kind, amount) in credits {
+            let counter = self.credit_counter(&kind);
+            let pos = self.encoder.error_manager().register(
+                self.mir.span,
+                ErrorCtxt::AssertEnoughCredits(kind),
+            );
+            let patched_amount = self.replace_old_places_with_ghost_vars(None, amount);
+            self.cfg_method.add_stmt(
+                return_cfg_block,
+                vir::Stmt::Assert(
+                    vir::Expr::ge_cmp(vir::Expr::local(counter), patched_amount),
+                    pos,
+                ),
+            );
+        }
 
         // Assert type invariants
         self.cfg_method.add_stmt(
@@ -4227,6 +5304,26 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 encoded_spec_spans.extend(spec_spans);
             }
             trace!("encoded_specs: {:?}", encoded_specs);
+        } else if config::infer_loop_invariants() {
+            let inferred_guard = self.loop_encoder.infer_guard_invariant(loop_head, loop_inv_block);
+            if let Some(guard) = inferred_guard {
+                let span = self.mir_encoder.get_span_of_basic_block(loop_inv_block);
+                let operand_ty = self.mir_encoder.get_operand_ty(&guard.left);
+                let encoded_left = self.mir_encoder.encode_operand_expr(&guard.left)
+                    .with_span(span)?;
+                let encoded_right = self.mir_encoder.encode_operand_expr(&guard.right)
+                    .with_span(span)?;
+                let inferred_spec = self.mir_encoder
+                    .encode_bin_op_expr(guard.op, encoded_left, encoded_right, operand_ty)
+                    .with_span(span)?;
+                debug!("Inferred invariant of loop {:?}: {}", loop_head, inferred_spec);
+                let spec_pos = self
+                    .encoder
+                    .error_manager()
+                    .register(span, ErrorCtxt::GenericExpression);
+                encoded_specs.push(inferred_spec.set_default_pos(spec_pos));
+                encoded_spec_spans.push(span);
+            }
         }
 
         Ok((encoded_specs, MultiSpan::from_spans(encoded_spec_spans)))
@@ -4567,7 +5664,9 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                     mir::ConstantKind::Ty(ty::Const { ty, val }) => (ty, *val),
                     mir::ConstantKind::Val(val, ty) => (ty, ty::ConstKind::Value(*val)),
                 };
-                if let ty::TyKind::Tuple(elements) = ty.kind() {
+                if let Some(static_def_id) = self.get_referenced_static(&val) {
+                    self.encode_static_reference(lhs, ty, static_def_id, location)?
+                } else if let ty::TyKind::Tuple(elements) = ty.kind() {
                     // FIXME: This is most likley completely wrong. We need to
                     // implement proper support for handling constants of
                     // non-primitive types.
@@ -4934,9 +6033,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 )).with_span(span);
             }
             mir::BorrowKind::Shallow => {
-                return Err(EncodingError::unsupported(
-                    "unsupported creation of shallow borrows (implicitly created when lowering matches)"
-                )).with_span(span);
+                unreachable!("shallow borrows are not encoded")
             }
             mir::BorrowKind::Mut { .. } => vir::AssignKind::MutableBorrow(loan.into()),
         };
@@ -5036,31 +6133,165 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             .to_u64().unwrap().try_into().unwrap();
 
         let mut stmts = self.encode_havoc_and_allocation(&encoded_lhs);
-        for i in 0..len {
-            let idx = vir::Expr::from(i);
-            let lookup_pure_call = vir::Expr::func_app(
-                lookup_pure.clone(),
-                vec![
-                    encoded_lhs.clone(),
-                    idx,
-                ],
-                vec![
-                    vir::LocalVar::new(
-                        String::from("self"),
-                        at.array_ty.clone(),
-                    ),
-                    vir::LocalVar::new(
-                        String::from("idx"),
-                        vir::Type::Int,
-                    ),
-                ],
-                at.elem_value_ty.clone(),
-                vir::Position::default(),
-            );
+        // A single quantifier instead of one equality per element, so that
+        // large buffers such as `[0u8; 4096]` stay cheap to encode.
+        let idx_var = vir::LocalVar::new("idx", vir::Type::Int);
+        let idx: vir::Expr = idx_var.clone().into();
+        let lookup_pure_call = vir::Expr::func_app(
+            lookup_pure,
+            vec![
+                encoded_lhs.clone(),
+                idx.clone(),
+            ],
+            vec![
+                vir::LocalVar::new(
+                    String::from("self"),
+                    at.array_ty.clone(),
+                ),
+                vir::LocalVar::new(
+                    String::from("idx"),
+                    vir::Type::Int,
+                ),
+            ],
+            at.elem_value_ty.clone(),
+            vir::Position::default(),
+        );
+        stmts.push(vir::Stmt::Inhale(vir::Expr::forall(
+            vec![idx_var],
+            vec![vir::Trigger::new(vec![lookup_pure_call.clone()])],
+            vir!{ (([vir::Expr::from(0)] <= [idx.clone()]) && ([idx] < [vir::Expr::from(len)])) ==>
+                ([lookup_pure_call] == [encoded_operand]) },
+        )));
+
+        Ok(stmts)
+    }
 
-            stmts.push(vir::Stmt::Inhale(vir!{ [lookup_pure_call] == [encoded_operand] }));
+    /// Unsizing a reference to an array `[T; N]` into a slice `[T]` is only
+    /// supported for passing the array to the slice methods with built-in
+    /// contracts (see `encode_slice_copy`). The slice is not encoded, but
+    /// the reference to the array that it comes from is recorded.
+    fn encode_unsize_array_ref(
+        &mut self,
+        lhs: &mir::Place<'tcx>,
+        operand: &mir::Operand<'tcx>,
+        dst_ty: ty::Ty<'tcx>,
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let src_ty = self.mir_encoder.get_operand_ty(operand);
+        let is_array_ref = match src_ty.kind() {
+            ty::TyKind::Ref(_, ty, _) => matches!(ty.kind(), ty::TyKind::Array(..)),
+            _ => false,
+        };
+        let is_slice_ref = match dst_ty.kind() {
+            ty::TyKind::Ref(_, ty, _) => matches!(ty.kind(), ty::TyKind::Slice(..)),
+            _ => false,
+        };
+        match (operand, lhs.as_local()) {
+            (mir::Operand::Move(place), Some(local))
+            | (mir::Operand::Copy(place), Some(local)) if is_array_ref && is_slice_ref => {
+                self.unsized_arrays.insert(local, *place);
+                Ok(vec![vir::Stmt::comment(format!(
+                    "{:?} is the slice of the array behind {:?}", local, place
+                ))])
+            }
+            _ => Err(SpannedEncodingError::unsupported(
+                "unsizing casts are only supported from references to arrays to slices",
+                span,
+            )),
         }
+    }
 
+    /// Encode a call to `<[T]>::copy_from_slice` or `<[T]>::clone_from_slice`
+    /// on slices of arrays. The call panics if the lengths differ; otherwise
+    /// the elements of the destination become equal to those of the source.
+    /// For `clone_from_slice` the elements are only known to be equal if they
+    /// are `Copy`, because `clone` may be implemented arbitrarily.
+    fn encode_slice_copy(
+        &mut self,
+        method_name: &str,
+        args: &[mir::Operand<'tcx>],
+        span: Span,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        assert_eq!(args.len(), 2);
+        let mut arrays = vec![];
+        for arg in args {
+            let array_ref = match arg {
+                mir::Operand::Move(place) | mir::Operand::Copy(place) => place.as_local()
+                    .and_then(|local| self.unsized_arrays.get(&local))
+                    .cloned(),
+                _ => None,
+            };
+            let array_ref = array_ref.ok_or_else(|| SpannedEncodingError::unsupported(
+                format!("`{}` is only supported on slices of arrays", method_name),
+                span,
+            ))?;
+            let array_place = self.encoder.env().tcx().mk_place_deref(array_ref);
+            let (encoded_array, pre_stmts, array_ty, _) = self.encode_place(&array_place)
+                .with_span(span)?;
+            arrays.push((encoded_array, pre_stmts, self.encode_array_types(array_ty).with_span(span)?));
+        }
+        let (src, src_stmts, src_at) = arrays.pop().unwrap();
+        let (dst, dst_stmts, dst_at) = arrays.pop().unwrap();
+        let mut stmts = dst_stmts;
+        stmts.extend(src_stmts);
+
+        if dst_at.array_len != src_at.array_len {
+            let pos = self.encoder.error_manager()
+                .register(span, ErrorCtxt::Panic(PanicCause::Generic));
+            stmts.push(vir::Stmt::comment(format!(
+                "{} on slices of different lengths", method_name
+            )));
+            if self.check_panics {
+                stmts.push(vir::Stmt::Assert(false.into(), pos));
+            }
+            // The call panics, so the code after it is unreachable.
+            stmts.push(vir::Stmt::Inhale(false.into()));
+            return Ok(stmts);
+        }
+
+        // Havoc the content of the destination.
+        let dst_pred = vir::Expr::predicate_access_predicate(
+            dst_at.array_pred.clone(),
+            dst.clone(),
+            vir::PermAmount::Write,
+        );
+        stmts.push(vir::Stmt::Exhale(dst_pred.clone(), vir::Position::default()));
+        stmts.push(vir::Stmt::Inhale(dst_pred));
+
+        let copies_values = method_name.ends_with("copy_from_slice") ||
+            self.encoder.env().type_is_copy(dst_at.elem_ty_rs);
+        if copies_values {
+            let idx_var = vir::LocalVar::new("idx", vir::Type::Int);
+            let idx: vir::Expr = idx_var.clone().into();
+            let lookup = |at: &EncodedArrayTypes<'tcx>, array: vir::Expr| {
+                let lookup_pure = self.encoder.encode_builtin_function_use(
+                    BuiltinFunctionKind::ArrayLookupPure {
+                        array_ty_pred: at.array_pred.clone(),
+                        elem_ty_pred: at.elem_pred.clone(),
+                        array_len: at.array_len,
+                        return_ty: at.elem_value_ty.clone(),
+                    }
+                );
+                vir::Expr::func_app(
+                    lookup_pure,
+                    vec![array, idx.clone()],
+                    vec![
+                        vir::LocalVar::new(String::from("self"), at.array_ty.clone()),
+                        vir::LocalVar::new(String::from("idx"), vir::Type::Int),
+                    ],
+                    at.elem_value_ty.clone(),
+                    vir::Position::default(),
+                )
+            };
+            let dst_elem = lookup(&dst_at, dst);
+            let src_elem = lookup(&src_at, src);
+            stmts.push(vir::Stmt::Inhale(vir::Expr::forall(
+                vec![idx_var],
+                vec![vir::Trigger::new(vec![dst_elem.clone()])],
+                vir!{ (([vir::Expr::from(0)] <= [idx.clone()]) && ([idx.clone()] < [vir::Expr::from(dst_at.array_len)])) ==>
+                    ([dst_elem] == [src_elem]) },
+            )));
+        }
         Ok(stmts)
     }
 
@@ -5096,6 +6327,74 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
         }
     }
 
+    /// If the constant is a reference to a `static` item, return the item.
+    fn get_referenced_static(&self, val: &ty::ConstKind<'tcx>) -> Option<DefId> {
+        if let ty::ConstKind::Value(ConstValue::Scalar(Scalar::Ptr(ptr))) = val {
+            match self.encoder.env().tcx().get_global_alloc(ptr.alloc_id) {
+                Some(GlobalAlloc::Static(def_id)) => Some(def_id),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Encode `lhs = &STATIC`. The static is modelled as a fresh memory
+    /// location that is allocated at the read; if the value of the static
+    /// can be evaluated, it is assumed. Mutable statics are not supported,
+    /// because their value can change between two reads.
+    fn encode_static_reference(
+        &mut self,
+        lhs: &vir::Expr,
+        ref_ty: ty::Ty<'tcx>,
+        static_def_id: DefId,
+        location: mir::Location,
+    ) -> SpannedEncodingResult<Vec<vir::Stmt>> {
+        let span = self.mir_encoder.get_span_of_location(location);
+        let tcx = self.encoder.env().tcx();
+        if tcx.is_mutable_static(static_def_id) {
+            return Err(SpannedEncodingError::unsupported(
+                "accessing a mutable static is not supported",
+                span,
+            ));
+        }
+        let static_ty = match ref_ty.kind() {
+            ty::TyKind::Ref(_, static_ty, _) | ty::TyKind::RawPtr(ty::TypeAndMut { ty: static_ty, .. }) => static_ty,
+            _ => return Err(SpannedEncodingError::internal(
+                format!("unexpected type of a reference to a static: {:?}", ref_ty),
+                span,
+            )),
+        };
+        let ref_field = self.encoder.encode_value_field(ref_ty);
+        let static_place = lhs.clone().field(ref_field.clone());
+        let mut stmts = self.prepare_assign_target(
+            lhs.clone(),
+            ref_field,
+            location,
+            vir::AssignKind::Move,
+        )?;
+        stmts.extend(self.encode_havoc_and_allocation(&static_place));
+        let is_primitive = matches!(
+            static_ty.kind(),
+            ty::TyKind::Bool | ty::TyKind::Char | ty::TyKind::Int(_) | ty::TyKind::Uint(_)
+        );
+        if is_primitive {
+            let opt_value = tcx.const_eval_poly(static_def_id)
+                .ok()
+                .filter(|const_value| const_value.try_to_scalar().is_some());
+            if let Some(const_value) = opt_value {
+                let encoded_value = self.encoder
+                    .encode_const_expr(static_ty, &ty::ConstKind::Value(const_value))
+                    .with_span(span)?;
+                let value_field = self.encoder.encode_value_field(static_ty);
+                stmts.push(vir::Stmt::Inhale(
+                    vir::Expr::eq_cmp(static_place.field(value_field), encoded_value),
+                ));
+            }
+        }
+        Ok(stmts)
+    }
+
     /// Havoc and assume permission on fields
     fn encode_havoc_and_allocation(&mut self, dst: &vir::Expr) -> Vec<vir::Stmt> {
         debug!("Encode havoc and allocation {:?}", dst);
@@ -5272,7 +6571,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
             ty::TyKind::Tuple(elems) => {
                 self.encode_deep_copy_tuple(src, dst, elems, location)?
             }
-            ty::TyKind::Param(_) => {
+            ty::TyKind::Param(_) | ty::TyKind::Opaque(..) => {
                 let mut stmts = self.encode_havoc_and_allocation(&dst.clone());
                 let eq = self.encoder.encode_memory_eq_func_app(
                     src,
@@ -5428,17 +6727,23 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
                 }
             }
 
-            mir::AggregateKind::Closure(def_id, _substs) => {
+            mir::AggregateKind::Closure(def_id, substs) => {
                 debug_assert!(!self.encoder.is_spec_closure(def_id), "spec closure: {:?}", def_id);
-                // TODO: assign to closure; this case should only handle assign
-                // of the same closure type (== instances of the same syntactic
-                // closure)
-                // closure state encoding should first be implemented in
-                // type_encoder
-                // this case might also need to assert history invariants?
-                //
-                // for now we generate nothing to at least allow
-                // let f = closure!(...);
+                // The operands are the captured paths, stored in the same
+                // fields as in the closure predicate.
+                // TODO: this might also need to assert history invariants?
+                let upvar_tys: Vec<_> = substs.as_closure().upvar_tys().collect();
+                for (field_num, operand) in operands.iter().enumerate() {
+                    let encoded_field = self
+                        .encoder
+                        .encode_raw_ref_field(format!("closure_{}", field_num), upvar_tys[field_num])
+                        .with_span(span)?;
+                    stmts.extend(self.encode_assign_operand(
+                        &dst.clone().field(encoded_field),
+                        operand,
+                        location,
+                    )?);
+                }
             }
 
             mir::AggregateKind::Array(..) => {
@@ -5617,6 +6922,130 @@ impl<'p, 'v: 'p, 'tcx: 'v> ProcedureEncoder<'p, 'v, 'tcx> {
     }
 }
 
+/// Does the expression mention credits, i.e. `prusti_contracts::credits`?
+fn mentions_credits(expr: &vir::Expr) -> bool {
+    struct CreditsFinder {
+        found: bool,
+    }
+    impl vir::ExprWalker for CreditsFinder {
+        fn walk_func_app(
+            &mut self,
+            name: &str,
+            args: &Vec<vir::Expr>,
+            _formal_args: &Vec<vir::LocalVar>,
+            _return_type: &vir::Type,
+            _pos: &vir::Position,
+        ) {
+            if name.starts_with(CREDITS_FUNCTION_PREFIX) {
+                self.found = true;
+            }
+            for arg in args {
+                self.walk(arg);
+            }
+        }
+    }
+    let mut finder = CreditsFinder { found: false };
+    vir::ExprWalker::walk(&mut finder, expr);
+    finder.found
+}
+
+/// Encode a method that inhales the precondition of `method`, with the
+/// folding and unfolding of its `start_block`, and then asserts `false`. The
+/// assertion fails, as expected, unless the precondition is unsatisfiable.
+fn encode_vacuity_check(
+    method: &vir::CfgMethod,
+    start_block: CfgBlockIndex,
+    pos: vir::Position,
+) -> vir::CfgMethod {
+    let mut stmts: Vec<_> = method.basic_blocks[start_block.index()].stmts
+        .iter()
+        .take_while(|stmt| !matches!(stmt, vir::Stmt::Label(label) if label == PRECONDITION_LABEL))
+        // E.g. the check that the precondition weakens that of the trait.
+        .filter(|stmt| !matches!(stmt, vir::Stmt::Assert(..)))
+        .cloned()
+        .collect();
+    stmts.push(vir::Stmt::Assert(false.into(), pos));
+    let mut vacuity_check = vir::CfgMethod::new(
+        // method name
+        format!("{}$vacuity", method.name()),
+        // formal args
+        0,
+        // formal returns
+        vec![],
+        // local vars
+        method.get_all_vars(),
+        // reserved labels
+        vec![],
+    );
+    let check_block = vacuity_check.add_block("start", stmts);
+    vacuity_check.set_successor(check_block, Successor::Return);
+    vacuity_check
+}
+
+/// Does the expression mention any local variable, i.e. an argument or the
+/// result of the function?
+fn mentions_local_vars(expr: &vir::Expr) -> bool {
+    struct LocalVarFinder {
+        found: bool,
+    }
+    impl vir::ExprWalker for LocalVarFinder {
+        fn walk_local_var(&mut self, _local_var: &vir::LocalVar) {
+            self.found = true;
+        }
+    }
+    let mut finder = LocalVarFinder { found: false };
+    vir::ExprWalker::walk(&mut finder, expr);
+    finder.found
+}
+
 fn convert_loans_to_borrows(loans: &[facts::Loan]) -> Vec<Borrow> {
     loans.iter().map(|l| l.into()).collect()
 }
+
+/// Abort the encoding of a procedure if it used more memory than allowed by
+/// `config::encoding_memory_limit()`, instead of risking to exhaust the
+/// memory of the whole process. `memory_at_start` is `None` if the memory is
+/// not limited.
+fn check_encoding_memory(memory_at_start: Option<usize>, span: Span) -> SpannedEncodingResult<()> {
+    let memory_at_start = match memory_at_start {
+        Some(memory) => memory,
+        None => return Ok(()),
+    };
+    let used = memory::resident_memory_mb()
+        .unwrap_or(memory_at_start)
+        .saturating_sub(memory_at_start);
+    let limit = config::encoding_memory_limit();
+    if used > limit {
+        return Err(SpannedEncodingError::unsupported(
+            format!(
+                "the encoding of this function needs more than {} MB of memory \
+                (encoding_memory_limit); consider marking it as #[trusted] or \
+                splitting it into smaller functions",
+                limit
+            ),
+            span,
+        ));
+    }
+    Ok(())
+}
+
+/// The number of statements and calls that assign to `local` or to a part of it.
+fn count_assignments(mir: &mir::Body, local: mir::Local) -> usize {
+    mir.basic_blocks().iter()
+        .map(|block| {
+            let statements = block.statements.iter()
+                .filter(|statement| match statement.kind {
+                    mir::StatementKind::Assign(box (ref place, _)) => place.local == local,
+                    _ => false,
+                })
+                .count();
+            let call = match block.terminator().kind {
+                mir::TerminatorKind::Call { destination: Some((ref place, _)), .. } => {
+                    place.local == local
+                }
+                _ => false,
+            };
+            statements + call as usize
+        })
+        .sum()
+}
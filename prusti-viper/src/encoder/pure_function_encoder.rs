@@ -442,6 +442,8 @@ pub(super) struct PureFunctionBackwardInterpreter<'p, 'v: 'p, 'tcx: 'v> {
     encoder: &'p Encoder<'v, 'tcx>,
     mir: &'p mir::Body<'tcx>,
     mir_encoder: MirEncoder<'p, 'v, 'tcx>,
+    /// The pure function (or the item of the assertion) that is encoded.
+    def_id: DefId,
     /// True if the encoder is currently encoding an assertion and not a pure function body. This
     /// flag is used to distinguish when assert terminators should be translated into `false` and
     /// when to a undefined function calls. This distinction allows overflow checks to be checked
@@ -463,6 +465,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionBackwardInterpreter<'p, 'v, 'tcx> {
             encoder,
             mir,
             mir_encoder: MirEncoder::new(encoder, mir, def_id),
+            def_id,
             is_encoding_assertion,
         }
     }
@@ -504,6 +507,45 @@ impl<'p, 'v: 'p, 'tcx: 'v> PureFunctionBackwardInterpreter<'p, 'v, 'tcx> {
         Ok(())
     }
 
+    /// Encode the condition under which a call of `callee` with the given
+    /// arguments terminates, if the call is recursive. A call of the encoded
+    /// function to itself terminates if its integer arguments decrease
+    /// lexicographically and stay non-negative; a mutually recursive call is
+    /// not known to terminate.
+    fn encode_termination_condition(
+        &self,
+        callee: DefId,
+        encoded_args: &[vir::Expr],
+    ) -> EncodingResult<Option<vir::Expr>> {
+        if callee != self.def_id {
+            let is_mutually_recursive = self.encoder
+                .is_mutually_recursive_pure_call(self.def_id, callee);
+            return Ok(if is_mutually_recursive { Some(false.into()) } else { None });
+        }
+        let mut measure = vec![];
+        for (local, encoded_arg) in self.mir.args_iter().zip(encoded_args) {
+            let ty = self.mir_encoder.get_local_ty(local);
+            if let ty::TyKind::Int(_) | ty::TyKind::Uint(_) = ty.kind() {
+                let param = self.encoder.encode_value_expr(
+                    vir::Expr::local(self.mir_encoder.encode_local(local)?),
+                    ty,
+                );
+                measure.push((encoded_arg.clone(), param));
+            }
+        }
+        // Built from the last component: `a0 < p0 || (a0 == p0 && ...)`.
+        let condition = measure.into_iter().rev().fold(false.into(), |rest, (arg, param)| {
+            vir::Expr::or(
+                vir::Expr::and(
+                    vir::Expr::le_cmp(0.into(), arg.clone()),
+                    vir::Expr::lt_cmp(arg.clone(), param.clone()),
+                ),
+                vir::Expr::and(vir::Expr::eq_cmp(arg, param), rest),
+            )
+        });
+        Ok(Some(condition))
+    }
+
     fn encode_place(
         &self,
         place: &mir::Place<'tcx>,
@@ -724,7 +766,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                 ..
             } => {
                 if let ty::TyKind::FnDef(def_id, substs) = ty.kind() {
-                    let def_id = *def_id;
+                    // Operators on user-defined types use the operator
+                    // implementation, which can be pure.
+                    let (def_id, substs) = self.encoder
+                        .resolve_operator_impl(self.def_id, *def_id, *substs)
+                        .unwrap_or((*def_id, *substs));
                     let full_func_proc_name: &str =
                         &self.encoder.env().tcx().def_path_str(def_id);
                         // &self.encoder.env().tcx().absolute_item_path_str(def_id);
@@ -789,10 +835,30 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                 state.substitute_value(&lhs_value, encoded_rhs);
                                 state
                             }
+
+                            "prusti_contracts::credits" => {
+                                trace!("Encoding credits {:?}", args[0]);
+                                assert_eq!(args.len(), 1);
+                                let kind = self.encoder.encode_credit_kind(substs.type_at(0));
+                                let function_name = self.encoder.encode_builtin_function_use(
+                                    BuiltinFunctionKind::Credits(kind)
+                                );
+                                let encoded_rhs = vir::Expr::func_app(
+                                    function_name,
+                                    vec![encoded_args[0].clone()],
+                                    vec![vir_local!{ amount: Int }],
+                                    vir::Type::Bool,
+                                    vir::Position::default(),
+                                );
+                                let mut state = states[&target_block].clone();
+                                state.substitute_value(&lhs_value, encoded_rhs);
+                                state
+                            }
                             // simple function call
                             _ => {
                                 let mut is_cmp_call = false;
-                                let is_pure_function = self.encoder.is_pure(def_id);
+                                let is_pure_function = self.encoder.is_pure(def_id)
+                                    || self.encoder.is_const_fn(def_id);
                                 let (function_name, return_type) = if is_pure_function {
                                     self.encoder.encode_pure_function_use(def_id)
                                         .with_span(term.source_info.span)?
@@ -845,6 +911,16 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                     .collect::<Result<_, _>>()
                                     .with_span(term.source_info.span)?;
 
+                                let termination_condition = if is_pure_function
+                                    && !self.is_encoding_assertion
+                                    && config::check_pure_function_totality()
+                                {
+                                    self.encode_termination_condition(def_id, &encoded_args)
+                                        .with_span(term.source_info.span)?
+                                } else {
+                                    None
+                                };
+
                                 let pos = self
                                     .encoder
                                     .error_manager()
@@ -853,9 +929,28 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                     function_name,
                                     encoded_args,
                                     formal_args,
-                                    return_type,
+                                    return_type.clone(),
                                     pos,
                                 );
+                                if let Some(condition) = termination_condition {
+                                    // The result of a call that might not terminate is
+                                    // unreachable, like the result of a panic.
+                                    let termination_pos = self.encoder.error_manager().register(
+                                        term.source_info.span,
+                                        ErrorCtxt::PureFunctionTermination,
+                                    );
+                                    let unreachable_function = self.encoder.encode_builtin_function_use(
+                                        BuiltinFunctionKind::Unreachable(return_type.clone()),
+                                    );
+                                    let non_terminating = vir::Expr::func_app(
+                                        unreachable_function,
+                                        vec![],
+                                        vec![],
+                                        return_type,
+                                        termination_pos,
+                                    );
+                                    encoded_rhs = vir::Expr::ite(condition, encoded_rhs, non_terminating);
+                                }
                                 if config::enable_purification_optimization() {
                                     if let Some(proc_id) = self.encoder.current_proc.borrow().clone() {
                                         if !self.encoder.is_pure(proc_id) {
@@ -1049,17 +1144,21 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                     &mir::Rvalue::Aggregate(ref aggregate, ref operands) => {
                         debug!("Encode aggregate {:?}, {:?}", aggregate, operands);
                         match aggregate.as_ref() {
-                            &mir::AggregateKind::Tuple => {
-                                let field_types = if let ty::TyKind::Tuple(ref x) = ty.kind() {
-                                    x
-                                } else {
-                                    unreachable!()
+                            &mir::AggregateKind::Tuple | &mir::AggregateKind::Closure(..) => {
+                                // Tuples and the captured state of closures are both encoded
+                                // as records with one field per element.
+                                let (field_prefix, field_types): (_, Vec<ty::Ty<'tcx>>) = match ty.kind() {
+                                    ty::TyKind::Tuple(ref x) => ("tuple", x.types().collect()),
+                                    ty::TyKind::Closure(_, ref substs) => {
+                                        ("closure", substs.as_closure().upvar_tys().collect())
+                                    }
+                                    _ => unreachable!(),
                                 };
                                 for (field_num, operand) in operands.iter().enumerate() {
-                                    let field_name = format!("tuple_{}", field_num);
+                                    let field_name = format!("{}_{}", field_prefix, field_num);
                                     let field_ty = field_types[field_num];
                                     let encoded_field = self.encoder
-                                        .encode_raw_ref_field(field_name, field_ty.expect_ty())
+                                        .encode_raw_ref_field(field_name, field_ty)
                                         .with_span(span)?;
                                     let field_place = encoded_lhs.clone().field(encoded_field);
 
@@ -1076,7 +1175,7 @@ impl<'p, 'v: 'p, 'tcx: 'v> BackwardMirInterpreter<'tcx>
                                                 self.mir_encoder.encode_operand_expr(operand)
                                                     .with_span(span)?;
                                             state.substitute_value(
-                                                &self.encoder.encode_value_expr(field_place, field_ty.expect_ty()),
+                                                &self.encoder.encode_value_expr(field_place, field_ty),
                                                 rhs_expr,
                                             );
                                         }
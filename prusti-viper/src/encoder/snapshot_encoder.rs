@@ -27,6 +27,13 @@ const SNAPSHOT_ARG: &str = "_arg";
 const SNAPSHOT_LEFT: &str = "_left";
 const SNAPSHOT_RIGHT: &str = "_right";
 
+/// Should the snapshot domains contain the selector functions of the fields
+/// and the axioms relating them to the constructors?
+fn encode_selectors() -> bool {
+    prusti_common::config::enable_purification_optimization()
+        || prusti_common::config::use_adt_snapshots()
+}
+
 pub struct SnapshotEncoder<'p, 'v: 'p, 'tcx: 'v> {
     encoder: &'p Encoder<'v, 'tcx>,
     ty: ty::Ty<'tcx>,
@@ -168,7 +175,7 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> SnapshotEncoder<'p, 'v, 'tcx> {
                     self.encoder.encode_value_field(self.ty)
                 )?
             }
-            ty::TyKind::Param(_) => {
+            ty::TyKind::Param(_) | ty::TyKind::Opaque(..) => {
                 self.encode_generic(true)?
             }
             ty::TyKind::Tuple(_) => {
@@ -208,7 +215,9 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> SnapshotEncoder<'p, 'v, 'tcx> {
 
     fn is_supported(&self) -> bool {
         let is_possible = self.is_ty_supported(self.ty, &mut vec![]);
-        let is_needed = prusti_common::config::enable_purification_optimization() || self.encoder.has_structural_eq_impl(self.ty);
+        let is_needed = prusti_common::config::enable_purification_optimization()
+            || prusti_common::config::use_adt_snapshots()
+            || self.encoder.has_structural_eq_impl(self.ty);
 
         is_possible && is_needed
     }
@@ -223,7 +232,8 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> SnapshotEncoder<'p, 'v, 'tcx> {
             | ty::TyKind::Uint(_)
             | ty::TyKind::Char
             | ty::TyKind::Bool
-            | ty::TyKind::Param(_) => {
+            | ty::TyKind::Param(_)
+            | ty::TyKind::Opaque(..) => {
                 true
             }
 
@@ -417,14 +427,16 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> SnapshotEncoder<'p, 'v, 'tcx> {
 
         let mut functions = vec![cons_func.clone()];
         let mut axioms = vec![cons_axiom_injectivity];
-        if prusti_common::config::enable_purification_optimization() {
-            let domain_name = self.encode_domain_name();
-            let valid_function = self.encode_valid_function();
-            let valid_axiom = self.encode_valid_axiom(cons_func)?;
+        if encode_selectors() {
             if let Some((mut field_funcs, mut field_axioms)) = self.encode_field_funcs()? {
                 functions.append(&mut field_funcs);
                 axioms.append(&mut field_axioms);
             }
+        }
+        if prusti_common::config::enable_purification_optimization() {
+            let domain_name = self.encode_domain_name();
+            let valid_function = self.encode_valid_function();
+            let valid_axiom = self.encode_valid_axiom(cons_func)?;
 
             functions.push(snapshot::encode_unfold_witness(domain_name));
             functions.push(valid_function);
@@ -944,12 +956,14 @@ impl<'s, 'v: 's, 'tcx: 'v> SnapshotAdtEncoder<'s, 'v, 'tcx> {
         }
 
 
-        if prusti_common::config::enable_purification_optimization() {
+        if encode_selectors() {
             if let Some((mut field_funcs, mut field_axioms)) = self.encode_field_funcs()? {
                 functions.append(&mut field_funcs);
                 axioms.append(&mut field_axioms);
             }
+        }
 
+        if prusti_common::config::enable_purification_optimization() {
             let domain_name = self.snapshot_encoder.encode_domain_name();
             let valid_function = self.snapshot_encoder.encode_valid_function();
             let valid_axiom = self.encode_valid_axiom()?;
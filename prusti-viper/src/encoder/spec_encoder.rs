@@ -241,7 +241,11 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
                     let encoded_trigger = self.encode_trigger(trigger, &encoded_args)?;
                     encoded_triggers.push(encoded_trigger);
                 }
-                let encoded_body = self.encode_assertion(body)?;
+                let encoded_body = self.register_spec_part(
+                    self.encode_assertion(body)?,
+                    rustc_span::MultiSpan::from_spans(self.assertion_spans(body)),
+                    vars.spec_id,
+                );
                 let final_body = if bounds.is_empty() {
                     encoded_body
                 } else {
@@ -665,14 +669,67 @@ impl<'p, 'v: 'p, 'tcx: 'v> SpecEncoder<'p, 'v, 'tcx> {
         });
 
         debug!("MIR expr {:?} --> {}", assertion_expr.id, curr_expr);
-        Ok(curr_expr.set_default_pos(
-            self.encoder
-                .error_manager()
-                .register(
-                    self.encoder.env().tcx().def_span(assertion_expr.expr),
-                    ErrorCtxt::GenericExpression),
+        Ok(self.register_spec_part(
+            curr_expr,
+            self.encoder.env().tcx().def_span(assertion_expr.expr).into(),
+            assertion_expr.spec_id,
         ))
     }
+
+    /// Give a position to a conjunct or quantifier body of a contract, so that
+    /// a verification error in any of its sub-expressions points to it. If the
+    /// user gave a message to the clause `spec_id`, it is reported instead of
+    /// the generic one.
+    fn register_spec_part(
+        &self,
+        expr: vir::Expr,
+        span: rustc_span::MultiSpan,
+        spec_id: typed::SpecificationId,
+    ) -> vir::Expr {
+        struct PositionCollector {
+            positions: Vec<vir::Position>,
+        }
+        impl vir::ExprWalker for PositionCollector {
+            fn walk(&mut self, expr: &vir::Expr) {
+                self.positions.push(expr.pos());
+                vir::default_walk_expr(self, expr);
+            }
+        }
+        let pos = self.encoder
+            .error_manager()
+            .register(span, ErrorCtxt::GenericExpression);
+        let expr = expr.set_default_pos(pos);
+        let mut collector = PositionCollector { positions: vec![] };
+        vir::ExprWalker::walk(&mut collector, &expr);
+        self.encoder.error_manager().register_spec_part(&pos, collector.positions);
+        if let Some(message) = self.encoder.def_spec().get_spec_message(spec_id) {
+            self.encoder.error_manager().register_spec_message(&pos, message.to_string());
+        }
+        expr
+    }
+
+    /// The spans of the Rust expressions of an assertion, without the spans of
+    /// the quantified variables and triggers.
+    fn assertion_spans(&self, assertion: &typed::Assertion<'tcx>) -> Vec<rustc_span::Span> {
+        let tcx = self.encoder.env().tcx();
+        match assertion.kind {
+            box typed::AssertionKind::Expr(ref assertion_expr) =>
+                vec![tcx.def_span(assertion_expr.expr)],
+            box typed::AssertionKind::And(ref assertions) => assertions
+                .iter()
+                .flat_map(|a| self.assertion_spans(a))
+                .collect(),
+            box typed::AssertionKind::Implies(ref lhs, ref rhs) => {
+                let mut spans = self.assertion_spans(lhs);
+                spans.extend(self.assertion_spans(rhs));
+                spans
+            }
+            box typed::AssertionKind::TypeCond(_, ref body) |
+            box typed::AssertionKind::ForAll(_, _, ref body) => self.assertion_spans(body),
+            box typed::AssertionKind::SpecEntailment { ref closure, .. } =>
+                vec![tcx.def_span(closure.expr)],
+        }
+    }
 }
 
 struct StraightLineBackwardInterpreter<'p, 'v: 'p, 'tcx: 'v> {
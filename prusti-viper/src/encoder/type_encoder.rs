@@ -22,7 +22,7 @@ use rustc_middle::ty::layout::IntegerExt;
 // use rustc_data_structures::indexed_vec::Idx;
 // use std;
 use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use prusti_interface::specs::typed;
 use rustc_attr::IntType::SignedInt;
@@ -39,6 +39,7 @@ pub struct TypeEncoder<'p, 'v: 'p, 'tcx: 'v> {
 
 impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
     pub fn new(encoder: &'p Encoder<'v, 'tcx>, ty: ty::Ty<'tcx>) -> Self {
+        let ty = encoder.normalize_projections(ty);
         TypeEncoder { encoder, ty }
     }
 
@@ -53,7 +54,8 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
             | ty::TyKind::Adt(_, _)
             | ty::TyKind::Tuple(_)
             | ty::TyKind::Never
-            | ty::TyKind::Param(_) => true,
+            | ty::TyKind::Param(_)
+            | ty::TyKind::Opaque(..) => true,
             _ => false,
         }
     }
@@ -102,6 +104,78 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
         supported_fields && self.is_supported_subst(subst)
     }
 
+    /// Check that the type is within the configured nesting depth and number
+    /// of reachable types, so that encoding the predicates of a deeply
+    /// nested type and their fold-unfold does not take unbounded time.
+    pub fn check_size_limits(&self) -> EncodingResult<()> {
+        let max_depth = config::max_type_depth();
+        let max_size = config::max_type_size();
+        if max_depth == 0 && max_size == 0 {
+            return Ok(());
+        }
+        let mut depths = HashMap::new();
+        let mut reachable = HashSet::new();
+        let depth = self.nesting_depth(self.ty, &mut vec![], &mut depths, &mut reachable, max_size);
+        let suggestion = "consider marking the functions that use it with `#[trusted]`";
+        if max_depth > 0 && depth > max_depth {
+            return Err(EncodingError::unsupported(format!(
+                "the type `{}` is nested {} levels deep, which exceeds the limit of {} \
+                set by the MAX_TYPE_DEPTH configuration flag; {}",
+                self.ty, depth, max_depth, suggestion,
+            )));
+        }
+        if max_size > 0 && reachable.len() > max_size {
+            return Err(EncodingError::unsupported(format!(
+                "the type `{}` contains more than {} distinct types, which is the limit \
+                set by the MAX_TYPE_SIZE configuration flag; {}",
+                self.ty, max_size, suggestion,
+            )));
+        }
+        Ok(())
+    }
+
+    /// The nesting depth of `ty`, collecting the types that are reachable
+    /// from it. The walk stops at the types that are being visited, because
+    /// recursive types are encoded with recursive predicates, and once more
+    /// than `max_size` types are reachable.
+    fn nesting_depth(
+        &self,
+        ty: ty::Ty<'tcx>,
+        stack: &mut Vec<ty::Ty<'tcx>>,
+        depths: &mut HashMap<ty::Ty<'tcx>, usize>,
+        reachable: &mut HashSet<ty::Ty<'tcx>>,
+        max_size: usize,
+    ) -> usize {
+        if let Some(&depth) = depths.get(&ty) {
+            return depth;
+        }
+        if stack.contains(&ty) || (max_size > 0 && reachable.len() > max_size) {
+            return 0;
+        }
+        reachable.insert(ty);
+        let tcx = self.encoder.env().tcx();
+        let inner_types: Vec<ty::Ty<'tcx>> = match ty.kind() {
+            ty::TyKind::Adt(adt_def, _) if adt_def.is_box() => vec![ty.boxed_ty()],
+            ty::TyKind::Adt(adt_def, substs) => adt_def.all_fields()
+                .map(|field| field.ty(tcx, substs))
+                .collect(),
+            ty::TyKind::Ref(_, inner_ty, _)
+            | ty::TyKind::Array(inner_ty, _)
+            | ty::TyKind::Slice(inner_ty)
+            | ty::TyKind::RawPtr(ty::TypeAndMut { ty: inner_ty, .. }) => vec![*inner_ty],
+            ty::TyKind::Tuple(elems) => elems.types().collect(),
+            _ => vec![],
+        };
+        stack.push(ty);
+        let depth = inner_types.into_iter()
+            .map(|inner_ty| self.nesting_depth(inner_ty, stack, depths, reachable, max_size))
+            .max()
+            .map_or(0, |inner_depth| inner_depth + 1);
+        stack.pop();
+        depths.insert(ty, depth);
+        depth
+    }
+
     pub fn encode_type(self) -> EncodingResult<vir::Type> {
         debug!("Encode type '{:?}'", self.ty);
         Ok(vir::Type::TypedRef(self.encode_predicate_use()?))
@@ -381,21 +455,29 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 vec![vir::Predicate::new_abstract(typ)]
             }
 
+            ty::TyKind::Opaque(..) => {
+                // `impl Trait` types are abstract like type parameters; their
+                // values are only constrained by the contracts of the trait
+                vec![vir::Predicate::new_abstract(typ)]
+            }
+
             ty::TyKind::Closure(_def_id, internal_substs) => {
                 let closure_substs = internal_substs.as_closure();
                 match closure_substs.tupled_upvars_ty().kind() {
                     ty::TyKind::Tuple(_upvar_substs) => {
-                        // TODO: this should encode the state of a closure, i.e.
-                        // the "self" parameter passed into the implementation
-                        // function generated for every closure. This should
-                        // work using snapshots. For now, the "self" parameter
-                        // is skipped in encoding.
-
-                        // let field_name = "upvars".to_owned();
-                        // let field = self.encoder.encode_raw_ref_field(field_name, cl_upvars);
-                        // let pred = vir::Predicate::new_struct(typ, vec![field.clone()]);
-                        let pred = vir::Predicate::new_struct(typ.clone(), vec![]);
-                        // trace!("Encoded closure type {:?} as {:?} with field {:?}", typ, pred, field);
+                        // The state of a closure are its captured paths, one
+                        // field `closure_<i>` per upvar (the same fields are
+                        // used when encoding the projections of the closure).
+                        let fields = closure_substs.upvar_tys()
+                            .enumerate()
+                            .map(|(index, upvar_ty)| {
+                                self.encoder.encode_raw_ref_field(
+                                    format!("closure_{}", index),
+                                    upvar_ty,
+                                )
+                            })
+                            .collect::<Result<Vec<_>, _>>()?;
+                        let pred = vir::Predicate::new_struct(typ.clone(), fields);
                         trace!("Encoded closure type {:?} as {:?}", typ, pred);
                         vec![pred]
                     }
@@ -533,6 +615,15 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
                 format!("__TYPARAM__${}$__", param_ty.name.as_str())
             }
 
+            ty::TyKind::Opaque(def_id, substs) => {
+                format!(
+                    "opaque${}_{}${}",
+                    def_id.krate.as_u32(),
+                    def_id.index.as_u32(),
+                    self.encode_substs(substs)?
+                )
+            }
+
             ty::TyKind::Projection(ty::ProjectionTy { item_def_id, substs }) => {
                 let mut composed_name = vec![self.encoder.encode_item_name(*item_def_id)];
                 // makes generics "less fragile"
@@ -744,7 +835,7 @@ impl<'p, 'v, 'r: 'v, 'tcx: 'v> TypeEncoder<'p, 'v, 'tcx> {
         let tag_name = self.encoder.encode_type_tag_use(self.ty);
 
         let body = match self.ty.kind() {
-            ty::TyKind::Param(_param_ty) => None,
+            ty::TyKind::Param(_param_ty) | ty::TyKind::Opaque(..) => None,
             _ => Some((vir::Const::Int((self.ty as *const ty::TyS<'tcx>) as i64)).into()),
         };
 
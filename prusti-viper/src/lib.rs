@@ -27,5 +27,6 @@ extern crate rustc_attr;
 extern crate lazy_static;
 
 pub mod encoder;
+mod quantifier_profile;
 mod utils;
 pub mod verifier;
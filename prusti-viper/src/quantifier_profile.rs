@@ -0,0 +1,107 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Summary of the quantifier instantiations recorded in a Z3 trace (the
+//! format read by the Axiom Profiler).
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// The number of instantiations of each quantifier, identified by its `qid`.
+pub struct QuantifierProfile {
+    instantiations: HashMap<String, u64>,
+}
+
+impl QuantifierProfile {
+    pub fn from_trace(path: &Path) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        // The ids of the terms are reused after a `pop`, so the name of a
+        // quantifier is looked up when it is matched.
+        let mut quantifiers: HashMap<String, String> = HashMap::new();
+        let mut matches: HashMap<String, String> = HashMap::new();
+        let mut instantiations: HashMap<String, u64> = HashMap::new();
+        for line in reader.lines() {
+            let line = line?;
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                // [mk-quant] #<id> <qid> <number of variables> <patterns> <body>
+                Some("[mk-quant]") => {
+                    if let (Some(id), Some(qid)) = (fields.next(), fields.next()) {
+                        quantifiers.insert(id.to_string(), qid.to_string());
+                    }
+                }
+                // [new-match] <hash> #<quantifier> #<pattern> <bindings> ; <used terms>
+                // [inst-discovered] <method> <hash> #<quantifier> <bindings> ; <used terms>
+                Some(kind @ "[new-match]") | Some(kind @ "[inst-discovered]") => {
+                    if kind == "[inst-discovered]" {
+                        fields.next();
+                    }
+                    if let (Some(hash), Some(id)) = (fields.next(), fields.next()) {
+                        if let Some(qid) = quantifiers.get(id) {
+                            matches.insert(hash.to_string(), qid.clone());
+                        }
+                    }
+                }
+                // [instance] <hash> <proof> ; <generation>
+                Some("[instance]") => {
+                    if let Some(qid) = fields.next().and_then(|hash| matches.get(hash)) {
+                        *instantiations.entry(qid.clone()).or_insert(0) += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(QuantifierProfile { instantiations })
+    }
+
+    pub fn total(&self) -> u64 {
+        self.instantiations.values().sum()
+    }
+
+    /// The `count` quantifiers that were instantiated the most, with their
+    /// number of instantiations.
+    pub fn top(&self, count: usize) -> Vec<(&str, u64)> {
+        let mut quantifiers: Vec<_> = self.instantiations
+            .iter()
+            .map(|(qid, &instantiations)| (qid.as_str(), instantiations))
+            .collect();
+        quantifiers.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        quantifiers.truncate(count);
+        quantifiers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn counts_instances_per_quantifier() {
+        let trace = "\
+[mk-quant] #10 prog.l3 1 #8 #9
+[mk-quant] #20 len_axiom 1 #18 #19
+[new-match] 0x1 #10 #8 #1 ; #2
+[instance] 0x1 ; 1
+[end-of-instance]
+[new-match] 0x2 #10 #8 #3 ; #4
+[instance] 0x2 ; 2
+[end-of-instance]
+[new-match] 0x3 #20 #18 #5 ; #6
+[instance] 0x3 ; 1
+[end-of-instance]
+[new-match] 0x4 #20 #18 #7 ; #6
+";
+        let path = std::env::temp_dir().join("prusti_quantifier_profile_test.log");
+        fs::write(&path, trace).unwrap();
+        let profile = QuantifierProfile::from_trace(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(profile.total(), 3);
+        assert_eq!(profile.top(1), vec![("prog.l3", 2)]);
+    }
+}
@@ -6,25 +6,38 @@
 
 use prusti_common::vir::{self, optimizations, ToViper, ToViperDecl};
 use prusti_common::{
-    config, report::log, verification_context::VerifierBuilder, verification_service::*, Stopwatch,
+    config, report::{log, progress, user}, utils::memory, verification_context::VerifierBuilder, verification_service::*, Stopwatch,
 };
 use crate::encoder::Encoder;
 // use prusti_filter::validators::Validator;
 use prusti_interface::data::VerificationResult;
 use prusti_interface::data::VerificationTask;
 use prusti_interface::environment::Environment;
+use prusti_interface::data::ProcedureDefId;
 use prusti_interface::PrustiError;
+use prusti_interface::utils::read_prusti_attr_args;
 // use prusti_interface::specifications::TypedSpecificationMap;
-use std::time::Instant;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 use viper::{self, VerificationBackend, Viper};
-use std::path::PathBuf;
-use std::fs::{create_dir_all, canonicalize};
+use std::path::{Path, PathBuf};
+use std::fs::{create_dir_all, canonicalize, remove_file, OpenOptions};
+use std::io::Write;
+use crate::quantifier_profile::QuantifierProfile;
 use std::ffi::OsString;
 use prusti_interface::specs::typed;
 use ::log::{info, debug, error};
 use prusti_server::{PrustiServerConnection, ServerSideService, VerifierRunner};
 use rustc_span::DUMMY_SP;
 
+/// The seed sweep reports a verification time that varies by more than this
+/// factor across the seeds...
+const SEED_SWEEP_TIME_RATIO: u32 = 4;
+/// ...and by more than this duration.
+const SEED_SWEEP_MIN_TIME_DIFFERENCE: Duration = Duration::from_secs(2);
+
 // /// A verifier builder is an object that lives entire program's
 // /// lifetime, has no mutable state, and is responsible for constructing
 // /// verification context instances. The user of this interface is supposed
@@ -139,6 +152,12 @@ where
 {
     env: &'v Environment<'tcx>,
     encoder: Encoder<'v, 'tcx>,
+    /// The items of the last verified task that verified successfully.
+    successful_items: Vec<ProcedureDefId>,
+    /// The builder that owns the JVM of this process, if the programs are
+    /// verified without a Prusti server. A process can start only one JVM,
+    /// so all the programs of the crate are verified with this builder.
+    verifier_builder: Option<VerifierBuilder>,
 }
 
 impl<'v, 'tcx> Verifier<'v, 'tcx> {
@@ -149,9 +168,236 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
         Verifier {
             env,
             encoder: Encoder::new(env, def_spec),
+            successful_items: vec![],
+            verifier_builder: None,
+        }
+    }
+
+    /// The items of the last verified task that were encoded and verified
+    /// without errors. Trusted items are not included.
+    pub fn get_successful_items(&self) -> &[ProcedureDefId] {
+        &self.successful_items
+    }
+
+    /// Report the quantifiers that were instantiated the most, which are
+    /// the usual suspects of matching loops.
+    fn report_quantifier_instantiations(&self, trace: &Path) {
+        // The trace is missing if the backend does not support it or runs
+        // on another machine.
+        let profile = match QuantifierProfile::from_trace(trace) {
+            Ok(profile) => profile,
+            Err(error) => {
+                user::message(format!(
+                    "Quantifier profiling is not available ({}): {}",
+                    trace.display(),
+                    error
+                ));
+                return;
+            }
+        };
+        user::message(format!("Quantifier instantiations: {} in total", profile.total()));
+        for (qid, instantiations) in profile.top(config::profile_quantifiers_top()) {
+            user::message(format!("{:>10}  {}", instantiations, qid));
+        }
+    }
+
+    /// Print the contract of the procedures selected by `print_contract`.
+    fn print_contracts(&self, procedures: &[ProcedureDefId]) {
+        let item_path = config::print_contract();
+        if item_path.is_empty() {
+            return;
+        }
+        let matching: Vec<_> = procedures.iter()
+            .filter(|&&proc_id| {
+                let name = self.env.get_item_name(proc_id);
+                name == item_path || name.ends_with(&format!("::{}", item_path))
+            })
+            .collect();
+        if matching.is_empty() {
+            user::message(format!("No verified item matches `{}`", item_path));
+        }
+        for &proc_id in matching {
+            user::message(self.encoder.describe_procedure_contract(proc_id));
+        }
+    }
+
+    /// Report an error for the functions marked with `#[trusted_unsafe]` that
+    /// contain no unsafe code, since `#[trusted]` states the intent better.
+    fn check_trusted_unsafe_functions(&self, procedures: &[ProcedureDefId]) {
+        for &proc_id in procedures {
+            if !self.env.has_prusti_attribute(proc_id, "trusted_unsafe") {
+                continue;
+            }
+            if let Some(local_id) = proc_id.as_local() {
+                if !self.env.contains_unsafe_code(local_id) {
+                    PrustiError::incorrect(
+                        "the `#[trusted_unsafe]` attribute can only be used on functions \
+                        that contain unsafe code",
+                        self.env.get_item_span(proc_id).into(),
+                    ).set_help("use `#[trusted]` instead").emit(self.env);
+                }
+            }
+        }
+    }
+
+    /// List the functions marked with `#[trusted_unsafe]`, whose unsafe code
+    /// is not verified and whose contract is assumed.
+    fn report_trusted_unsafe_functions(&self, procedures: &[ProcedureDefId]) {
+        let trusted_unsafe: Vec<_> = procedures
+            .iter()
+            .filter(|&&proc_id| self.env.has_prusti_attribute(proc_id, "trusted_unsafe"))
+            .collect();
+        if trusted_unsafe.is_empty() {
+            return;
+        }
+        user::message(format!("Trusted unsafe functions ({}):", trusted_unsafe.len()));
+        let source_map = self.env.tcx().sess.source_map();
+        for &proc_id in trusted_unsafe {
+            user::message(format!(
+                " - {} at {}",
+                self.env.get_absolute_item_name(proc_id),
+                source_map.span_to_string(self.env.get_item_span(proc_id)),
+            ));
+        }
+    }
+
+    /// List every trusted function, the verified functions that call it and
+    /// how many postconditions were assumed at those calls. The report is
+    /// printed and also written as JSON to the `trusted` log directory.
+    fn report_trusted_functions(&self, procedures: &[ProcedureDefId]) {
+        let trusted_calls = self.encoder.get_trusted_calls();
+        let mut trusted: Vec<ProcedureDefId> = procedures
+            .iter()
+            .cloned()
+            .filter(|&proc_id| self.encoder.is_trusted(proc_id))
+            .chain(trusted_calls.keys().cloned())
+            .collect();
+        trusted.sort_by_key(|&proc_id| self.env.get_absolute_item_name(proc_id));
+        trusted.dedup();
+
+        let source_map = self.env.tcx().sess.source_map();
+        user::message(format!("Trusted functions ({}):", trusted.len()));
+        let mut json_report = vec![];
+        for proc_id in trusted {
+            let name = self.env.get_absolute_item_name(proc_id);
+            let location = source_map.span_to_string(self.env.get_item_span(proc_id));
+            user::message(format!(" - {} at {}", name, location));
+            let mut callers: Vec<_> = trusted_calls
+                .get(&proc_id)
+                .map(|callers| callers.iter()
+                    .map(|(&caller, &assumed)| (self.env.get_absolute_item_name(caller), assumed))
+                    .collect())
+                .unwrap_or_default();
+            callers.sort();
+            for (caller, assumed) in &callers {
+                user::message(format!(
+                    "     used by {} ({} assumed postcondition(s))", caller, assumed
+                ));
+            }
+            json_report.push(serde_json::json!({
+                "name": name,
+                "location": location,
+                "callers": callers.iter().map(|(caller, assumed)| serde_json::json!({
+                    "name": caller,
+                    "assumed_postconditions": assumed,
+                })).collect::<Vec<_>>(),
+            }));
+        }
+        log::report(
+            "trusted",
+            "trusted_functions.json",
+            serde_json::Value::Array(json_report),
+        );
+    }
+
+    /// Warn that the verification of `proc_id` relies on the contracts of the
+    /// procedures of its recursive cycle that could not be proven.
+    fn report_unproven_cycle_contracts(
+        &self,
+        proc_id: ProcedureDefId,
+        cycle: &[ProcedureDefId],
+        failed_items: &HashSet<ProcedureDefId>,
+    ) {
+        let unproven: Vec<_> = cycle.iter()
+            .filter(|item| failed_items.contains(item))
+            .map(|&item| format!("`{}`", self.env.get_item_name(item)))
+            .collect();
+        PrustiError::warning(
+            format!(
+                "the verification of `{}` assumes the contracts of {}, which could not be proven",
+                self.env.get_item_name(proc_id),
+                unproven.join(", "),
+            ),
+            self.env.get_item_span(proc_id).into(),
+        ).emit(self.env);
+    }
+
+    /// Verify the vacuity checks of the preconditions, which are expected to
+    /// fail, and warn about the preconditions whose check succeeds because
+    /// they are unsatisfiable.
+    fn check_vacuity(&self, program_name: &str) {
+        let program = match self.encoder.get_vacuity_check_program() {
+            Some(program) => program.lower().into_program(),
+            None => return,
+        };
+        let verification_errors = match self.run_backend(program, format!("{}-vacuity", program_name)) {
+            viper::VerificationResult::Success() => vec![],
+            viper::VerificationResult::Failure(errors) => errors,
+            _ => {
+                PrustiError::internal(
+                    "the vacuity checks of the preconditions could not be verified",
+                    DUMMY_SP.into(),
+                ).emit(self.env);
+                return;
+            }
+        };
+        let error_manager = self.encoder.error_manager();
+        let failed_vacuity_checks: HashSet<u64> = verification_errors
+            .iter()
+            .filter_map(|error| error_manager.get_vacuity_check(error))
+            .collect();
+        for (pos_id, span) in error_manager.vacuity_checks() {
+            if !failed_vacuity_checks.contains(&pos_id) {
+                PrustiError::warning(
+                    "this precondition is unsatisfiable, so the function verifies vacuously",
+                    span,
+                ).emit(self.env);
+            }
         }
     }
 
+    /// Explain how the procedures of the recursive cycle of `proc_id` are
+    /// verified, for the errors reported in `proc_id`.
+    fn describe_cycle(&self, proc_id: ProcedureDefId, cycle: &[ProcedureDefId]) -> String {
+        let mut names: Vec<_> = cycle.iter()
+            .map(|&item| format!("`{}`", self.env.get_item_name(item)))
+            .collect();
+        names.push(names[0].clone());
+        let mut help = format!(
+            "`{}` is part of the recursive cycle {}; the calls within the cycle assume the \
+            contracts of the called functions, so every contract of the cycle has to be proven",
+            self.env.get_item_name(proc_id),
+            names.join(" -> "),
+        );
+        let without_contract: Vec<_> = cycle.iter()
+            .filter(|&&item| {
+                self.encoder.get_procedure_specs(item)
+                    .map(|spec| spec.pres.is_empty() && spec.posts.is_empty())
+                    .unwrap_or(true)
+            })
+            .map(|&item| format!("`{}`", self.env.get_item_name(item)))
+            .collect();
+        if !without_contract.is_empty() {
+            help.push_str(&format!(
+                "; {} {} no contract, so calling {} within the cycle guarantees nothing",
+                without_contract.join(", "),
+                if without_contract.len() == 1 { "has" } else { "have" },
+                if without_contract.len() == 1 { "it" } else { "them" },
+            ));
+        }
+        help
+    }
+
     pub fn verify(&mut self, task: &VerificationTask) -> VerificationResult {
         info!(
             "Received {} functions to be verified:",
@@ -159,16 +405,135 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
         );
 
         let mut stopwatch = Stopwatch::start("prusti-viper", "encoding to Viper");
+        let mut phase_times = PhaseTimes::start();
+        self.successful_items.clear();
 
         // Dump the configuration
         log::report("config", "prusti", config::dump());
 
+        let mut program = self.encode(task);
+        let encoding_errors_count = self.encoder.count_encoding_errors();
+        phase_times.finish("encoding");
+
+        if config::simplify_encoding() {
+            stopwatch.start_next("optimizing Viper program");
+            program = self.optimize(program);
+            phase_times.finish("optimization");
+        }
+        let program = self.lower(program);
+        phase_times.finish("lowering");
+
+        let program_statistics = if config::encoding_statistics().is_empty() {
+            None
+        } else {
+            Some(program.statistics())
+        };
+        if config::encode_only() {
+            stopwatch.finish();
+            if let Some(program_statistics) = program_statistics {
+                self.report_encoding_statistics(program_statistics, &phase_times);
+            }
+            return if encoding_errors_count == 0 {
+                VerificationResult::Success
+            } else {
+                VerificationResult::Failure
+            };
+        }
+
+        if config::server_address().is_none() && self.verifier_builder.is_none() {
+            stopwatch.start_next("JVM startup");
+            self.verifier_builder = Some(VerifierBuilder::new());
+        }
+        stopwatch.start_next("verifying Viper program");
+        let source_path = self.env.source_path();
+        let program_name = source_path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+        // Grouping the errors by cause and the seed sweep verify variants of
+        // the program.
+        let retained_program = if config::group_errors_by_cause() || config::seed_sweep_runs() > 0 {
+            Some(program.clone())
+        } else {
+            None
+        };
+        let start = Instant::now();
+        let verification_result = self.run_verification(program, &program_name, &task.procedures);
+        let verification_time = start.elapsed();
+        phase_times.finish("verification");
+
+        stopwatch.finish();
+        if let Some(program_statistics) = program_statistics {
+            self.report_encoding_statistics(program_statistics, &phase_times);
+        }
+
+        let verification_errors = match self.take_verification_errors(verification_result) {
+            Some(verification_errors) => verification_errors,
+            None => return VerificationResult::Failure,
+        };
+        if encoding_errors_count == 0 {
+            self.check_vacuity(&program_name);
+        }
+        let verification_errors = self.remove_allowed_errors(verification_errors);
+
+        let failed_items: HashSet<ProcedureDefId> = {
+            let error_manager = self.encoder.error_manager();
+            verification_errors
+                .iter()
+                .filter_map(|error| error_manager.get_item(error))
+                .collect()
+        };
+        let failed_cycles = self.failed_cycles(&task.procedures, &failed_items);
+        let verified_items = self.report_item_results(
+            &task.procedures,
+            &verification_errors,
+            &failed_items,
+            &failed_cycles,
+        );
+        if config::seed_sweep_runs() > 0 {
+            if let Some(program) = &retained_program {
+                self.sweep_seeds(
+                    program,
+                    &program_name,
+                    &verified_items,
+                    &failed_items,
+                    verification_time,
+                );
+            }
+        }
+
+        if encoding_errors_count == 0 && verification_errors.is_empty() {
+            VerificationResult::Success
+        } else {
+            let program_to_group_errors = retained_program
+                .as_ref()
+                .filter(|_| config::group_errors_by_cause());
+            self.report_verification_errors(
+                &verification_errors,
+                &failed_cycles,
+                program_to_group_errors,
+                &program_name,
+            );
+            VerificationResult::Failure
+        }
+    }
+
+    /// Run the checks and reports that precede the encoding, and encode the
+    /// procedures of the task.
+    fn encode(&mut self, task: &VerificationTask) -> vir::Program {
         for &proc_id in &task.procedures {
             let proc_name = self.env.get_absolute_item_name(proc_id);
             let proc_def_path = self.env.get_item_def_path(proc_id);
             let proc_span = self.env.get_item_span(proc_id);
             info!(" - {} from {:?} ({})", proc_name, proc_span, proc_def_path);
         }
+        self.check_trusted_unsafe_functions(&task.procedures);
+        if config::report_trusted_unsafe_functions() {
+            self.report_trusted_unsafe_functions(&task.procedures);
+        }
+        self.print_contracts(&task.procedures);
 
         // // Check support status, and queue encoding
         // let validator = Validator::new(self.env.tcx());
@@ -239,61 +604,74 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
             // FIXME: Use the loop above.
             self.encoder.queue_procedure_encoding(proc_id);
         }
+        if config::check_const_evaluation() {
+            self.encoder.encode_const_eval_checks();
+        }
         self.encoder.process_encoding_queue();
+        if config::report_trusted_functions() {
+            self.report_trusted_functions(&task.procedures);
+        }
 
-        let encoding_errors_count = self.encoder.count_encoding_errors();
-        let mut program = self.encoder.get_viper_program();
+        self.encoder.get_viper_program()
+    }
 
-        if config::simplify_encoding() {
-            stopwatch.start_next("optimizing Viper program");
-            let source_file_name = self.encoder.env().source_file_name();
-            program = program.optimized(&source_file_name);
+    /// Optimize the encoded program, except for the methods whose
+    /// optimization is disabled.
+    fn optimize(&self, program: vir::Program) -> vir::Program {
+        let source_file_name = self.encoder.env().source_file_name();
+        let unoptimized_methods = self.encoder.get_unoptimized_methods();
+        program.optimized_except(&source_file_name, &unoptimized_methods)
+    }
+
+    /// Lower the program to the backend-level VIR, and export its proof
+    /// obligations if `config::export_obligations()` is set.
+    fn lower(&self, program: vir::Program) -> vir::Program {
+        report_vir_passes(&program, 3, "simplified");
+        // The backends only understand the backend-level VIR.
+        let program = program.lower().into_program();
+        report_vir_passes(&program, 4, "lowered");
+        if !config::export_obligations().is_empty() {
+            self.export_obligations(&program);
         }
+        program
+    }
 
-        stopwatch.start_next("verifying Viper program");
-        let source_path = self.env.source_path();
-        let program_name = source_path
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_owned();
-        let verification_result: viper::VerificationResult = if let Some(server_address) =
-            config::server_address()
-        {
-            let server_address = if server_address == "MOCK" {
-                ServerSideService::spawn_off_thread().to_string()
-            } else {
-                server_address
-            };
-            info!("Connecting to Prusti server at {}", server_address);
-            let service = PrustiServerConnection::new(&server_address).unwrap_or_else(|error| {
-                panic!(
-                    "Could not parse server address ({}) due to {:?}",
-                    server_address, error
-                )
-            });
-
-            let request = VerificationRequest {
-                program,
-                program_name,
-                backend_config: Default::default(),
-            };
-            service.verify(request)
+    /// Verify the program with the backend, or with the portfolio of
+    /// backends if one is configured, profiling the quantifier
+    /// instantiations if `config::profile_quantifiers()` is set.
+    fn run_verification(
+        &self,
+        program: vir::Program,
+        program_name: &str,
+        procedures: &[ProcedureDefId],
+    ) -> viper::VerificationResult {
+        let quantifier_trace = config::quantifier_trace_file();
+        if config::profile_quantifiers() {
+            if let Some(trace_dir) = quantifier_trace.parent() {
+                create_dir_all(trace_dir).ok();
+            }
+            remove_file(&quantifier_trace).ok();
+        }
+        let verification_result = if config::portfolio_backends().is_empty() {
+            self.run_backend(program, program_name.to_string())
         } else {
-            let mut stopwatch = Stopwatch::start("prusti-viper", "JVM startup");
-            let verifier_builder = VerifierBuilder::new();
-            stopwatch.start_next("running verifier");
-            VerifierRunner::with_default_configured_runner(&verifier_builder, |runner| {
-                runner.verify(program, program_name.as_str())
-            })
+            self.run_portfolio(program, program_name, procedures)
         };
+        if config::profile_quantifiers() {
+            self.report_quantifier_instantiations(&quantifier_trace);
+        }
+        verification_result
+    }
 
-        stopwatch.finish();
-
-        let verification_errors = match verification_result {
-            viper::VerificationResult::Success() => vec![],
-            viper::VerificationResult::Failure(errors) => errors,
+    /// The verification errors reported by the backend, or `None` if the
+    /// backend failed, in which case the failure has been reported.
+    fn take_verification_errors(
+        &self,
+        verification_result: viper::VerificationResult,
+    ) -> Option<Vec<viper::VerificationError>> {
+        match verification_result {
+            viper::VerificationResult::Success() => Some(vec![]),
+            viper::VerificationResult::Failure(errors) => Some(errors),
             viper::VerificationResult::ConsistencyErrors(errors) => {
                 debug_assert!(!errors.is_empty());
                 errors.iter().for_each(|e| {
@@ -301,29 +679,762 @@ impl<'v, 'tcx> Verifier<'v, 'tcx> {
                         format!("consistency error: {}", e), DUMMY_SP.into()
                     ).emit(self.env)
                 });
-                return VerificationResult::Failure;
+                None
             }
             viper::VerificationResult::JavaException(exception) => {
                 error!("Java exception: {}", exception.get_stack_trace());
                 PrustiError::internal(
                     format!("{}", exception), DUMMY_SP.into()
                 ).emit(self.env);
-                return VerificationResult::Failure;
+                None
             }
+        }
+    }
+
+    /// Remove the errors whose class is allowed with
+    /// `#[prusti::allow_error(<code>)]` on the item in which they occur.
+    fn remove_allowed_errors(
+        &self,
+        verification_errors: Vec<viper::VerificationError>,
+    ) -> Vec<viper::VerificationError> {
+        let error_manager = self.encoder.error_manager();
+        verification_errors
+            .into_iter()
+            .filter(|error| {
+                match (error_manager.get_error_code(error), error_manager.get_item(error)) {
+                    (Some(code), Some(item)) => {
+                        let attrs = self.env.tcx().get_attrs(item);
+                        let allowed = read_prusti_attr_args("allow_error", attrs)
+                            .iter()
+                            .any(|allowed_code| allowed_code == code.as_str());
+                        if allowed {
+                            debug!("Allowed verification error: {:?}", error);
+                        }
+                        !allowed
+                    }
+                    _ => true,
+                }
+            })
+            .collect()
+    }
+
+    /// The cycles of mutually recursive procedures that contain a failed
+    /// item. Within a cycle, each procedure is verified assuming the
+    /// contracts of the others at the calls within the cycle, so the cycle
+    /// is proven only if all its contracts are.
+    fn failed_cycles(
+        &self,
+        procedures: &[ProcedureDefId],
+        failed_items: &HashSet<ProcedureDefId>,
+    ) -> Vec<Vec<ProcedureDefId>> {
+        self.env
+            .get_recursive_cycles(procedures)
+            .into_iter()
+            .filter(|cycle| cycle.len() > 1 && cycle.iter().any(|item| failed_items.contains(item)))
+            .collect()
+    }
+
+    /// Report the verification status of each procedure, remember the
+    /// procedures that verified successfully, and return the procedures
+    /// that were verified.
+    fn report_item_results<'a>(
+        &mut self,
+        procedures: &'a [ProcedureDefId],
+        verification_errors: &[viper::VerificationError],
+        failed_items: &HashSet<ProcedureDefId>,
+        failed_cycles: &[Vec<ProcedureDefId>],
+    ) -> Vec<&'a ProcedureDefId> {
+        // An error that cannot be attributed to an item may belong to any.
+        let has_unattributed_errors = {
+            let error_manager = self.encoder.error_manager();
+            verification_errors
+                .iter()
+                .any(|error| error_manager.get_item(error).is_none())
         };
+        let verified_items: Vec<_> = procedures
+            .iter()
+            .filter(|&&proc_id| {
+                !self.encoder.has_encoding_failed(proc_id) && !self.encoder.is_trusted(proc_id)
+            })
+            .collect();
+        for (index, &&proc_id) in verified_items.iter().enumerate() {
+            let status = if failed_items.contains(&proc_id) {
+                progress::ItemStatus::Failed
+            } else {
+                progress::ItemStatus::Verified
+            };
+            if status == progress::ItemStatus::Verified {
+                if let Some(cycle) = cycle_of(failed_cycles, proc_id) {
+                    self.report_unproven_cycle_contracts(proc_id, cycle, failed_items);
+                } else if !has_unattributed_errors {
+                    self.successful_items.push(proc_id);
+                }
+            }
+            self.encoder.report_progress(proc_id, status, index + 1, verified_items.len());
+        }
+        let trusted_items: Vec<_> = procedures
+            .iter()
+            .filter(|&&proc_id| self.encoder.is_trusted(proc_id))
+            .collect();
+        for (index, &&proc_id) in trusted_items.iter().enumerate() {
+            self.encoder.report_progress(
+                proc_id,
+                progress::ItemStatus::Skipped,
+                index + 1,
+                trusted_items.len(),
+            );
+        }
+        verified_items
+    }
 
-        if encoding_errors_count == 0 && verification_errors.is_empty() {
-            VerificationResult::Success
-        } else {
+    /// Translate the verification errors to Prusti errors and emit them,
+    /// grouped by cause if `program_to_group_errors` is given.
+    fn report_verification_errors(
+        &self,
+        verification_errors: &[viper::VerificationError],
+        failed_cycles: &[Vec<ProcedureDefId>],
+        program_to_group_errors: Option<&vir::Program>,
+        program_name: &str,
+    ) {
+        let mut prusti_errors: Vec<_> = {
             let error_manager = self.encoder.error_manager();
+            verification_errors
+                .iter()
+                .map(|verification_error| {
+                    debug!("Verification error: {:?}", verification_error);
+                    let mut prusti_error = error_manager.translate_verification_error(verification_error);
+                    let failed_cycle = error_manager.get_item(verification_error)
+                        .and_then(|item| cycle_of(failed_cycles, item).map(|cycle| (item, cycle)));
+                    if let Some((item, cycle)) = failed_cycle {
+                        prusti_error = prusti_error.add_help(self.describe_cycle(item, cycle));
+                    }
+                    prusti_error
+                })
+                .collect()
+        };
+        if let Some(program) = program_to_group_errors {
+            let causes = self.find_error_causes(program, program_name, verification_errors);
+            prusti_errors = self.group_errors_by_cause(prusti_errors, &causes);
+        }
+        for prusti_error in prusti_errors {
+            debug!("Prusti error: {:?}", prusti_error);
+            prusti_error.emit(self.env);
+        }
+    }
 
-            for verification_error in verification_errors {
-                debug!("Verification error: {:?}", verification_error);
-                let prusti_error = error_manager.translate_verification_error(&verification_error);
-                debug!("Prusti error: {:?}", prusti_error);
-                prusti_error.emit(self.env);
+    /// Append the statistics of the verification of this crate to the file
+    /// of `config::encoding_statistics()`.
+    fn report_encoding_statistics(
+        &self,
+        program_statistics: vir::ProgramStatistics,
+        phase_times: &PhaseTimes,
+    ) {
+        let phases: serde_json::Map<_, _> = phase_times.phases
+            .iter()
+            .map(|(phase, duration)| (phase.to_string(), duration.as_secs_f64().into()))
+            .collect();
+        let statistics = serde_json::json!({
+            "crate": self.env.crate_name(),
+            "program": program_statistics,
+            "peak_memory_mb": memory::peak_memory_mb(),
+            "phases": phases,
+        });
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(config::encoding_statistics())
+            .and_then(|mut file| writeln!(file, "{}", statistics));
+        if let Err(err) = written {
+            error!("Failed to write the encoding statistics: {}", err);
+        }
+    }
+
+    /// Write the proof obligations of the program to the file of
+    /// `config::export_obligations()`, which also describes the format.
+    fn export_obligations(&self, program: &vir::Program) {
+        struct ObligationCollector {
+            obligations: Vec<(vir::Expr, vir::Position)>,
+        }
+        impl vir::StmtWalker for ObligationCollector {
+            fn walk_exhale(&mut self, expr: &vir::Expr, pos: &vir::Position) {
+                self.obligations.push((expr.clone(), *pos));
             }
-            VerificationResult::Failure
+            fn walk_assert(&mut self, expr: &vir::Expr, pos: &vir::Position) {
+                self.obligations.push((expr.clone(), *pos));
+            }
+        }
+
+        let mut items: Vec<(String, Vec<(vir::Expr, vir::Position)>)> = vec![];
+        for method in &program.methods {
+            let mut collector = ObligationCollector { obligations: vec![] };
+            for block in &method.basic_blocks {
+                for stmt in &block.stmts {
+                    vir::StmtWalker::walk(&mut collector, stmt);
+                }
+            }
+            items.push((method.name(), collector.obligations));
+        }
+        for function in &program.functions {
+            // The postconditions of bodyless functions are assumed.
+            if function.body.is_some() {
+                let posts = function.posts.iter().map(|post| (post.clone(), post.pos())).collect();
+                items.push((function.name.clone(), posts));
+            }
+        }
+
+        let error_manager = self.encoder.error_manager();
+        let source_map = self.env.tcx().sess.source_map();
+        let mut obligations = vec![];
+        for (item, item_obligations) in items {
+            for (formula, pos) in item_obligations {
+                let (span, kind) = match error_manager.get_registered_error(&pos) {
+                    Some((span, error_ctxt)) => match error_ctxt.obligation_kind() {
+                        Some(kind) => (span, kind),
+                        None => continue,
+                    },
+                    None => continue,
+                };
+                let spans: Vec<_> = span.primary_spans()
+                    .iter()
+                    .map(|&span| {
+                        let start = source_map.lookup_char_pos(span.lo());
+                        let end = source_map.lookup_char_pos(span.hi());
+                        serde_json::json!({
+                            "location": source_map.span_to_string(span),
+                            "start_line": start.line,
+                            "start_column": start.col.0 + 1,
+                            "end_line": end.line,
+                            "end_column": end.col.0 + 1,
+                        })
+                    })
+                    .collect();
+                obligations.push(serde_json::json!({
+                    "kind": kind,
+                    "item": item,
+                    "spans": spans,
+                    "formula": formula.to_string(),
+                }));
+            }
+        }
+        let report = obligations_report(&self.env.crate_name(), obligations);
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(config::export_obligations())
+            .and_then(|mut file| writeln!(file, "{}", report));
+        if let Err(err) = written {
+            error!("Failed to write the proof obligations: {}", err);
+        }
+    }
+
+    /// Verify a backend-level program, on the Prusti server if one is
+    /// configured.
+    fn run_backend(&self, program: vir::Program, program_name: String) -> viper::VerificationResult {
+        run_backend_with_config(
+            self.verifier_builder.as_ref(),
+            program,
+            program_name,
+            ViperBackendConfig::default(),
+        )
+    }
+
+    /// Verify the program with the default backend configuration and with
+    /// those of `config::portfolio_backends()`, and report which
+    /// configuration verified each item. The errors of the items that no
+    /// configuration verifies are those of the default configuration.
+    fn run_portfolio(
+        &self,
+        program: vir::Program,
+        program_name: &str,
+        procedures: &[ProcedureDefId],
+    ) -> viper::VerificationResult {
+        let mut configurations = vec![
+            ("the default configuration".to_string(), ViperBackendConfig::default()),
+        ];
+        for portfolio_backend in config::portfolio_backends() {
+            let mut words = portfolio_backend.split_whitespace();
+            let backend = match words.next() {
+                Some(backend) => VerificationBackend::from_str(backend),
+                None => continue,
+            };
+            // Without a server, the programs are verified with the JVM of
+            // this process, whose classpath only contains the default backend.
+            if config::server_address().is_none() && backend != configurations[0].1.backend {
+                PrustiError::warning(
+                    format!(
+                        "the portfolio backend `{}` is ignored, because a backend other than \
+                        the default one requires a Prusti server (SERVER_ADDRESS)",
+                        portfolio_backend.trim(),
+                    ),
+                    DUMMY_SP.into(),
+                ).emit(self.env);
+                continue;
+            }
+            let mut backend_config = ViperBackendConfig::new(backend);
+            backend_config.verifier_args.extend(words.map(String::from));
+            configurations.push((format!("`{}`", portfolio_backend.trim()), backend_config));
         }
+
+        // With a server, the configurations run in parallel. Otherwise, they
+        // are tried one after the other with the JVM of this process, which
+        // is shared by all the runs because a process can start only one JVM.
+        let results: Box<dyn Iterator<Item = (usize, viper::VerificationResult)> + '_> =
+            if config::server_address().is_some() {
+                let (sender, receiver) = mpsc::channel();
+                for (index, (_, backend_config)) in configurations.iter().enumerate() {
+                    let sender = sender.clone();
+                    let program = program.clone();
+                    let program_name = program_name.to_string();
+                    let backend_config = backend_config.clone();
+                    thread::spawn(move || {
+                        let result = run_backend_with_config(
+                            None,
+                            program,
+                            program_name,
+                            backend_config,
+                        );
+                        // The portfolio might not wait for all the results.
+                        let _ = sender.send((index, result));
+                    });
+                }
+                Box::new(receiver.into_iter())
+            } else {
+                Box::new(configurations.iter().enumerate().map(|(index, (_, backend_config))| {
+                    let result = run_backend_with_config(
+                        self.verifier_builder.as_ref(),
+                        program.clone(),
+                        program_name.to_string(),
+                        backend_config.clone(),
+                    );
+                    (index, result)
+                }))
+            };
+
+        let error_manager = self.encoder.error_manager();
+        let mut pending_items: HashSet<ProcedureDefId> = procedures.iter().cloned().collect();
+        let mut winning_configurations = vec![];
+        let mut default_result = None;
+        for (index, result) in results {
+            // A configuration verifies the items without errors, unless one
+            // of its errors cannot be attributed to an item.
+            let failed_items: Option<HashSet<ProcedureDefId>> = match &result {
+                viper::VerificationResult::Success() => Some(HashSet::new()),
+                viper::VerificationResult::Failure(errors) => errors
+                    .iter()
+                    .map(|error| error_manager.get_item(error))
+                    .collect(),
+                _ => None,
+            };
+            if let Some(failed_items) = failed_items {
+                let verified_items: Vec<_> = pending_items
+                    .iter()
+                    .filter(|item| !failed_items.contains(item))
+                    .cloned()
+                    .collect();
+                for item in verified_items {
+                    pending_items.remove(&item);
+                    winning_configurations.push((item, index));
+                }
+            }
+            if index == 0 {
+                default_result = Some(result);
+            }
+            if pending_items.is_empty() {
+                break;
+            }
+        }
+
+        for (item, index) in winning_configurations {
+            if !self.encoder.is_trusted(item) {
+                user::message(format!(
+                    "{} verified with {}",
+                    self.env.get_absolute_item_name(item),
+                    configurations[index].0,
+                ));
+            }
+        }
+        if pending_items.is_empty() {
+            return viper::VerificationResult::Success();
+        }
+        match default_result.expect("the default backend configuration did not report a result") {
+            viper::VerificationResult::Failure(errors) => viper::VerificationResult::Failure(
+                errors
+                    .into_iter()
+                    .filter(|error| {
+                        error_manager.get_item(error).map_or(true, |item| pending_items.contains(&item))
+                    })
+                    .collect()
+            ),
+            result => result,
+        }
+    }
+
+    /// Verify the program again with `config::seed_sweep_runs()` different
+    /// random seeds of Z3, and warn about the items whose verdict depends on
+    /// the seed and about a verification time that varies wildly.
+    fn sweep_seeds(
+        &self,
+        program: &vir::Program,
+        program_name: &str,
+        verified_items: &[&ProcedureDefId],
+        failed_items: &HashSet<ProcedureDefId>,
+        verification_time: Duration,
+    ) {
+        let default_config = ViperBackendConfig::default();
+        if default_config.backend != VerificationBackend::Silicon {
+            PrustiError::warning(
+                "the seed sweep (SEED_SWEEP_RUNS) is only supported by Silicon",
+                DUMMY_SP.into(),
+            ).emit(self.env);
+            return;
+        }
+        let error_manager = self.encoder.error_manager();
+        // The seeds with which each item failed; the seed 0 is the default.
+        let mut failing_seeds: HashMap<ProcedureDefId, Vec<u64>> = failed_items
+            .iter()
+            .map(|&item| (item, vec![0]))
+            .collect();
+        let mut times = vec![verification_time];
+        for seed in 1..=config::seed_sweep_runs() {
+            let mut backend_config = default_config.clone();
+            backend_config.add_z3_args(
+                &format!("smt.random_seed={} sat.random_seed={}", seed, seed),
+            );
+            debug!("Re-verifying the program with the random seed {}", seed);
+            let start = Instant::now();
+            let result = run_backend_with_config(
+                self.verifier_builder.as_ref(),
+                program.clone(),
+                program_name.to_string(),
+                backend_config,
+            );
+            let errors = match result {
+                viper::VerificationResult::Success() => vec![],
+                viper::VerificationResult::Failure(errors) => errors,
+                // A run that did not verify anything tells nothing about
+                // the seeds, so it is not counted.
+                _ => continue,
+            };
+            times.push(start.elapsed());
+            let failed_with_seed: HashSet<_> = errors
+                .iter()
+                .filter_map(|error| error_manager.get_item(error))
+                .collect();
+            for item in failed_with_seed {
+                failing_seeds.entry(item).or_default().push(seed);
+            }
+        }
+        // The runs that completed, including the one with the default seed.
+        let runs = times.len();
+        for &&proc_id in verified_items {
+            if let Some(seeds) = failing_seeds.get(&proc_id) {
+                if seeds.len() < runs {
+                    PrustiError::warning(
+                        format!(
+                            "the verification of `{}` is brittle: it failed with {} of {} \
+                            random seeds of the SMT solver",
+                            self.env.get_item_name(proc_id),
+                            seeds.len(),
+                            runs,
+                        ),
+                        self.env.get_item_span(proc_id).into(),
+                    ).set_help(format!(
+                        "failing seeds: {}; consider simplifying the quantifiers of the \
+                        specification or adding triggers",
+                        seeds.iter().map(|seed| seed.to_string()).collect::<Vec<_>>().join(", "),
+                    )).emit(self.env);
+                }
+            }
+        }
+        let fastest = times.iter().min().unwrap();
+        let slowest = times.iter().max().unwrap();
+        if *slowest > *fastest * SEED_SWEEP_TIME_RATIO
+            && *slowest - *fastest > SEED_SWEEP_MIN_TIME_DIFFERENCE
+        {
+            PrustiError::warning(
+                format!(
+                    "the verification time varies between {:.2}s and {:.2}s across {} random \
+                    seeds of the SMT solver",
+                    fastest.as_secs_f64(),
+                    slowest.as_secs_f64(),
+                    runs,
+                ),
+                DUMMY_SP.into(),
+            ).emit(self.env);
+        }
+    }
+
+    /// For each error of a function that reports several errors, find the
+    /// error of the same function whose failure causes it, if any.
+    ///
+    /// The failing assertion of each error is assumed in turn and the
+    /// program is verified again: the errors of the same function that
+    /// disappear are consequences of the assumed one. An error that is the
+    /// consequence of another is not used as a cause itself, so the errors
+    /// are grouped around the first root cause found.
+    fn find_error_causes(
+        &self,
+        program: &vir::Program,
+        program_name: &str,
+        errors: &[viper::VerificationError],
+    ) -> Vec<Option<usize>> {
+        let error_manager = self.encoder.error_manager();
+        let mut causes = vec![None; errors.len()];
+        for (index, error) in errors.iter().enumerate() {
+            if causes[index].is_some() {
+                continue;
+            }
+            let item = match error_manager.get_item(error) {
+                Some(item) => item,
+                None => continue,
+            };
+            let candidates: Vec<usize> = (0..errors.len())
+                .filter(|&other| {
+                    other != index
+                        && causes[other].is_none()
+                        && errors[other].pos_id != error.pos_id
+                        && error_manager.get_item(&errors[other]) == Some(item)
+                })
+                .collect();
+            let pos_id = match error.pos_id.as_ref().and_then(|pos_id| pos_id.parse().ok()) {
+                Some(pos_id) if !candidates.is_empty() => pos_id,
+                _ => continue,
+            };
+            let mut assumed_program = program.clone();
+            if !assume_assertion(&mut assumed_program, pos_id) {
+                continue;
+            }
+            debug!("Re-verifying the program assuming the assertion at position {}", pos_id);
+            let remaining_errors = match self.run_backend(assumed_program, program_name.to_string()) {
+                viper::VerificationResult::Success() => vec![],
+                viper::VerificationResult::Failure(errors) => errors,
+                // The program that is verified with an assumption cannot tell
+                // anything about the causes.
+                _ => continue,
+            };
+            for other in candidates {
+                let disappeared = remaining_errors.iter().all(|remaining| {
+                    remaining.pos_id != errors[other].pos_id
+                        || remaining.full_id != errors[other].full_id
+                });
+                if disappeared {
+                    causes[other] = Some(index);
+                }
+            }
+        }
+        // A cause that is itself the consequence of a later cause is
+        // attributed to the root.
+        causes.iter()
+            .map(|&cause| {
+                let mut root = cause;
+                while let Some(next) = root.and_then(|root| causes[root]) {
+                    root = Some(next);
+                }
+                root
+            })
+            .collect()
+    }
+
+    /// Order the errors so that each root cause is followed by its
+    /// consequences, and explain the relation on both sides.
+    fn group_errors_by_cause(
+        &self,
+        errors: Vec<PrustiError>,
+        causes: &[Option<usize>],
+    ) -> Vec<PrustiError> {
+        let source_map = self.env.tcx().sess.source_map();
+        let line_of = |error: &PrustiError| {
+            error.span().primary_span()
+                .map(|span| source_map.lookup_char_pos(span.lo()).line)
+        };
+        let lines: Vec<_> = errors.iter().map(line_of).collect();
+        let mut errors: Vec<Option<PrustiError>> = errors.into_iter().map(Some).collect();
+        let mut grouped = vec![];
+        for root in 0..errors.len() {
+            if causes[root].is_some() {
+                continue;
+            }
+            let consequences: Vec<usize> = (0..errors.len())
+                .filter(|&index| causes[index] == Some(root))
+                .collect();
+            let mut root_error = errors[root].take().unwrap();
+            if !consequences.is_empty() {
+                let consequence_lines: Vec<_> = consequences.iter()
+                    .filter_map(|&index| lines[index])
+                    .map(|line| line.to_string())
+                    .collect();
+                root_error = root_error.add_help(format!(
+                    "this error is the root cause of {} other error{} (at line{} {}), \
+                    which disappear{} when this assertion holds",
+                    consequences.len(),
+                    if consequences.len() == 1 { "" } else { "s" },
+                    if consequence_lines.len() == 1 { "" } else { "s" },
+                    consequence_lines.join(", "),
+                    if consequences.len() == 1 { "s" } else { "" },
+                ));
+            }
+            grouped.push(root_error);
+            for index in consequences {
+                let mut consequence = errors[index].take().unwrap();
+                if let Some(line) = lines[root] {
+                    consequence = consequence.add_help(format!(
+                        "this error might be a consequence of the error at line {}; fix that one first",
+                        line,
+                    ));
+                }
+                grouped.push(consequence);
+            }
+        }
+        grouped
+    }
+}
+
+/// The line that `export_obligations` appends for a crate: a JSON object
+/// with the crate name and its obligations, without line breaks, so that
+/// the crates of a workspace can share the file.
+fn obligations_report(crate_name: &str, obligations: Vec<serde_json::Value>) -> String {
+    serde_json::json!({
+        "crate": crate_name,
+        "obligations": obligations,
+    }).to_string()
+}
+
+/// The cycle among `cycles` that contains `proc_id`, if any.
+fn cycle_of(cycles: &[Vec<ProcedureDefId>], proc_id: ProcedureDefId) -> Option<&[ProcedureDefId]> {
+    cycles.iter().find(|cycle| cycle.contains(&proc_id)).map(|cycle| cycle.as_slice())
+}
+
+/// Verify a backend-level program with the given backend configuration, on
+/// the Prusti server if one is configured and otherwise with the JVM of
+/// `verifier_builder`.
+fn run_backend_with_config(
+    verifier_builder: Option<&VerifierBuilder>,
+    program: vir::Program,
+    program_name: String,
+    backend_config: ViperBackendConfig,
+) -> viper::VerificationResult {
+    if let Some(server_address) = config::server_address() {
+        let server_address = if server_address == "MOCK" {
+            ServerSideService::spawn_off_thread().to_string()
+        } else {
+            server_address
+        };
+        info!("Connecting to Prusti server at {}", server_address);
+        let service = PrustiServerConnection::new(&server_address).unwrap_or_else(|error| {
+            panic!(
+                "Could not parse server address ({}) due to {:?}",
+                server_address, error
+            )
+        });
+
+        let request = VerificationRequest {
+            program,
+            program_name,
+            backend_config,
+        };
+        service.verify(request)
+    } else {
+        let verifier_builder = verifier_builder
+            .expect("the JVM should be started when there is no Prusti server");
+        VerifierRunner::with_runner(verifier_builder, &backend_config, |runner| {
+            runner.verify(program, program_name.as_str())
+        })
+    }
+}
+
+/// Replace the assertion at the given position with an assumption of its
+/// pure part, and assume the pure part before the exhale at the given
+/// position. Returns whether there is such a statement.
+fn assume_assertion(program: &mut vir::Program, pos_id: u64) -> bool {
+    fn assume_in_stmts(stmts: &mut Vec<vir::Stmt>, pos_id: u64) -> bool {
+        let mut found = false;
+        for stmt in std::mem::take(stmts) {
+            match stmt {
+                vir::Stmt::Assert(expr, pos) if pos.id() == pos_id => {
+                    stmts.push(vir::Stmt::Inhale(expr.purify()));
+                    found = true;
+                }
+                vir::Stmt::Exhale(expr, pos) if pos.id() == pos_id => {
+                    stmts.push(vir::Stmt::Inhale(expr.clone().purify()));
+                    stmts.push(vir::Stmt::Exhale(expr, pos));
+                    found = true;
+                }
+                vir::Stmt::If(guard, mut then_stmts, mut else_stmts) => {
+                    found |= assume_in_stmts(&mut then_stmts, pos_id);
+                    found |= assume_in_stmts(&mut else_stmts, pos_id);
+                    stmts.push(vir::Stmt::If(guard, then_stmts, else_stmts));
+                }
+                stmt => stmts.push(stmt),
+            }
+        }
+        found
+    }
+    let mut found = false;
+    for method in &mut program.methods {
+        for block in &mut method.basic_blocks {
+            found |= assume_in_stmts(&mut block.stmts, pos_id);
+        }
+    }
+    found
+}
+
+/// The wall time of each phase of the verification of a crate.
+struct PhaseTimes {
+    phase_start: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl PhaseTimes {
+    fn start() -> Self {
+        PhaseTimes {
+            phase_start: Instant::now(),
+            phases: vec![],
+        }
+    }
+
+    /// Record the time since the end of the previous phase.
+    fn finish(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.phases.push((phase, now.duration_since(self.phase_start)));
+        self.phase_start = now;
+    }
+}
+
+/// Dump the methods and functions of `program` after the given pass.
+fn report_vir_passes(program: &vir::Program, pass_index: usize, pass: &str) {
+    if config::dump_vir_passes().is_empty() {
+        return;
+    }
+    for method in &program.methods {
+        log::report_vir_pass(&method.name(), pass_index, pass, method);
+    }
+    for function in &program.functions {
+        log::report_vir_pass(&function.name, pass_index, pass, function);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::obligations_report;
+
+    #[test]
+    fn obligations_report_is_one_json_line() {
+        let obligation = serde_json::json!({
+            "kind": "overflow",
+            "item": "m_f",
+            "spans": [{
+                "location": "src/lib.rs:2:5: 2:10",
+                "start_line": 2,
+                "start_column": 5,
+                "end_line": 2,
+                "end_column": 10,
+            }],
+            "formula": "x + 1 <= 255",
+        });
+        let report = obligations_report("krate", vec![obligation.clone()]);
+        assert!(!report.contains('\n'));
+        let parsed: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(parsed["crate"], "krate");
+        assert_eq!(parsed["obligations"], serde_json::json!([obligation]));
     }
 }
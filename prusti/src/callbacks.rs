@@ -6,16 +6,46 @@ use rustc_interface::Queries;
 use regex::Regex;
 use prusti_common::config;
 use crate::verifier::verify;
+use tracing::Span;
 
 #[derive(Default)]
-pub struct PrustiCompilerCalls;
+pub struct PrustiCompilerCalls {
+    /// The tracing span of the current phase of the compiler.
+    phase: Option<Span>,
+}
+
+impl PrustiCompilerCalls {
+    /// Enter the span of the next phase of the compiler. The span is
+    /// entered by hand because the callbacks must be `Send`, which an
+    /// `EnteredSpan` is not.
+    fn start_phase(&mut self, phase: Span) {
+        self.finish_phase();
+        phase.with_subscriber(|(id, dispatch)| dispatch.enter(id));
+        self.phase = Some(phase);
+    }
+
+    fn finish_phase(&mut self) {
+        if let Some(phase) = self.phase.take() {
+            phase.with_subscriber(|(id, dispatch)| dispatch.exit(id));
+        }
+    }
+}
 
 impl rustc_driver::Callbacks for PrustiCompilerCalls {
+    fn after_parsing<'tcx>(
+        &mut self,
+        _compiler: &Compiler,
+        _queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        self.start_phase(tracing::info_span!("expansion"));
+        Compilation::Continue
+    }
     fn after_expansion<'tcx>(
         &mut self,
         compiler: &Compiler,
         queries: &'tcx Queries<'tcx>,
     ) -> Compilation {
+        self.finish_phase();
         compiler.session().abort_if_errors();
         let (krate, _resolver, _lint_store) = &mut *queries.expansion().unwrap().peek_mut();
         if config::print_desugared_specs() {
@@ -29,6 +59,7 @@ impl rustc_driver::Callbacks for PrustiCompilerCalls {
                 None,
             );
         }
+        self.start_phase(tracing::info_span!("typeck"));
         Compilation::Continue
     }
     fn after_analysis<'tcx>(
@@ -36,6 +67,7 @@ impl rustc_driver::Callbacks for PrustiCompilerCalls {
         compiler: &Compiler,
         queries: &'tcx Queries<'tcx>,
     ) -> Compilation {
+        self.finish_phase();
         compiler.session().abort_if_errors();
         queries.global_ctxt().unwrap().peek_mut().enter(|tcx| {
             let hir = tcx.hir();
@@ -46,9 +78,14 @@ impl rustc_driver::Callbacks for PrustiCompilerCalls {
             spec_checker.report_errors(&env);
             compiler.session().abort_if_errors();
 
+            let collect_span = tracing::info_span!("collect_specs").entered();
             let mut spec_collector = specs::SpecCollector::new(tcx);
             intravisit::walk_crate(&mut spec_collector, &krate);
             let def_spec = spec_collector.build_def_specs(&env);
+            drop(collect_span);
+            if config::lint_specs() {
+                specs::lints::lint_specs(&env, &def_spec);
+            }
             if config::print_typeckd_specs() {
                 let mut values: Vec<_> = def_spec
                     .specs
@@ -76,6 +113,7 @@ impl rustc_driver::Callbacks for PrustiCompilerCalls {
                 }
             }
             if !config::no_verify() {
+                let _span = tracing::info_span!("verify").entered();
                 verify(env, def_spec);
             }
         });
@@ -31,12 +31,14 @@ extern crate regex;
 extern crate prusti_common;
 
 mod callbacks;
+mod incremental;
 mod verifier;
 mod arg_value;
 
 use std::{env, panic, borrow::Cow, path::PathBuf};
 use prusti_common::report::user;
 use lazy_static::lazy_static;
+use tracing_subscriber::layer::SubscriberExt;
 use callbacks::PrustiCompilerCalls;
 use prusti_common::config;
 use arg_value::arg_value;
@@ -110,6 +112,26 @@ fn report_prusti_ice(info: &panic::PanicInfo<'_>, bug_report_url: &str) {
     }
 }
 
+/// Record the tracing spans in a Chrome trace if `config::chrome_trace()` is
+/// set. The trace is written when the returned guard is dropped.
+///
+/// Spans are recorded from their creation to their closing, because the spans
+/// of stopwatch sections are only entered while logging.
+fn init_chrome_trace() -> Option<tracing_chrome::FlushGuard> {
+    let path = config::chrome_trace();
+    if path.is_empty() {
+        return None;
+    }
+    let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+        .file(path.into())
+        .include_args(true)
+        .trace_style(tracing_chrome::TraceStyle::Async)
+        .build();
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer))
+        .expect("failed to install the tracing subscriber");
+    Some(guard)
+}
+
 /// Initialize Prusti and the Rust compiler loggers.
 fn init_loggers() {
     let env = env_logger::Env::new()
@@ -135,6 +157,7 @@ fn main() {
 
     lazy_static::initialize(&ICE_HOOK);
     init_loggers();
+    let chrome_trace_guard = init_chrome_trace();
 
     let exit_code = rustc_driver::catch_with_exit_code(move || {
 
@@ -167,6 +190,19 @@ fn main() {
         rustc_args.push("-Zcrate-attr=register_tool(prusti)".to_owned());
         rustc_args.push("--cfg=prusti".to_owned());
 
+        // The specification macros read this variable to decide whether to
+        // emit runtime checks.
+        if config::runtime_checks() && config::full_compilation() {
+            env::set_var("PRUSTI_RUNTIME_CHECKS", "true");
+        } else {
+            env::remove_var("PRUSTI_RUNTIME_CHECKS");
+        }
+        if config::trusted_runtime_checks() && config::full_compilation() {
+            env::set_var("PRUSTI_TRUSTED_RUNTIME_CHECKS", "true");
+        } else {
+            env::remove_var("PRUSTI_TRUSTED_RUNTIME_CHECKS");
+        }
+
         if config::check_overflows() {
             // Some crates might have a `overflow-checks = false` in their `Cargo.toml` to
             // disable integer overflow checks, but we want to ignore that.
@@ -180,7 +216,10 @@ fn main() {
 
         let mut callbacks = PrustiCompilerCalls::default();
 
+        let _span = tracing::info_span!("compile").entered();
         rustc_driver::RunCompiler::new(&rustc_args, &mut callbacks).run()
     });
+    // `exit` does not run the destructors.
+    drop(chrome_trace_guard);
     std::process::exit(exit_code)
 }
@@ -0,0 +1,265 @@
+//! Selective re-verification. Each verified item is hashed together with
+//! everything its verification depends on: its MIR, its contract, the
+//! contracts of the procedures it calls (and the bodies of the pure ones),
+//! including the implementations that trait calls resolve to, and the
+//! definitions of the local types it uses. Items whose hash is the same as
+//! in the last successful verification are not verified again.
+
+use prusti_common::{
+    config,
+    utils::dep_graph::{DepGraphHasher, DepNode},
+};
+use prusti_interface::{
+    data::ProcedureDefId,
+    environment::Environment,
+    specs::typed,
+};
+use prusti_specs::specifications::common::AssertionKind;
+use regex::Regex;
+use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_middle::mir;
+use rustc_middle::ty::{self, TyCtxt};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use log::{debug, warn};
+
+/// The hashes of the items that verified successfully, keyed by def path.
+pub struct VerificationCache {
+    salt: String,
+    items: HashMap<String, String>,
+}
+
+impl VerificationCache {
+    /// Load the cache from `config::incremental_cache()`. The cache is
+    /// discarded if it was written by another version of Prusti or with
+    /// another configuration.
+    pub fn load(salt: String) -> Self {
+        let path = config::incremental_cache();
+        let items = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .filter(|json| json["salt"].as_str() == Some(&salt))
+            .and_then(|json| serde_json::from_value(json["items"].clone()).ok())
+            .unwrap_or_default();
+        VerificationCache { salt, items }
+    }
+
+    pub fn is_up_to_date(&self, item: &str, hash: &str) -> bool {
+        self.items.get(item).map_or(false, |cached| cached == hash)
+    }
+
+    /// Record that `item` verified successfully.
+    pub fn insert(&mut self, item: String, hash: String) {
+        self.items.insert(item, hash);
+    }
+
+    pub fn remove(&mut self, item: &str) {
+        self.items.remove(item);
+    }
+
+    pub fn store(&self) {
+        let json = serde_json::json!({
+            "salt": self.salt,
+            "items": self.items,
+        });
+        if let Err(error) = fs::write(config::incremental_cache(), json.to_string()) {
+            warn!("Failed to write the verification cache: {}", error);
+        }
+    }
+}
+
+/// Computes the transitive hashes of the items of a crate.
+pub struct DependencyHasher<'a, 'tcx> {
+    env: &'a Environment<'tcx>,
+    def_spec: &'a typed::DefSpecificationMap<'tcx>,
+    /// The hashes of the contracts, including the bodies of pure functions.
+    contract_hashes: RefCell<DepGraphHasher<DefId>>,
+    /// The hashes of the definitions of the local types.
+    type_hashes: RefCell<HashMap<DefId, u64>>,
+    uuid: Regex,
+    num_uuid: Regex,
+}
+
+/// The hash of a MIR body and the procedures that it calls.
+struct BodyHash {
+    hash: u64,
+    callees: Vec<DefId>,
+}
+
+impl<'a, 'tcx> DependencyHasher<'a, 'tcx> {
+    pub fn new(env: &'a Environment<'tcx>, def_spec: &'a typed::DefSpecificationMap<'tcx>) -> Self {
+        DependencyHasher {
+            env,
+            def_spec,
+            contract_hashes: RefCell::new(DepGraphHasher::new()),
+            type_hashes: RefCell::new(HashMap::new()),
+            uuid: Regex::new("[a-z0-9]{8}-[a-z0-9]{4}-[a-z0-9]{4}-[a-z0-9]{4}-[a-z0-9]{12}").unwrap(),
+            num_uuid: Regex::new("[a-z0-9]{32}").unwrap(),
+        }
+    }
+
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.env.tcx()
+    }
+
+    /// The hash of a verified item: its body, its contract and everything
+    /// that they depend on.
+    pub fn item_hash(&self, def_id: ProcedureDefId) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.contract_hash(def_id).hash(&mut hasher);
+        if let Some(local_def_id) = def_id.as_local() {
+            let body = self.body_hash(local_def_id);
+            body.hash.hash(&mut hasher);
+            for callee in body.callees {
+                self.contract_hash(callee).hash(&mut hasher);
+            }
+        }
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// The hash of everything that callers of `def_id` depend on.
+    fn contract_hash(&self, def_id: DefId) -> u64 {
+        self.contract_hashes
+            .borrow_mut()
+            .hash(def_id, &mut |def_id| self.contract_node(def_id))
+    }
+
+    /// The part of the contract of `def_id` that does not depend on other
+    /// contracts, and the contracts that it depends on.
+    fn contract_node(&self, def_id: DefId) -> DepNode<DefId> {
+        let mut hasher = DefaultHasher::new();
+        let mut dependencies = vec![];
+        if !self.tcx().is_closure(def_id) {
+            self.tcx().fn_sig(def_id).to_string().hash(&mut hasher);
+        }
+        if let Some(spec) = self.def_spec.get(&def_id) {
+            let spec = spec.expect_procedure();
+            spec.pure.hash(&mut hasher);
+            spec.trusted.hash(&mut hasher);
+            let assertions = spec.pres.iter()
+                .chain(spec.posts.iter())
+                .chain(spec.pledges.iter().flat_map(|pledge| pledge.lhs.iter().chain(Some(&pledge.rhs))))
+                .chain(spec.predicate_body.iter())
+                .chain(spec.refined_posts.iter().map(|(_, post)| post));
+            let mut bodies: Vec<_> = assertions.flat_map(collect_expressions).collect();
+            // The body of a pure function is part of its contract.
+            if spec.pure && !spec.trusted {
+                bodies.extend(def_id.as_local());
+            }
+            for body in bodies {
+                let body = self.body_hash(body);
+                body.hash.hash(&mut hasher);
+                dependencies.extend(body.callees);
+            }
+        }
+        DepNode {
+            name: self.tcx().def_path_str(def_id),
+            hash: hasher.finish(),
+            dependencies,
+        }
+    }
+
+    /// Hash a local MIR body together with the definitions of the local
+    /// types that it uses, and collect the procedures that it calls. A call
+    /// of a trait method depends on the contract of the trait method and,
+    /// if it is known, on the contract of the implementation that is called.
+    fn body_hash(&self, def_id: LocalDefId) -> BodyHash {
+        let tcx = self.tcx();
+        let mut hasher = DefaultHasher::new();
+        let mut callees = vec![];
+        let mir = self.env.local_mir(def_id);
+        let param_env = tcx.param_env(def_id.to_def_id());
+        self.mir_text(&mir).hash(&mut hasher);
+        for bb_data in mir.basic_blocks() {
+            if let mir::TerminatorKind::Call { ref func, .. } = bb_data.terminator().kind {
+                if let ty::TyKind::FnDef(callee_def_id, substs) = func.ty(&*mir, tcx).kind() {
+                    callees.push(*callee_def_id);
+                    let resolved = ty::Instance::resolve(tcx, param_env, *callee_def_id, substs)
+                        .ok()
+                        .flatten()
+                        .map(|instance| instance.def_id());
+                    if let Some(resolved_def_id) = resolved {
+                        if resolved_def_id != *callee_def_id {
+                            callees.push(resolved_def_id);
+                        }
+                    }
+                }
+            }
+        }
+        for local_decl in mir.local_decls.iter() {
+            for ty in local_decl.ty.walk() {
+                if let ty::subst::GenericArgKind::Type(ty) = ty.unpack() {
+                    if let ty::TyKind::Adt(adt_def, _) = ty.kind() {
+                        self.type_hash(adt_def.did).hash(&mut hasher);
+                    }
+                }
+            }
+        }
+        BodyHash {
+            hash: hasher.finish(),
+            callees,
+        }
+    }
+
+    fn type_hash(&self, def_id: DefId) -> u64 {
+        if let Some(&hash) = self.type_hashes.borrow().get(&def_id) {
+            return hash;
+        }
+        let mut hasher = DefaultHasher::new();
+        self.tcx().def_path_str(def_id).hash(&mut hasher);
+        if let Some(span) = self.tcx().hir().span_if_local(def_id) {
+            self.tcx().sess.source_map().span_to_snippet(span).ok().hash(&mut hasher);
+        }
+        let hash = hasher.finish();
+        self.type_hashes.borrow_mut().insert(def_id, hash);
+        hash
+    }
+
+    /// The textual MIR of a body, without the source locations and the
+    /// identifiers of the specifications, which change between compilations.
+    fn mir_text(&self, mir: &mir::Body<'tcx>) -> String {
+        let mut bytes = vec![];
+        if let Err(error) = mir::pretty::write_mir_fn(self.tcx(), mir, &mut |_, _| Ok(()), &mut bytes) {
+            debug!("Failed to print MIR: {}", error);
+        }
+        let text: String = String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(|line| line.split("//").next().unwrap().trim_end())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let text = self.num_uuid.replace_all(&text, "");
+        self.uuid.replace_all(&text, "").into_owned()
+    }
+}
+
+/// The closures that encode the expressions of an assertion.
+fn collect_expressions(assertion: &typed::Assertion) -> Vec<LocalDefId> {
+    let mut expressions = vec![];
+    let mut pending = vec![assertion];
+    while let Some(assertion) = pending.pop() {
+        match &*assertion.kind {
+            AssertionKind::Expr(expression) => expressions.push(expression.expr),
+            AssertionKind::And(assertions) => pending.extend(assertions),
+            AssertionKind::Implies(lhs, rhs) => {
+                pending.push(lhs);
+                pending.push(rhs);
+            }
+            AssertionKind::TypeCond(_, body) => pending.push(body),
+            AssertionKind::ForAll(_, triggers, body) => {
+                expressions.extend(
+                    triggers.triggers().iter().flat_map(|trigger| trigger.terms()).map(|term| term.expr)
+                );
+                pending.push(body);
+            }
+            AssertionKind::SpecEntailment { closure, pres, posts, .. } => {
+                expressions.push(closure.expr);
+                pending.extend(pres);
+                pending.extend(posts);
+            }
+        }
+    }
+    expressions
+}
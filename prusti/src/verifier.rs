@@ -8,7 +8,13 @@ use prusti_interface::{
 };
 use prusti_viper::verifier::Verifier;
 use prusti_common::config;
-use prusti_common::report::user;
+use prusti_common::report::{progress, user};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::OpenOptions;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use crate::incremental::{DependencyHasher, VerificationCache};
 
 pub fn verify<'tcx>(
     env: Environment<'tcx>,
@@ -20,7 +26,47 @@ pub fn verify<'tcx>(
         warn!("The compiler reported an error, so the program will not be verified.");
     } else {
         debug!("Prepare verification task...");
-        let annotated_procedures = env.get_annotated_procedures();
+        let mut annotated_procedures = env.get_annotated_procedures();
+        if config::whole_program() {
+            match env.get_procedures_reachable_from_main(&annotated_procedures) {
+                Some(reachable_procedures) => annotated_procedures = reachable_procedures,
+                None => user::message("The crate has no main function, so all functions are verified."),
+            }
+        }
+        let mut incremental = if config::incremental_cache().is_empty() {
+            None
+        } else {
+            let hasher = DependencyHasher::new(&env, &def_spec);
+            let cache = VerificationCache::load(cache_salt());
+            let hashes: HashMap<_, _> = annotated_procedures
+                .iter()
+                .map(|&procedure| (procedure, hasher.item_hash(procedure)))
+                .collect();
+            let (up_to_date, outdated): (Vec<_>, Vec<_>) = annotated_procedures
+                .into_iter()
+                .partition(|procedure| {
+                    cache.is_up_to_date(&env.get_item_def_path(*procedure), &hashes[procedure])
+                });
+            annotated_procedures = outdated;
+            let skipped = up_to_date.len();
+            let source_map = env.tcx().sess.source_map();
+            for (index, &procedure) in up_to_date.iter().enumerate() {
+                progress::report(progress::ProgressEvent {
+                    status: progress::ItemStatus::Skipped,
+                    item: env.get_absolute_item_name(procedure),
+                    location: source_map.span_to_string(env.get_item_span(procedure)),
+                    done: index + 1,
+                    total: skipped,
+                });
+            }
+            if skipped > 0 {
+                user::message(format!(
+                    "Skipping {} items that did not change since their last successful verification",
+                    skipped
+                ));
+            }
+            Some((cache, hashes))
+        };
         let verification_task = VerificationTask {
             procedures: annotated_procedures,
         };
@@ -48,9 +94,23 @@ pub fn verify<'tcx>(
             let verification_result = verifier.verify(&verification_task);
             debug!("Verifier returned {:?}", verification_result);
 
+            if let Some((cache, hashes)) = incremental.as_mut() {
+                for procedure in &verification_task.procedures {
+                    cache.remove(&env.get_item_def_path(*procedure));
+                }
+                for procedure in verifier.get_successful_items() {
+                    cache.insert(env.get_item_def_path(*procedure), hashes[procedure].clone());
+                }
+                cache.store();
+            }
+
             verification_result
         };
 
+        if !config::batch_report().is_empty() {
+            append_batch_report(&env.crate_name(), verification_task.procedures.len(), &verification_result);
+        }
+
         match verification_result {
             VerificationResult::Success => {
                 user::message(format!(
@@ -66,4 +126,33 @@ pub fn verify<'tcx>(
     }
 
     trace!("[verify] exit");
-}
\ No newline at end of file
+}
+
+/// Record the result of the verification of this crate in the report shared
+/// by the crates verified by one `cargo prusti` invocation.
+fn append_batch_report(crate_name: &str, items: usize, result: &VerificationResult) {
+    let outcome = match result {
+        VerificationResult::Success => "verified",
+        VerificationResult::Failure => "failed",
+    };
+    let line = format!("{}\t{}\t{}\n", crate_name, items, outcome);
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(config::batch_report())
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(err) = written {
+        warn!("Failed to write the batch report: {}", err);
+    }
+}
+
+/// The verification cache is valid only for the same version of Prusti and
+/// the same configuration.
+fn cache_salt() -> String {
+    // The settings are not dumped in a deterministic order.
+    let mut settings: Vec<_> = config::dump().lines().map(str::trim).map(String::from).collect();
+    settings.sort();
+    let mut hasher = DefaultHasher::new();
+    settings.hash(&mut hasher);
+    format!("{}-{:016x}", crate::get_prusti_version_info(), hasher.finish())
+}
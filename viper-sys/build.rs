@@ -105,6 +105,10 @@ fn main() {
                 method!("append", "(Ljava/lang/Object;)Lscala/collection/mutable/Buffer;"),
                 method!("toSeq"),
             ]),
+            java_class!("scala.collection.immutable.ArraySeq$", vec![
+                object_getter!(),
+                method!("unsafeWrapArray", "(Ljava/lang/Object;)Lscala/collection/immutable/ArraySeq;"),
+            ]),
             java_class!("scala.collection.mutable.ListBuffer", vec![
                 constructor!(),
             ]),
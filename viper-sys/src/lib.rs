@@ -7,6 +7,7 @@
 #![deny(unused_must_use)]
 
 extern crate jni;
+extern crate once_cell;
 
 #[path = "../gen/mod.rs"]
 pub mod wrappers;
@@ -17,6 +17,16 @@ macro_rules! jobject_wrapper {
             pub(crate) fn to_jobject(&self) -> JObject<'a> {
                 self.obj
             }
+            /// A reference to the node that stays valid in every verification
+            /// context, so that the node can be shared between programs.
+            pub fn to_global(&self, ast: &AstFactory<'a>) -> GlobalRef {
+                ast.new_global_ref(self.obj)
+            }
+            /// The node of a reference created by `to_global`, in the
+            /// verification context of `ast`.
+            pub fn from_global(global: &GlobalRef, ast: &AstFactory<'a>) -> Self {
+                $name { obj: ast.new_local_ref(global) }
+            }
         }
     };
 }
@@ -11,7 +11,7 @@ mod program;
 mod statement;
 mod structs;
 
-use jni::objects::JObject;
+use jni::objects::{GlobalRef, JObject};
 use jni::JNIEnv;
 use jni_utils::JniUtils;
 use viper_sys::wrappers::viper::silver::ast;
@@ -59,4 +59,15 @@ impl<'a> AstFactory<'a> {
         self.jni
             .unwrap_result(ast::NoTrafos_object::with(self.env).singleton())
     }
+
+    // === Global references ===
+
+    fn new_global_ref(&self, obj: JObject<'a>) -> GlobalRef {
+        self.jni.unwrap_result(self.env.new_global_ref(obj))
+    }
+
+    fn new_local_ref(&self, global: &GlobalRef) -> JObject<'a> {
+        let obj = JObject::from(global.as_obj().into_inner());
+        self.jni.unwrap_result(self.env.new_local_ref(obj))
+    }
 }
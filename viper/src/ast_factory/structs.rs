@@ -2,7 +2,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use jni::objects::JObject;
+use super::AstFactory;
+use jni::objects::{GlobalRef, JObject};
 
 jobject_wrapper!(Program);
 jobject_wrapper!(Type);
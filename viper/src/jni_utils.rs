@@ -99,13 +99,18 @@ impl<'a> JniUtils<'a> {
 
     /// Converts a Rust Vec<JObject> to a Scala Seq
     pub fn new_seq(&self, objects: &[JObject]) -> JObject {
-        let array_buffer_wrapper = scala::collection::mutable::ArrayBuffer::with(self.env);
-        let len = objects.len();
-        let res = self.unwrap_result(array_buffer_wrapper.new(len as i32));
-        for obj in objects.iter() {
-            self.unwrap_result(array_buffer_wrapper.call_append(res, *obj));
+        // Storing the elements in a Java array is much cheaper than calling a
+        // Scala method for each of them, and the array is wrapped without
+        // copying it.
+        let array = self.new_object_array(objects.len() as jsize);
+        for (index, obj) in objects.iter().enumerate() {
+            self.unwrap_result(
+                self.env.set_object_array_element(array.into_inner(), index as jsize, *obj)
+            );
         }
-        self.unwrap_result(array_buffer_wrapper.call_toSeq(res))
+        let array_seq_wrapper = scala::collection::immutable::ArraySeq_object::with(self.env);
+        let array_seq_object = self.unwrap_result(array_seq_wrapper.singleton());
+        self.unwrap_result(array_seq_wrapper.call_unsafeWrapArray(array_seq_object, array))
     }
 
     /// Converts a Java String to a Rust String
@@ -15,6 +15,7 @@ extern crate uuid;
 extern crate viper_sys;
 #[macro_use]
 extern crate serde;
+extern crate sha2;
 
 mod ast_factory;
 mod ast_utils;
@@ -23,6 +24,7 @@ mod jni_utils;
 #[macro_use]
 pub mod utils;
 mod verification_backend;
+mod verification_cache;
 mod verification_context;
 mod verification_result;
 mod verifier;
@@ -32,8 +34,10 @@ mod java_exception;
 pub use ast_factory::*;
 pub use ast_utils::*;
 pub use verification_backend::*;
+pub use verification_cache::*;
 pub use verification_context::*;
 pub use verification_result::*;
 pub use verifier::*;
 pub use viper::*;
 pub use java_exception::*;
+pub use jni::objects::GlobalRef;
@@ -0,0 +1,124 @@
+// © 2021, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use verification_result::VerificationResult;
+
+/// The verdicts of the backends within one session (i.e. one JVM), keyed by
+/// a hash of the pretty-printed program and of the configuration of the
+/// verifier, so that verifying an identical program again returns
+/// immediately. Computing the key is not free, hence the cache is only used
+/// if it is enabled with `Viper::with_verification_cache`.
+///
+/// The pretty-printed program does not contain the identifiers of the
+/// positions, which the errors of a failed verification refer to. Hence only
+/// the results that do not depend on them (successes and consistency errors)
+/// are cached.
+#[derive(Clone, Default)]
+pub struct VerificationCache {
+    results: Arc<Mutex<HashMap<String, VerificationResult>>>,
+}
+
+impl VerificationCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The key of a program verified by a verifier with the given
+    /// configuration (e.g. the backend and its command-line arguments).
+    pub fn key(configuration: &str, program: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(configuration.as_bytes());
+        hasher.update([0]);
+        hasher.update(program.as_bytes());
+        hasher.finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn get(&self, key: &str) -> Option<VerificationResult> {
+        self.results.lock().unwrap().get(key).cloned()
+    }
+
+    /// Store the result, if it does not depend on the positions of the
+    /// program.
+    pub fn insert(&self, key: String, result: &VerificationResult) {
+        if let VerificationResult::Success() | VerificationResult::ConsistencyErrors(_) = result {
+            self.results.lock().unwrap().insert(key, result.clone());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use java_exception::JavaException;
+    use verification_result::VerificationError;
+
+    #[test]
+    fn key_is_stable() {
+        let key = VerificationCache::key("silicon --z3Exe z3", "method m() {}");
+        assert_eq!(key, VerificationCache::key("silicon --z3Exe z3", "method m() {}"));
+        assert_eq!(key.len(), 64);
+        assert_ne!(key, VerificationCache::key("carbon", "method m() {}"));
+        assert_ne!(key, VerificationCache::key("silicon --z3Exe z3", "method n() {}"));
+    }
+
+    #[test]
+    fn key_separates_configuration_and_program() {
+        assert_ne!(VerificationCache::key("ab", "c"), VerificationCache::key("a", "bc"));
+    }
+
+    #[test]
+    fn stores_only_results_without_positions() {
+        let cache = VerificationCache::new();
+        cache.insert("success".to_string(), &VerificationResult::Success());
+        cache.insert(
+            "consistency".to_string(),
+            &VerificationResult::ConsistencyErrors(vec!["error".to_string()]),
+        );
+        cache.insert(
+            "failure".to_string(),
+            &VerificationResult::Failure(vec![VerificationError::new(
+                "assert.failed:assertion.false".to_string(),
+                Some("1".to_string()),
+                None,
+                "Assert might fail.".to_string(),
+            )]),
+        );
+        cache.insert(
+            "exception".to_string(),
+            &VerificationResult::JavaException(JavaException::new(
+                "message".to_string(),
+                "stack trace".to_string(),
+            )),
+        );
+        assert_eq!(cache.len(), 2);
+        assert!(matches!(cache.get("success"), Some(VerificationResult::Success())));
+        assert!(matches!(cache.get("consistency"), Some(VerificationResult::ConsistencyErrors(_))));
+        assert!(cache.get("failure").is_none());
+        assert!(cache.get("exception").is_none());
+    }
+
+    #[test]
+    fn clones_share_the_results() {
+        let cache = VerificationCache::new();
+        let clone = cache.clone();
+        clone.insert("key".to_string(), &VerificationResult::Success());
+        assert!(cache.get("key").is_some());
+    }
+}
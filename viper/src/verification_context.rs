@@ -10,16 +10,27 @@ use jni::AttachGuard;
 use std::env;
 use std::path::{Path, PathBuf};
 use verification_backend::VerificationBackend;
+use verification_cache::VerificationCache;
 use verifier::state;
 use verifier::Verifier;
 
 pub struct VerificationContext<'a> {
     env: AttachGuard<'a>,
+    cache: Option<VerificationCache>,
 }
 
 impl<'a> VerificationContext<'a> {
     pub fn new(env_guard: AttachGuard<'a>) -> Self {
-        VerificationContext { env: env_guard }
+        VerificationContext {
+            env: env_guard,
+            cache: None,
+        }
+    }
+
+    /// Share the results of the verifiers of this context with `cache`.
+    pub fn with_cache(mut self, cache: VerificationCache) -> Self {
+        self.cache = Some(cache);
+        self
     }
 
     pub fn new_ast_factory(&self) -> AstFactory {
@@ -77,6 +88,7 @@ impl<'a> VerificationContext<'a> {
 
         Verifier::<state::Uninitialized>::new(&self.env, backend, report_path)
             .parse_command_line(&verifier_args)
+            .with_cache(self.cache.clone())
             .start()
     }
 }
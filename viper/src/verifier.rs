@@ -14,6 +14,7 @@ use jni_utils::JniUtils;
 use std::marker::PhantomData;
 use std::path::PathBuf;
 use verification_backend::VerificationBackend;
+use verification_cache::VerificationCache;
 use verification_result::VerificationError;
 use verification_result::VerificationResult;
 use viper_sys::wrappers::viper::*;
@@ -29,6 +30,10 @@ pub struct Verifier<'a, VerifierState> {
     verifier_wrapper: silver::verifier::Verifier<'a>,
     verifier_instance: JObject<'a>,
     jni: JniUtils<'a>,
+    /// The backend, its version and its arguments, which are part of the
+    /// key of the cached results.
+    configuration: String,
+    cache: Option<VerificationCache>,
     state: PhantomData<VerifierState>,
 }
 
@@ -69,6 +74,8 @@ impl<'a, VerifierState> Verifier<'a, VerifierState> {
             verifier_wrapper,
             verifier_instance,
             jni,
+            configuration: format!("{} {}", name, build_version),
+            cache: None,
             state: PhantomData,
         }
     }
@@ -93,12 +100,20 @@ impl<'a> Verifier<'a, state::Uninitialized> {
             verifier_wrapper: self.verifier_wrapper,
             verifier_instance: self.verifier_instance,
             jni: self.jni,
+            configuration: format!("{} {}", self.configuration, args.join(" ")),
+            cache: self.cache,
             state: PhantomData,
         }
     }
 }
 
 impl<'a> Verifier<'a, state::Stopped> {
+    /// Memoize the results of the verifier in `cache`.
+    pub fn with_cache(mut self, cache: Option<VerificationCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
     pub fn start(self) -> Verifier<'a, state::Started> {
         self.jni
             .unwrap_result(self.verifier_wrapper.call_start(self.verifier_instance));
@@ -108,6 +123,8 @@ impl<'a> Verifier<'a, state::Stopped> {
             verifier_wrapper: self.verifier_wrapper,
             verifier_instance: self.verifier_instance,
             jni: self.jni,
+            configuration: self.configuration,
+            cache: self.cache,
             state: PhantomData,
         }
     }
@@ -117,6 +134,25 @@ impl<'a> Verifier<'a, state::Started> {
     pub fn verify(&self, program: Program) -> VerificationResult {
         let ast_utils = AstUtils::new(self.env);
 
+        let cache_key = self.cache.as_ref().map(|_| {
+            VerificationCache::key(&self.configuration, &ast_utils.pretty_print(program))
+        });
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            if let Some(result) = cache.get(key) {
+                debug!("Reusing the cached verification result of the Viper program {}", key);
+                return result;
+            }
+        }
+        let result = self.verify_uncached(program);
+        if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+            cache.insert(key, &result);
+        }
+        result
+    }
+
+    fn verify_uncached(&self, program: Program) -> VerificationResult {
+        let ast_utils = AstUtils::new(self.env);
+
         debug!(
             "Program to be verified:\n{}",
             ast_utils.pretty_print(program)
@@ -8,12 +8,15 @@ use jni::*;
 use jni_utils::JniUtils;
 use std::env;
 use std::fs;
+use verification_cache::VerificationCache;
 use verification_context::*;
 use viper_sys::wrappers::*;
 use VerificationBackend;
 use std::path::Path;
 
 pub struct Viper {
+    /// The results of the verifiers of this JVM, if they are cached.
+    cache: Option<VerificationCache>,
     jvm: JavaVM,
 }
 
@@ -101,7 +104,10 @@ impl Viper {
             info!("Using JVM {}, Java {}", vm_name, java_version);
         }
 
-        let this = Viper { jvm };
+        let this = Viper {
+            cache: None,
+            jvm,
+        };
 
         this
     }
@@ -112,6 +118,17 @@ impl Viper {
             .attach_current_thread()
             .expect("failed to attach jvm thread");
 
-        VerificationContext::new(env_guard)
+        let context = VerificationContext::new(env_guard);
+        match self.cache {
+            Some(ref cache) => context.with_cache(cache.clone()),
+            None => context,
+        }
+    }
+
+    /// Memoize the results of the verifiers of this JVM. Verifying a program
+    /// then also pretty-prints it, to compute the key of the cache.
+    pub fn with_verification_cache(mut self) -> Self {
+        self.cache = Some(VerificationCache::new());
+        self
     }
 }